@@ -0,0 +1,14 @@
+// Builds the programs of the ebpf backend, see ebpf/
+fn main() {
+    #[cfg(feature = "ebpf")]
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux") {
+        let package = aya_build::Package {
+            name: "net-stat-ebpf",
+            root_dir: concat!(env!("CARGO_MANIFEST_DIR"), "/ebpf"),
+            ..Default::default()
+        };
+        if let Err(e) = aya_build::build_ebpf([package], aya_build::Toolchain::default()) {
+            panic!("failed to build the eBPF programs: {e:#}");
+        }
+    }
+}
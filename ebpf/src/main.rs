@@ -0,0 +1,127 @@
+#![cfg_attr(target_arch = "bpf", no_std, no_main)]
+
+use aya_ebpf::{
+    bindings::BPF_NOEXIST,
+    helpers::{bpf_get_current_uid_gid, bpf_probe_read_kernel},
+    macros::{map, tracepoint},
+    maps::{LruHashMap, LruPerCpuHashMap},
+    programs::TracePointContext,
+};
+
+// Per-socket byte counts for the ebpf backend, keyed by struct sock
+// pointers. The layouts of the values are read back by
+// src/collectors/ebpf.rs.
+
+const IPPROTO_TCP: u16 = 6;
+const MSG_PEEK: i32 = 2;
+const TCP_CLOSE: i32 = 7;
+// the least recently used sockets make room when the maps are full
+const MAX_SOCKETS: u32 = 65536;
+
+// The uid the first call ran as and the first 24 bytes of struct
+// sock_common at the time
+#[repr(C)]
+struct Owner {
+    uid: u64,
+    common: [u8; 24],
+}
+
+// inet_sock_set_state's fields from oldstate to daddr_v6
+type State = [u8; 56];
+
+// bytes sent and received, per CPU so no CPU waits on another
+#[map]
+static COUNTS: LruPerCpuHashMap<u64, [u64; 2]> = LruPerCpuHashMap::with_max_entries(MAX_SOCKETS, 0);
+
+#[map]
+static OWNERS: LruHashMap<u64, Owner> = LruHashMap::with_max_entries(MAX_SOCKETS, 0);
+
+#[map]
+static STATES: LruHashMap<u64, State> = LruHashMap::with_max_entries(MAX_SOCKETS, 0);
+
+#[tracepoint]
+pub fn sock_send_length(ctx: TracePointContext) -> u32 {
+    let _ = count(&ctx, false);
+    0
+}
+
+#[tracepoint]
+pub fn sock_recv_length(ctx: TracePointContext) -> u32 {
+    let _ = count(&ctx, true);
+    0
+}
+
+#[tracepoint]
+pub fn inet_sock_set_state(ctx: TracePointContext) -> u32 {
+    let _ = set_state(&ctx);
+    0
+}
+
+// Adds the bytes a TCP send or receive moved to the socket's counts. The
+// record has struct sock *sk at 8, __u16 protocol at 18, int ret at 20 and
+// int flags at 24. Peeks are left for the receive that follows.
+fn count(ctx: &TracePointContext, receive: bool) -> Option<()> {
+    let protocol: u16 = unsafe { ctx.read_at(18) }.ok()?;
+    let ret: i32 = unsafe { ctx.read_at(20) }.ok()?;
+    if protocol != IPPROTO_TCP || ret <= 0 {
+        return None;
+    }
+    if receive {
+        let flags: i32 = unsafe { ctx.read_at(24) }.ok()?;
+        if flags & MSG_PEEK != 0 {
+            return None;
+        }
+    }
+    let sk: u64 = unsafe { ctx.read_at(8) }.ok()?;
+    let counts = match COUNTS.get_ptr_mut(sk) {
+        Some(counts) => counts,
+        None => {
+            let owner = Owner {
+                uid: bpf_get_current_uid_gid() as u32 as u64,
+                common: unsafe { bpf_probe_read_kernel(sk as *const [u8; 24]) }.ok()?,
+            };
+            // these fail when another CPU got there first, which is fine,
+            // the lookup finds this CPU's counts either way
+            let _ = OWNERS.insert(sk, owner, BPF_NOEXIST as u64);
+            let _ = COUNTS.insert(sk, [0, 0], BPF_NOEXIST as u64);
+            COUNTS.get_ptr_mut(sk)?
+        }
+    };
+    unsafe { (*counts)[receive as usize] += ret as u64 };
+    Some(())
+}
+
+// Keeps the last state change of every TCP socket. The record has void
+// *skaddr at 8, int oldstate at 16 and __u16 protocol at 30. A socket
+// leaving TCP_CLOSE is a new one that may reuse a closed socket's memory,
+// so its counts start over.
+fn set_state(ctx: &TracePointContext) -> Option<()> {
+    let protocol: u16 = unsafe { ctx.read_at(30) }.ok()?;
+    if protocol != IPPROTO_TCP {
+        return None;
+    }
+    let sk: u64 = unsafe { ctx.read_at(8) }.ok()?;
+    let oldstate: i32 = unsafe { ctx.read_at(16) }.ok()?;
+    if oldstate == TCP_CLOSE {
+        let _ = COUNTS.remove(sk);
+        let _ = OWNERS.remove(sk);
+    }
+    let state: State = unsafe { ctx.read_at(16) }.ok()?;
+    STATES.insert(sk, state, 0).ok()
+}
+
+#[cfg(target_arch = "bpf")]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+// the helpers used need a GPL compatible license
+#[cfg(target_arch = "bpf")]
+#[no_mangle]
+#[link_section = "license"]
+static LICENSE: [u8; 4] = *b"GPL\0";
+
+// built on the host only to be checked with the rest of the workspace
+#[cfg(not(target_arch = "bpf"))]
+fn main() {}
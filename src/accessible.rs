@@ -0,0 +1,164 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    time::Instant,
+};
+
+use crate::{
+    alerts::{AlertEvent, AlertSink},
+    app::{update, App},
+    capabilities::Capabilities,
+    cli::TuiOptions,
+    collector::Pipeline,
+    error::{Result, ResultExt},
+    tui::{finish_sampling, open_app, start_sampling, stopped, Signals},
+    units::format_rate,
+    Action,
+};
+
+// A change of the total rate between two readings smaller than this is
+// spoken as steady
+const TREND_RATIO: f64 = 1.25;
+// bytes per second below which an interface counts as idle
+const IDLE_RATE: f64 = 64.0;
+
+// Plain sentences on stdout in place of the dashboard, fed by the same
+// pipeline, e.g. "eth0: down 1.2 MB/s, up 300 KB/s, rising". Idle interfaces
+// are mentioned once when they go quiet, alerts as they happen.
+pub async fn run(capabilities: Capabilities, options: TuiOptions) -> Result<App> {
+    let mut app = open_app(capabilities, &options)?;
+    app.alert_sinks.push(Box::new(SpokenAlerts));
+    let mut signals = Signals::termination()?;
+    let pipeline = Pipeline::default();
+    let mut samples = pipeline.subscribe("accessible", app.config.pipeline_queue);
+    start_sampling(&mut app, &options, &pipeline).await?;
+
+    let mut announcer = Announcer::default();
+    let mut out = std::io::stdout();
+    loop {
+        let action = tokio::select! {
+            _ = signals.recv() => Action::Quit,
+            snapshot = samples.recv() => match snapshot {
+                Some(snapshot) => Action::Tick(snapshot),
+                None => return Err(stopped(&pipeline)),
+            },
+        };
+        update(&mut app, action);
+        if app.should_quit {
+            break;
+        }
+        for line in announcer.announce(&app) {
+            writeln!(out, "{line}").or_ui(|| "failed to write to stdout")?;
+        }
+    }
+
+    finish_sampling(&mut app, &pipeline);
+    Ok(app)
+}
+
+// What was said last, to speak only every `announce_interval` and to tell
+// how the rates moved since
+#[derive(Default)]
+pub struct Announcer {
+    last: Option<Instant>,
+    // bytes moved per interface at the last reading
+    moved: HashMap<String, (u64, u64)>,
+    // total rate per interface at the last reading
+    rates: HashMap<String, f64>,
+    idle: HashSet<String>,
+}
+
+impl Announcer {
+    // The sentences due for the latest sample, none between readings. The
+    // first reading only sets the starting point.
+    pub fn announce(&mut self, app: &App) -> Vec<String> {
+        let now = app.last_tick;
+        let elapsed = match self.last {
+            Some(last) => now.saturating_duration_since(last).as_secs_f64(),
+            None => {
+                self.last = Some(now);
+                self.remember(app);
+                return Vec::new();
+            }
+        };
+        if elapsed < app.config.announce_interval.max(0.0) || elapsed <= 0.0 {
+            return Vec::new();
+        }
+        self.last = Some(now);
+
+        let mut lines = Vec::new();
+        let mut rates = HashMap::new();
+        for interface in &app.net_interfaces {
+            let Some(stats) = app.stats.get(&interface.name) else {
+                continue;
+            };
+            let (sent, rec) = self
+                .moved
+                .get(&interface.name)
+                .copied()
+                .unwrap_or((stats.sent_bytes, stats.rec_bytes));
+            let up = stats.sent_bytes.saturating_sub(sent) as f64 / elapsed;
+            let down = stats.rec_bytes.saturating_sub(rec) as f64 / elapsed;
+            let total = up + down;
+            rates.insert(interface.name.clone(), total);
+            if total < IDLE_RATE {
+                if self.idle.insert(interface.name.clone()) {
+                    lines.push(format!("{}: idle", interface.name));
+                }
+                continue;
+            }
+            self.idle.remove(&interface.name);
+            let mut line = format!(
+                "{}: down {}, up {}",
+                interface.name,
+                format_rate(down),
+                format_rate(up)
+            );
+            if let Some(before) = self.rates.get(&interface.name) {
+                line.push_str(", ");
+                line.push_str(trend(*before, total));
+            }
+            lines.push(line);
+        }
+        self.rates = rates;
+        self.remember(app);
+        lines
+    }
+
+    fn remember(&mut self, app: &App) {
+        self.moved = app
+            .stats
+            .iter()
+            .map(|(name, stats)| (name.clone(), (stats.sent_bytes, stats.rec_bytes)))
+            .collect();
+        self.idle.retain(|name| {
+            app.net_interfaces
+                .iter()
+                .any(|interface| interface.name == *name)
+        });
+    }
+}
+
+fn trend(before: f64, now: f64) -> &'static str {
+    if now > before * TREND_RATIO {
+        "rising"
+    } else if now * TREND_RATIO < before {
+        "falling"
+    } else {
+        "steady"
+    }
+}
+
+// Alerts read out the moment they fire
+struct SpokenAlerts;
+
+impl AlertSink for SpokenAlerts {
+    fn name(&self) -> &str {
+        "accessible"
+    }
+
+    fn send(&mut self, event: &AlertEvent) -> Result<()> {
+        writeln!(std::io::stdout(), "alert: {}", event.describe())
+            .or_ui(|| "failed to write to stdout")
+    }
+}
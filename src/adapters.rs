@@ -0,0 +1,117 @@
+// What Windows calls an adapter in the network settings, e.g. "Wi-Fi" with
+// the description "Intel(R) Wi-Fi 6 AX201 160MHz"
+#[derive(Debug, Clone)]
+pub struct Adapter {
+    pub alias: String,
+    pub description: String,
+    // IANA ifType, e.g. 6 for Ethernet and 71 for Wi-Fi
+    pub if_type: u32,
+    // false for virtual adapters, whatever their ifType says
+    pub hardware: bool,
+}
+
+// Resolves an interface name, either an alias or a GUID like
+// "{4D36E972-E325-11CE-BFC1-08002BE10318}", to the adapter's friendly names.
+// None elsewhere and for names Windows doesn't know.
+#[cfg(windows)]
+pub fn lookup(name: &str) -> Option<Adapter> {
+    windows::lookup(name)
+}
+
+#[cfg(not(windows))]
+pub fn lookup(_name: &str) -> Option<Adapter> {
+    None
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::Adapter;
+
+    #[repr(C)]
+    struct Guid {
+        data1: u32,
+        data2: u16,
+        data3: u16,
+        data4: [u8; 8],
+    }
+
+    // MIB_IF_ROW2, of which only the LUID going in and the names coming out
+    // are used
+    const ROW_SIZE: usize = 1352;
+    const ALIAS_OFFSET: usize = 28;
+    const DESCRIPTION_OFFSET: usize = 542;
+    const NAME_LEN: usize = 257;
+    const TYPE_OFFSET: usize = 1128;
+    // InterfaceAndOperStatusFlags, HardwareInterface is the lowest bit
+    const FLAGS_OFFSET: usize = 1152;
+
+    #[link(name = "iphlpapi")]
+    extern "system" {
+        fn ConvertInterfaceAliasToLuid(alias: *const u16, luid: *mut u64) -> u32;
+        fn ConvertInterfaceGuidToLuid(guid: *const Guid, luid: *mut u64) -> u32;
+        fn GetIfEntry2(row: *mut u64) -> u32;
+    }
+
+    // "{...}", also as part of a device path like "\DEVICE\TCPIP_{...}"
+    fn parse_guid(name: &str) -> Option<Guid> {
+        let start = name.find('{')?;
+        let end = name[start..].find('}')? + start;
+        let hex: String = name[start + 1..end].chars().filter(|c| *c != '-').collect();
+        if hex.len() != 32 {
+            return None;
+        }
+        let byte = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+        let mut data4 = [0u8; 8];
+        for (i, b) in data4.iter_mut().enumerate() {
+            *b = byte(8 + i)?;
+        }
+        Some(Guid {
+            data1: u32::from_str_radix(&hex[0..8], 16).ok()?,
+            data2: u16::from_str_radix(&hex[8..12], 16).ok()?,
+            data3: u16::from_str_radix(&hex[12..16], 16).ok()?,
+            data4,
+        })
+    }
+
+    fn luid(name: &str) -> Option<u64> {
+        let mut luid = 0u64;
+        let status = match parse_guid(name) {
+            Some(guid) => unsafe { ConvertInterfaceGuidToLuid(&guid, &mut luid) },
+            None => {
+                let alias: Vec<u16> = name.encode_utf16().chain([0]).collect();
+                unsafe { ConvertInterfaceAliasToLuid(alias.as_ptr(), &mut luid) }
+            }
+        };
+        (status == 0).then_some(luid)
+    }
+
+    fn wide_string(row: &[u8], offset: usize) -> String {
+        let units: Vec<u16> = row[offset..offset + NAME_LEN * 2]
+            .chunks_exact(2)
+            .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+            .take_while(|unit| *unit != 0)
+            .collect();
+        String::from_utf16_lossy(&units)
+    }
+
+    pub fn lookup(name: &str) -> Option<Adapter> {
+        // u64s keep the row aligned for the counters at its end
+        let mut row = [0u64; ROW_SIZE / 8];
+        row[0] = luid(name)?;
+        if unsafe { GetIfEntry2(row.as_mut_ptr()) } != 0 {
+            return None;
+        }
+        let bytes: Vec<u8> = row.iter().flat_map(|word| word.to_ne_bytes()).collect();
+        let alias = wide_string(&bytes, ALIAS_OFFSET);
+        Some(Adapter {
+            alias: if alias.is_empty() {
+                name.to_string()
+            } else {
+                alias
+            },
+            description: wide_string(&bytes, DESCRIPTION_OFFSET),
+            if_type: u32::from_ne_bytes(bytes[TYPE_OFFSET..TYPE_OFFSET + 4].try_into().unwrap()),
+            hardware: bytes[FLAGS_OFFSET] & 1 != 0,
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    app::App,
+    iftype::InterfaceType,
+    networks::{create_interface_graph, effective_graph_style, GraphPair, DISPLAY_SAMPLES},
+    plot::Stacked,
+    units::{format_bytes, format_rate},
+};
+
+// Rates and totals summed over every interface except loopback, whose
+// traffic never leaves the machine
+#[derive(Default)]
+pub struct Aggregate {
+    pub interfaces: usize,
+    pub sent_rate: f64,
+    pub rec_rate: f64,
+    pub sent_peak: f64,
+    pub rec_peak: f64,
+    pub sent_total: u64,
+    pub rec_total: u64,
+    // bytes per sample, for the combined graphs
+    pub sent: Vec<u64>,
+    pub rec: Vec<u64>,
+    // bytes sent and received per sample by each interface, in the order
+    // they were first seen, all as long as `sent`
+    pub shares: Vec<(String, Vec<u64>)>,
+}
+
+pub fn toggle_aggregate(app: &mut App) {
+    app.aggregate_panel = !app.aggregate_panel;
+}
+
+// Switches the panel between the TX and RX graphs and the stacked one,
+// showing it if hidden
+pub fn toggle_stacked(app: &mut App) {
+    app.aggregate_stacked = !app.aggregate_stacked;
+    if app.aggregate_stacked {
+        app.aggregate_panel = true;
+    }
+}
+
+pub fn update_aggregate(app: &mut App, elapsed: f64) {
+    let aggregate = &mut app.aggregate;
+    let interfaces = app
+        .net_interfaces
+        .iter()
+        .filter(|interface| interface.kind != InterfaceType::Loopback);
+    let (mut sent, mut rec) = (0, 0);
+    let samples = aggregate.sent.len() + 1;
+    for (_, values) in &mut aggregate.shares {
+        values.push(0);
+    }
+    aggregate.interfaces = 0;
+    aggregate.sent_total = 0;
+    aggregate.rec_total = 0;
+    for interface in interfaces {
+        aggregate.interfaces += 1;
+        sent += interface.sent;
+        rec += interface.rec;
+        aggregate.sent_total += interface.sent_total;
+        aggregate.rec_total += interface.rec_total;
+        let shares = &mut aggregate.shares;
+        let index = match shares.iter().position(|(name, _)| *name == interface.name) {
+            Some(index) => index,
+            None => {
+                shares.push((interface.name.clone(), vec![0; samples]));
+                shares.len() - 1
+            }
+        };
+        if let Some(last) = shares[index].1.last_mut() {
+            *last += interface.sent + interface.rec;
+        }
+    }
+    if elapsed > 0.0 {
+        aggregate.sent_rate = sent as f64 / elapsed;
+        aggregate.rec_rate = rec as f64 / elapsed;
+        aggregate.sent_peak = aggregate.sent_peak.max(aggregate.sent_rate);
+        aggregate.rec_peak = aggregate.rec_peak.max(aggregate.rec_rate);
+    }
+    aggregate.sent.push(sent);
+    aggregate.rec.push(rec);
+    if aggregate.sent.len() > DISPLAY_SAMPLES {
+        aggregate.sent.remove(0);
+        aggregate.rec.remove(0);
+        for (_, values) in &mut aggregate.shares {
+            values.remove(0);
+        }
+    }
+    // interfaces gone or idle for the whole graph drop out of it
+    aggregate
+        .shares
+        .retain(|(_, values)| values.iter().any(|value| *value > 0));
+}
+
+// Summary line plus TX and RX graphs, each `width` columns wide
+pub fn create_aggregate_widgets(app: &App, width: usize) -> (Paragraph<'_>, GraphPair<'_>) {
+    let theme = &app.theme;
+    let aggregate = &app.aggregate;
+    let line = Line::from(format!(
+        "Sent/Recieved: {} / {} (peak {} / {}), total {} / {}",
+        format_rate(aggregate.sent_rate),
+        format_rate(aggregate.rec_rate),
+        format_rate(aggregate.sent_peak),
+        format_rate(aggregate.rec_peak),
+        format_bytes(aggregate.sent_total as f64),
+        format_bytes(aggregate.rec_total as f64)
+    ));
+    let block = Block::default()
+        .title(Span::styled(
+            format!("All interfaces ({})", aggregate.interfaces),
+            theme.title,
+        ))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    let paragraph = Paragraph::new(line).style(theme.text).block(block);
+
+    let graph_style = effective_graph_style(app);
+    let samples = graph_style.samples(width);
+    let tail = |values: &[u64]| values.len().saturating_sub(samples);
+    let graphs = (
+        create_interface_graph(
+            "All TX".to_string(),
+            &aggregate.sent[tail(&aggregate.sent)..],
+            theme.tx_graph,
+            theme.tx_symbols.clone(),
+            graph_style,
+            app.capabilities.unicode,
+            theme,
+        ),
+        create_interface_graph(
+            "All RX".to_string(),
+            &aggregate.rec[tail(&aggregate.rec)..],
+            theme.rx_graph,
+            theme.rx_symbols.clone(),
+            graph_style,
+            app.capabilities.unicode,
+            theme,
+        ),
+    );
+    (paragraph, graphs)
+}
+
+// Every interface's traffic stacked in one graph `width` columns wide, the
+// title giving each one's color and share of the bytes shown
+pub fn create_stacked_graph(app: &App, width: usize) -> Stacked<'_> {
+    let theme = &app.theme;
+    let mut title = vec![Span::styled("All interfaces stacked", theme.title)];
+    let shares: Vec<(&str, &[u64])> = app
+        .aggregate
+        .shares
+        .iter()
+        .map(|(name, values)| (name.as_str(), &values[values.len().saturating_sub(width)..]))
+        .collect();
+    let total: u64 = shares.iter().flat_map(|(_, values)| values.iter()).sum();
+    let layers = shares
+        .iter()
+        .enumerate()
+        .map(|(i, (name, values))| {
+            // neighbours told apart by color, or by pattern without any
+            let configured = app
+                .config
+                .color(name)
+                .map(|color| app.capabilities.color(color));
+            let color = configured.or_else(|| {
+                (!theme.palette.is_empty()).then(|| theme.palette[i % theme.palette.len()])
+            });
+            let (style, symbols) = match color {
+                Some(color) => (theme.tx_graph.fg(color), theme.tx_symbols.clone()),
+                None if i % 2 == 1 => (theme.rx_graph, theme.rx_symbols.clone()),
+                None => (theme.tx_graph, theme.tx_symbols.clone()),
+            };
+            let share = values.iter().sum::<u64>() as f64 * 100.0 / total.max(1) as f64;
+            title.push(Span::styled(format!(" {name} {share:.0}%"), style));
+            (*values, style, symbols)
+        })
+        .collect();
+    let block = Block::default()
+        .title(Line::from(title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Stacked { block, layers }
+}
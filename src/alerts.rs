@@ -0,0 +1,673 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::OpenOptions,
+    io::{self, LineWriter, Write},
+};
+
+use ratatui::{
+    prelude::Constraint,
+    text::Span,
+    widgets::{Block, Borders, Row, Table},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    anomaly::FAN_OUT_RULE,
+    app::App,
+    carrier::read_link,
+    config::save_alert_rule,
+    error::{Error, Result, ResultExt},
+    latency::{JITTER_RULE, LOSS_RULE},
+    theme::Theme,
+    units::{format_bytes, format_clock, format_rate, format_timestamp, parse_bytes},
+};
+
+// A rule from the config, e.g.
+//   [[alerts]]
+//   name = "uplink saturated"
+//   interface = "eth0"
+//   metric = "rx"
+//   above = "10 MB"
+//   for = 30
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AlertRuleConfig {
+    pub name: Option<String>,
+    // every interface when unset
+    pub interface: Option<String>,
+    // "tx", "rx" or "total"
+    pub metric: String,
+    // rate per second the metric has to exceed
+    pub above: String,
+    // seconds the rate has to stay above the threshold before firing
+    #[serde(rename = "for")]
+    pub duration: f64,
+}
+
+impl Default for AlertRuleConfig {
+    fn default() -> Self {
+        AlertRuleConfig {
+            name: None,
+            interface: None,
+            metric: "total".to_string(),
+            above: String::new(),
+            duration: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Tx,
+    Rx,
+    Total,
+}
+
+impl Metric {
+    pub fn from_name(name: &str) -> Option<Metric> {
+        match name {
+            "tx" => Some(Metric::Tx),
+            "rx" => Some(Metric::Rx),
+            "total" => Some(Metric::Total),
+            _ => None,
+        }
+    }
+
+    pub fn value(&self, sent_rate: f64, rec_rate: f64) -> f64 {
+        match self {
+            Metric::Tx => sent_rate,
+            Metric::Rx => rec_rate,
+            Metric::Total => sent_rate + rec_rate,
+        }
+    }
+}
+
+pub struct AlertRule {
+    pub name: String,
+    pub interface: Option<String>,
+    pub metric: Metric,
+    pub threshold: f64,
+    pub duration: f64,
+}
+
+impl AlertRule {
+    pub fn from_config(config: &AlertRuleConfig) -> Result<AlertRule> {
+        let metric = Metric::from_name(&config.metric).ok_or_else(|| {
+            Error::config(format!(
+                "unknown alert metric \"{}\", expected tx, rx or total",
+                config.metric
+            ))
+        })?;
+        let threshold = parse_bytes(&config.above).ok_or_else(|| {
+            Error::config(format!("invalid alert threshold \"{}\"", config.above))
+        })?;
+        let name = config.name.clone().unwrap_or_else(|| {
+            let interface = config.interface.as_deref().unwrap_or("any");
+            format!("{interface} {} above {}", config.metric, config.above)
+        });
+        Ok(AlertRule {
+            name,
+            interface: config.interface.clone(),
+            metric,
+            threshold: threshold as f64,
+            duration: config.duration.max(0.0),
+        })
+    }
+
+    fn applies_to(&self, interface: &str) -> bool {
+        self.interface
+            .as_deref()
+            .is_none_or(|name| name == interface)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AlertState {
+    Fired,
+    Resolved,
+    // carrier lost or regained, logged next to the threshold alerts
+    LinkDown,
+    LinkUp,
+    // flagged by the anomaly heuristics, never resolved
+    Anomaly,
+}
+
+impl AlertState {
+    pub const ALL: [AlertState; 5] = [
+        AlertState::Fired,
+        AlertState::Resolved,
+        AlertState::LinkDown,
+        AlertState::LinkUp,
+        AlertState::Anomaly,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertState::Fired => "fired",
+            AlertState::Resolved => "resolved",
+            AlertState::LinkDown => "link down",
+            AlertState::LinkUp => "link up",
+            AlertState::Anomaly => "anomaly",
+        }
+    }
+
+    // syslog severity: error, warning or notice
+    pub fn priority(&self) -> u8 {
+        match self {
+            AlertState::LinkDown => 3,
+            AlertState::Fired | AlertState::Anomaly => 4,
+            AlertState::Resolved | AlertState::LinkUp => 5,
+        }
+    }
+
+    // the event ends an earlier one, whose time is the event's `since`
+    fn ends(&self) -> bool {
+        matches!(self, AlertState::Resolved | AlertState::LinkUp)
+    }
+}
+
+#[derive(Clone)]
+pub struct AlertEvent {
+    pub rule: String,
+    pub interface: String,
+    pub state: AlertState,
+    pub value: f64,
+    pub threshold: f64,
+    // unix time of the sample that fired or resolved the alert
+    pub time: f64,
+    // when fired, the time the value went above the threshold, when
+    // resolved, the time the alert fired, when the link came back, the
+    // time it went down
+    pub since: f64,
+}
+
+impl AlertEvent {
+    // e.g. "uplink saturated fired on eth0: 12.0 MB/s above 10.0 MB/s"
+    pub fn describe(&self) -> String {
+        let lasted = self.time - self.since;
+        match self.state {
+            AlertState::Fired => {
+                let (value, threshold) = self.values();
+                format!(
+                    "{} fired on {}: {value} above {threshold}",
+                    self.rule, self.interface
+                )
+            }
+            AlertState::Resolved => format!(
+                "{} resolved on {} after {lasted:.0}s",
+                self.rule, self.interface
+            ),
+            AlertState::LinkDown => format!("{} link down", self.interface),
+            AlertState::LinkUp => format!("{} link up after {lasted:.0}s", self.interface),
+            AlertState::Anomaly if self.rule == FAN_OUT_RULE => format!(
+                "fan-out: {:.0} new remote hosts within a minute, usually {:.1}",
+                self.value, self.threshold
+            ),
+            AlertState::Anomaly => format!(
+                "{} on {}: {} for {lasted:.0}s, usually {}",
+                self.rule,
+                self.interface,
+                format_rate(self.value),
+                format_rate(self.threshold)
+            ),
+        }
+    }
+
+    // One line of JSON, as the JSON output and hooks get it
+    pub fn to_json(&self) -> Result<String> {
+        let resolved = self.state.ends();
+        let record = JsonEvent {
+            rule: &self.rule,
+            interface: &self.interface,
+            state: self.state.name(),
+            value: self.value,
+            threshold: self.threshold,
+            time: self.time,
+            above_since: (self.state == AlertState::Fired).then_some(self.since),
+            fired_at: resolved.then_some(self.since),
+            duration: resolved.then_some(self.time - self.since),
+        };
+        serde_json::to_string(&record).or_export(|| "failed to encode alert")
+    }
+
+    // value and threshold as shown on the alerts screen
+    fn values(&self) -> (String, String) {
+        match self.state {
+            AlertState::LinkDown | AlertState::LinkUp => ("-".to_string(), "-".to_string()),
+            AlertState::Anomaly if self.rule == FAN_OUT_RULE => (
+                format!("{:.0} hosts", self.value),
+                format!("{:.1} usual", self.threshold),
+            ),
+            _ if self.rule == LOSS_RULE => (
+                format!("{:.1}%", self.value),
+                format!("{}%", self.threshold),
+            ),
+            _ if self.rule == JITTER_RULE => (
+                format!("{:.1} ms", self.value),
+                format!("{} ms", self.threshold),
+            ),
+            _ => (format_rate(self.value), format_rate(self.threshold)),
+        }
+    }
+}
+
+// Name the link events are logged under in place of a rule
+const LINK_RULE: &str = "link";
+
+#[derive(Default)]
+struct RuleState {
+    above_since: Option<f64>,
+    fired_at: Option<f64>,
+    firing: bool,
+}
+
+// Events kept for the alerts screen
+const EVENT_HISTORY: usize = 500;
+
+// Evaluates rules sample by sample, so live data and recorded samples go
+// through exactly the same logic
+#[derive(Default)]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    states: HashMap<(usize, String), RuleState>,
+    // carrier of each interface and since when
+    links: HashMap<String, (bool, f64)>,
+    pub events: VecDeque<AlertEvent>,
+    // alerts fired since start, in all and by rule name, the history above
+    // keeps only the latest
+    pub fired: usize,
+    fired_by_rule: HashMap<String, usize>,
+}
+
+impl AlertEngine {
+    pub fn from_config(rules: &[AlertRuleConfig]) -> Result<AlertEngine> {
+        let rules = rules
+            .iter()
+            .map(AlertRule::from_config)
+            .collect::<Result<Vec<AlertRule>>>()?;
+        Ok(AlertEngine {
+            rules,
+            ..Default::default()
+        })
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    // `index` is the rule's position in the config. Alerts already firing
+    // stay firing until the new threshold resolves them.
+    pub fn set_rule(&mut self, index: usize, rule: AlertRule) {
+        match self.rules.get_mut(index) {
+            Some(existing) => *existing = rule,
+            None => self.rules.push(rule),
+        }
+    }
+
+    pub fn evaluate(
+        &mut self,
+        time: f64,
+        interface: &str,
+        sent_rate: f64,
+        rec_rate: f64,
+    ) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !rule.applies_to(interface) {
+                continue;
+            }
+            let value = rule.metric.value(sent_rate, rec_rate);
+            let state = self.states.entry((i, interface.to_string())).or_default();
+            let state_change = if value > rule.threshold {
+                let since = *state.above_since.get_or_insert(time);
+                (!state.firing && time - since >= rule.duration)
+                    .then_some((AlertState::Fired, since))
+            } else {
+                state.above_since = None;
+                state
+                    .firing
+                    .then(|| (AlertState::Resolved, state.fired_at.unwrap_or(time)))
+            };
+            if let Some((change, since)) = state_change {
+                state.firing = change == AlertState::Fired;
+                state.fired_at = state.firing.then_some(time);
+                events.push(AlertEvent {
+                    rule: rule.name.to_string(),
+                    interface: interface.to_string(),
+                    state: change,
+                    value,
+                    threshold: rule.threshold,
+                    time,
+                    since,
+                });
+            }
+        }
+        for event in &events {
+            self.record(event);
+        }
+        events
+    }
+
+    pub fn record(&mut self, event: &AlertEvent) {
+        if event.state == AlertState::Fired {
+            self.fired += 1;
+            *self.fired_by_rule.entry(event.rule.clone()).or_default() += 1;
+        }
+        if self.events.len() == EVENT_HISTORY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event.clone());
+    }
+
+    // An event when the carrier changed since the last call, the first
+    // state seen for an interface is only remembered
+    pub fn link(&mut self, time: f64, interface: &str, up: bool) -> Option<AlertEvent> {
+        let Some((was_up, since)) = self.links.get(interface).copied() else {
+            self.links.insert(interface.to_string(), (up, time));
+            return None;
+        };
+        if was_up == up {
+            return None;
+        }
+        self.links.insert(interface.to_string(), (up, time));
+        let event = AlertEvent {
+            rule: LINK_RULE.to_string(),
+            interface: interface.to_string(),
+            state: match up {
+                true => AlertState::LinkUp,
+                false => AlertState::LinkDown,
+            },
+            value: 0.0,
+            threshold: 0.0,
+            time,
+            since: if up { since } else { time },
+        };
+        self.record(&event);
+        Some(event)
+    }
+
+    // How often the rule named `rule` fired since start
+    pub fn fired_count(&self, rule: &str) -> usize {
+        self.fired_by_rule.get(rule).copied().unwrap_or(0)
+    }
+
+    pub fn active(&self) -> usize {
+        self.states.values().filter(|state| state.firing).count()
+    }
+
+    // names of the rules currently firing for an interface
+    pub fn firing(&self, interface: &str) -> Vec<&str> {
+        self.states
+            .iter()
+            .filter(|((_, name), state)| state.firing && name == interface)
+            .map(|((i, _), _)| self.rules[*i].name.as_str())
+            .collect()
+    }
+}
+
+// Receives alert events as they happen, independent of what the UI shows
+pub trait AlertSink {
+    fn name(&self) -> &str;
+    fn send(&mut self, event: &AlertEvent) -> Result<()>;
+}
+
+#[derive(Serialize)]
+struct JsonEvent<'a> {
+    rule: &'a str,
+    interface: &'a str,
+    state: &'a str,
+    value: f64,
+    threshold: f64,
+    time: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    above_since: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fired_at: Option<f64>,
+    // seconds the alert was firing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<f64>,
+}
+
+// One JSON object per line, written to a file or stdout
+pub struct JsonLinesSink {
+    writer: LineWriter<Box<dyn Write>>,
+}
+
+impl JsonLinesSink {
+    pub fn open(path: &str) -> Result<JsonLinesSink> {
+        let writer: Box<dyn Write> = if path == "-" {
+            Box::new(io::stdout())
+        } else {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .or_export(|| format!("failed to open alert output {path}"))?;
+            Box::new(file)
+        };
+        Ok(JsonLinesSink {
+            writer: LineWriter::new(writer),
+        })
+    }
+}
+
+impl AlertSink for JsonLinesSink {
+    fn name(&self) -> &str {
+        "JSON"
+    }
+
+    fn send(&mut self, event: &AlertEvent) -> Result<()> {
+        writeln!(self.writer, "{}", event.to_json()?).or_export(|| "failed to write alert")?;
+        Ok(())
+    }
+}
+
+// `time` is when the sample was taken
+pub fn update_alerts(app: &mut App, time: f64, elapsed: f64) {
+    if elapsed <= 0.0 {
+        return;
+    }
+    let mut events: Vec<AlertEvent> = app
+        .all_interfaces
+        .iter()
+        .flat_map(|interface| {
+            app.alerts.evaluate(
+                time,
+                &interface.name,
+                interface.sent as f64 / elapsed,
+                interface.rec as f64 / elapsed,
+            )
+        })
+        .collect();
+    // sysfs describes this machine, not the one a remote session watches or
+    // a recording was made on
+    if !app.is_remote() {
+        for interface in &app.all_interfaces {
+            if let Some(up) = read_link(&interface.name) {
+                events.extend(app.alerts.link(time, &interface.name, up));
+            }
+        }
+    }
+    dispatch_alerts(app, &events);
+}
+
+// Hands events to every alert output, the last failure is shown in the
+// status line
+pub fn dispatch_alerts(app: &mut App, events: &[AlertEvent]) {
+    for sink in &mut app.alert_sinks {
+        for event in events {
+            if let Err(e) = sink.send(event) {
+                app.alert_sink_error = Some(format!("{} alert output failed: {e:#}", sink.name()));
+            }
+        }
+    }
+}
+
+// e.g. "rx 10 MB for 30", the metric defaults to total and the duration to
+// the one the rule already has
+fn parse_threshold(text: &str) -> Result<(String, String, Option<f64>)> {
+    let invalid = || {
+        Error::config(format!(
+            "invalid threshold \"{text}\", expected e.g. rx 10 MB for 30"
+        ))
+    };
+    let text = text.trim();
+    let (text, duration) = match text.rsplit_once(" for ") {
+        Some((text, duration)) => {
+            let duration: f64 = duration.trim().parse().map_err(|_| invalid())?;
+            (text.trim(), Some(duration))
+        }
+        None => (text, None),
+    };
+    let (metric, above) = match text.split_once(' ') {
+        Some((metric @ ("tx" | "rx" | "total"), above)) => (metric, above.trim()),
+        _ => ("total", text),
+    };
+    if parse_bytes(above).is_none() {
+        return Err(invalid());
+    }
+    Ok((metric.to_string(), above.to_string(), duration))
+}
+
+// The dialog starts at the selected interface's peak so far, in whichever
+// direction peaked higher unless a rule for it exists already
+pub fn current_threshold(app: &App) -> String {
+    let Some(interface) = app.net_interfaces.get(app.selected) else {
+        return String::new();
+    };
+    let Some(stats) = app.stats.get(&interface.name) else {
+        return String::new();
+    };
+    let existing = app
+        .config
+        .alerts
+        .iter()
+        .find(|rule| rule.interface.as_deref() == Some(&interface.name));
+    let metric = match existing {
+        Some(rule) => rule.metric.as_str(),
+        None if stats.rec.peak >= stats.sent.peak => "rx",
+        None => "tx",
+    };
+    let peak = Metric::from_name(metric)
+        .map(|metric| metric.value(stats.sent.peak, stats.rec.peak))
+        .unwrap_or_default();
+    format!("{metric} {}", format_bytes(peak))
+}
+
+// Add or adjust the selected interface's rule for the metric and write it
+// back to the config file
+pub fn set_threshold(app: &mut App, text: String) {
+    let Some(interface) = app.net_interfaces.get(app.selected) else {
+        return;
+    };
+    let name = interface.name.clone();
+    let result = parse_threshold(&text).and_then(|(metric, above, duration)| {
+        let index = app.config.alerts.iter().position(|rule| {
+            rule.interface.as_deref() == Some(name.as_str()) && rule.metric == metric
+        });
+        let mut rule = match index {
+            Some(i) => app.config.alerts[i].clone(),
+            None => AlertRuleConfig {
+                interface: Some(name),
+                metric,
+                ..Default::default()
+            },
+        };
+        rule.above = above;
+        if let Some(duration) = duration {
+            rule.duration = duration;
+        }
+        let index = index.unwrap_or(app.config.alerts.len());
+        app.alerts.set_rule(index, AlertRule::from_config(&rule)?);
+        match app.config.alerts.get_mut(index) {
+            Some(existing) => *existing = rule.clone(),
+            None => app.config.alerts.push(rule.clone()),
+        }
+        save_alert_rule(&rule)
+    });
+    app.settings_error = result.err().map(|e| format!("{e:#}"));
+}
+
+const ALERT_WIDTHS: [Constraint; 6] = [
+    Constraint::Length(10),
+    Constraint::Length(9),
+    Constraint::Percentage(30),
+    Constraint::Length(12),
+    Constraint::Length(12),
+    Constraint::Length(12),
+];
+
+// with the recording's date in front of the time
+const REPLAY_WIDTHS: [Constraint; 6] = [
+    Constraint::Length(20),
+    Constraint::Length(9),
+    Constraint::Percentage(30),
+    Constraint::Length(12),
+    Constraint::Length(12),
+    Constraint::Length(12),
+];
+
+// `replaying` marks the events as the recording's, timed by when they
+// happened in it, only --alerts-json is sent those
+pub fn create_alerts_table<'a>(engine: &AlertEngine, theme: &Theme, replaying: bool) -> Table<'a> {
+    let rows: Vec<Row> = engine
+        .events
+        .iter()
+        .rev()
+        .map(|event| {
+            let style = match event.state {
+                AlertState::Fired | AlertState::LinkDown | AlertState::Anomaly => theme.alert,
+                AlertState::Resolved | AlertState::LinkUp => theme.text,
+            };
+            let (value, threshold) = event.values();
+            // a recording may well be from another day
+            let time = match replaying {
+                true => format_timestamp(event.time),
+                false => format_clock(event.time),
+            };
+            Row::new(vec![
+                time,
+                event.state.name().to_string(),
+                event.rule.to_string(),
+                event.interface.to_string(),
+                value,
+                threshold,
+            ])
+            .style(style)
+        })
+        .collect();
+    let mut title = if engine.rules.is_empty() {
+        "Alerts (no rules configured)".to_string()
+    } else {
+        format!(
+            "Alerts ({} rules, {} firing)",
+            engine.rules.len(),
+            engine.active()
+        )
+    };
+    if replaying {
+        title.push_str(", replayed");
+    }
+    let block = Block::default()
+        .title(Span::styled(title, theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Table::new(rows)
+        .header(
+            Row::new(vec![
+                "Time",
+                "State",
+                "Rule",
+                "Interface",
+                "Value",
+                "Threshold",
+            ])
+            .style(theme.title),
+        )
+        .style(theme.text)
+        .block(block)
+        .widths(match replaying {
+            true => &REPLAY_WIDTHS,
+            false => &ALERT_WIDTHS,
+        })
+}
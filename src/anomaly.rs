@@ -0,0 +1,246 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::IpAddr,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    alerts::{dispatch_alerts, AlertEvent, AlertState},
+    app::App,
+    error::{Error, Result},
+    history::Resolution,
+    sockdiag::SocketBytes,
+    units::parse_bytes,
+};
+
+// Heuristics that flag traffic unlike what the host or an interface usually
+// does, raised as alert events. On by default, e.g.
+//   [anomalies]
+//   fan_out = 50
+//   idle_rate = "4 KB"
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    pub enabled: bool,
+    // fewest new remote hosts within a minute that count as a fan-out
+    pub fan_out: usize,
+    // hourly average below which an interface counts as normally idle
+    pub idle_rate: String,
+    // rate an idle interface has to sustain for a minute to be flagged
+    pub active_rate: String,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            enabled: true,
+            fan_out: 20,
+            idle_rate: "1 KB".to_string(),
+            active_rate: "100 KB".to_string(),
+        }
+    }
+}
+
+impl AnomalyConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, rate) in [
+            ("idle_rate", &self.idle_rate),
+            ("active_rate", &self.active_rate),
+        ] {
+            parse_bytes(rate)
+                .ok_or_else(|| Error::config(format!("invalid anomalies {name} \"{rate}\"")))?;
+        }
+        if self.fan_out == 0 {
+            return Err(Error::config("anomalies fan_out must be above 0"));
+        }
+        Ok(())
+    }
+}
+
+// Names the anomalies are logged under in place of a rule
+pub const FAN_OUT_RULE: &str = "fan-out";
+pub const IDLE_RULE: &str = "idle traffic";
+// Fan-outs aren't tied to one interface
+pub const ALL_INTERFACES: &str = "all";
+
+// Seconds of new hosts counted together, and that an idle interface has to
+// stay busy
+const WINDOW: f64 = 60.0;
+// everything is new at first, the fan-out baseline needs a few minutes
+const WARMUP: f64 = 300.0;
+// a fan-out this many times the usual rate of new hosts is flagged
+const FAN_OUT_FACTOR: f64 = 4.0;
+// seconds before the same anomaly is raised again
+const COOLDOWN: f64 = 600.0;
+// complete hours of history before an interface can count as idle
+const MIN_HOURS: usize = 6;
+const KNOWN_HOSTS: usize = 65536;
+
+#[derive(Default)]
+pub struct AnomalyDetector {
+    known: HashSet<IpAddr>,
+    // when each host seen for the first time within the window was contacted
+    new_hosts: VecDeque<f64>,
+    started: Option<f64>,
+    // new hosts per minute, smoothed
+    baseline: f64,
+    last_minute: f64,
+    busy_since: HashMap<String, f64>,
+    // when each anomaly was last raised, by rule and interface
+    raised: HashMap<(&'static str, String), f64>,
+}
+
+impl AnomalyDetector {
+    fn cooled_down(&self, rule: &'static str, interface: &str, time: f64) -> bool {
+        self.raised
+            .get(&(rule, interface.to_string()))
+            .is_none_or(|raised| time - raised >= COOLDOWN)
+    }
+
+    fn raise(&mut self, event: &AlertEvent) {
+        let rule = if event.rule == FAN_OUT_RULE {
+            FAN_OUT_RULE
+        } else {
+            IDLE_RULE
+        };
+        self.raised
+            .insert((rule, event.interface.to_string()), event.time);
+    }
+
+    // Counts remote hosts not contacted before this session, loopback and
+    // listening sockets aside
+    fn fan_out(
+        &mut self,
+        time: f64,
+        sockets: &[SocketBytes],
+        minimum: usize,
+    ) -> Option<AlertEvent> {
+        let remotes = sockets
+            .iter()
+            .map(|socket| socket.remote.ip())
+            .filter(|ip| !ip.is_loopback() && !ip.is_unspecified());
+        let started = match self.started {
+            Some(started) if self.known.len() < KNOWN_HOSTS => started,
+            // the hosts already connected are only remembered
+            _ => {
+                self.known = remotes.collect();
+                self.started.get_or_insert(time);
+                self.last_minute = time;
+                return None;
+            }
+        };
+        for ip in remotes {
+            if self.known.insert(ip) {
+                self.new_hosts.push_back(time);
+            }
+        }
+        while self
+            .new_hosts
+            .front()
+            .is_some_and(|first| time - first > WINDOW)
+        {
+            self.new_hosts.pop_front();
+        }
+        let count = self.new_hosts.len();
+        if time - self.last_minute >= WINDOW {
+            self.last_minute = time;
+            self.baseline = if time - started < WINDOW * 2.0 {
+                count as f64
+            } else {
+                self.baseline * 0.9 + count as f64 * 0.1
+            };
+        }
+        let threshold = (minimum as f64).max(self.baseline * FAN_OUT_FACTOR);
+        let flagged = time - started >= WARMUP
+            && count as f64 >= threshold
+            && self.cooled_down(FAN_OUT_RULE, ALL_INTERFACES, time);
+        flagged.then(|| AlertEvent {
+            rule: FAN_OUT_RULE.to_string(),
+            interface: ALL_INTERFACES.to_string(),
+            state: AlertState::Anomaly,
+            value: count as f64,
+            threshold: self.baseline,
+            time,
+            since: self.new_hosts.front().copied().unwrap_or(time),
+        })
+    }
+}
+
+// Mean rate over the complete hours of history, None while there are too
+// few of them
+fn hourly_baseline(app: &App, name: &str) -> Option<f64> {
+    let buckets = app.history.get(name)?.buckets(Resolution::Hour)?;
+    // the last hour is still being filled
+    let complete = buckets.len().checked_sub(1)?;
+    if complete < MIN_HOURS {
+        return None;
+    }
+    let (bytes, elapsed) = buckets
+        .iter()
+        .take(complete)
+        .fold((0u64, 0.0), |(bytes, elapsed), bucket| {
+            (bytes + bucket.sent + bucket.rec, elapsed + bucket.elapsed)
+        });
+    (elapsed > 0.0).then(|| bytes as f64 / elapsed)
+}
+
+pub fn update_anomalies(
+    app: &mut App,
+    sockets: &Result<Vec<SocketBytes>>,
+    time: f64,
+    elapsed: f64,
+) {
+    let config = &app.config.anomalies;
+    if !config.enabled || elapsed <= 0.0 {
+        return;
+    }
+    let idle_rate = parse_bytes(&config.idle_rate).unwrap_or_default() as f64;
+    let active_rate = parse_bytes(&config.active_rate).unwrap_or_default() as f64;
+    let mut events = Vec::new();
+    if let Ok(sockets) = sockets {
+        events.extend(app.anomalies.fan_out(time, sockets, config.fan_out));
+    }
+    for interface in &app.all_interfaces {
+        let name = &interface.name;
+        let rate = (interface.sent + interface.rec) as f64 / elapsed;
+        if rate < active_rate {
+            app.anomalies.busy_since.remove(name);
+            continue;
+        }
+        let since = *app
+            .anomalies
+            .busy_since
+            .entry(name.to_string())
+            .or_insert(time);
+        let Some(usual) = hourly_baseline(app, name) else {
+            continue;
+        };
+        // once per busy stretch
+        let raised = app
+            .anomalies
+            .raised
+            .get(&(IDLE_RULE, name.to_string()))
+            .is_some_and(|raised| *raised >= since);
+        if usual < idle_rate
+            && time - since >= WINDOW
+            && !raised
+            && app.anomalies.cooled_down(IDLE_RULE, name, time)
+        {
+            events.push(AlertEvent {
+                rule: IDLE_RULE.to_string(),
+                interface: name.to_string(),
+                state: AlertState::Anomaly,
+                value: rate,
+                threshold: usual,
+                time,
+                since,
+            });
+        }
+    }
+    for event in &events {
+        app.anomalies.raise(event);
+        app.alerts.record(event);
+    }
+    dispatch_alerts(app, &events);
+}
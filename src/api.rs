@@ -0,0 +1,108 @@
+use std::{env, path::PathBuf, sync::Arc};
+
+use tokio::sync::broadcast;
+
+use crate::{
+    collector::{Snapshot, Subscription},
+    error::{Error, Result},
+    wire,
+};
+
+// Local API secondary instances attach to. Every collector snapshot is
+// streamed over a unix socket as a line of the JSON sample format `net-stat
+// serve` speaks, see wire.rs. Unix only, elsewhere there is nothing to
+// attach to.
+pub struct Api {
+    path: PathBuf,
+    sender: broadcast::Sender<Arc<String>>,
+}
+
+pub fn socket_path() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("net-stat.sock"),
+        #[cfg(unix)]
+        None => env::temp_dir().join(format!("net-stat-{}.sock", unsafe { libc::getuid() })),
+        #[cfg(not(unix))]
+        None => env::temp_dir().join("net-stat.sock"),
+    }
+}
+
+impl Api {
+    // Fails when another primary instance already serves the socket
+    #[cfg(unix)]
+    pub fn start() -> Result<Api> {
+        use std::os::unix::net::UnixStream as StdUnixStream;
+
+        use tokio::{io::AsyncWriteExt, net::UnixListener, sync::broadcast::error::RecvError};
+
+        use crate::error::ResultExt;
+
+        let path = socket_path();
+        if StdUnixStream::connect(&path).is_ok() {
+            return Err(Error::ui(format!(
+                "another instance is serving {}",
+                path.display()
+            )));
+        }
+        // left behind by an instance that didn't shut down cleanly
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)
+            .or_ui(|| format!("failed to listen on {}", path.display()))?;
+        let (sender, _) = broadcast::channel(16);
+        let clients = sender.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut messages = clients.subscribe();
+                tokio::spawn(async move {
+                    loop {
+                        let message: Arc<String> = match messages.recv().await {
+                            Ok(message) => message,
+                            // a slow client just misses snapshots
+                            Err(RecvError::Lagged(_)) => continue,
+                            Err(RecvError::Closed) => break,
+                        };
+                        if stream.write_all(message.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        Ok(Api { path, sender })
+    }
+
+    #[cfg(not(unix))]
+    pub fn start() -> Result<Api> {
+        Err(Error::ui("the local API needs unix sockets"))
+    }
+
+    pub fn publish(&self, snapshot: &Snapshot) {
+        publish(&self.sender, snapshot);
+    }
+
+    // Streams the pipeline's samples to attached clients from a task of its
+    // own, so a slow socket never holds up the UI
+    pub fn serve(&self, mut subscription: Subscription) {
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            while let Some(snapshot) = subscription.recv().await {
+                publish(&sender, &snapshot);
+            }
+        });
+    }
+}
+
+fn publish(sender: &broadcast::Sender<Arc<String>>, snapshot: &Snapshot) {
+    if sender.receiver_count() == 0 {
+        return;
+    }
+    if let Ok(line) = wire::encode(snapshot) {
+        let _ = sender.send(Arc::new(line + "\n"));
+    }
+}
+
+impl Drop for Api {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -1,66 +1,1338 @@
-use std::{collections::HashMap, iter::zip};
+use std::{
+    collections::{HashMap, HashSet},
+    iter::zip,
+    time::Instant,
+};
 
-use ratatui::prelude::{Constraint, Direction, Layout, Margin, Rect};
-use sysinfo::{System, SystemExt};
+use ratatui::{
+    prelude::{Alignment, Backend, Constraint, Direction, Layout, Margin, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Tabs},
+};
 
 use crate::{
-    networks::{to_network_stat_widgets, update_graph_data, update_net_data, InterfaceData},
+    aggregate::{
+        create_aggregate_widgets, create_stacked_graph, toggle_aggregate, toggle_stacked,
+        update_aggregate, Aggregate,
+    },
+    alerts::{create_alerts_table, set_threshold, update_alerts, AlertEngine, AlertSink},
+    anomaly::{update_anomalies, AnomalyDetector},
+    baseline::{toggle_baseline, update_baseline, BaselineMode},
+    bufferbloat::{
+        create_bufferbloat_paragraph, toggle_bufferbloat, update_bufferbloat, Bufferbloat,
+        BUFFERBLOAT_HEIGHT,
+    },
+    canary::{update_canary, Canary},
+    capabilities::Capabilities,
+    carrier::{run_cable_test, update_cable_test, CableTests},
+    collector::Pipeline,
+    compare::{compared, create_compare_widgets, toggle_mark, Compare},
+    config::{self, Config},
+    connections::{
+        create_connections_table, create_processes_table, update_connection_rates,
+        update_connections, Connection, ConnectionRates, ConnectionSort, ProcessSockets,
+    },
+    conntrack::{create_conntrack_paragraph, update_conntrack, Conntrack, CONNTRACK_HEIGHT},
+    containers::{create_containers_table, update_containers, Containers},
+    damage::{update_damage, Damage},
+    derived::{create_derived_graphs, parse_derived, update_derived, DerivedSeries},
+    error::Result,
+    export::{export_samples, ExportWorker, Exporter},
+    focus::{toggle_auto_focus, update_auto_focus, AutoFocus},
+    forecast::{update_forecasts, Forecast},
+    geoip::GeoIp,
+    histogram::create_histogram,
+    history::{
+        save_history, unix_time, update_history, HistoryStore, InterfaceHistory, Resolution,
+    },
+    hotplug::{create_departed_table, departed_spark_width, Hotplug},
+    iftype::toggle_physical_only,
+    input::{edit_prompt, expire_prefix, open_prompt, InputState, Prompt},
+    keymap::{build_keymap, Keymap},
+    latency::{
+        create_latency_charts, create_latency_table, start_latency, update_latency, LatencyProbe,
+        LatencySeries,
+    },
+    layout::{
+        columns, create_compact_table, cycle_layout, overflow_text, sparkline_width, LayoutMode,
+        COMPACT_HEADER_HEIGHT,
+    },
+    macros::{parse_macros, Macros},
+    networks::{
+        is_overlaid, search_interfaces, to_network_stat_widgets, to_overlay_graphs,
+        to_packet_graphs, update_display_data, update_graph_data, update_net_data, DisplayData,
+        Graph, GraphData, GraphPair, InterfaceData,
+    },
+    notes::NoteStore,
+    picker::{create_picker, edit_picker, update_picker, Picker},
+    plot::{GraphStyle, OverlayMode},
+    quota::{save_quotas, update_quotas, Quotas},
+    replay::{Recorder, ReplayControl},
+    route::{create_route_paragraph, update_route, RouteLookup},
+    scale::{toggle_scale_lock, ScaleLock},
+    share::create_share_paragraphs,
+    slo::{create_slo_table, update_slo, Slo},
+    snapshot::save_snapshot,
+    sockstates::{
+        create_socket_strip, socket_strip_height, toggle_socket_strip, update_socket_states,
+        SocketStates,
+    },
+    stats::{update_stats, InterfaceStats},
+    sysload::{
+        create_system_strip, system_strip_height, toggle_system_strip, update_system_load,
+        SystemLoad,
+    },
+    tcp::{create_tcp_graphs, create_tcp_paragraph, update_tcp, TcpHealth, TCP_SUMMARY_HEIGHT},
+    theme::Theme,
+    throughput::{toggle_speed_test, update_speed_test, SpeedTest},
+    traceroute::{create_traceroute_table, start_traceroute, update_traceroute, Traceroute},
+    tui::TICK_RATE,
+    units::format_clock,
+    users::{create_users_table, update_users, UserTracker},
+    wol::{create_hosts_table, update_hosts, wake_selected, Hosts},
+    zoom::{create_extended_stats, toggle_zoom},
     Action, Frame,
 };
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Overview,
+    Connections,
+    Processes,
+    Users,
+    Alerts,
+    Hosts,
+    Share,
+    Latency,
+    Slo,
+    Tcp,
+    Containers,
+}
+
+impl Screen {
+    pub const ALL: [Screen; 11] = [
+        Screen::Overview,
+        Screen::Connections,
+        Screen::Processes,
+        Screen::Users,
+        Screen::Alerts,
+        Screen::Hosts,
+        Screen::Share,
+        Screen::Latency,
+        Screen::Slo,
+        Screen::Tcp,
+        Screen::Containers,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Screen::Overview => "Overview",
+            Screen::Connections => "Connections",
+            Screen::Processes => "Processes",
+            Screen::Users => "Users",
+            Screen::Share => "Share",
+            Screen::Alerts => "Alerts",
+            Screen::Hosts => "Hosts",
+            Screen::Latency => "Latency",
+            Screen::Slo => "SLO",
+            Screen::Tcp => "TCP",
+            Screen::Containers => "Containers",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        Screen::ALL
+            .iter()
+            .position(|screen| screen == self)
+            .unwrap()
+    }
+
+    pub fn next(&self) -> Screen {
+        Screen::ALL[(self.index() + 1) % Screen::ALL.len()]
+    }
+
+    pub fn prev(&self) -> Screen {
+        Screen::ALL[(self.index() + Screen::ALL.len() - 1) % Screen::ALL.len()]
+    }
+}
+
 pub struct App {
     pub should_quit: bool,
-    pub sys: System,
+    pub detached: bool,
     pub net_interfaces: Vec<InterfaceData>,
-    pub net_interface_graphs: HashMap<String, Vec<u64>>,
+    pub net_interface_graphs: HashMap<String, GraphData>,
+    // series as shown in the graphs, derived from `net_interface_graphs`
+    pub net_interface_display: HashMap<String, DisplayData>,
+    pub baseline: BaselineMode,
+    pub smoothed: bool,
+    // render graphs as charts with axes instead of sparklines
+    pub chart: bool,
+    pub layout: LayoutMode,
+    pub graph_style: GraphStyle,
+    pub overlay: OverlayMode,
+    pub zoomed: bool,
+    pub compare: Compare,
+    pub packet_graphs: bool,
+    pub aggregate_panel: bool,
+    pub aggregate_stacked: bool,
+    pub aggregate: Aggregate,
+    // series from the [derived] table, graphed under the aggregate panel
+    pub derived: Vec<DerivedSeries>,
+    // hide loopback, virtual and unrecognised interfaces
+    pub physical_only: bool,
+    // CPU and memory strip under the tabs
+    pub system_strip: bool,
+    pub system_load: SystemLoad,
+    // TCP sockets by state, on a strip under the tabs
+    pub socket_strip: bool,
+    pub socket_states: SocketStates,
+    // interfaces removed this session
+    pub hotplug: Hotplug,
+    // the last sample's interfaces before physical_only and the search
+    pub all_interfaces: Vec<InterfaceData>,
+    // name fragment typed after /, only matching interfaces are shown
+    pub search: Option<String>,
+    // whether samples since the last frame changed anything shown
+    pub damage: Damage,
+    // the --pick checklist, shown instead of the screens while open
+    pub picker: Option<Picker>,
+    pub auto_focus: AutoFocus,
+    // failure writing a runtime setting back to the config file
+    pub settings_error: Option<String>,
+    // where the last snapshot or graph image went, or why saving failed
+    pub file_status: Option<String>,
+    // interfaces whose graphs use a logarithmic scale
+    pub log_scale: HashSet<String>,
+    // graphs held at a fixed scale, by interface name
+    pub scale_locks: HashMap<String, ScaleLock>,
+    // minute, five minute and hourly aggregates per interface
+    pub history: HashMap<String, InterfaceHistory>,
+    // time range shown in the graphs
+    pub resolution: Resolution,
+    // graph bytes transferred since `cumulative_since` instead of rates
+    pub cumulative: bool,
+    // interfaces graphing totals while the others show rates
+    pub cumulative_interfaces: HashSet<String>,
+    // unix time of the last cumulative reset
+    pub cumulative_since: f64,
+    // set when `persist_history` is enabled
+    pub history_store: Option<HistoryStore>,
+    pub stats: HashMap<String, InterfaceStats>,
+    // only interfaces with a sustained transfer have a forecast
+    pub forecasts: HashMap<String, Forecast>,
+    pub last_tick: Instant,
+    // seconds between the last two samples
+    pub sample_interval: f64,
+    pub started: Instant,
+    pub config: Config,
+    pub theme: Theme,
+    pub capabilities: Capabilities,
+    pub debug: bool,
+    pub selected: usize,
+    // screen areas of the rendered interface blocks, used for mouse hit testing
+    pub interface_areas: Vec<Rect>,
+    pub scroll: usize,
+    // number of interface rows that fit on screen, from the last render
+    pub page_size: usize,
+    pub tab_areas: Vec<Rect>,
+    // the "3 more interfaces" line, clicking it pages on
+    pub overflow_area: Option<Rect>,
+    pub screen: Screen,
+    pub connections: Vec<Connection>,
+    pub processes: Vec<ProcessSockets>,
+    pub connection_filter: Option<String>,
+    pub connection_sort: Option<ConnectionSort>,
+    pub connection_rates: ConnectionRates,
+    pub geoip: GeoIp,
+    pub conntrack: Conntrack,
+    pub tcp: TcpHealth,
+    pub containers: Containers,
+    pub keymap: Keymap,
+    pub input: InputState,
+    pub prompt: Option<Prompt>,
+    pub route: Option<RouteLookup>,
+    pub traceroute: Option<Traceroute>,
+    pub notes: NoteStore,
+    pub latency: LatencyProbe,
+    // probes of the `latency_targets`, started with the one above
+    pub latency_targets: Vec<LatencyProbe>,
+    pub bufferbloat: Bufferbloat,
+    pub speed_test: SpeedTest,
+    pub cable_tests: CableTests,
+    pub canary: Canary,
+    pub slo: Slo,
+    pub quotas: Quotas,
+    pub hosts: Hosts,
+    pub macros: Macros,
+    pub user_traffic: UserTracker,
+    pub exporter: Exporter,
+    // set when the exporter runs on its own pipeline subscription
+    pub export_worker: Option<ExportWorker>,
+    // set while --record writes the samples to a file
+    pub recorder: Option<Recorder>,
+    // set while --replay plays a recording back
+    pub replay: Option<ReplayControl>,
+    // where samples come from when running the TUI, for showing lagging
+    // consumers
+    pub pipeline: Option<Pipeline>,
+    // ssh destination or server the interfaces are sampled on, None for
+    // this machine
+    pub remote: Option<String>,
+    pub alerts: AlertEngine,
+    pub anomalies: AnomalyDetector,
+    pub alert_sinks: Vec<Box<dyn AlertSink>>,
+    pub alert_sink_error: Option<String>,
 }
 
-pub fn ui(f: &mut Frame<'_>, app: &App) {
-    calc_network_status(f, app, None);
+impl App {
+    // UI state for `config`. Embedders feed it samples from a `Collector`
+    // with `update(&mut app, Action::Tick(collector.sample()))`
+    pub fn new(config: Config, capabilities: Capabilities) -> Result<App> {
+        let theme = config.theme()?.adapt(&capabilities);
+        let exporter = Exporter::from_config(&config.export)?;
+        let keymap = build_keymap(&config.keys)?;
+        let macros = parse_macros(&config.macros)?;
+        let alerts = AlertEngine::from_config(&config.alerts)?;
+        let derived = parse_derived(&config.derived)?;
+        let slo = Slo::open(&config.probes, config::slo_path())?;
+        let geoip = GeoIp::open(&config.geoip)?;
+        let (history_store, history) = match config::history_path() {
+            Some(path) if config.persist_history => {
+                let (store, history) = HistoryStore::open(path);
+                (Some(store), history)
+            }
+            _ => (None, HashMap::new()),
+        };
+        let has_quota = config.interfaces.values().any(|i| i.quota.is_some());
+        let scale_locks = config
+            .interfaces
+            .keys()
+            .filter_map(|name| {
+                let rate = config.y_max(name)?;
+                Some((
+                    name.to_string(),
+                    ScaleLock {
+                        sent: rate,
+                        rec: rate,
+                    },
+                ))
+            })
+            .collect();
+        let quotas = Quotas::open(config::usage_path().filter(|_| has_quota));
+
+        let mut app = App {
+            should_quit: false,
+            net_interfaces: Vec::new(),
+            net_interface_graphs: HashMap::new(),
+            net_interface_display: HashMap::new(),
+            baseline: BaselineMode::Off,
+            smoothed: false,
+            chart: config.chart,
+            layout: config.layout,
+            graph_style: config.graph_style,
+            overlay: config.overlay,
+            zoomed: false,
+            compare: Compare::default(),
+            packet_graphs: config.packet_graphs,
+            aggregate_panel: config.aggregate_panel,
+            aggregate_stacked: config.aggregate_stacked,
+            physical_only: false,
+            system_strip: config.system_strip,
+            system_load: SystemLoad::default(),
+            socket_strip: config.socket_strip,
+            socket_states: SocketStates::default(),
+            hotplug: Hotplug::default(),
+            all_interfaces: Vec::new(),
+            search: None,
+            damage: Damage::default(),
+            picker: None,
+            aggregate: Aggregate::default(),
+            derived,
+            auto_focus: AutoFocus::new(config.auto_focus),
+            settings_error: None,
+            file_status: None,
+            log_scale: HashSet::new(),
+            scale_locks,
+            history,
+            resolution: Resolution::Raw,
+            cumulative: false,
+            cumulative_interfaces: HashSet::new(),
+            cumulative_since: 0.0,
+            history_store,
+            stats: HashMap::new(),
+            forecasts: HashMap::new(),
+            last_tick: Instant::now(),
+            sample_interval: TICK_RATE.as_secs_f64(),
+            started: Instant::now(),
+            config,
+            theme,
+            capabilities,
+            debug: false,
+            selected: 0,
+            interface_areas: Vec::new(),
+            scroll: 0,
+            page_size: 1,
+            tab_areas: Vec::new(),
+            overflow_area: None,
+            screen: Screen::Overview,
+            connections: Vec::new(),
+            processes: Vec::new(),
+            user_traffic: UserTracker::default(),
+            connection_filter: None,
+            connection_sort: None,
+            connection_rates: ConnectionRates::default(),
+            geoip,
+            conntrack: Conntrack::default(),
+            tcp: TcpHealth::default(),
+            containers: Containers::default(),
+            keymap,
+            input: InputState::default(),
+            prompt: None,
+            route: None,
+            traceroute: None,
+            notes: NoteStore::open(config::notes_path()),
+            latency: LatencyProbe::default(),
+            latency_targets: Vec::new(),
+            bufferbloat: Bufferbloat::open(config::bufferbloat_path()),
+            speed_test: SpeedTest::default(),
+            cable_tests: CableTests::default(),
+            canary: Canary::default(),
+            slo,
+            quotas,
+            hosts: Hosts::default(),
+            macros,
+            exporter,
+            export_worker: None,
+            recorder: None,
+            replay: None,
+            pipeline: None,
+            remote: None,
+            alerts,
+            anomalies: AnomalyDetector::default(),
+            alert_sinks: Vec::new(),
+            alert_sink_error: None,
+            detached: false,
+        };
+        if app.config.latency_probe {
+            start_latency(&mut app);
+        }
+        Ok(app)
+    }
+
+    // Whether the samples are another machine's or a recording's, so this
+    // machine's own counters and files don't go with them
+    pub fn is_remote(&self) -> bool {
+        self.remote.is_some() || self.replay.is_some()
+    }
+}
+
+pub fn ui<B: Backend>(f: &mut Frame<'_, B>, app: &mut App) {
+    let slot = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(system_strip_height(app)),
+            Constraint::Length(socket_strip_height(app)),
+            Constraint::Min(0),
+            Constraint::Length(app.debug as u16),
+        ])
+        .split(f.size());
+    render_tabs(f, app, slot[0]);
+    if system_strip_height(app) > 0 {
+        f.render_widget(create_system_strip(app), slot[1]);
+    }
+    if socket_strip_height(app) > 0 {
+        f.render_widget(create_socket_strip(app), slot[2]);
+    }
+    let slot = [slot[0], slot[3], slot[4]];
+    if app.debug {
+        let capabilities = Paragraph::new(app.capabilities.summary()).style(app.theme.text);
+        f.render_widget(capabilities, slot[2]);
+    }
+
+    if let Some(picker) = &app.picker {
+        f.render_widget(create_picker(app, picker, slot[1].height), slot[1]);
+        return;
+    }
+    match app.screen {
+        Screen::Overview => calc_network_status(f, app, Some(slot[1])),
+        Screen::Connections => render_connections(f, app, slot[1]),
+        Screen::Processes => render_processes(f, app, slot[1]),
+        Screen::Users => render_users(f, app, slot[1]),
+        Screen::Share => render_share(f, app, slot[1]),
+        Screen::Alerts => render_alerts(f, app, slot[1]),
+        Screen::Hosts => render_hosts(f, app, slot[1]),
+        Screen::Latency => render_latency(f, app, slot[1]),
+        Screen::Slo => f.render_widget(create_slo_table(&app.slo, &app.theme), slot[1]),
+        Screen::Tcp => render_tcp(f, app, slot[1]),
+        Screen::Containers => f.render_widget(create_containers_table(app), slot[1]),
+    }
+    render_popup(f, app, slot[1]);
+}
+
+// Prompts and lookup results are drawn over the bottom of the screen
+fn render_popup<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    let popup = |height: u16| {
+        let height = height.min(area.height);
+        Rect::new(area.x, area.bottom() - height, area.width, height)
+    };
+    if let Some(prompt) = &app.prompt {
+        let block = Block::default()
+            .title(Span::styled(prompt.kind.title(), app.theme.title))
+            .borders(Borders::ALL)
+            .border_style(app.theme.title);
+        let text = Paragraph::new(format!("{}_", prompt.text))
+            .style(app.theme.text)
+            .block(block);
+        let area = popup(3);
+        f.render_widget(Clear, area);
+        f.render_widget(text, area);
+    } else if let Some(lookup) = &app.route {
+        let area = popup(4);
+        f.render_widget(Clear, area);
+        f.render_widget(create_route_paragraph(app, lookup), area);
+    }
+}
+
+// e.g. "export lagging, 12 samples dropped"
+fn lag_status(pipeline: &Pipeline) -> Option<String> {
+    let lagged = pipeline.lagged();
+    let names: Vec<&str> = lagged.iter().map(|(name, _)| name.as_str()).collect();
+    let dropped: u64 = lagged.iter().map(|(_, dropped)| dropped).sum();
+    (dropped > 0).then(|| format!("{} lagging, {dropped} samples dropped", names.join(", ")))
 }
 
 pub fn update(app: &mut App, action: Action) {
     match action {
-        Action::Quit => app.should_quit = true,
-        Action::Tick => {
-            app.sys.refresh_networks();
-            update_net_data(app);
-            update_graph_data(app)
+        Action::Quit => {
+            app.should_quit = true;
+            save_history(app, true);
+            save_quotas(app, true);
+            app.slo.close();
+        }
+        Action::Detach => {
+            app.should_quit = true;
+            app.detached = true;
+            app.exporter.flush();
+            save_history(app, true);
+            save_quotas(app, true);
+            app.slo.close();
+        }
+        Action::ToggleDebug => app.debug = !app.debug,
+        Action::Tick(snapshot) => {
+            let elapsed = snapshot
+                .taken
+                .saturating_duration_since(app.last_tick)
+                .as_secs_f64();
+            app.last_tick = snapshot.taken;
+            if elapsed > 0.0 {
+                app.sample_interval = elapsed;
+            }
+            update_picker(app, &snapshot.interfaces);
+            update_net_data(app, snapshot.interfaces);
+            update_graph_data(app);
+            update_history(app, elapsed);
+            save_history(app, false);
+            update_quotas(app);
+            save_quotas(app, false);
+            update_stats(app, elapsed);
+            update_aggregate(app, elapsed);
+            update_derived(app, elapsed);
+            update_forecasts(app);
+            // rules and anomalies run on the recording's clock in a replay
+            let time = snapshot.recorded.unwrap_or_else(unix_time);
+            update_alerts(app, time, elapsed);
+            update_anomalies(app, &snapshot.sockets, time, elapsed);
+            update_socket_states(app, &snapshot.sockets);
+            update_connection_rates(app, &snapshot.sockets, elapsed);
+            update_users(app, snapshot.sockets, elapsed);
+            export_samples(app, elapsed);
+            update_baseline(app);
+            update_display_data(app);
+            update_auto_focus(app, elapsed);
+            update_latency(app, elapsed);
+            update_bufferbloat(app);
+            update_speed_test(app);
+            update_cable_test(app);
+            update_canary(app, elapsed);
+            update_traceroute(app);
+            update_route(app);
+            update_slo(app);
+            update_conntrack(app);
+            update_tcp(app, elapsed);
+            update_containers(app, elapsed);
+            update_system_load(app);
+            clamp_selection(app);
+            update_damage(app);
+            if let Some(action) = expire_prefix(app) {
+                update(app, action);
+            }
+            // also kept current for the HTTP API to serve
+            if matches!(app.screen, Screen::Connections | Screen::Processes)
+                || app.config.http_api.is_some()
+            {
+                update_connections(app);
+            }
+            if app.screen == Screen::Hosts {
+                update_hosts(app);
+            }
+        }
+        Action::ToggleBaseline => {
+            toggle_baseline(app);
+            update_display_data(app);
+        }
+        Action::ToggleSmoothing => {
+            app.smoothed = !app.smoothed;
+            update_display_data(app);
+        }
+        Action::CycleLayout => cycle_layout(app),
+        Action::CycleGraphStyle => app.graph_style = app.graph_style.next(),
+        Action::CycleOverlay => app.overlay = app.overlay.next(),
+        Action::ToggleAutoFocus => toggle_auto_focus(app),
+        Action::OpenPrompt(kind) => open_prompt(app, kind),
+        Action::PromptInput(code) => edit_prompt(app, code),
+        Action::PickerInput(code) => edit_picker(app, code),
+        Action::ToggleZoom => toggle_zoom(app),
+        Action::ToggleCompareMark => toggle_mark(app),
+        Action::ToggleScaleLock => toggle_scale_lock(app),
+        Action::TogglePacketGraphs => app.packet_graphs = !app.packet_graphs,
+        Action::ToggleAggregate => toggle_aggregate(app),
+        Action::ToggleStacked => toggle_stacked(app),
+        Action::ToggleSystemStrip => toggle_system_strip(app),
+        Action::ToggleSocketStrip => toggle_socket_strip(app),
+        Action::TogglePhysicalOnly => {
+            toggle_physical_only(app);
+            clamp_selection(app);
+        }
+        Action::ToggleBufferbloat => toggle_bufferbloat(app),
+        Action::ToggleSpeedTest => toggle_speed_test(app),
+        Action::CableTest => run_cable_test(app),
+        Action::SaveSnapshot => save_snapshot(app),
+        Action::SetThreshold(text) => set_threshold(app, text),
+        Action::Traceroute(target) => start_traceroute(app, target),
+        Action::Dismiss if app.route.is_some() => app.route = None,
+        Action::Dismiss if app.traceroute.is_some() => app.traceroute = None,
+        Action::Dismiss if compared(app).is_some() => app.compare.marked.clear(),
+        Action::Dismiss if app.search.is_some() => {
+            search_interfaces(app, None);
+            clamp_selection(app);
+        }
+        Action::Dismiss if !app.zoomed && app.hotplug.shown() => app.hotplug.hidden = true,
+        Action::Dismiss => app.zoomed = false,
+        Action::ToggleChart => {
+            app.chart = !app.chart;
+            update_display_data(app);
+        }
+        Action::ToggleCumulative => {
+            app.cumulative = !app.cumulative;
+            update_display_data(app);
+        }
+        Action::ToggleInterfaceCumulative => {
+            if let Some(interface) = app.net_interfaces.get(app.selected) {
+                let name = interface.name.to_string();
+                if !app.cumulative_interfaces.remove(&name) {
+                    app.cumulative_interfaces.insert(name);
+                }
+                update_display_data(app);
+            }
+        }
+        Action::ResetCumulative => {
+            app.cumulative_since = unix_time();
+            update_display_data(app);
+        }
+        Action::CycleResolution => {
+            app.resolution = app.resolution.next();
+            update_display_data(app);
+        }
+        Action::ToggleLogScale => {
+            if let Some(interface) = app.net_interfaces.get(app.selected) {
+                let name = interface.name.to_string();
+                if !app.log_scale.remove(&name) {
+                    app.log_scale.insert(name);
+                }
+                update_display_data(app);
+            }
+        }
+        Action::SelectInterface(name) => {
+            // an exact name first, then part of a name or adapter description
+            let position = app
+                .net_interfaces
+                .iter()
+                .position(|i| i.name == name)
+                .or_else(|| app.net_interfaces.iter().position(|i| i.matches(&name)));
+            if let Some(i) = position {
+                app.selected = i;
+            }
+        }
+        Action::SearchInterfaces(fragment) => {
+            search_interfaces(app, fragment);
+            clamp_selection(app);
+        }
+        Action::SelectIndex(i) => {
+            app.selected = i;
+            clamp_selection(app);
+        }
+        Action::ToggleReplayPause => {
+            if let Some(replay) = &app.replay {
+                replay.toggle_pause();
+            }
+        }
+        Action::ReplaySpeed(faster) => {
+            if let Some(replay) = &app.replay {
+                replay.change_speed(faster);
+            }
+        }
+        Action::ReplaySeek(seconds) => {
+            if let Some(replay) = &app.replay {
+                replay.seek(seconds as f64);
+            }
+        }
+        Action::Sequence(actions) => {
+            for action in actions {
+                update(app, action);
+            }
+        }
+        Action::FilterConnections(filter) => {
+            app.connection_filter = filter;
+            update_connections(app);
+        }
+        Action::SortConnections(sort) => {
+            app.connection_sort = Some(sort);
+            update_connections(app);
+        }
+        Action::RunMacro(key) => {
+            let steps = app.macros.get(&key).cloned().unwrap_or_default();
+            for step in steps {
+                update(app, step);
+            }
+        }
+        Action::NextScreen => switch_screen(app, app.screen.next()),
+        Action::PrevScreen => switch_screen(app, app.screen.prev()),
+        Action::SelectScreen(i) => {
+            if let Some(screen) = Screen::ALL.get(i) {
+                switch_screen(app, *screen);
+            }
+        }
+        Action::SelectNext if app.screen == Screen::Hosts => app.hosts.select_next(),
+        Action::SelectPrev if app.screen == Screen::Hosts => app.hosts.select_prev(),
+        Action::WakeHost if app.screen == Screen::Hosts => wake_selected(app),
+        Action::SelectNext => {
+            app.selected = app.selected.saturating_add(1);
+            clamp_selection(app);
+        }
+        Action::SelectPrev => app.selected = app.selected.saturating_sub(1),
+        Action::PageDown => {
+            app.selected = app.selected.saturating_add(app.page_size);
+            clamp_selection(app);
+        }
+        Action::PageUp => app.selected = app.selected.saturating_sub(app.page_size),
+        Action::Click(column, row) => {
+            let position = Rect::new(column, row, 1, 1);
+            if let Some(i) = app
+                .tab_areas
+                .iter()
+                .position(|area| area.intersects(position))
+            {
+                switch_screen(app, Screen::ALL[i]);
+            } else if let Some(i) = app
+                .interface_areas
+                .iter()
+                .position(|area| area.intersects(position))
+            {
+                app.selected = app.scroll + i;
+            } else if app
+                .overflow_area
+                .is_some_and(|area| area.intersects(position))
+            {
+                // down while there's more below, back up from the end
+                let below = app.scroll + app.page_size < app.net_interfaces.len();
+                update(
+                    app,
+                    if below {
+                        Action::PageDown
+                    } else {
+                        Action::PageUp
+                    },
+                );
+            }
         }
         _ => {}
     };
 }
 
-fn calc_network_status(f: &mut Frame<'_>, app: &App, inner_layout: Option<Rect>) {
-    let (network_data, network_spark) = to_network_stat_widgets(app);
+pub fn switch_screen(app: &mut App, screen: Screen) {
+    app.screen = screen;
+    if matches!(screen, Screen::Connections | Screen::Processes) {
+        update_connections(app);
+    }
+    if screen == Screen::Hosts {
+        update_hosts(app);
+    }
+    if screen == Screen::Latency {
+        start_latency(app);
+    }
+}
+
+fn render_tabs<B: Backend>(f: &mut Frame<'_, B>, app: &mut App, area: Rect) {
+    // mirror the Tabs widget layout: one cell of padding either side of each
+    // title and a single cell divider
+    let mut x = area.x;
+    app.tab_areas = Screen::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, screen)| {
+            let width = format!("{} {}", i + 1, screen.title()).len() as u16;
+            // tabs past the right edge aren't drawn and can't be clicked
+            let tab = if x + 1 < area.right() {
+                Rect::new(x + 1, area.y, width, 1).intersection(area)
+            } else {
+                Rect::default()
+            };
+            x += width + 3;
+            tab
+        })
+        .collect();
 
-    let percentage: u16 = (100 / network_data.len()).try_into().unwrap();
-    let constraints: Vec<Constraint> = network_data
+    let titles: Vec<Line> = Screen::ALL
         .iter()
-        .map(|_| Constraint::Percentage(percentage))
+        .enumerate()
+        .map(|(i, screen)| Line::from(format!("{} {}", i + 1, screen.title())))
         .collect();
-    let inner_slot = Layout::default()
+    let divider = if app.capabilities.unicode { "│" } else { "|" };
+    let tabs = Tabs::new(titles)
+        .divider(divider)
+        .select(app.screen.index())
+        .style(app.theme.text)
+        .highlight_style(app.theme.title);
+    f.render_widget(tabs, area);
+
+    let status = status_line(app);
+    let status = Paragraph::new(status)
+        .style(app.theme.alert)
+        .alignment(Alignment::Right);
+    f.render_widget(status, area);
+}
+
+// Everything going on that the tab bar reports on the right, e.g.
+// "smoothed ewma | 2 alerts firing"
+pub fn status_line(app: &App) -> String {
+    let smoothing = app
+        .smoothed
+        .then(|| format!("smoothed {}", app.config.smoothing.name()));
+    let graph_style = (app.graph_style != GraphStyle::Bar && !app.chart)
+        .then(|| format!("{} graphs", app.graph_style.name()));
+    let resolution =
+        (app.resolution != Resolution::Raw).then(|| format!("{} history", app.resolution.name()));
+    let cumulative = (app.cumulative || !app.cumulative_interfaces.is_empty()).then(|| {
+        let since = if app.cumulative_since > 0.0 {
+            format_clock(app.cumulative_since)
+        } else {
+            "start".to_string()
+        };
+        if app.cumulative {
+            format!("totals since {since}")
+        } else {
+            let mut names: Vec<&str> = app
+                .cumulative_interfaces
+                .iter()
+                .map(|s| s.as_str())
+                .collect();
+            names.sort();
+            format!("{} totals since {since}", names.join(", "))
+        }
+    });
+    let status: Vec<String> = [
+        app.remote
+            .as_ref()
+            .map(|target| format!("watching {target}")),
+        app.replay.as_ref().map(ReplayControl::status),
+        app.input.pending(),
+        app.baseline.status(),
+        app.auto_focus.status(),
+        app.compare.status(),
+        app.bufferbloat.status(),
+        app.speed_test.status(),
+        app.cable_tests.status(),
+        app.traceroute.as_ref().and_then(Traceroute::status),
+        app.canary.status(),
+        smoothing,
+        graph_style,
+        app.overlay.status().filter(|_| !app.chart),
+        resolution,
+        cumulative,
+        app.physical_only
+            .then(|| "physical interfaces only".to_string()),
+        app.search
+            .as_ref()
+            .map(|fragment| format!("matching \"{fragment}\"")),
+        (app.alerts.active() > 0).then(|| format!("{} alerts firing", app.alerts.active())),
+        app.conntrack.warning(),
+        app.tcp.warning(),
+        app.exporter.error.clone(),
+        app.export_worker.as_ref().and_then(ExportWorker::error),
+        app.recorder.as_ref().and_then(Recorder::error),
+        app.pipeline.as_ref().and_then(lag_status),
+        app.alert_sink_error.clone(),
+        app.settings_error.clone(),
+        app.file_status.clone(),
+        app.notes.error.clone(),
+        app.quotas.error.clone(),
+        app.geoip.error.clone(),
+        app.history_store
+            .as_ref()
+            .and_then(|store| store.error.clone()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    status.join(" | ")
+}
+
+fn render_connections<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    let area = if app.conntrack.available {
+        let slot = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(CONNTRACK_HEIGHT), Constraint::Min(0)])
+            .split(area);
+        f.render_widget(create_conntrack_paragraph(app), slot[0]);
+        slot[1]
+    } else {
+        area
+    };
+    let table = create_connections_table(
+        &app.connections,
+        app.connection_filter.as_deref(),
+        app.geoip.enabled(),
+        &app.theme,
+    );
+    f.render_widget(table, area);
+}
+
+fn render_processes<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    f.render_widget(create_processes_table(&app.processes, &app.theme), area);
+}
+
+fn render_users<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    f.render_widget(create_users_table(&app.user_traffic, &app.theme), area);
+}
+
+fn render_share<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    let slot = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(constraints);
-
-    let slot = match inner_layout {
-        Some(layout) => inner_slot.split(layout.inner(&Margin {
-            horizontal: 1,
-            vertical: 1,
-        })),
-        None => inner_slot.split(f.size().inner(&Margin {
-            horizontal: 1,
-            vertical: 1,
-        })),
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let (interfaces, users) = create_share_paragraphs(app, area.width);
+    f.render_widget(interfaces, slot[0]);
+    f.render_widget(users, slot[1]);
+}
+
+fn render_alerts<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    let alerts = create_alerts_table(&app.alerts, &app.theme, app.replay.is_some());
+    f.render_widget(alerts, area);
+}
+
+fn render_hosts<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    f.render_widget(create_hosts_table(&app.hosts, &app.theme), area);
+}
+
+fn render_latency<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    let series = LatencySeries::from(&app.latency, unix_time());
+    let (scatter, rtt, throughput) = create_latency_charts(app, &series);
+    let outer = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(BUFFERBLOAT_HEIGHT)])
+        .split(area);
+    f.render_widget(create_bufferbloat_paragraph(app), outer[1]);
+    let slot = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(outer[0]);
+    let series_slot = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(slot[1]);
+    // a row per target, header and borders
+    let targets_height = 1 + app.latency_targets.len() as u16 + 3;
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(targets_height), Constraint::Min(0)])
+        .split(slot[0]);
+    f.render_widget(create_latency_table(app), left[0]);
+    match &app.traceroute {
+        Some(trace) => {
+            let left = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(left[1]);
+            f.render_widget(scatter, left[0]);
+            f.render_widget(create_traceroute_table(trace, &app.theme), left[1]);
+        }
+        None => f.render_widget(scatter, left[1]),
+    }
+    f.render_widget(rtt, series_slot[0]);
+    f.render_widget(throughput, series_slot[1]);
+}
+
+fn render_tcp<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    let slot = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(TCP_SUMMARY_HEIGHT), Constraint::Min(0)])
+        .split(area);
+    f.render_widget(create_tcp_paragraph(app), slot[0]);
+    if !app.tcp.available {
+        return;
+    }
+    let graphs = create_tcp_graphs(app, slot[1].width.saturating_sub(2) as usize);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Ratio(1, graphs.len() as u32);
+            graphs.len()
+        ])
+        .split(slot[1]);
+    for (graph, area) in graphs.into_iter().zip(rows.iter()) {
+        f.render_widget(graph, *area);
+    }
+}
+
+fn clamp_selection(app: &mut App) {
+    app.selected = app.selected.min(app.net_interfaces.len().saturating_sub(1));
+}
+
+// Each interface gets a fixed height row: the stats paragraph plus TX and
+// RX graphs. Rows that don't fit are reached by scrolling.
+pub const PARAGRAPH_HEIGHT: u16 = 10;
+const GRAPH_HEIGHT: u16 = 6;
+// shortest a zoomed graph gets, border and title included
+const MIN_GRAPH_HEIGHT: u16 = 4;
+const EXTENDED_HEIGHT: u16 = 8;
+// percentiles and four rows of bars
+const HISTOGRAM_HEIGHT: u16 = 8;
+// TX and RX lines of the compare view, borders included
+const COMPARE_HEIGHT: u16 = 4;
+
+fn row_height(app: &App) -> u16 {
+    PARAGRAPH_HEIGHT + app.debug as u16 + GRAPH_HEIGHT * graph_rows(app)
+}
+
+// TX and RX bytes, or one row with both when overlaid, plus a row with both
+// packet graphs when enabled
+fn graph_rows(app: &App) -> u16 {
+    match is_overlaid(app) {
+        true => 1 + app.packet_graphs as u16,
+        false => 2 + app.packet_graphs as u16,
+    }
+}
+
+// The byte graphs of each interface, top to bottom
+fn byte_graphs<'a>(app: &'a App, pairs: Vec<GraphPair<'a>>, width: usize) -> Vec<Vec<Graph<'a>>> {
+    match is_overlaid(app) {
+        true => to_overlay_graphs(app, width)
+            .into_iter()
+            .map(|graph| vec![graph])
+            .collect(),
+        false => pairs.into_iter().map(|(tx, rx)| vec![tx, rx]).collect(),
+    }
+}
+
+fn graph_constraints(app: &App) -> Vec<Constraint> {
+    let rows = graph_rows(app) as u32;
+    vec![Constraint::Ratio(1, rows); rows as usize]
+}
+
+// Summary line above the combined TX and RX graphs, or the stacked one
+const AGGREGATE_HEIGHT: u16 = 3 + GRAPH_HEIGHT;
+
+fn render_aggregate<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    let slot = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+    let graph_width = (area.width / 2).saturating_sub(2) as usize;
+    let (summary, graphs) = create_aggregate_widgets(app, graph_width);
+    f.render_widget(summary, slot[0]);
+    if app.aggregate_stacked {
+        let width = area.width.saturating_sub(2) as usize;
+        f.render_widget(create_stacked_graph(app, width), slot[1]);
+    } else {
+        render_graph_pair(f, graphs, slot[1]);
+    }
+}
+
+fn render_derived<B: Backend>(f: &mut Frame<'_, B>, app: &App, area: Rect) {
+    let count = app.derived.len() as u32;
+    let slot = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, count); count as usize])
+        .split(area);
+    let graph_width = (area.width / count as u16).saturating_sub(2) as usize;
+    for (graph, area) in create_derived_graphs(app, graph_width)
+        .into_iter()
+        .zip(slot.iter())
+    {
+        f.render_widget(graph, *area);
+    }
+}
+
+fn render_graph_pair<B: Backend>(f: &mut Frame<'_, B>, graphs: GraphPair<'_>, area: Rect) {
+    let slot = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    f.render_widget(graphs.0, slot[0]);
+    f.render_widget(graphs.1, slot[1]);
+}
+
+fn calc_network_status<B: Backend>(
+    f: &mut Frame<'_, B>,
+    app: &mut App,
+    inner_layout: Option<Rect>,
+) {
+    let area = inner_layout.unwrap_or(f.size()).inner(&Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    let area = if app.aggregate_panel {
+        let slot = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(AGGREGATE_HEIGHT), Constraint::Min(0)])
+            .split(area);
+        render_aggregate(f, app, slot[0]);
+        slot[1]
+    } else {
+        area
+    };
+    let area = if app.derived.is_empty() {
+        area
+    } else {
+        let slot = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(GRAPH_HEIGHT), Constraint::Min(0)])
+            .split(area);
+        render_derived(f, app, slot[0]);
+        slot[1]
+    };
+    let area = if app.hotplug.shown() {
+        let slot = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(app.hotplug.panel_height()),
+            ])
+            .split(area);
+        let table = create_departed_table(app, departed_spark_width(slot[1].width));
+        f.render_widget(table, slot[1]);
+        slot[0]
+    } else {
+        area
     };
+    if compared(app).is_some() {
+        return render_compare(f, app, area);
+    }
+    // panes too short for a single interface block fall back to the table
+    if app.zoomed && !app.net_interfaces.is_empty() {
+        return render_zoomed(f, app, area);
+    }
+    if app.layout == LayoutMode::Compact || area.height < row_height(app) {
+        return render_compact(f, app, area);
+    }
+    let columns = columns(app.layout, area.width);
+    let row_height = row_height(app);
+    // whole blocks only, what doesn't fit is scrolled to rather than squeezed
+    let mut rows = (area.height / row_height).max(1) as usize;
+    let overflows = app.net_interfaces.len().div_ceil(columns) > rows;
+    // the overflow line takes a row of blocks when no spare line is left
+    if overflows && area.height % row_height == 0 && rows > 1 {
+        rows -= 1;
+    }
+    let visible = rows * columns;
+    app.page_size = visible;
+    scroll_to_selection(app, columns);
 
-    let widgets_zip = zip(network_data, network_spark);
-    for (i, (data, spark)) in widgets_zip.enumerate() {
+    let shown = app
+        .net_interfaces
+        .len()
+        .saturating_sub(app.scroll)
+        .min(visible);
+    let mut constraints: Vec<Constraint> = (0..shown.div_ceil(columns))
+        .map(|_| Constraint::Length(row_height))
+        .collect();
+    constraints.push(Constraint::Min(0));
+    let row_slots = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+    let cell_constraints = vec![Constraint::Ratio(1, columns as u32); columns];
+    let slot: Vec<Rect> = row_slots[..row_slots.len() - 1]
+        .iter()
+        .flat_map(|row| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(cell_constraints.clone())
+                .split(*row)
+                .to_vec()
+        })
+        .take(shown)
+        .collect();
+    app.interface_areas = slot.clone();
+    app.overflow_area = None;
+    if let Some(overflow) = overflow_text(app, shown) {
+        let line = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1);
+        if !slot.iter().any(|cell| cell.intersects(line)) {
+            let paragraph = Paragraph::new(overflow)
+                .style(app.theme.title)
+                .alignment(Alignment::Right);
+            f.render_widget(paragraph, line);
+            app.overflow_area = Some(line);
+        }
+    }
+
+    let cell_width = slot.first().map_or(area.width, |cell| cell.width);
+    let graph_width = cell_width.saturating_sub(2) as usize;
+    let (network_data, network_spark) = to_network_stat_widgets(app, graph_width);
+    let mut packets = to_packet_graphs(app, graph_width / 2)
+        .into_iter()
+        .skip(app.scroll);
+    let network_spark = byte_graphs(app, network_spark, graph_width);
+    let widgets_zip = zip(network_data, network_spark).skip(app.scroll);
+    for (i, (data, spark)) in widgets_zip.take(visible).enumerate() {
         let inner_slot = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .constraints([
+                Constraint::Length(PARAGRAPH_HEIGHT + app.debug as u16),
+                Constraint::Min(0),
+            ])
             .split(slot[i]);
+        let graph_slot = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(graph_constraints(app))
+            .split(inner_slot[1]);
         f.render_widget(data, inner_slot[0]);
-        f.render_widget(spark, inner_slot[1]);
+        let bytes = spark.len();
+        for (graph, graph_slot) in zip(spark, graph_slot.iter()) {
+            f.render_widget(graph, *graph_slot);
+        }
+        if let (Some(packets), Some(packet_slot)) = (packets.next(), graph_slot.get(bytes)) {
+            render_graph_pair(f, packets, *packet_slot);
+        }
+    }
+}
+
+// The selected interface on its own, graphs taking all the remaining height
+fn render_zoomed<B: Backend>(f: &mut Frame<'_, B>, app: &mut App, area: Rect) {
+    app.interface_areas.clear();
+    app.overflow_area = None;
+    let name = app.net_interfaces[app.selected].name.to_string();
+    let extended = create_extended_stats(app, &name);
+    let graph_width = area.width.saturating_sub(2) as usize;
+    let (network_data, network_spark) = to_network_stat_widgets(app, graph_width);
+    let packets = to_packet_graphs(app, graph_width / 2)
+        .into_iter()
+        .nth(app.selected);
+    let (Some(data), Some(spark)) = (
+        network_data.into_iter().nth(app.selected),
+        byte_graphs(app, network_spark, graph_width)
+            .into_iter()
+            .nth(app.selected),
+    ) else {
+        return;
+    };
+    // the histogram and then the extended stats give way before the graphs
+    // get too short to read
+    let fixed = PARAGRAPH_HEIGHT + app.debug as u16 + MIN_GRAPH_HEIGHT * graph_rows(app);
+    let extended_height = if area.height >= fixed + EXTENDED_HEIGHT {
+        EXTENDED_HEIGHT
+    } else {
+        0
+    };
+    let histogram_height = if area.height >= fixed + EXTENDED_HEIGHT + HISTOGRAM_HEIGHT {
+        HISTOGRAM_HEIGHT
+    } else {
+        0
+    };
+    let slot = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(PARAGRAPH_HEIGHT + app.debug as u16),
+            Constraint::Length(extended_height),
+            Constraint::Length(histogram_height),
+            Constraint::Min(0),
+        ])
+        .split(area);
+    let graph_slot = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(graph_constraints(app))
+        .split(slot[3]);
+    f.render_widget(data, slot[0]);
+    f.render_widget(extended, slot[1]);
+    f.render_widget(create_histogram(app, &name), slot[2]);
+    let bytes = spark.len();
+    for (graph, graph_slot) in zip(spark, graph_slot.iter()) {
+        f.render_widget(graph, *graph_slot);
+    }
+    if let (Some(packets), Some(packet_slot)) = (packets, graph_slot.get(bytes)) {
+        render_graph_pair(f, packets, *packet_slot);
+    }
+}
+
+// The marked pair with the difference above, each in a column of its own
+fn render_compare<B: Backend>(f: &mut Frame<'_, B>, app: &mut App, area: Rect) {
+    app.interface_areas.clear();
+    app.overflow_area = None;
+    let graph_width = (area.width / 2).saturating_sub(2) as usize;
+    let Some((summary, graphs)) = create_compare_widgets(app, graph_width) else {
+        return;
+    };
+    let slot = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(COMPARE_HEIGHT), Constraint::Min(0)])
+        .split(area);
+    f.render_widget(summary, slot[0]);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(slot[1]);
+    for ((sent, rec), column) in graphs.into_iter().zip(columns.iter()) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+            .split(*column);
+        f.render_widget(sent, rows[0]);
+        f.render_widget(rec, rows[1]);
+    }
+}
+
+// One table row per interface, for small terminals or many interfaces
+fn render_compact<B: Backend>(f: &mut Frame<'_, B>, app: &mut App, area: Rect) {
+    let visible = area.height.saturating_sub(COMPACT_HEADER_HEIGHT + 1).max(1) as usize;
+    app.page_size = visible;
+    scroll_to_selection(app, 1);
+    let shown = app
+        .net_interfaces
+        .len()
+        .saturating_sub(app.scroll)
+        .min(visible);
+    app.interface_areas = (0..shown as u16)
+        .map(|i| Rect::new(area.x, area.y + COMPACT_HEADER_HEIGHT + i, area.width, 1))
+        .collect();
+    let spark_width = sparkline_width(area.width);
+    // the indicator sits in the bottom border
+    app.overflow_area = (overflow_text(app, shown).is_some() && area.height > 0)
+        .then(|| Rect::new(area.x, area.bottom() - 1, area.width, 1));
+    f.render_widget(create_compact_table(app, visible, spark_width), area);
+}
+
+// Adjust the scroll offset so the selected interface stays on screen. The
+// offset is kept to whole rows of `columns` interfaces.
+fn scroll_to_selection(app: &mut App, columns: usize) {
+    let rows = (app.page_size / columns).max(1);
+    let selected = app.selected / columns;
+    let mut scroll = app.scroll / columns;
+    if selected < scroll {
+        scroll = selected;
+    } else if selected >= scroll + rows {
+        scroll = selected + 1 - rows;
     }
+    let total = app.net_interfaces.len().div_ceil(columns);
+    app.scroll = scroll.min(total.saturating_sub(rows)) * columns;
 }
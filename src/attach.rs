@@ -0,0 +1,160 @@
+use futures::StreamExt;
+use ratatui::{
+    prelude::{Backend, Constraint, CrosstermBackend, Direction, Layout, Terminal},
+    widgets::Paragraph,
+};
+use tokio::io::{BufReader, Lines};
+
+use crossterm::event::EventStream;
+
+use crate::{
+    app::{update, App, PARAGRAPH_HEIGHT},
+    capabilities::Capabilities,
+    config::Config,
+    error::{Error, Result, ResultExt},
+    networks::to_network_stat_widgets,
+    tui::event_action,
+    wire, Action, Frame,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PanelKind {
+    // stats paragraph and both sparklines, as on the overview
+    Full,
+    Graph,
+    Chart,
+    Stats,
+}
+
+// One interface panel shown by an attached instance, e.g. "eth0-chart"
+pub struct Panel {
+    pub interface: String,
+    pub kind: PanelKind,
+}
+
+impl Panel {
+    pub fn parse(spec: &str) -> Panel {
+        let kind = spec.rsplit_once('-').and_then(|(interface, kind)| {
+            let kind = match kind {
+                "graph" => PanelKind::Graph,
+                "chart" => PanelKind::Chart,
+                "stats" => PanelKind::Stats,
+                _ => return None,
+            };
+            Some((interface, kind))
+        });
+        match kind {
+            Some((interface, kind)) => Panel {
+                interface: interface.to_string(),
+                kind,
+            },
+            // interface names may contain dashes themselves
+            None => Panel {
+                interface: spec.to_string(),
+                kind: PanelKind::Full,
+            },
+        }
+    }
+}
+
+fn render_panel<B: Backend>(f: &mut Frame<'_, B>, app: &App, panel: &Panel) {
+    let area = f.size();
+    let Some(index) = app
+        .net_interfaces
+        .iter()
+        .position(|interface| interface.name == panel.interface)
+    else {
+        let waiting =
+            Paragraph::new(format!("waiting for {}", panel.interface)).style(app.theme.text);
+        f.render_widget(waiting, area);
+        return;
+    };
+    let (paragraphs, graphs) = to_network_stat_widgets(app, area.width.saturating_sub(2) as usize);
+    let (Some(paragraph), Some((tx, rx))) = (
+        paragraphs.into_iter().nth(index),
+        graphs.into_iter().nth(index),
+    ) else {
+        return;
+    };
+    if panel.kind == PanelKind::Stats {
+        f.render_widget(paragraph, area);
+        return;
+    }
+    let mut constraints = vec![Constraint::Percentage(50), Constraint::Percentage(50)];
+    if panel.kind == PanelKind::Full {
+        constraints.insert(0, Constraint::Length(PARAGRAPH_HEIGHT));
+    }
+    let slot = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+    let graph_slot = &slot[slot.len() - 2..];
+    if panel.kind == PanelKind::Full {
+        f.render_widget(paragraph, slot[0]);
+    }
+    f.render_widget(tx, graph_slot[0]);
+    f.render_widget(rx, graph_slot[1]);
+}
+
+#[cfg(unix)]
+async fn connect() -> Result<Lines<BufReader<tokio::net::UnixStream>>> {
+    use tokio::io::AsyncBufReadExt;
+
+    use crate::api::socket_path;
+
+    let path = socket_path();
+    let stream = tokio::net::UnixStream::connect(&path).await.or_ui(|| {
+        format!(
+            "no net-stat instance to attach to at {}, start one first",
+            path.display()
+        )
+    })?;
+    Ok(BufReader::new(stream).lines())
+}
+
+#[cfg(not(unix))]
+async fn connect() -> Result<Lines<BufReader<tokio::io::Empty>>> {
+    Err(Error::ui("attaching needs unix sockets"))
+}
+
+// Renders a single panel from the snapshots streamed by the primary instance
+// instead of collecting itself
+pub async fn run(capabilities: Capabilities, panel: Panel) -> Result<App> {
+    let mut lines = connect().await?;
+
+    let mut t = Terminal::new(CrosstermBackend::new(std::io::stderr()))
+        .or_ui(|| "failed to set up the terminal")?;
+    let mut config = Config::load()?;
+    // the primary instance already exports, persists history and probes
+    config.persist_history = false;
+    config.export = Default::default();
+    config.canary = Default::default();
+    config.chart = panel.kind == PanelKind::Chart;
+    let mut app = App::new(config, capabilities)?;
+    // and saves the quota usage both count
+    app.quotas.stop_saving();
+    let mut events = EventStream::new();
+
+    loop {
+        let action = tokio::select! {
+            line = lines.next_line() => match line.or_ui(|| "lost the primary net-stat instance")? {
+                Some(line) => Action::Tick(wire::decode(&line)?),
+                None => return Err(Error::ui("the primary net-stat instance went away")),
+            },
+            event = events.next() => match event {
+                Some(Ok(event)) => event_action(&mut app, event),
+                _ => Action::Detach,
+            },
+        };
+        if matches!(action, Action::None) {
+            continue;
+        }
+        update(&mut app, action);
+        if app.should_quit {
+            break;
+        }
+        t.draw(|f| render_panel(f, &app, &panel))
+            .or_ui(|| "failed to draw")?;
+    }
+    Ok(app)
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use crate::app::App;
+
+// Constant background traffic of an interface, in bytes per tick
+#[derive(Clone, Copy, Default)]
+pub struct Baseline {
+    pub sent: u64,
+    pub rec: u64,
+}
+
+pub enum BaselineMode {
+    Off,
+    // averaging samples until `remaining` ticks have passed
+    Calibrating {
+        remaining: usize,
+        samples: usize,
+        totals: HashMap<String, (u64, u64)>,
+    },
+    Active(HashMap<String, Baseline>),
+}
+
+impl BaselineMode {
+    pub fn get(&self, name: &str) -> Option<Baseline> {
+        match self {
+            BaselineMode::Active(baselines) => baselines.get(name).copied(),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self) -> Option<String> {
+        match self {
+            BaselineMode::Off => None,
+            BaselineMode::Calibrating { remaining, .. } => {
+                Some(format!("calibrating baseline, {remaining} ticks left"))
+            }
+            BaselineMode::Active(_) => Some("baseline subtracted".to_string()),
+        }
+    }
+}
+
+pub fn toggle_baseline(app: &mut App) {
+    app.baseline = match app.baseline {
+        BaselineMode::Off => BaselineMode::Calibrating {
+            remaining: app.config.baseline_window.max(1),
+            samples: 0,
+            totals: HashMap::new(),
+        },
+        _ => BaselineMode::Off,
+    };
+}
+
+pub fn update_baseline(app: &mut App) {
+    let BaselineMode::Calibrating {
+        remaining,
+        samples,
+        totals,
+    } = &mut app.baseline
+    else {
+        return;
+    };
+    app.all_interfaces.iter().for_each(|interface| {
+        let total = totals.entry(interface.name.to_string()).or_default();
+        total.0 += interface.sent;
+        total.1 += interface.rec;
+    });
+    *samples += 1;
+    *remaining -= 1;
+    if *remaining == 0 {
+        let samples = *samples as u64;
+        let baselines = totals
+            .iter()
+            .map(|(name, (sent, rec))| {
+                let baseline = Baseline {
+                    sent: sent / samples,
+                    rec: rec / samples,
+                };
+                (name.to_string(), baseline)
+            })
+            .collect();
+        app.baseline = BaselineMode::Active(baselines);
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+
+// Received broadcast frames from the driver's own statistics, the ones
+// `ethtool -S` lists. The kernel keeps no generic broadcast counter, so
+// interfaces whose driver doesn't count them, virtual ones included, have
+// none.
+#[derive(Default)]
+pub struct BroadcastCounter {
+    // how many statistics the driver has and which one counts broadcasts,
+    // looked up again when the count changes, e.g. after a driver reload
+    #[cfg(target_os = "linux")]
+    stats: HashMap<String, (usize, Option<usize>)>,
+}
+
+impl BroadcastCounter {
+    // The total since the driver was loaded
+    #[cfg(target_os = "linux")]
+    pub fn read(&mut self, name: &str) -> Option<u64> {
+        let count = ethtool::stat_count(name).ok()?;
+        let index = match self.stats.get(name) {
+            Some((known, index)) if *known == count => *index,
+            _ => {
+                let names = ethtool::stat_names(name, count).ok()?;
+                let index = names.iter().position(|stat| counts_rx_broadcasts(stat));
+                self.stats.insert(name.to_string(), (count, index));
+                index
+            }
+        }?;
+        ethtool::stat_values(name, count).ok()?.get(index).copied()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn read(&mut self, _name: &str) -> Option<u64> {
+        None
+    }
+}
+
+// Drivers name it differently, e.g. rx_broadcast (igb, i40e), broadcast
+// (r8169, ixgbe) or rx_bcast_packets (tg3, bnxt)
+#[cfg(target_os = "linux")]
+fn counts_rx_broadcasts(stat: &str) -> bool {
+    let stat = stat.to_lowercase();
+    (stat.contains("broadcast") || stat.contains("bcast"))
+        && !stat.contains("tx")
+        && !stat.contains("byte")
+}
+
+// The SIOCETHTOOL ioctl, the genetlink family only has broadcasts for the
+// few drivers reporting the standard MAC statistics
+#[cfg(target_os = "linux")]
+mod ethtool {
+    use std::{
+        io,
+        os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    };
+
+    use crate::netlink::read_u32;
+
+    const ETHTOOL_GSTRINGS: u32 = 0x1b;
+    const ETHTOOL_GSTATS: u32 = 0x1d;
+    const ETHTOOL_GSSET_INFO: u32 = 0x37;
+    const ETH_SS_STATS: u32 = 1;
+    const ETH_GSTRING_LEN: usize = 32;
+
+    // `data` starts with the command, the kernel writes its answer after it
+    fn ioctl(name: &str, data: &mut [u8]) -> io::Result<()> {
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+        let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+        if name.len() >= request.ifr_name.len() {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        }
+        for (slot, byte) in request.ifr_name.iter_mut().zip(name.bytes()) {
+            *slot = byte as libc::c_char;
+        }
+        request.ifr_ifru.ifru_data = data.as_mut_ptr() as *mut libc::c_char;
+        let result =
+            unsafe { libc::ioctl(socket.as_raw_fd(), libc::SIOCETHTOOL as _, &mut request) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // struct ethtool_sset_info asking for the statistics only
+    pub fn stat_count(name: &str) -> io::Result<usize> {
+        let mut data = [0u8; 20];
+        data[..4].copy_from_slice(&ETHTOOL_GSSET_INFO.to_ne_bytes());
+        data[8..16].copy_from_slice(&(1u64 << ETH_SS_STATS).to_ne_bytes());
+        ioctl(name, &mut data)?;
+        // the mask comes back without the bit when the driver has none
+        match u64::from_ne_bytes(data[8..16].try_into().unwrap()) & (1 << ETH_SS_STATS) {
+            0 => Ok(0),
+            _ => Ok(read_u32(&data, 16) as usize),
+        }
+    }
+
+    // struct ethtool_gstrings, NUL padded names
+    pub fn stat_names(name: &str, count: usize) -> io::Result<Vec<String>> {
+        let mut data = vec![0u8; 12 + count * ETH_GSTRING_LEN];
+        data[..4].copy_from_slice(&ETHTOOL_GSTRINGS.to_ne_bytes());
+        data[4..8].copy_from_slice(&ETH_SS_STATS.to_ne_bytes());
+        data[8..12].copy_from_slice(&(count as u32).to_ne_bytes());
+        ioctl(name, &mut data)?;
+        Ok(data[12..]
+            .chunks(ETH_GSTRING_LEN)
+            .map(|chunk| {
+                let end = chunk
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .unwrap_or(chunk.len());
+                String::from_utf8_lossy(&chunk[..end]).into_owned()
+            })
+            .collect())
+    }
+
+    // struct ethtool_stats, in the order of the names
+    pub fn stat_values(name: &str, count: usize) -> io::Result<Vec<u64>> {
+        let mut data = vec![0u8; 8 + count * 8];
+        data[..4].copy_from_slice(&ETHTOOL_GSTATS.to_ne_bytes());
+        data[4..8].copy_from_slice(&(count as u32).to_ne_bytes());
+        ioctl(name, &mut data)?;
+        Ok(data[8..]
+            .chunks(8)
+            .map(|chunk| u64::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect())
+    }
+}
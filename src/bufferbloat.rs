@@ -0,0 +1,348 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::App,
+    error::{Result, ResultExt},
+    history::unix_time,
+    latency::start_latency,
+    speedtest::{Direction, HttpUrl, Load},
+    units::{format_rate, format_timestamp},
+};
+
+// Seconds spent measuring each phase
+const IDLE_SECONDS: f64 = 5.0;
+const LOADED_SECONDS: f64 = 10.0;
+// results listed under the latency charts
+const SHOWN_RESULTS: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Idle,
+    Loaded(Direction),
+}
+
+impl Phase {
+    fn name(&self) -> &'static str {
+        match self {
+            Phase::Idle => "measuring idle latency",
+            Phase::Loaded(Direction::Download) => "saturating download",
+            Phase::Loaded(Direction::Upload) => "saturating upload",
+        }
+    }
+}
+
+// A test in progress: round trip times collected per phase while the speed
+// test load runs
+struct Running {
+    phase: Phase,
+    phase_started: f64,
+    // time of the last latency sample taken into account
+    last_sample: f64,
+    idle: Vec<f64>,
+    download: Vec<f64>,
+    upload: Vec<f64>,
+    download_rate: Option<f64>,
+    upload_rate: Option<f64>,
+    load: Option<Load>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BufferbloatResult {
+    pub time: f64,
+    pub grade: String,
+    // median round trip times in milliseconds
+    pub idle: f64,
+    pub download: Option<f64>,
+    pub upload: Option<f64>,
+    pub download_rate: Option<f64>,
+    pub upload_rate: Option<f64>,
+}
+
+impl BufferbloatResult {
+    pub fn describe(&self) -> String {
+        let loaded = |name: &str, rtt: Option<f64>, rate: Option<f64>| {
+            rtt.map(|rtt| {
+                format!(
+                    ", {name} +{:.0} ms at {}",
+                    rtt - self.idle,
+                    format_rate(rate.unwrap_or(0.0))
+                )
+            })
+            .unwrap_or_default()
+        };
+        format!(
+            "{} {}: idle {:.1} ms{}{}",
+            format_timestamp(self.time),
+            self.grade,
+            self.idle,
+            loaded("download", self.download, self.download_rate),
+            loaded("upload", self.upload, self.upload_rate)
+        )
+    }
+}
+
+// Grades by how much latency rises under load, using the same thresholds
+// as the common web bufferbloat tests
+pub fn grade(increase: f64) -> &'static str {
+    match increase {
+        increase if increase < 5.0 => "A+",
+        increase if increase < 30.0 => "A",
+        increase if increase < 60.0 => "B",
+        increase if increase < 200.0 => "C",
+        increase if increase < 400.0 => "D",
+        _ => "F",
+    }
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    Some(values[values.len() / 2])
+}
+
+// Idle latency against latency while the speed test saturates the link,
+// past results are kept in the data dir
+#[derive(Default)]
+pub struct Bufferbloat {
+    path: Option<PathBuf>,
+    running: Option<Running>,
+    pub results: Vec<BufferbloatResult>,
+    pub error: Option<String>,
+}
+
+impl Bufferbloat {
+    pub fn open(path: Option<PathBuf>) -> Bufferbloat {
+        let (results, error) = match path.as_deref().map(load) {
+            Some(Ok(results)) => (results, None),
+            Some(Err(e)) => (Vec::new(), Some(format!("{e:#}"))),
+            None => (Vec::new(), None),
+        };
+        Bufferbloat {
+            path,
+            running: None,
+            results,
+            error,
+        }
+    }
+
+    pub fn status(&self) -> Option<String> {
+        let running = self.running.as_ref()?;
+        let elapsed = unix_time() - running.phase_started;
+        let rate = running
+            .load
+            .as_ref()
+            .map(|load| format!(" at {}", format_rate(load.rate())))
+            .unwrap_or_default();
+        Some(format!(
+            "bufferbloat test: {} {elapsed:.0}s{rate}",
+            running.phase.name()
+        ))
+    }
+
+    fn finish(&mut self, mut running: Running) {
+        if let Some(load) = running.load.take() {
+            load.stop();
+        }
+        let Some(idle) = median(&mut running.idle) else {
+            self.error = Some("bufferbloat test got no idle latency samples".to_string());
+            return;
+        };
+        let download = median(&mut running.download);
+        let upload = median(&mut running.upload);
+        let worst = download.into_iter().chain(upload).fold(idle, f64::max);
+        self.results.push(BufferbloatResult {
+            time: unix_time(),
+            grade: grade(worst - idle).to_string(),
+            idle,
+            download,
+            upload,
+            download_rate: running.download_rate,
+            upload_rate: running.upload_rate,
+        });
+        self.error = match &self.path {
+            Some(path) => save(path, &self.results).err().map(|e| format!("{e:#}")),
+            None => None,
+        };
+    }
+}
+
+fn load(path: &Path) -> Result<Vec<BufferbloatResult>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        fs::read_to_string(path).or_export(|| format!("failed to read {}", path.display()))?;
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).or_export(|| format!("failed to read {}", path.display()))
+        })
+        .collect()
+}
+
+fn save(path: &Path, results: &[BufferbloatResult]) -> Result<()> {
+    let mut contents = String::new();
+    for result in results {
+        contents.push_str(&serde_json::to_string(result).or_export(|| "failed to encode result")?);
+        contents.push('\n');
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).or_export(|| format!("failed to create {}", dir.display()))?;
+    }
+    fs::write(path, contents).or_export(|| format!("failed to write {}", path.display()))
+}
+
+fn speed_test_url(app: &App, direction: Direction) -> Option<Result<HttpUrl>> {
+    let url = match direction {
+        Direction::Download => &app.config.speedtest.download,
+        Direction::Upload => &app.config.speedtest.upload,
+    };
+    url.as_deref().map(HttpUrl::parse)
+}
+
+// Starts a test, or cancels the one running
+pub fn toggle_bufferbloat(app: &mut App) {
+    if let Some(mut running) = app.bufferbloat.running.take() {
+        if let Some(load) = running.load.take() {
+            load.stop();
+        }
+        return;
+    }
+    if app.config.speedtest.download.is_none() && app.config.speedtest.upload.is_none() {
+        app.bufferbloat.error = Some(
+            "set a [speedtest] download or upload URL to run the bufferbloat test".to_string(),
+        );
+        return;
+    }
+    start_latency(app);
+    let now = unix_time();
+    app.bufferbloat.error = None;
+    app.bufferbloat.running = Some(Running {
+        phase: Phase::Idle,
+        phase_started: now,
+        last_sample: now,
+        idle: Vec::new(),
+        download: Vec::new(),
+        upload: Vec::new(),
+        download_rate: None,
+        upload_rate: None,
+        load: None,
+    });
+}
+
+// The next phase after `phase` that has a URL configured
+fn next_phase(app: &App, phase: Phase) -> Option<(Direction, HttpUrl)> {
+    let directions = match phase {
+        Phase::Idle => vec![Direction::Download, Direction::Upload],
+        Phase::Loaded(Direction::Download) => vec![Direction::Upload],
+        Phase::Loaded(Direction::Upload) => Vec::new(),
+    };
+    directions.into_iter().find_map(|direction| {
+        let url = speed_test_url(app, direction)?.ok()?;
+        Some((direction, url))
+    })
+}
+
+pub fn update_bufferbloat(app: &mut App) {
+    let Some(running) = &mut app.bufferbloat.running else {
+        return;
+    };
+    for sample in app.latency.samples.iter() {
+        if sample.time <= running.last_sample {
+            continue;
+        }
+        running.last_sample = sample.time;
+        // lost probes count as the reply timeout, they are what bufferbloat
+        // looks like at its worst
+        let rtt = sample.rtt.unwrap_or(2000.0);
+        match running.phase {
+            Phase::Idle => running.idle.push(rtt),
+            Phase::Loaded(Direction::Download) => running.download.push(rtt),
+            Phase::Loaded(Direction::Upload) => running.upload.push(rtt),
+        }
+    }
+    if let Some(error) = running.load.as_ref().and_then(Load::error) {
+        if let Some(load) = running.load.take() {
+            load.stop();
+        }
+        app.bufferbloat.running = None;
+        app.bufferbloat.error = Some(format!("bufferbloat test failed: {error}"));
+        return;
+    }
+    if let Some(error) = &app.latency.error {
+        app.bufferbloat.error = Some(format!("bufferbloat test needs ping: {error}"));
+        app.bufferbloat.running = None;
+        return;
+    }
+
+    let now = unix_time();
+    let duration = match running.phase {
+        Phase::Idle => IDLE_SECONDS,
+        Phase::Loaded(_) => LOADED_SECONDS,
+    };
+    if now - running.phase_started < duration {
+        return;
+    }
+    if let Some(load) = running.load.take() {
+        let rate = Some(load.stop());
+        match running.phase {
+            Phase::Loaded(Direction::Download) => running.download_rate = rate,
+            Phase::Loaded(Direction::Upload) => running.upload_rate = rate,
+            Phase::Idle => {}
+        }
+    }
+    let phase = running.phase;
+    match next_phase(app, phase) {
+        Some((direction, url)) => {
+            let streams = app.config.speedtest.streams;
+            let running = app.bufferbloat.running.as_mut().unwrap();
+            running.phase = Phase::Loaded(direction);
+            running.phase_started = now;
+            running.load = Some(Load::start(&url, direction, streams));
+        }
+        None => {
+            let running = app.bufferbloat.running.take().unwrap();
+            app.bufferbloat.finish(running);
+        }
+    }
+}
+
+pub const BUFFERBLOAT_HEIGHT: u16 = SHOWN_RESULTS as u16 + 3;
+
+pub fn create_bufferbloat_paragraph<'a>(app: &App) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let bufferbloat = &app.bufferbloat;
+    let mut lines = Vec::new();
+    if let Some(status) = bufferbloat.status() {
+        lines.push(Line::from(status));
+    } else if let Some(error) = &bufferbloat.error {
+        lines.push(Line::styled(error.to_string(), theme.alert));
+    } else {
+        lines.push(Line::from("B to run a bufferbloat test"));
+    }
+    lines.extend(
+        bufferbloat
+            .results
+            .iter()
+            .rev()
+            .take(SHOWN_RESULTS)
+            .map(|result| Line::from(result.describe())),
+    );
+    let block = Block::default()
+        .title(Span::styled("Bufferbloat", theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Paragraph::new(lines).style(theme.text).block(block)
+}
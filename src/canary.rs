@@ -0,0 +1,269 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Write},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    app::App,
+    error::{Error, Result, ResultExt},
+    history::unix_time,
+    route::{resolve, route_get},
+    speedtest::{connect, Direction, HttpUrl},
+    units::{format_rate, parse_bytes},
+};
+
+// Small periodic transfers against endpoints of your own, so a quiet link
+// can be told apart from a broken one. Disabled unless a URL is set, e.g.
+//   [canary]
+//   download = "http://example.com/256k.bin"
+//   interval = 300
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CanaryConfig {
+    // plain http:// URL of a small file, read up to `size` bytes
+    pub download: Option<String>,
+    // http:// URL accepting POST bodies of `size` bytes
+    pub upload: Option<String>,
+    pub size: String,
+    // seconds between probes
+    pub interval: f64,
+}
+
+impl Default for CanaryConfig {
+    fn default() -> Self {
+        CanaryConfig {
+            download: None,
+            upload: None,
+            size: "256 KB".to_string(),
+            interval: 300.0,
+        }
+    }
+}
+
+impl CanaryConfig {
+    pub fn validate(&self) -> Result<()> {
+        for url in self.download.iter().chain(&self.upload) {
+            HttpUrl::parse(url)?;
+        }
+        if parse_bytes(&self.size).is_none_or(|size| size == 0) {
+            return Err(Error::config(format!(
+                "invalid canary size \"{}\"",
+                self.size
+            )));
+        }
+        if self.interval <= 0.0 {
+            return Err(Error::config("canary interval must be above 0"));
+        }
+        Ok(())
+    }
+
+    fn enabled(&self) -> bool {
+        self.download.is_some() || self.upload.is_some()
+    }
+}
+
+// One probe transfer, with the passive rate of the interface it went
+// through just before it started
+pub struct CanaryResult {
+    pub time: f64,
+    pub direction: Direction,
+    pub interface: Option<String>,
+    // bytes per second, or why the transfer failed
+    pub rate: Result<f64>,
+    pub passive: f64,
+}
+
+// What a probe thread found out: the interface the endpoints are routed
+// through and each transfer's rate
+type Transfers = (Option<String>, Vec<(Direction, Result<f64>)>);
+
+struct Running {
+    thread: JoinHandle<Transfers>,
+    // sent and received bytes per second of every interface at the start
+    passive: HashMap<String, (f64, f64)>,
+}
+
+// Results kept for the status line and snapshots
+const CANARY_HISTORY: usize = 100;
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct Canary {
+    running: Option<Running>,
+    // unix time of the next probe, the first one runs right away
+    next: f64,
+    pub results: VecDeque<CanaryResult>,
+}
+
+impl Canary {
+    // The latest outcome in each direction, e.g. "canary down 2.1 MB/s, up failed: ..."
+    pub fn status(&self) -> Option<String> {
+        let latest = |direction| {
+            self.results
+                .iter()
+                .rev()
+                .find(|result| result.direction == direction)
+        };
+        let parts: Vec<String> = [("down", Direction::Download), ("up", Direction::Upload)]
+            .into_iter()
+            .filter_map(|(name, direction)| {
+                let result = latest(direction)?;
+                Some(match &result.rate {
+                    Ok(rate) => format!("{name} {}", format_rate(*rate)),
+                    Err(e) => format!("{name} failed: {e:#}"),
+                })
+            })
+            .collect();
+        (!parts.is_empty()).then(|| format!("canary {}", parts.join(", ")))
+    }
+
+    fn finish(&mut self, running: Running) {
+        let time = unix_time();
+        let (interface, transfers) = running.thread.join().unwrap_or_else(|_| (None, Vec::new()));
+        for (direction, rate) in transfers {
+            let passive = interface
+                .as_ref()
+                .and_then(|name| running.passive.get(name))
+                .map_or(0.0, |(sent, rec)| match direction {
+                    Direction::Download => *rec,
+                    Direction::Upload => *sent,
+                });
+            if self.results.len() == CANARY_HISTORY {
+                self.results.pop_front();
+            }
+            self.results.push_back(CanaryResult {
+                time,
+                direction,
+                interface: interface.clone(),
+                rate,
+                passive,
+            });
+        }
+    }
+}
+
+fn probe(config: CanaryConfig) -> Transfers {
+    let size = parse_bytes(&config.size).unwrap_or_default();
+    let urls = [
+        (Direction::Download, &config.download),
+        (Direction::Upload, &config.upload),
+    ];
+    let mut interface = None;
+    let mut transfers = Vec::new();
+    for (direction, url) in urls {
+        let Some(url) = url.as_deref().and_then(|url| HttpUrl::parse(url).ok()) else {
+            continue;
+        };
+        if interface.is_none() {
+            interface = resolve(url.host.trim_matches(['[', ']']))
+                .and_then(route_get)
+                .ok()
+                .map(|route| route.interface);
+        }
+        let rate = match direction {
+            Direction::Download => download(&url, size),
+            Direction::Upload => upload(&url, size),
+        };
+        transfers.push((direction, rate.or_backend(|| url.host.clone())));
+    }
+    (interface, transfers)
+}
+
+// Measured from the request until `size` bytes or the whole response arrived
+fn download(url: &HttpUrl, size: u64) -> io::Result<f64> {
+    let started = Instant::now();
+    let mut stream = connect(url, TRANSFER_TIMEOUT)?;
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        url.path, url.host
+    )?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut received = 0;
+    while received < size {
+        let len = stream.read(&mut buffer)?;
+        if len == 0 {
+            break;
+        }
+        if received == 0 {
+            check_status(&buffer[..len])?;
+        }
+        received += len as u64;
+    }
+    Ok(received as f64 / started.elapsed().as_secs_f64().max(f64::EPSILON))
+}
+
+// Measured from the request until the server answered the whole body
+fn upload(url: &HttpUrl, size: u64) -> io::Result<f64> {
+    let started = Instant::now();
+    let mut stream = connect(url, TRANSFER_TIMEOUT)?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/octet-stream\r\nContent-Length: {size}\r\nConnection: close\r\n\r\n",
+        url.path, url.host
+    )?;
+    let buffer = vec![0u8; 64 * 1024];
+    let mut sent = 0;
+    while sent < size {
+        let len = (size - sent).min(buffer.len() as u64) as usize;
+        stream.write_all(&buffer[..len])?;
+        sent += len as u64;
+    }
+    let mut response = [0u8; 64];
+    let len = stream.read(&mut response)?;
+    check_status(&response[..len])?;
+    Ok(sent as f64 / started.elapsed().as_secs_f64().max(f64::EPSILON))
+}
+
+// Redirects count as failures too, the probe doesn't follow them
+fn check_status(response: &[u8]) -> io::Result<()> {
+    let line = String::from_utf8_lossy(response);
+    let status = line.split(' ').nth(1).unwrap_or_default();
+    if status.starts_with('2') {
+        Ok(())
+    } else {
+        let line = line.lines().next().unwrap_or_default().to_string();
+        Err(io::Error::other(format!("unexpected response \"{line}\"")))
+    }
+}
+
+// Starts a probe when one is due and collects its results once done
+pub fn update_canary(app: &mut App, elapsed: f64) {
+    let config = &app.config.canary;
+    if !config.enabled() {
+        return;
+    }
+    if let Some(running) = app.canary.running.take() {
+        if running.thread.is_finished() {
+            app.canary.finish(running);
+        } else {
+            app.canary.running = Some(running);
+            return;
+        }
+    }
+    let now = unix_time();
+    if now < app.canary.next || elapsed <= 0.0 {
+        return;
+    }
+    app.canary.next = now + config.interval;
+    let passive = app
+        .all_interfaces
+        .iter()
+        .map(|interface| {
+            let rates = (
+                interface.sent as f64 / elapsed,
+                interface.rec as f64 / elapsed,
+            );
+            (interface.name.to_string(), rates)
+        })
+        .collect();
+    let config = config.clone();
+    app.canary.running = Some(Running {
+        thread: thread::spawn(move || probe(config)),
+        passive,
+    });
+}
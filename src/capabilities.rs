@@ -0,0 +1,125 @@
+use std::env;
+
+use ratatui::style::Color;
+
+// What the attached terminal is able to display, detected once at startup
+// from the environment so renderers can degrade gracefully
+#[derive(Clone, Copy)]
+pub struct Capabilities {
+    pub truecolor: bool,
+    pub colors256: bool,
+    pub unicode: bool,
+    pub braille: bool,
+    pub sixel: bool,
+    pub mouse: bool,
+}
+
+const SIXEL_TERMINALS: [&str; 4] = ["mlterm", "foot", "yaft", "contour"];
+
+impl Capabilities {
+    pub fn detect() -> Capabilities {
+        Capabilities::from_env(|name| env::var(name).ok())
+    }
+
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Capabilities {
+        let term = var("TERM").unwrap_or_default().to_lowercase();
+        let colorterm = var("COLORTERM").unwrap_or_default().to_lowercase();
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|name| var(name).filter(|value| !value.is_empty()))
+            .unwrap_or_default()
+            .to_lowercase();
+        let dumb = term.is_empty() || term == "dumb";
+        // the Linux VT only has a 512 glyph font, which lacks braille
+        let console = term == "linux";
+
+        let truecolor = !dumb && (colorterm == "truecolor" || colorterm == "24bit");
+        let unicode = !dumb && (locale.contains("utf-8") || locale.contains("utf8"));
+        Capabilities {
+            truecolor,
+            colors256: truecolor || term.contains("256color"),
+            unicode,
+            braille: unicode && !console,
+            sixel: term.contains("sixel")
+                || SIXEL_TERMINALS.iter().any(|name| term.starts_with(name)),
+            mouse: !dumb,
+        }
+    }
+
+    // Map a color to the closest one the terminal can show
+    pub fn color(&self, color: Color) -> Color {
+        match color {
+            Color::Rgb(r, g, b) if !self.truecolor => {
+                if self.colors256 {
+                    Color::Indexed(rgb_to_256(r, g, b))
+                } else {
+                    rgb_to_16(r, g, b)
+                }
+            }
+            Color::Indexed(i) if !self.colors256 && i >= 16 => {
+                let (r, g, b) = indexed_to_rgb(i);
+                rgb_to_16(r, g, b)
+            }
+            color => color,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let flag = |value: bool| if value { "yes" } else { "no" };
+        format!(
+            "truecolor: {} 256-color: {} unicode: {} braille: {} sixel: {} mouse: {}",
+            flag(self.truecolor),
+            flag(self.colors256),
+            flag(self.unicode),
+            flag(self.braille),
+            flag(self.sixel),
+            flag(self.mouse)
+        )
+    }
+}
+
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| ((v as u16 * 5 + 127) / 255) as u8;
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+fn indexed_to_rgb(i: u8) -> (u8, u8, u8) {
+    if i >= 232 {
+        let v = 8 + (i - 232) * 10;
+        return (v, v, v);
+    }
+    let i = i - 16;
+    let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+    (level(i / 36), level(i / 6 % 6), level(i % 6))
+}
+
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn rgb_to_16(r: u8, g: u8, b: u8) -> Color {
+    let distance = |(cr, cg, cb): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, cr) + d(g, cg) + d(b, cb)
+    };
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
@@ -0,0 +1,415 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use crate::{
+    app::App,
+    error::{Error, Result, ResultExt},
+};
+
+// Physical layer state of an interface from sysfs
+pub struct Carrier {
+    pub up: bool,
+    pub operstate: String,
+    // link speed in Mb/s, unknown for virtual interfaces and when down
+    pub speed: Option<u32>,
+    pub duplex: Option<String>,
+    pub changes: u64,
+    pub up_count: Option<u64>,
+    pub down_count: Option<u64>,
+    pub collisions: u64,
+}
+
+impl Carrier {
+    pub fn describe(&self) -> String {
+        let mut link = format!("Link: {}", self.operstate);
+        if let Some(speed) = self.speed {
+            link.push_str(&format!(", {speed} Mb/s"));
+        }
+        if let Some(duplex) = &self.duplex {
+            link.push_str(&format!(" {duplex} duplex"));
+        }
+        let counts = match (self.up_count, self.down_count) {
+            (Some(up), Some(down)) => format!(" ({up} up / {down} down)"),
+            _ => String::new(),
+        };
+        format!(
+            "{link}, carrier changes {}{counts}, collisions {}",
+            self.changes, self.collisions
+        )
+    }
+}
+
+// Just the carrier, cheap enough to check every tick. None when sysfs has
+// no such interface.
+pub fn read_link(name: &str) -> Option<bool> {
+    let dir = format!("/sys/class/net/{name}");
+    fs::metadata(&dir).ok()?;
+    // reading carrier fails while the interface is administratively down
+    Some(fs::read_to_string(format!("{dir}/carrier")).is_ok_and(|carrier| carrier.trim() == "1"))
+}
+
+pub fn read_carrier(name: &str) -> Option<Carrier> {
+    let read = |file: &str| {
+        fs::read_to_string(format!("/sys/class/net/{name}/{file}"))
+            .ok()
+            .map(|value| value.trim().to_string())
+    };
+    let number = |file: &str| read(file)?.parse::<u64>().ok();
+    let operstate = read("operstate")?;
+    Some(Carrier {
+        // reading carrier fails while the interface is administratively down
+        up: read("carrier").as_deref() == Some("1"),
+        operstate,
+        // sysfs reports -1 when the speed is unknown
+        speed: read("speed")
+            .and_then(|speed| speed.parse::<i64>().ok())
+            .filter(|speed| *speed > 0)
+            .map(|speed| speed as u32),
+        duplex: read("duplex").filter(|duplex| duplex != "unknown"),
+        changes: number("carrier_changes").unwrap_or(0),
+        up_count: number("carrier_up_count"),
+        down_count: number("carrier_down_count"),
+        collisions: number("statistics/collisions").unwrap_or(0),
+    })
+}
+
+// Outcome for one twisted pair of the cable
+pub struct CablePair {
+    pub pair: char,
+    pub status: &'static str,
+    // distance to the fault in centimeters, when the PHY reports it
+    pub fault_length: Option<u32>,
+}
+
+pub type CableResult = Result<Vec<CablePair>>;
+
+// Cable tests run in the background as the PHY takes a few seconds,
+// finished results are kept per interface
+#[derive(Default)]
+pub struct CableTests {
+    running: Option<(String, Receiver<CableResult>)>,
+    pub results: HashMap<String, CableResult>,
+}
+
+impl CableTests {
+    pub fn running(&self) -> Option<&str> {
+        self.running.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    pub fn status(&self) -> Option<String> {
+        self.running()
+            .map(|name| format!("cable test on {name} running"))
+    }
+
+    pub fn describe(&self, name: &str) -> Option<String> {
+        if self.running() == Some(name) {
+            return Some("Cable test: running".to_string());
+        }
+        let pairs = match self.results.get(name)? {
+            Ok(pairs) if pairs.is_empty() => return Some("Cable test: no results".to_string()),
+            Ok(pairs) => pairs,
+            Err(e) => return Some(format!("Cable test failed: {e:#}")),
+        };
+        let pairs: Vec<String> = pairs
+            .iter()
+            .map(|pair| match pair.fault_length {
+                Some(cm) => format!(
+                    "{} {} at {:.1} m",
+                    pair.pair,
+                    pair.status,
+                    cm as f64 / 100.0
+                ),
+                None => format!("{} {}", pair.pair, pair.status),
+            })
+            .collect();
+        Some(format!("Cable test: {}", pairs.join(", ")))
+    }
+}
+
+// Runs a cable test on the selected interface, one at a time
+pub fn run_cable_test(app: &mut App) {
+    if app.cable_tests.running.is_some() {
+        return;
+    }
+    let Some(interface) = app.net_interfaces.get(app.selected) else {
+        return;
+    };
+    let name = interface.name.to_string();
+    let (sender, receiver) = mpsc::channel();
+    let thread_name = name.clone();
+    thread::spawn(move || {
+        let result = cable_test(&thread_name).or_backend(|| thread_name.clone());
+        let _ = sender.send(result);
+    });
+    app.cable_tests.results.remove(&name);
+    app.cable_tests.running = Some((name, receiver));
+}
+
+pub fn update_cable_test(app: &mut App) {
+    let Some((name, receiver)) = &app.cable_tests.running else {
+        return;
+    };
+    let result = match receiver.try_recv() {
+        Ok(result) => result,
+        Err(TryRecvError::Empty) => return,
+        Err(TryRecvError::Disconnected) => Err(Error::backend("cable test stopped")),
+    };
+    let name = name.to_string();
+    app.cable_tests.results.insert(name, result);
+    app.cable_tests.running = None;
+}
+
+#[cfg(target_os = "linux")]
+fn cable_test(name: &str) -> io::Result<Vec<CablePair>> {
+    ethtool::cable_test(name)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cable_test(_name: &str) -> io::Result<Vec<CablePair>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "cable tests are only available on Linux",
+    ))
+}
+
+// The ethtool generic netlink family: resolve its id and monitor group,
+// start the test, then wait for the completion notification
+#[cfg(target_os = "linux")]
+mod ethtool {
+    use std::{ffi::CString, time::Duration};
+
+    use super::*;
+    use crate::netlink::{self, attribute, attributes, Socket, NLA_F_NESTED};
+
+    const GENL_HEADER_LEN: usize = 4;
+
+    const GENL_ID_CTRL: u16 = 0x10;
+    const CTRL_CMD_GETFAMILY: u8 = 3;
+    const CTRL_ATTR_FAMILY_ID: u16 = 1;
+    const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+    const CTRL_ATTR_MCAST_GROUPS: u16 = 7;
+    const CTRL_ATTR_MCAST_GRP_NAME: u16 = 1;
+    const CTRL_ATTR_MCAST_GRP_ID: u16 = 2;
+
+    const ETHTOOL_MSG_CABLE_TEST_ACT: u8 = 26;
+    const ETHTOOL_MSG_CABLE_TEST_NTF: u8 = 27;
+    const ETHTOOL_A_HEADER_DEV_INDEX: u16 = 1;
+    const ETHTOOL_A_CABLE_TEST_HEADER: u16 = 1;
+    const ETHTOOL_A_CABLE_TEST_NTF_HEADER: u16 = 1;
+    const ETHTOOL_A_CABLE_TEST_NTF_STATUS: u16 = 2;
+    const ETHTOOL_A_CABLE_TEST_NTF_NEST: u16 = 3;
+    const ETHTOOL_CABLE_TEST_NTF_STATUS_COMPLETED: u8 = 2;
+    const ETHTOOL_A_CABLE_NEST_RESULT: u16 = 1;
+    const ETHTOOL_A_CABLE_NEST_FAULT_LENGTH: u16 = 2;
+    const ETHTOOL_A_CABLE_RESULT_PAIR: u16 = 1;
+    const ETHTOOL_A_CABLE_RESULT_CODE: u16 = 2;
+    const ETHTOOL_A_CABLE_FAULT_LENGTH_CM: u16 = 2;
+
+    // the PHY is given this long to finish
+    const TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+    pub fn cable_test(name: &str) -> io::Result<Vec<CablePair>> {
+        let index = interface_index(name)?;
+        let socket = Socket::open(libc::NETLINK_GENERIC)?;
+        socket.set_timeout(TEST_TIMEOUT)?;
+        let mut buffer = vec![0u8; 16 * 1024];
+
+        let (family, monitor) = resolve_family(&socket, &mut buffer)?;
+        // join the monitor group first so the notification can't be missed
+        socket.join(monitor)?;
+        let mut header = attribute(ETHTOOL_A_HEADER_DEV_INDEX, &index.to_ne_bytes());
+        header = attribute(ETHTOOL_A_CABLE_TEST_HEADER | NLA_F_NESTED, &header);
+        let flags = (libc::NLM_F_REQUEST | libc::NLM_F_ACK) as u16;
+        send(&socket, family, flags, ETHTOOL_MSG_CABLE_TEST_ACT, &header)?;
+
+        loop {
+            for message in netlink::messages(receive(&socket, &mut buffer)?) {
+                let (kind, payload) = (message.kind, message.payload);
+                if kind as libc::c_int == libc::NLMSG_ERROR {
+                    // an ack carries error 0, anything else is why the
+                    // test couldn't start
+                    match netlink::outcome(payload) {
+                        Err(e) if e.raw_os_error() == Some(libc::EOPNOTSUPP) => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::Unsupported,
+                                "not supported by this interface",
+                            ));
+                        }
+                        Err(e) => return Err(e),
+                        Ok(()) => continue,
+                    }
+                }
+                if kind != family || payload.len() < GENL_HEADER_LEN {
+                    continue;
+                }
+                if payload[0] != ETHTOOL_MSG_CABLE_TEST_NTF {
+                    continue;
+                }
+                if let Some(pairs) = parse_notification(&payload[GENL_HEADER_LEN..], index) {
+                    return Ok(pairs);
+                }
+            }
+        }
+    }
+
+    fn interface_index(name: &str) -> io::Result<u32> {
+        let name =
+            CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+            0 => Err(io::Error::last_os_error()),
+            index => Ok(index),
+        }
+    }
+
+    // The ethtool family id and its "monitor" multicast group
+    fn resolve_family(socket: &Socket, buffer: &mut [u8]) -> io::Result<(u16, u32)> {
+        let name = attribute(CTRL_ATTR_FAMILY_NAME, b"ethtool\0");
+        send(
+            socket,
+            GENL_ID_CTRL,
+            libc::NLM_F_REQUEST as u16,
+            CTRL_CMD_GETFAMILY,
+            &name,
+        )?;
+        let unsupported =
+            || io::Error::new(io::ErrorKind::Unsupported, "kernel has no ethtool netlink");
+        for message in netlink::messages(receive(socket, buffer)?) {
+            let (kind, payload) = (message.kind, message.payload);
+            if kind as libc::c_int == libc::NLMSG_ERROR {
+                return Err(unsupported());
+            }
+            if kind != GENL_ID_CTRL || payload.len() < GENL_HEADER_LEN {
+                continue;
+            }
+            let mut family = None;
+            let mut monitor = None;
+            for (kind, value) in attributes(&payload[GENL_HEADER_LEN..]) {
+                match kind {
+                    CTRL_ATTR_FAMILY_ID if value.len() >= 2 => {
+                        family = Some(u16::from_ne_bytes([value[0], value[1]]));
+                    }
+                    CTRL_ATTR_MCAST_GROUPS => {
+                        for (_, group) in attributes(value) {
+                            let group = attributes(group);
+                            let name = group
+                                .iter()
+                                .find(|(kind, _)| *kind == CTRL_ATTR_MCAST_GRP_NAME);
+                            let id = group
+                                .iter()
+                                .find(|(kind, _)| *kind == CTRL_ATTR_MCAST_GRP_ID);
+                            if let (Some((_, b"monitor\0")), Some((_, id))) = (name, id) {
+                                monitor = id
+                                    .get(..4)
+                                    .map(|id| u32::from_ne_bytes(id.try_into().unwrap()));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if let (Some(family), Some(monitor)) = (family, monitor) {
+                return Ok((family, monitor));
+            }
+        }
+        Err(unsupported())
+    }
+
+    // Results once the notification for `index` reports completion
+    fn parse_notification(payload: &[u8], index: u32) -> Option<Vec<CablePair>> {
+        let attributes = attributes(payload);
+        let header = attributes
+            .iter()
+            .find(|(kind, _)| *kind == ETHTOOL_A_CABLE_TEST_NTF_HEADER)?;
+        let device = self::attributes(header.1)
+            .into_iter()
+            .find(|(kind, _)| *kind == ETHTOOL_A_HEADER_DEV_INDEX)?;
+        if device.1.get(..4)? != index.to_ne_bytes() {
+            return None;
+        }
+        let status = attributes
+            .iter()
+            .find(|(kind, _)| *kind == ETHTOOL_A_CABLE_TEST_NTF_STATUS)?;
+        if status.1.first() != Some(&ETHTOOL_CABLE_TEST_NTF_STATUS_COMPLETED) {
+            return None;
+        }
+        let mut pairs: Vec<CablePair> = Vec::new();
+        let Some((_, nest)) = attributes
+            .iter()
+            .find(|(kind, _)| *kind == ETHTOOL_A_CABLE_TEST_NTF_NEST)
+        else {
+            return Some(pairs);
+        };
+        for (kind, value) in self::attributes(nest) {
+            let fields = self::attributes(value);
+            let field = |wanted: u16| {
+                fields
+                    .iter()
+                    .find(|(kind, _)| *kind == wanted)
+                    .map(|(_, value)| *value)
+            };
+            // result and fault length attributes share the pair number
+            let Some(pair) = field(ETHTOOL_A_CABLE_RESULT_PAIR).and_then(<[u8]>::first) else {
+                continue;
+            };
+            let pair = (b'A' + pair) as char;
+            match kind {
+                ETHTOOL_A_CABLE_NEST_RESULT => {
+                    let code = field(ETHTOOL_A_CABLE_RESULT_CODE).and_then(<[u8]>::first);
+                    pairs.push(CablePair {
+                        pair,
+                        status: result_name(code.copied().unwrap_or(0)),
+                        fault_length: None,
+                    });
+                }
+                ETHTOOL_A_CABLE_NEST_FAULT_LENGTH => {
+                    let cm = field(ETHTOOL_A_CABLE_FAULT_LENGTH_CM)
+                        .and_then(|cm| Some(u32::from_ne_bytes(cm.get(..4)?.try_into().ok()?)));
+                    if let Some(result) = pairs.iter_mut().find(|result| result.pair == pair) {
+                        result.fault_length = cm;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(pairs)
+    }
+
+    fn result_name(code: u8) -> &'static str {
+        match code {
+            1 => "OK",
+            2 => "open",
+            3 => "short",
+            4 => "cross short",
+            5 => "impedance mismatch",
+            6 => "noise",
+            _ => "unknown",
+        }
+    }
+
+    fn send(
+        socket: &Socket,
+        family: u16,
+        flags: u16,
+        command: u8,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        // genlmsghdr: command, version 1, reserved
+        let mut message = vec![command, 1, 0, 0];
+        message.extend(payload);
+        socket.send(&netlink::request(family, flags, 0, &message))
+    }
+
+    // One datagram, a PHY that hasn't answered by the timeout never will
+    fn receive<'a>(socket: &Socket, buffer: &'a mut [u8]) -> io::Result<&'a [u8]> {
+        socket.recv(buffer).map_err(|error| match error.kind() {
+            io::ErrorKind::WouldBlock => {
+                io::Error::new(io::ErrorKind::TimedOut, "no result from the PHY")
+            }
+            _ => error,
+        })
+    }
+}
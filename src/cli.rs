@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+
+use crate::{
+    attach::Panel,
+    collectors::BackendKind,
+    error::{Error, Result},
+};
+
+// Flags of the interactive mode
+#[derive(Default)]
+pub struct TuiOptions {
+    // file, or "-" for stdout, receiving every alert event as a JSON line
+    pub alerts_json: Option<String>,
+    // ssh destination whose interfaces are shown instead of this machine's
+    pub remote: Option<String>,
+    // address of a `net-stat serve` instance whose samples are shown instead
+    pub connect: Option<String>,
+    // choose the interfaces to monitor before the dashboard opens
+    pub pick: bool,
+    // in place of `backend` in the config
+    pub backend: Option<BackendKind>,
+    // plain sentences on stdout instead of the dashboard, for screen readers
+    pub accessible: bool,
+    // file every sample is written to
+    pub record: Option<PathBuf>,
+    // recording played back instead of live samples
+    pub replay: Option<PathBuf>,
+}
+
+impl TuiOptions {
+    // the interfaces shown belong to another machine, or another time
+    pub fn is_remote(&self) -> bool {
+        self.remote.is_some() || self.connect.is_some() || self.replay.is_some()
+    }
+}
+
+pub enum Command {
+    Tui {
+        options: TuiOptions,
+    },
+    // render one panel from a running instance
+    Attach {
+        panel: Panel,
+    },
+    // load samples exported by the CSV sink into the history store
+    DbImport {
+        csv: PathBuf,
+    },
+    // evaluate alert rules against a CSV export, or the demo scenarios
+    RulesTest {
+        samples: Option<PathBuf>,
+    },
+    // stream samples to TCP and WebSocket clients without a UI, announced
+    // on the LAN when `announce` is set
+    Serve {
+        bind: String,
+        backend: Option<BackendKind>,
+        announce: bool,
+    },
+    // list the agents announcing themselves on the LAN
+    Discover,
+}
+
+// Local only unless asked otherwise
+const DEFAULT_BIND: &str = "127.0.0.1:9091";
+
+const USAGE: &str = "usage: net-stat [--pick | --accessible] [--alerts-json <file|->] [--record <file>] [--backend <sysinfo|proc|netlink|ebpf> | --remote <user@host> | --connect <host:port> | --replay <file>] [--attach --panel <interface>[-graph|-chart|-stats] | db import --csv <file> | rules test [<file> | --demo] | serve [--bind <address:port>] [--backend <name>] [--announce] | discover]";
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let args: Vec<String> = args.into_iter().collect();
+    let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let mut options = TuiOptions::default();
+    if let Some(i) = args.iter().position(|arg| *arg == "--alerts-json") {
+        let path = args.get(i + 1).ok_or_else(|| Error::config(USAGE))?;
+        options.alerts_json = Some(path.to_string());
+        args.drain(i..i + 2);
+    }
+    if let Some(i) = args.iter().position(|arg| *arg == "--remote") {
+        let target = args.get(i + 1).ok_or_else(|| Error::config(USAGE))?;
+        options.remote = Some(target.to_string());
+        args.drain(i..i + 2);
+    }
+    if let Some(i) = args.iter().position(|arg| *arg == "--connect") {
+        let address = args.get(i + 1).ok_or_else(|| Error::config(USAGE))?;
+        options.connect = Some(address.to_string());
+        args.drain(i..i + 2);
+    }
+    if let Some(i) = args.iter().position(|arg| *arg == "--backend") {
+        let name = args.get(i + 1).ok_or_else(|| Error::config(USAGE))?;
+        options.backend = Some(BackendKind::parse(name)?);
+        args.drain(i..i + 2);
+    }
+    if let Some(i) = args.iter().position(|arg| *arg == "--record") {
+        let path = args.get(i + 1).ok_or_else(|| Error::config(USAGE))?;
+        options.record = Some(PathBuf::from(path));
+        args.drain(i..i + 2);
+    }
+    if let Some(i) = args.iter().position(|arg| *arg == "--replay") {
+        let path = args.get(i + 1).ok_or_else(|| Error::config(USAGE))?;
+        options.replay = Some(PathBuf::from(path));
+        args.drain(i..i + 2);
+    }
+    if let Some(i) = args.iter().position(|arg| *arg == "--pick") {
+        options.pick = true;
+        args.remove(i);
+    }
+    if let Some(i) = args.iter().position(|arg| *arg == "--accessible") {
+        options.accessible = true;
+        args.remove(i);
+    }
+    let announce = match args.iter().position(|arg| *arg == "--announce") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+    if options.pick && options.accessible {
+        return Err(Error::config(
+            "--pick needs the dashboard, it can't be combined with --accessible",
+        ));
+    }
+    let sources = [
+        options.remote.is_some(),
+        options.connect.is_some(),
+        options.replay.is_some(),
+    ];
+    if sources.iter().filter(|set| **set).count() > 1 {
+        return Err(Error::config(
+            "only one of --remote, --connect and --replay can be used",
+        ));
+    }
+    if options.backend.is_some() && options.is_remote() {
+        return Err(Error::config(
+            "--backend only applies to this machine, not with --remote, --connect or --replay",
+        ));
+    }
+    if options.record.is_some() && options.replay.is_some() {
+        return Err(Error::config("--record and --replay can't be combined"));
+    }
+    let backend = options.backend;
+    let tui_flags = options.alerts_json.is_some()
+        || options.is_remote()
+        || options.pick
+        || options.accessible
+        || options.record.is_some();
+    match args.as_slice() {
+        [] => Ok(Command::Tui { options }),
+        _ if tui_flags => Err(Error::config(USAGE)),
+        [command, ..] if (backend.is_some() || announce) && *command != "serve" => {
+            Err(Error::config(USAGE))
+        }
+        ["--attach", "--panel", panel] | ["--panel", panel, "--attach"] => Ok(Command::Attach {
+            panel: Panel::parse(panel),
+        }),
+        ["db", "import", "--csv", file] => Ok(Command::DbImport {
+            csv: PathBuf::from(file),
+        }),
+        ["rules", "test"] | ["rules", "test", "--demo"] => Ok(Command::RulesTest { samples: None }),
+        ["rules", "test", file] => Ok(Command::RulesTest {
+            samples: Some(PathBuf::from(file)),
+        }),
+        ["serve"] => Ok(Command::Serve {
+            bind: DEFAULT_BIND.to_string(),
+            backend,
+            announce,
+        }),
+        ["serve", "--bind", bind] => Ok(Command::Serve {
+            bind: bind.to_string(),
+            backend,
+            announce,
+        }),
+        ["discover"] => Ok(Command::Discover),
+        _ => Err(Error::config(USAGE)),
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+
+use crate::{
+    collectors::{sysinfo::SysinfoBackend, Backend, BackendKind},
+    error::Result,
+    iftype::InterfaceType,
+    networks::InterfaceData,
+    sockdiag::SocketBytes,
+};
+
+// Counters of every interface and TCP socket taken at one point in time.
+// Interfaces are neither calibrated nor ordered yet, that depends on the
+// config and happens when the snapshot is applied.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub taken: Instant,
+    // unix time a replayed sample was recorded at, None for live ones
+    pub recorded: Option<f64>,
+    pub interfaces: Vec<InterfaceData>,
+    pub sockets: Result<Vec<SocketBytes>>,
+}
+
+pub struct Collector {
+    backend: Box<dyn Backend>,
+    // detected once per interface name
+    kinds: HashMap<String, InterfaceType>,
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Collector {
+            backend: Box::<SysinfoBackend>::default(),
+            kinds: HashMap::new(),
+        }
+    }
+}
+
+impl Collector {
+    pub fn new(kind: BackendKind) -> Result<Collector> {
+        Ok(Collector::with_backend(kind.open()?))
+    }
+
+    // Any other source of counters, e.g. the remote agent or a MockBackend
+    // in tests
+    pub fn with_backend(backend: Box<dyn Backend>) -> Collector {
+        Collector {
+            backend,
+            kinds: HashMap::new(),
+        }
+    }
+
+    pub fn sample(&mut self) -> Result<Snapshot> {
+        let mut interfaces = self.backend.interfaces()?;
+        for interface in &mut interfaces {
+            let backend = &self.backend;
+            interface.kind = *self
+                .kinds
+                .entry(interface.name.to_string())
+                .or_insert_with(|| backend.kind(&interface.name));
+        }
+        Ok(Snapshot {
+            taken: Instant::now(),
+            recorded: None,
+            interfaces,
+            sockets: self.backend.sockets(),
+        })
+    }
+}
+
+struct Consumer {
+    name: String,
+    sender: Sender<Snapshot>,
+    lagged: Arc<AtomicU64>,
+}
+
+// Delivers every sample to each subscribed consumer through its own bounded
+// queue. A consumer that falls behind misses samples once its queue is full
+// instead of holding up the others.
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    consumers: Arc<Mutex<Vec<Consumer>>>,
+    // why the source stopped publishing, when it did
+    stopped: Arc<Mutex<Option<String>>>,
+}
+
+pub struct Subscription {
+    receiver: Receiver<Snapshot>,
+}
+
+impl Subscription {
+    pub async fn recv(&mut self) -> Option<Snapshot> {
+        self.receiver.recv().await
+    }
+
+    pub fn blocking_recv(&mut self) -> Option<Snapshot> {
+        self.receiver.blocking_recv()
+    }
+}
+
+impl Pipeline {
+    // `capacity` is how many samples the consumer may fall behind by
+    pub fn subscribe(&self, name: &str, capacity: usize) -> Subscription {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        self.consumers.lock().unwrap().push(Consumer {
+            name: name.to_string(),
+            sender,
+            lagged: Arc::new(AtomicU64::new(0)),
+        });
+        Subscription { receiver }
+    }
+
+    // Consumers whose subscription is dropped are removed
+    pub fn publish(&self, snapshot: &Snapshot) {
+        self.consumers.lock().unwrap().retain(|consumer| {
+            match consumer.sender.try_send(snapshot.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    consumer.lagged.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            }
+        });
+    }
+
+    // Samples each consumer missed because its queue was full
+    pub fn lagged(&self) -> Vec<(String, u64)> {
+        self.consumers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|consumer| {
+                (
+                    consumer.name.clone(),
+                    consumer.lagged.load(Ordering::Relaxed),
+                )
+            })
+            .filter(|(_, lagged)| *lagged > 0)
+            .collect()
+    }
+
+    // Ends every subscription, consumers see the end of their stream once
+    // they've drained their queue
+    pub fn close(&self) {
+        self.consumers.lock().unwrap().clear();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.consumers.lock().unwrap().is_empty()
+    }
+
+    // Called by a source that can't deliver more samples
+    pub fn stop(&self, reason: String) {
+        *self.stopped.lock().unwrap() = Some(reason);
+        self.close();
+    }
+
+    pub fn stop_reason(&self) -> Option<String> {
+        self.stopped.lock().unwrap().clone()
+    }
+}
+
+// Samples on a dedicated thread since refreshing blocks, so slow refreshes
+// never hold up the event loop. Each sample is taken once however many
+// consumers subscribe, so subscribe them first. The thread stops once every
+// subscription is gone, or with the reason once the backend fails.
+pub fn spawn(pipeline: &Pipeline, interval: Duration, mut collector: Collector) {
+    let publisher = pipeline.clone();
+    thread::spawn(move || {
+        let mut next = Instant::now() + interval;
+        loop {
+            thread::sleep(next.saturating_duration_since(Instant::now()));
+            next += interval;
+            if publisher.is_closed() {
+                break;
+            }
+            match collector.sample() {
+                Ok(snapshot) => publisher.publish(&snapshot),
+                Err(e) => {
+                    publisher.stop(format!("{e:#}"));
+                    break;
+                }
+            }
+        }
+    });
+}
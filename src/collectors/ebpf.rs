@@ -0,0 +1,254 @@
+use std::{collections::HashMap, net::SocketAddr};
+
+use crate::{
+    collectors::{netlink::NetlinkBackend, Backend},
+    connections::{read_connections, Protocol},
+    error::{Result, ResultExt},
+    iftype::InterfaceType,
+    netlink::{address, read_u16, read_u32},
+    networks::InterfaceData,
+    sockdiag::SocketBytes,
+};
+
+const TCP_CLOSE: u32 = 7;
+
+// Per-socket byte counts taken by eBPF programs on the sock:sock_send_length
+// and sock:sock_recv_length tracepoints as the bytes pass through the send
+// and receive calls, instead of a sock_diag dump of every socket per sample.
+// sock:inet_sock_set_state gives the addresses and state of the sockets
+// opened or closed since, older IPv6 sockets go unreported. Interface
+// counters come from netlink. The programs are built from ebpf/ and loaded
+// with aya. Needs root, or CAP_BPF and CAP_PERFMON.
+pub struct EbpfBackend {
+    interfaces: NetlinkBackend,
+    probes: linux::Probes,
+}
+
+impl EbpfBackend {
+    pub fn open() -> Result<EbpfBackend> {
+        Ok(EbpfBackend {
+            interfaces: NetlinkBackend::open()
+                .or_backend(|| "failed to open the netlink backend")?,
+            probes: linux::Probes::attach()?,
+        })
+    }
+
+    fn read_sockets(&mut self) -> Result<Vec<SocketBytes>> {
+        let entries = self
+            .probes
+            .entries()
+            .map_err(|e| e.context("failed to read the eBPF socket counts"))?;
+        // the inode and owner as the socket tables have them, so the counts
+        // line up with the connections and processes shown
+        let known: HashMap<(SocketAddr, SocketAddr), (u64, u32)> = read_connections()
+            .into_iter()
+            .filter(|connection| connection.protocol == Protocol::Tcp)
+            .map(|connection| {
+                (
+                    (connection.local, connection.remote),
+                    (connection.inode, connection.uid),
+                )
+            })
+            .collect();
+        let mut sockets = Vec::new();
+        for entry in entries {
+            let closed = entry
+                .state
+                .is_some_and(|state| read_u32(&state, 4) == TCP_CLOSE);
+            if let Some(mut socket) = socket(&entry) {
+                if let Some((inode, uid)) = known.get(&(socket.local, socket.remote)) {
+                    socket.inode = *inode;
+                    socket.uid = *uid;
+                }
+                sockets.push(socket);
+            }
+            // reported with its last bytes once, then dropped
+            if closed {
+                self.probes
+                    .forget(entry.sock)
+                    .map_err(|e| e.context("failed to drop a closed socket's counts"))?;
+            }
+        }
+        Ok(sockets)
+    }
+}
+
+impl Backend for EbpfBackend {
+    fn interfaces(&mut self) -> Result<Vec<InterfaceData>> {
+        self.interfaces.interfaces()
+    }
+
+    fn kind(&self, name: &str) -> InterfaceType {
+        self.interfaces.kind(name)
+    }
+
+    fn sockets(&mut self) -> Result<Vec<SocketBytes>> {
+        self.read_sockets()
+    }
+}
+
+// The last inet_sock_set_state seen for the socket when there is one, the
+// start of its struct sock_common otherwise. None when neither has the
+// addresses.
+fn socket(entry: &linux::Entry) -> Option<SocketBytes> {
+    let (local, remote, state) = match &entry.state {
+        // the tracepoint's fields from oldstate on, ports in host order
+        Some(state) => {
+            let (sport, dport) = (read_u16(state, 8), read_u16(state, 10));
+            let (local, remote) = match read_u16(state, 12) as i32 {
+                libc::AF_INET => (address(&state[16..20])?, address(&state[20..24])?),
+                libc::AF_INET6 => (address(&state[24..40])?, address(&state[40..56])?),
+                _ => return None,
+            };
+            (
+                SocketAddr::new(local, sport),
+                SocketAddr::new(remote, dport),
+                read_u32(state, 4) as u8,
+            )
+        }
+        // skc_daddr, skc_rcv_saddr, skc_hash, skc_dport in network order,
+        // skc_num, skc_family and skc_state
+        None => {
+            let common = &entry.common;
+            if read_u16(common, 16) as i32 != libc::AF_INET {
+                return None;
+            }
+            let dport = u16::from_be_bytes([common[12], common[13]]);
+            (
+                SocketAddr::new(address(&common[4..8])?, read_u16(common, 14)),
+                SocketAddr::new(address(&common[0..4])?, dport),
+                common[18],
+            )
+        }
+    };
+    Some(SocketBytes {
+        inode: 0,
+        uid: entry.uid,
+        local,
+        remote,
+        state,
+        sent: entry.sent,
+        rec: entry.rec,
+    })
+}
+
+mod linux {
+    use aya::{
+        include_bytes_aligned,
+        maps::{HashMap, MapData, PerCpuHashMap},
+        programs::TracePoint,
+        Ebpf,
+    };
+
+    use crate::error::{Error, Result, ResultExt};
+
+    // the values of ebpf/src/main.rs's maps: the uid and struct
+    // sock_common of the owners and the inet_sock_set_state fields
+    const COMMON_LEN: usize = 24;
+    const OWNER_LEN: usize = 8 + COMMON_LEN;
+    const STATE_LEN: usize = 56;
+    const TRACEPOINTS: [&str; 3] = [
+        "sock_send_length",
+        "sock_recv_length",
+        "inet_sock_set_state",
+    ];
+
+    pub struct Entry {
+        pub sock: u64,
+        pub sent: u64,
+        pub rec: u64,
+        pub uid: u32,
+        pub common: [u8; COMMON_LEN],
+        pub state: Option<[u8; STATE_LEN]>,
+    }
+
+    // The maps and attached programs, detached when dropped
+    pub struct Probes {
+        counts: PerCpuHashMap<MapData, u64, [u64; 2]>,
+        owners: HashMap<MapData, u64, [u8; OWNER_LEN]>,
+        states: HashMap<MapData, u64, [u8; STATE_LEN]>,
+        _ebpf: Ebpf,
+    }
+
+    impl Probes {
+        pub fn attach() -> Result<Probes> {
+            let mut ebpf = Ebpf::load(include_bytes_aligned!(concat!(
+                env!("OUT_DIR"),
+                "/net-stat-ebpf"
+            )))
+            .or_backend(|| {
+                "failed to load the eBPF programs, they need root, or CAP_BPF and CAP_PERFMON"
+            })?;
+            for name in TRACEPOINTS {
+                let program: &mut TracePoint = ebpf
+                    .program_mut(name)
+                    .ok_or_else(|| Error::backend(format!("no {name} program")))?
+                    .try_into()
+                    .or_backend(|| format!("{name} is not a tracepoint program"))?;
+                program
+                    .load()
+                    .or_backend(|| format!("the kernel refused the {name} program"))?;
+                program
+                    .attach("sock", name)
+                    .or_backend(|| format!("failed to attach to sock:{name}"))?;
+            }
+            let mut map = |name: &str| {
+                ebpf.take_map(name)
+                    .ok_or_else(|| Error::backend(format!("no {name} map")))
+            };
+            let (counts, owners, states) = (map("COUNTS")?, map("OWNERS")?, map("STATES")?);
+            Ok(Probes {
+                counts: counts
+                    .try_into()
+                    .or_backend(|| "COUNTS is not a per-CPU hash")?,
+                owners: owners.try_into().or_backend(|| "OWNERS is not a hash")?,
+                states: states.try_into().or_backend(|| "STATES is not a hash")?,
+                _ebpf: ebpf,
+            })
+        }
+
+        pub fn entries(&self) -> Result<Vec<Entry>> {
+            let mut entries = Vec::new();
+            for sock in self.counts.keys() {
+                let sock = sock.or_backend(|| "failed to list the sockets")?;
+                // gone since the key was read
+                let Some(counts) = found(self.counts.get(&sock, 0))? else {
+                    continue;
+                };
+                let (sent, rec) = counts
+                    .iter()
+                    .fold((0, 0), |(sent, rec), cpu| (sent + cpu[0], rec + cpu[1]));
+                let owner = found(self.owners.get(&sock, 0))?.unwrap_or([0; OWNER_LEN]);
+                entries.push(Entry {
+                    sock,
+                    sent,
+                    rec,
+                    uid: u32::from_ne_bytes(owner[..4].try_into().unwrap()),
+                    common: owner[8..].try_into().unwrap(),
+                    state: found(self.states.get(&sock, 0))?,
+                });
+            }
+            Ok(entries)
+        }
+
+        pub fn forget(&mut self, sock: u64) -> Result<()> {
+            for removed in [
+                self.counts.remove(&sock),
+                self.owners.remove(&sock),
+                self.states.remove(&sock),
+            ] {
+                found(removed)?;
+            }
+            Ok(())
+        }
+    }
+
+    // None for keys that aren't in the map
+    fn found<T>(result: std::result::Result<T, aya::maps::MapError>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(aya::maps::MapError::KeyNotFound) => Ok(None),
+            result => result.map(Some).or_backend(|| "failed to read an eBPF map"),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+#[cfg(all(target_os = "linux", feature = "ebpf"))]
+pub mod ebpf;
+pub mod mock;
+pub mod netlink;
+pub mod procfs;
+pub mod remote;
+pub mod sysinfo;
+
+use std::collections::HashMap;
+
+use ::sysinfo::MacAddr;
+use serde::Deserialize;
+
+use self::{netlink::NetlinkBackend, procfs::ProcBackend, sysinfo::SysinfoBackend};
+use crate::{
+    error::{Error, Result, ResultExt},
+    iftype::InterfaceType,
+    networks::{InterfaceData, PacketCounters, PacketErrors, RawCounters},
+    sockdiag::{tcp_socket_bytes, SocketBytes},
+};
+
+// Where this machine's interface counters come from, `backend` in the config
+// or --backend. The remote agent is picked with --remote instead since it
+// needs a host.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Sysinfo,
+    // /proc/net/dev, Linux only
+    Proc,
+    Netlink,
+    // eBPF programs on the socket tracepoints for the per-socket bytes,
+    // Linux only and built with the ebpf feature
+    Ebpf,
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 4] = [
+        BackendKind::Sysinfo,
+        BackendKind::Proc,
+        BackendKind::Netlink,
+        BackendKind::Ebpf,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendKind::Sysinfo => "sysinfo",
+            BackendKind::Proc => "proc",
+            BackendKind::Netlink => "netlink",
+            BackendKind::Ebpf => "ebpf",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<BackendKind> {
+        BackendKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = BackendKind::ALL.iter().map(BackendKind::name).collect();
+                Error::config(format!(
+                    "unknown backend \"{name}\", expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+
+    pub fn open(&self) -> Result<Box<dyn Backend>> {
+        Ok(match self {
+            BackendKind::Sysinfo => Box::<SysinfoBackend>::default(),
+            BackendKind::Proc => {
+                Box::new(ProcBackend::open().or_backend(|| "failed to open the proc backend")?)
+            }
+            BackendKind::Netlink => Box::new(
+                NetlinkBackend::open().or_backend(|| "failed to open the netlink backend")?,
+            ),
+            BackendKind::Ebpf => open_ebpf()?,
+        })
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "ebpf"))]
+fn open_ebpf() -> Result<Box<dyn Backend>> {
+    Ok(Box::new(ebpf::EbpfBackend::open()?))
+}
+
+#[cfg(not(all(target_os = "linux", feature = "ebpf")))]
+fn open_ebpf() -> Result<Box<dyn Backend>> {
+    Err(Error::config(
+        "the ebpf backend is Linux only and needs net-stat built with --features ebpf",
+    ))
+}
+
+// A source of interface counters, totals since boot and the change since
+// the previous call. An error ends collection, a source that can carry on
+// reports what it last saw instead.
+pub trait Backend: Send {
+    fn interfaces(&mut self) -> Result<Vec<InterfaceData>>;
+
+    // Asked once per interface name
+    fn kind(&self, name: &str) -> InterfaceType {
+        InterfaceType::detect(name)
+    }
+
+    fn sockets(&mut self) -> Result<Vec<SocketBytes>> {
+        tcp_socket_bytes().or_backend(|| "failed to read the TCP sockets")
+    }
+}
+
+// Totals of one interface as most sources report them, the counters of
+// struct rtnl_link_stats64 that are shown
+#[derive(Clone, Copy, Default)]
+pub struct Totals {
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+    pub rx_multicast: u64,
+    // from the driver, see BroadcastCounter
+    pub rx_broadcast: Option<u64>,
+}
+
+// Works out the change per sample for sources that only report totals.
+// Interfaces missing from a sample are forgotten.
+#[derive(Default)]
+pub struct Deltas {
+    previous: HashMap<String, Totals>,
+}
+
+impl Deltas {
+    pub fn interfaces(
+        &mut self,
+        links: impl IntoIterator<Item = (String, [u8; 6], Totals)>,
+    ) -> Vec<InterfaceData> {
+        let previous = std::mem::take(&mut self.previous);
+        links
+            .into_iter()
+            .map(|(name, mac, totals)| {
+                // the first sample only establishes the starting point
+                let before = previous.get(&name).copied().unwrap_or(totals);
+                let interface = interface(&name, mac, &totals, &before);
+                self.previous.insert(name, totals);
+                interface
+            })
+            .collect()
+    }
+}
+
+// Counters wrap or reset when a driver is reloaded, that sample counts as
+// nothing moved
+fn delta(now: u64, before: u64) -> u64 {
+    now.saturating_sub(before)
+}
+
+fn interface(name: &str, mac: [u8; 6], totals: &Totals, before: &Totals) -> InterfaceData {
+    let raw = RawCounters {
+        sent_total: totals.tx_bytes,
+        rec_total: totals.rx_bytes,
+        sent: delta(totals.tx_bytes, before.tx_bytes),
+        rec: delta(totals.rx_bytes, before.rx_bytes),
+    };
+    InterfaceData {
+        name: name.to_string(),
+        sent_total: raw.sent_total,
+        rec_total: raw.rec_total,
+        sent: raw.sent,
+        rec: raw.rec,
+        mac: MacAddr(mac),
+        raw,
+        errors: PacketErrors {
+            rx_errors: delta(totals.rx_errors, before.rx_errors),
+            tx_errors: delta(totals.tx_errors, before.tx_errors),
+            rx_errors_total: totals.rx_errors,
+            tx_errors_total: totals.tx_errors,
+            rx_dropped: delta(totals.rx_dropped, before.rx_dropped),
+            tx_dropped: delta(totals.tx_dropped, before.tx_dropped),
+            rx_dropped_total: totals.rx_dropped,
+            tx_dropped_total: totals.tx_dropped,
+        },
+        packets: PacketCounters {
+            sent: delta(totals.tx_packets, before.tx_packets),
+            rec: delta(totals.rx_packets, before.rx_packets),
+            sent_total: totals.tx_packets,
+            rec_total: totals.rx_packets,
+            multicast: delta(totals.rx_multicast, before.rx_multicast),
+            multicast_total: totals.rx_multicast,
+            broadcast: totals
+                .rx_broadcast
+                .map(|now| delta(now, before.rx_broadcast.unwrap_or(now))),
+            broadcast_total: totals.rx_broadcast,
+        },
+        carrier_changes: None,
+        description: None,
+        kind: InterfaceType::Unknown,
+    }
+}
+
+// "aa:bb:cc:dd:ee:ff" as sysfs writes it, or with dashes. None for anything
+// else, e.g. the longer addresses of some tunnels.
+pub fn parse_mac(text: &str) -> Option<[u8; 6]> {
+    let bytes: Vec<u8> = text
+        .trim()
+        .split([':', '-'])
+        .map(|byte| {
+            u8::from_str_radix(byte, 16)
+                .ok()
+                .filter(|_| byte.len() == 2)
+        })
+        .collect::<Option<_>>()?;
+    bytes.try_into().ok()
+}
+
+pub fn format_mac(mac: &[u8; 6]) -> String {
+    mac.map(|byte| format!("{byte:02x}")).join(":")
+}
@@ -0,0 +1,194 @@
+use std::io;
+
+use crate::{
+    broadcast::BroadcastCounter,
+    collectors::{Backend, Deltas, Totals},
+    error::Result,
+    networks::{InterfaceData, PacketCounters, PacketErrors},
+};
+
+struct Link {
+    name: String,
+    mac: [u8; 6],
+    stats: Totals,
+    carrier_changes: Option<u64>,
+}
+
+// Interface counters straight from rtnetlink, one RTM_GETLINK dump per
+// sample instead of a sysfs read per counter, with the drops, multicast and
+// carrier changes sysinfo leaves out. Linux only.
+pub struct NetlinkBackend {
+    socket: linux::Socket,
+    deltas: Deltas,
+    broadcasts: BroadcastCounter,
+    // reported again with nothing moved when a dump fails
+    last: Vec<InterfaceData>,
+}
+
+impl NetlinkBackend {
+    pub fn open() -> io::Result<NetlinkBackend> {
+        let socket = linux::Socket::open()?;
+        let mut backend = NetlinkBackend {
+            socket,
+            deltas: Deltas::default(),
+            broadcasts: BroadcastCounter::default(),
+            last: Vec::new(),
+        };
+        // fail now rather than on every sample
+        backend.last = backend.read()?;
+        Ok(backend)
+    }
+
+    fn read(&mut self) -> io::Result<Vec<InterfaceData>> {
+        let links = self.socket.dump_links()?;
+        let carrier_changes: Vec<Option<u64>> =
+            links.iter().map(|link| link.carrier_changes).collect();
+        let broadcasts = &mut self.broadcasts;
+        let mut interfaces = self.deltas.interfaces(links.into_iter().map(|mut link| {
+            link.stats.rx_broadcast = broadcasts.read(&link.name);
+            (link.name, link.mac, link.stats)
+        }));
+        for (interface, carrier_changes) in interfaces.iter_mut().zip(carrier_changes) {
+            interface.carrier_changes = carrier_changes;
+        }
+        Ok(interfaces)
+    }
+}
+
+impl Backend for NetlinkBackend {
+    fn interfaces(&mut self) -> Result<Vec<InterfaceData>> {
+        let interfaces = match self.read() {
+            Ok(interfaces) => {
+                self.last = interfaces.clone();
+                interfaces
+            }
+            Err(_) => self
+                .last
+                .iter()
+                .cloned()
+                .map(|mut interface| {
+                    interface.raw.sent = 0;
+                    interface.raw.rec = 0;
+                    interface.sent = 0;
+                    interface.rec = 0;
+                    interface.packets = PacketCounters {
+                        sent: 0,
+                        rec: 0,
+                        multicast: 0,
+                        broadcast: interface.packets.broadcast.map(|_| 0),
+                        ..interface.packets
+                    };
+                    interface.errors = PacketErrors {
+                        rx_errors: 0,
+                        tx_errors: 0,
+                        rx_dropped: 0,
+                        tx_dropped: 0,
+                        ..interface.errors
+                    };
+                    interface
+                })
+                .collect(),
+        };
+        Ok(interfaces)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::*;
+    use crate::netlink;
+
+    const IFINFOMSG_LEN: usize = 16;
+    const RTM_NEWLINK: u16 = 16;
+    const RTM_GETLINK: u16 = 18;
+    const IFLA_ADDRESS: u16 = 1;
+    const IFLA_IFNAME: u16 = 3;
+    const IFLA_STATS64: u16 = 23;
+    const IFLA_CARRIER_CHANGES: u16 = 35;
+    // the counters used, in the order of struct rtnl_link_stats64
+    const STATS64_LEN: usize = 9 * 8;
+
+    pub struct Socket(netlink::Socket);
+
+    impl Socket {
+        pub fn open() -> io::Result<Socket> {
+            netlink::Socket::open(libc::NETLINK_ROUTE).map(Socket)
+        }
+
+        pub fn dump_links(&mut self) -> io::Result<Vec<Link>> {
+            let mut links = Vec::new();
+            // an empty ifinfomsg, AF_UNSPEC and every link
+            self.0.dump(RTM_GETLINK, &[0u8; IFINFOMSG_LEN], |message| {
+                if message.kind == RTM_NEWLINK {
+                    links.extend(parse_link(message.payload));
+                }
+            })?;
+            Ok(links)
+        }
+    }
+
+    fn parse_link(message: &[u8]) -> Option<Link> {
+        let mut name = None;
+        let mut mac = [0u8; 6];
+        let mut stats = None;
+        let mut carrier_changes = None;
+        for (kind, payload) in netlink::attributes(message.get(IFINFOMSG_LEN..)?) {
+            match kind {
+                IFLA_IFNAME => {
+                    let end = payload
+                        .iter()
+                        .position(|b| *b == 0)
+                        .unwrap_or(payload.len());
+                    name = Some(String::from_utf8_lossy(&payload[..end]).into_owned());
+                }
+                // tunnels have shorter or no hardware addresses
+                IFLA_ADDRESS if payload.len() == 6 => mac.copy_from_slice(payload),
+                IFLA_STATS64 if payload.len() >= STATS64_LEN => {
+                    let counter = |i: usize| netlink::read_u64(payload, i * 8);
+                    stats = Some(Totals {
+                        rx_packets: counter(0),
+                        tx_packets: counter(1),
+                        rx_bytes: counter(2),
+                        tx_bytes: counter(3),
+                        rx_errors: counter(4),
+                        tx_errors: counter(5),
+                        rx_dropped: counter(6),
+                        tx_dropped: counter(7),
+                        rx_multicast: counter(8),
+                        rx_broadcast: None,
+                    });
+                }
+                IFLA_CARRIER_CHANGES if payload.len() >= 4 => {
+                    carrier_changes = Some(netlink::read_u32(payload, 0) as u64);
+                }
+                _ => {}
+            }
+        }
+        Some(Link {
+            name: name?,
+            mac,
+            stats: stats.unwrap_or_default(),
+            carrier_changes,
+        })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod linux {
+    use super::*;
+
+    pub struct Socket;
+
+    impl Socket {
+        pub fn open() -> io::Result<Socket> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the netlink backend is only available on Linux",
+            ))
+        }
+
+        pub fn dump_links(&mut self) -> io::Result<Vec<Link>> {
+            Ok(Vec::new())
+        }
+    }
+}
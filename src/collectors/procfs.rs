@@ -0,0 +1,77 @@
+use std::{fs, io};
+
+use crate::{
+    broadcast::BroadcastCounter,
+    collectors::{parse_mac, Backend, Deltas, Totals},
+    error::{Result, ResultExt},
+    networks::InterfaceData,
+};
+
+const NET_DEV: &str = "/proc/net/dev";
+
+// Interface counters from /proc/net/dev, one file read per sample for
+// every interface with the drops and multicast included. Works where netlink sockets are
+// blocked, e.g. in some containers. Linux only.
+#[derive(Default)]
+pub struct ProcBackend {
+    deltas: Deltas,
+    broadcasts: BroadcastCounter,
+}
+
+impl ProcBackend {
+    pub fn open() -> io::Result<ProcBackend> {
+        // fail now rather than on every sample
+        fs::metadata(NET_DEV)?;
+        Ok(ProcBackend::default())
+    }
+}
+
+impl Backend for ProcBackend {
+    fn interfaces(&mut self) -> Result<Vec<InterfaceData>> {
+        let text =
+            fs::read_to_string(NET_DEV).or_backend(|| format!("failed to read {NET_DEV}"))?;
+        let broadcasts = &mut self.broadcasts;
+        let links = parse_net_dev(&text).into_iter().map(|(name, mut totals)| {
+            totals.rx_broadcast = broadcasts.read(&name);
+            let mac = fs::read_to_string(format!("/sys/class/net/{name}/address"))
+                .ok()
+                .and_then(|address| parse_mac(&address))
+                // zeros for loopback, tunnels and longer addresses
+                .unwrap_or_default();
+            (name, mac, totals)
+        });
+        Ok(self.deltas.interfaces(links))
+    }
+}
+
+// Two header lines, then per interface "name:" followed by 8 receive and 8
+// transmit counters: bytes, packets, errs, drop, fifo, frame or colls,
+// compressed and multicast or carrier. Lines that don't parse are skipped.
+pub fn parse_net_dev(text: &str) -> Vec<(String, Totals)> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let values: Vec<u64> = counters
+                .split_whitespace()
+                .map(|field| field.parse().ok())
+                .collect::<Option<_>>()?;
+            if values.len() < 16 {
+                return None;
+            }
+            let totals = Totals {
+                rx_bytes: values[0],
+                rx_packets: values[1],
+                rx_errors: values[2],
+                rx_dropped: values[3],
+                rx_multicast: values[7],
+                tx_bytes: values[8],
+                tx_packets: values[9],
+                tx_errors: values[10],
+                tx_dropped: values[11],
+                rx_broadcast: None,
+            };
+            Some((name.trim().to_string(), totals))
+        })
+        .collect()
+}
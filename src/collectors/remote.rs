@@ -0,0 +1,152 @@
+use std::{
+    io::{BufRead, BufReader, Lines, Read},
+    process::{Child, ChildStderr, ChildStdout, Command, Stdio},
+};
+
+use crate::{
+    collectors::{parse_mac, Backend, Deltas, Totals},
+    error::{Error, Result, ResultExt},
+    iftype::InterfaceType,
+    networks::InterfaceData,
+    sockdiag::SocketBytes,
+};
+
+// Sampling loop run on the remote machine. It only needs a POSIX shell and
+// sysfs, so nothing has to be installed there. Every second it prints one
+// tab separated line per interface (name, the totals in COUNTERS order, MAC)
+// followed by an empty line. It avoids single quotes so it can be wrapped in
+// `sh -c '...'` whatever the remote login shell is.
+const AGENT: &str = r#"
+[ -d /sys/class/net ] || { echo "no /sys/class/net, only Linux hosts can be watched" >&2; exit 1; }
+while :; do
+  for dir in /sys/class/net/*; do
+    printf "%s" "${dir##*/}"
+    for counter in tx_bytes rx_bytes tx_packets rx_packets rx_errors tx_errors rx_dropped tx_dropped multicast; do
+      printf "\t%s" "$(cat "$dir/statistics/$counter" 2>/dev/null || echo 0)"
+    done
+    printf "\t%s\n" "$(cat "$dir/address" 2>/dev/null)"
+  done
+  echo
+  sleep 1
+done
+"#;
+
+const COUNTERS: usize = 9;
+
+// Runs the agent on `target` (anything ssh accepts, e.g. user@host) and
+// reads its samples in place of this machine's counters. Only key based
+// logins work since the terminal belongs to the TUI.
+pub struct RemoteBackend {
+    target: String,
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+    stderr: ChildStderr,
+    deltas: Deltas,
+}
+
+impl RemoteBackend {
+    pub fn open(target: &str) -> Result<RemoteBackend> {
+        if target.starts_with('-') {
+            return Err(Error::config(format!("invalid remote host \"{target}\"")));
+        }
+        let mut child = Command::new("ssh")
+            .args(["-T", "-o", "BatchMode=yes", target])
+            .arg(format!("sh -c '{AGENT}'"))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .or_backend(|| "failed to run ssh")?;
+        let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+            return Err(Error::backend("ssh has no output to read"));
+        };
+        Ok(RemoteBackend {
+            target: target.to_string(),
+            child,
+            lines: BufReader::new(stdout).lines(),
+            stderr,
+            deltas: Deltas::default(),
+        })
+    }
+
+    // Why the agent stopped, from the last thing ssh or the agent said
+    fn failure(&mut self) -> Error {
+        let _ = self.child.kill();
+        let status = self.child.wait();
+        let mut message = String::new();
+        let _ = self.stderr.read_to_string(&mut message);
+        let target = &self.target;
+        Error::backend(match message.lines().last() {
+            Some(line) => format!("remote agent on {target} failed: {line}"),
+            None => match status {
+                Ok(status) => format!("ssh {target} exited ({status})"),
+                Err(e) => format!("ssh {target} exited: {e}"),
+            },
+        })
+    }
+}
+
+impl Backend for RemoteBackend {
+    // Blocks until the agent prints its next sample
+    fn interfaces(&mut self) -> Result<Vec<InterfaceData>> {
+        let mut links = Vec::new();
+        loop {
+            match self.lines.next() {
+                Some(Ok(line)) if line.is_empty() => break,
+                Some(Ok(line)) => links.push(parse_line(&line)?),
+                _ => return Err(self.failure()),
+            }
+        }
+        Ok(self.deltas.interfaces(links))
+    }
+
+    // no sysfs here for the remote interfaces
+    fn kind(&self, name: &str) -> InterfaceType {
+        InterfaceType::guess(name)
+    }
+
+    fn sockets(&mut self) -> Result<Vec<SocketBytes>> {
+        Err(Error::backend(
+            "socket statistics are not collected from remote hosts",
+        ))
+    }
+}
+
+impl Drop for RemoteBackend {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn parse_line(line: &str) -> Result<(String, [u8; 6], Totals)> {
+    let malformed = || Error::backend(format!("malformed agent line \"{line}\""));
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [name, counters @ .., mac] = fields.as_slice() else {
+        return Err(malformed());
+    };
+    if counters.len() != COUNTERS {
+        return Err(malformed());
+    }
+    let mut values = [0; COUNTERS];
+    for (value, field) in values.iter_mut().zip(counters) {
+        *value = field.parse().map_err(|_| malformed())?;
+    }
+    let [tx_bytes, rx_bytes, tx_packets, rx_packets, rx_errors, tx_errors, rx_dropped, tx_dropped, rx_multicast] =
+        values;
+    let totals = Totals {
+        rx_packets,
+        tx_packets,
+        rx_bytes,
+        tx_bytes,
+        rx_errors,
+        tx_errors,
+        rx_dropped,
+        tx_dropped,
+        rx_multicast,
+        // ethtool isn't asked on the remote host
+        rx_broadcast: None,
+    };
+    // zeros for interfaces without a MAC
+    Ok((name.to_string(), parse_mac(mac).unwrap_or_default(), totals))
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use sysinfo::{RefreshKind, System, SystemExt};
+
+use crate::{
+    adapters::{self, Adapter},
+    broadcast::BroadcastCounter,
+    collectors::Backend,
+    error::Result,
+    networks::{read_dropped, read_statistic, InterfaceData},
+};
+
+// Portable counters through sysinfo, with drops and multicast from sysfs
+// where there is one
+pub struct SysinfoBackend {
+    sys: System,
+    // drop totals of the previous sample, to report the change per sample
+    dropped: HashMap<String, (u64, u64)>,
+    // multicast and broadcast totals of the previous sample
+    casts: HashMap<String, (u64, Option<u64>)>,
+    broadcasts: BroadcastCounter,
+    // friendly names by the name sysinfo reports, looked up once
+    adapters: HashMap<String, Option<Adapter>>,
+}
+
+impl Default for SysinfoBackend {
+    fn default() -> Self {
+        // interfaces only, processes, disks and components take a while to
+        // collect and nothing here reads them
+        let sys = System::new_with_specifics(RefreshKind::new().with_networks_list());
+        SysinfoBackend {
+            sys,
+            dropped: HashMap::new(),
+            casts: HashMap::new(),
+            broadcasts: BroadcastCounter::default(),
+            adapters: HashMap::new(),
+        }
+    }
+}
+
+impl Backend for SysinfoBackend {
+    fn interfaces(&mut self) -> Result<Vec<InterfaceData>> {
+        // the list too, so interfaces that come and go are picked up
+        self.sys.refresh_networks_list();
+        let interfaces = self
+            .sys
+            .networks()
+            .into_iter()
+            .map(|(name, data)| {
+                let mut interface = InterfaceData::from(name, data);
+                if let Some((rx, tx)) = read_dropped(name) {
+                    let (previous_rx, previous_tx) = self
+                        .dropped
+                        .insert(name.to_string(), (rx, tx))
+                        .unwrap_or((rx, tx));
+                    let errors = &mut interface.errors;
+                    errors.rx_dropped_total = rx;
+                    errors.tx_dropped_total = tx;
+                    errors.rx_dropped = rx.saturating_sub(previous_rx);
+                    errors.tx_dropped = tx.saturating_sub(previous_tx);
+                }
+                let multicast = read_statistic(name, "multicast");
+                let broadcast = self.broadcasts.read(name);
+                if multicast.is_some() || broadcast.is_some() {
+                    let multicast = multicast.unwrap_or_default();
+                    let (previous_multicast, previous_broadcast) = self
+                        .casts
+                        .insert(name.to_string(), (multicast, broadcast))
+                        .unwrap_or((multicast, broadcast));
+                    let packets = &mut interface.packets;
+                    packets.multicast_total = multicast;
+                    packets.multicast = multicast.saturating_sub(previous_multicast);
+                    packets.broadcast_total = broadcast;
+                    packets.broadcast =
+                        broadcast.map(|now| now.saturating_sub(previous_broadcast.unwrap_or(now)));
+                }
+                // Windows names an interface by its GUID when it has no alias
+                let adapter = self
+                    .adapters
+                    .entry(name.to_string())
+                    .or_insert_with(|| adapters::lookup(name));
+                if let Some(adapter) = adapter {
+                    interface.name = adapter.alias.to_string();
+                    interface.description =
+                        (!adapter.description.is_empty()).then(|| adapter.description.to_string());
+                }
+                interface
+            })
+            .collect();
+        Ok(interfaces)
+    }
+}
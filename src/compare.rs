@@ -0,0 +1,146 @@
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    app::App,
+    networks::{create_interface_graph, effective_graph_style, GraphPair, InterfaceData},
+    units::format_rate,
+};
+
+// Two interfaces marked with x shown side by side, e.g. both legs of a bond
+// or a link and its failover
+#[derive(Default)]
+pub struct Compare {
+    // at most two, the oldest mark goes when a third is made
+    pub marked: Vec<String>,
+}
+
+impl Compare {
+    pub fn status(&self) -> Option<String> {
+        match self.marked.as_slice() {
+            [name] => Some(format!("{name} marked, x on another interface to compare")),
+            _ => None,
+        }
+    }
+}
+
+// Marks the selected interface, or unmarks it when it already is
+pub fn toggle_mark(app: &mut App) {
+    let Some(interface) = app.net_interfaces.get(app.selected) else {
+        return;
+    };
+    let name = interface.name.to_string();
+    let marked = &mut app.compare.marked;
+    if let Some(i) = marked.iter().position(|marked| *marked == name) {
+        marked.remove(i);
+        return;
+    }
+    if marked.len() == 2 {
+        marked.remove(0);
+    }
+    marked.push(name);
+}
+
+// The pair compared, while both are still shown
+pub fn compared(app: &App) -> Option<(&InterfaceData, &InterfaceData)> {
+    let [first, second] = app.compare.marked.as_slice() else {
+        return None;
+    };
+    let find = |name: &str| app.net_interfaces.iter().find(|i| i.name == name);
+    Some((find(first)?, find(second)?))
+}
+
+// "eth0 1.2 MB/s, eth1 800.0 KB/s, eth0 400.0 KB/s ahead, split 60/40"
+fn delta_line(direction: &str, names: (&str, &str), rates: (f64, f64)) -> Line<'static> {
+    let (first, second) = rates;
+    let mut text = format!(
+        "{direction}: {} {}, {} {}",
+        names.0,
+        format_rate(first),
+        names.1,
+        format_rate(second)
+    );
+    let total = first + second;
+    if total > 0.0 {
+        let ahead = if first >= second { names.0 } else { names.1 };
+        let share = (first * 100.0 / total).round();
+        text.push_str(&format!(
+            ", {ahead} {} ahead, split {share:.0}/{:.0}",
+            format_rate((first - second).abs()),
+            100.0 - share
+        ));
+    }
+    Line::from(text)
+}
+
+// The rates and their difference, then each interface's TX and RX graphs.
+// Both TX graphs share one scale and both RX graphs another, so the heights
+// compare directly. Each graph is `width` columns wide.
+pub fn create_compare_widgets(
+    app: &App,
+    width: usize,
+) -> Option<(Paragraph<'_>, [GraphPair<'_>; 2])> {
+    let (first, second) = compared(app)?;
+    let theme = &app.theme;
+    let names = (first.name.as_str(), second.name.as_str());
+    let rate = |name: &str| {
+        app.stats.get(name).map_or((0.0, 0.0), |stats| {
+            (stats.sent.current(), stats.rec.current())
+        })
+    };
+    let (first_rate, second_rate) = (rate(names.0), rate(names.1));
+    let lines = vec![
+        delta_line("TX", names, (first_rate.0, second_rate.0)),
+        delta_line("RX", names, (first_rate.1, second_rate.1)),
+    ];
+    let block = Block::default()
+        .title(Span::styled(
+            format!("{} vs {} (x to unmark, esc to return)", names.0, names.1),
+            theme.title,
+        ))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    let paragraph = Paragraph::new(lines).style(theme.text).block(block);
+
+    let graph_style = effective_graph_style(app);
+    let samples = graph_style.samples(width);
+    let series = |name: &str| {
+        app.net_interface_display
+            .get(name)
+            .map_or((&[][..], &[][..]), |data| {
+                let tail = |values: &'_ [u64]| values.len().saturating_sub(samples);
+                (&data.sent[tail(&data.sent)..], &data.rec[tail(&data.rec)..])
+            })
+    };
+    let (first_series, second_series) = (series(names.0), series(names.1));
+    let peak = |values: &[u64]| values.iter().copied().max().unwrap_or(0);
+    let sent_max = peak(first_series.0).max(peak(second_series.0));
+    let rec_max = peak(first_series.1).max(peak(second_series.1));
+    let graphs = [(names.0, first_series), (names.1, second_series)].map(|(name, (sent, rec))| {
+        (
+            create_interface_graph(
+                format!("{name} TX"),
+                sent,
+                theme.tx_graph,
+                theme.tx_symbols.clone(),
+                graph_style,
+                app.capabilities.unicode,
+                theme,
+            )
+            .with_max(sent_max),
+            create_interface_graph(
+                format!("{name} RX"),
+                rec,
+                theme.rx_graph,
+                theme.rx_symbols.clone(),
+                graph_style,
+                app.capabilities.unicode,
+                theme,
+            )
+            .with_max(rec_max),
+        )
+    });
+    Some((paragraph, graphs))
+}
@@ -0,0 +1,530 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    str::FromStr,
+};
+
+use ratatui::style::Color;
+use serde::Deserialize;
+
+use crate::{
+    alerts::{AlertRule, AlertRuleConfig},
+    anomaly::AnomalyConfig,
+    canary::CanaryConfig,
+    collectors::BackendKind,
+    error::{Error, Result, ResultExt},
+    export::ExportConfig,
+    hooks::HookConfig,
+    iftype::InterfaceType,
+    journal::LogTarget,
+    keymap::KeyList,
+    latency::LatencySlo,
+    layout::LayoutMode,
+    plot::{GraphStyle, OverlayMode},
+    slo::ProbeConfig,
+    smoothing::Smoothing,
+    speedtest::SpeedTestConfig,
+    theme::Theme,
+    units::{parse_bytes, parse_rate},
+    wol::{validate_host, HostConfig},
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub theme: Option<String>,
+    // render RX with a shaded pattern so it doesn't rely on color alone
+    pub patterns: bool,
+    // give every interface its own accent color
+    pub interface_colors: bool,
+    // interfaces listed here are shown first, in this order, the rest by
+    // type then name
+    pub priority: Vec<String>,
+    // only monitor these interfaces, every one when empty, see --pick
+    pub monitor: Vec<String>,
+    // only show interfaces of these types, e.g. ["physical"] or
+    // ["ethernet", "virtual"], everything when empty
+    pub interface_types: Vec<String>,
+    // ticks averaged when calibrating the baseline
+    pub baseline_window: usize,
+    // samples in the sliding window used for recent peak/avg/min
+    pub stats_window: usize,
+    // samples each consumer (UI, exporters, API) may fall behind by before
+    // it starts missing some
+    pub pipeline_queue: usize,
+    pub smoothing: Smoothing,
+    // start with charts instead of sparklines
+    pub chart: bool,
+    // where interface counters come from: sysinfo, or on Linux proc for
+    // /proc/net/dev and netlink for drops and carrier changes at lower
+    // overhead
+    pub backend: BackendKind,
+    // list, grid or compact, updated when the layout is switched at runtime
+    pub layout: LayoutMode,
+    // bar, braille, dot or block, how sparklines are drawn
+    pub graph_style: GraphStyle,
+    // off, overlay or mirrored, RX and TX in one graph per interface to save
+    // height on hosts with many interfaces
+    pub overlay: OverlayMode,
+    // start with the selection following the busiest interface
+    pub auto_focus: bool,
+    // show packets per second graphs under the byte graphs
+    pub packet_graphs: bool,
+    // pin a panel summing all interfaces above the interface list
+    pub aggregate_panel: bool,
+    // draw that panel as one graph stacking every interface's traffic
+    pub aggregate_stacked: bool,
+    // start with the CPU and memory strip under the tabs
+    pub system_strip: bool,
+    // start with the socket states strip under the tabs
+    pub socket_strip: bool,
+    // ping the gateway from startup instead of when the Latency screen opens
+    pub latency_probe: bool,
+    // host or IP to ping instead of the default gateway
+    pub latency_target: Option<String>,
+    // more hosts pinged alongside, each with its own loss and jitter
+    pub latency_targets: Vec<String>,
+    // loss and jitter above which a target raises an alert, see LatencySlo
+    pub latency_slo: LatencySlo,
+    // seconds of history shown on the chart X axis
+    pub chart_window: f64,
+    // seconds between the sentences printed by --accessible
+    pub announce_interval: f64,
+    // keep the 1m/5m/1h history in the XDG data dir across restarts
+    pub persist_history: bool,
+    // amount used for "at current rate, 10 GB in ~14m" forecasts
+    pub forecast_size: String,
+    pub export: ExportConfig,
+    // servers loaded by the bufferbloat test
+    pub speedtest: SpeedTestConfig,
+    // periodic small transfers that check the link still moves data
+    pub canary: CanaryConfig,
+    // address to serve the read-only HTTP API on, e.g. "127.0.0.1:9092"
+    pub http_api: Option<String>,
+    // reachability checks scored against availability and latency
+    // objectives on the SLO screen
+    pub probes: Vec<ProbeConfig>,
+    pub alerts: Vec<AlertRuleConfig>,
+    // fan-outs to many new hosts and busy idle interfaces, see AnomalyConfig
+    pub anomalies: AnomalyConfig,
+    // also log alert and link events to "journal" or "syslog"
+    pub alert_log: Option<String>,
+    // shell commands run on alert and link events, see HookConfig
+    pub hooks: HookConfig,
+    // MaxMind DB files, e.g. GeoLite2-Country.mmdb and GeoLite2-ASN.mmdb,
+    // used to show where remote addresses are on the Connections screen
+    pub geoip: Vec<String>,
+    // action name to one or more keys, e.g. quit = ["q", "ctrl-c"]
+    pub keys: HashMap<String, KeyList>,
+    // key name to a list of steps, e.g. F2 = ["screen connections", "filter port 443"]
+    pub macros: HashMap<String, Vec<String>>,
+    // series name to an expression over interface rates, see DerivedSeries
+    pub derived: BTreeMap<String, String>,
+    pub interfaces: HashMap<String, InterfaceConfig>,
+    // machines to wake, keyed by name
+    pub hosts: HashMap<String, HostConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            theme: None,
+            patterns: false,
+            interface_colors: false,
+            priority: Vec::new(),
+            monitor: Vec::new(),
+            interface_types: Vec::new(),
+            baseline_window: 20,
+            stats_window: 240,
+            pipeline_queue: 16,
+            smoothing: Smoothing::default(),
+            chart: false,
+            backend: BackendKind::default(),
+            layout: LayoutMode::default(),
+            graph_style: GraphStyle::default(),
+            overlay: OverlayMode::default(),
+            auto_focus: false,
+            packet_graphs: false,
+            aggregate_panel: false,
+            aggregate_stacked: false,
+            system_strip: false,
+            socket_strip: false,
+            latency_probe: false,
+            latency_target: None,
+            latency_targets: Vec::new(),
+            latency_slo: LatencySlo::default(),
+            chart_window: 60.0,
+            announce_interval: 10.0,
+            persist_history: false,
+            forecast_size: "10 GB".to_string(),
+            export: ExportConfig::default(),
+            speedtest: SpeedTestConfig::default(),
+            canary: CanaryConfig::default(),
+            anomalies: AnomalyConfig::default(),
+            http_api: None,
+            probes: Vec::new(),
+            alerts: Vec::new(),
+            alert_log: None,
+            hooks: HookConfig::default(),
+            geoip: Vec::new(),
+            keys: HashMap::new(),
+            macros: HashMap::new(),
+            derived: BTreeMap::new(),
+            interfaces: HashMap::new(),
+            hosts: HashMap::new(),
+        }
+    }
+}
+
+// Per interface overrides, keyed by interface name in the config file
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct InterfaceConfig {
+    pub calibration: Calibration,
+    pub color: Option<String>,
+    // monthly data allowance, e.g. "50 GB", tracked across sessions
+    pub quota: Option<String>,
+    // day of the month the allowance resets on, 1 to 28
+    pub quota_day: Option<u32>,
+    // top of the graphs instead of following the largest value shown, e.g.
+    // the link speed "1 Gb/s"
+    pub y_max: Option<String>,
+    // corrects the detected type, e.g. type = "wifi"
+    #[serde(rename = "type")]
+    pub kind: Option<InterfaceType>,
+}
+
+// Correction applied to raw counters before they are displayed or stored.
+// `factor` scales every counter (e.g. 0.5 for a driver that double counts or
+// 1.04 for modem-side overhead), the offsets are added to the totals.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Calibration {
+    pub factor: f64,
+    pub sent_offset: i64,
+    pub rec_offset: i64,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration {
+            factor: 1.0,
+            sent_offset: 0,
+            rec_offset: 0,
+        }
+    }
+}
+
+impl Calibration {
+    pub fn scale(&self, value: u64) -> u64 {
+        (value as f64 * self.factor).round().max(0.0) as u64
+    }
+
+    pub fn total(&self, value: u64, offset: i64) -> u64 {
+        (self.scale(value) as i64).saturating_add(offset).max(0) as u64
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Config> {
+        match config_path() {
+            Some(path) if path.exists() => Config::from_file(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    pub fn from_file(path: &PathBuf) -> Result<Config> {
+        let contents = fs::read_to_string(path)
+            .or_config(|| format!("failed to read config file {}", path.display()))?;
+        let config: Config = toml::from_str(&contents)
+            .or_config(|| format!("failed to parse config file {}", path.display()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.export.validate()?;
+        self.speedtest.validate()?;
+        self.canary.validate()?;
+        self.anomalies.validate()?;
+        self.latency_slo.validate()?;
+        for probe in &self.probes {
+            probe.validate()?;
+        }
+        if self.pipeline_queue == 0 {
+            return Err(Error::config("pipeline_queue must be at least 1"));
+        }
+        for rule in &self.alerts {
+            AlertRule::from_config(rule)?;
+        }
+        if let Some(target) = &self.alert_log {
+            LogTarget::parse(target)?;
+        }
+        parse_bytes(&self.forecast_size).ok_or_else(|| {
+            Error::config(format!("invalid forecast_size \"{}\"", self.forecast_size))
+        })?;
+        for name in &self.interface_types {
+            InterfaceType::parse_filter(name).ok_or_else(|| {
+                Error::config(format!(
+                    "unknown interface type \"{name}\", expected physical, ethernet, wifi, modem, loopback, virtual or unknown"
+                ))
+            })?;
+        }
+        for (name, host) in &self.hosts {
+            validate_host(name, host)?;
+        }
+        for (name, interface) in &self.interfaces {
+            if let Some(color) = &interface.color {
+                Color::from_str(color).map_err(|_| {
+                    Error::config(format!("invalid color \"{color}\" for interface {name}"))
+                })?;
+            }
+            if let Some(quota) = &interface.quota {
+                parse_bytes(quota).ok_or_else(|| {
+                    Error::config(format!("invalid quota \"{quota}\" for interface {name}"))
+                })?;
+            }
+            if let Some(y_max) = &interface.y_max {
+                parse_rate(y_max).ok_or_else(|| {
+                    Error::config(format!("invalid y_max \"{y_max}\" for interface {name}"))
+                })?;
+            }
+            if let Some(day) = interface.quota_day {
+                if !(1..=28).contains(&day) {
+                    return Err(Error::config(format!(
+                        "quota_day for interface {name} must be between 1 and 28"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn theme(&self) -> Result<Theme> {
+        match &self.theme {
+            Some(name) => Theme::by_name(name).ok_or_else(|| {
+                Error::config(format!(
+                    "unknown theme \"{name}\", expected one of dark, light, solarized, monochrome, colorblind, colorblind-light"
+                ))
+            }),
+            None => Ok(Theme::default()),
+        }
+        .map(|theme| theme.with_patterns(self.patterns))
+    }
+
+    // Sort key placing prioritised interfaces first, everything else by type
+    // then alphabetically
+    pub fn order_key<'a>(&self, name: &'a str, kind: InterfaceType) -> (usize, usize, &'a str) {
+        let rank = self
+            .priority
+            .iter()
+            .position(|priority| priority == name)
+            .unwrap_or(self.priority.len());
+        (rank, kind.rank(), name)
+    }
+
+    pub fn monitors(&self, name: &str) -> bool {
+        self.monitor.is_empty() || self.monitor.iter().any(|monitored| monitored == name)
+    }
+
+    pub fn shows_type(&self, kind: InterfaceType) -> bool {
+        self.interface_types.is_empty()
+            || self
+                .interface_types
+                .iter()
+                .filter_map(|name| InterfaceType::parse_filter(name))
+                .any(|kinds| kinds.contains(&kind))
+    }
+
+    pub fn interface_type(&self, name: &str) -> Option<InterfaceType> {
+        self.interfaces
+            .get(name)
+            .and_then(|interface| interface.kind)
+    }
+
+    pub fn color(&self, name: &str) -> Option<Color> {
+        self.interfaces
+            .get(name)
+            .and_then(|interface| interface.color.as_deref())
+            .and_then(|color| Color::from_str(color).ok())
+    }
+
+    pub fn forecast_size(&self) -> u64 {
+        parse_bytes(&self.forecast_size).unwrap_or_default()
+    }
+
+    pub fn quota(&self, name: &str) -> Option<u64> {
+        self.interfaces
+            .get(name)
+            .and_then(|interface| interface.quota.as_deref())
+            .and_then(parse_bytes)
+    }
+
+    pub fn y_max(&self, name: &str) -> Option<f64> {
+        self.interfaces
+            .get(name)
+            .and_then(|interface| interface.y_max.as_deref())
+            .and_then(parse_rate)
+    }
+
+    pub fn quota_day(&self, name: &str) -> u32 {
+        self.interfaces
+            .get(name)
+            .and_then(|interface| interface.quota_day)
+            .unwrap_or(1)
+    }
+
+    pub fn calibration(&self, name: &str) -> Calibration {
+        self.interfaces
+            .get(name)
+            .map(|interface| interface.calibration)
+            .unwrap_or_default()
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("net-stat").join("config.toml"))
+}
+
+// Set a top level `key = "value"` in the config file, leaving the rest of the
+// file and its comments untouched
+pub fn save_setting(key: &str, value: &str) -> Result<()> {
+    save_value(key, toml::Value::String(value.to_string()))
+}
+
+// Like save_setting for any TOML value, e.g. a list of names
+pub fn save_value(key: &str, value: toml::Value) -> Result<()> {
+    let (path, mut lines) = read_config_lines()?;
+    // top level keys have to come before the first table
+    let tables = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let setting = format!("{key} = {value}");
+    match lines[..tables]
+        .iter()
+        .position(|line| line.split('=').next().map(str::trim) == Some(key))
+    {
+        Some(i) => lines[i] = setting,
+        None => lines.insert(0, setting),
+    }
+    write_config_lines(&path, &lines)
+}
+
+// Update the threshold and duration of the [[alerts]] table with the same
+// interface and metric as `rule`, or append a table for it
+pub fn save_alert_rule(rule: &AlertRuleConfig) -> Result<()> {
+    let (path, mut lines) = read_config_lines()?;
+    let value = |line: &String, key: &str| {
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key).then(|| value.trim().trim_matches(['"', '\'']).to_string())
+    };
+    let mut table = None;
+    let mut start = 0;
+    while let Some(offset) = lines[start..]
+        .iter()
+        .position(|line| line.trim() == "[[alerts]]")
+    {
+        start += offset + 1;
+        let end = lines[start..]
+            .iter()
+            .position(|line| line.trim_start().starts_with('['))
+            .map_or(lines.len(), |offset| start + offset);
+        let keys = &lines[start..end];
+        let interface = keys.iter().find_map(|line| value(line, "interface"));
+        let metric = keys.iter().find_map(|line| value(line, "metric"));
+        if interface == rule.interface && metric.as_deref().unwrap_or("total") == rule.metric {
+            table = Some(start..end);
+            break;
+        }
+        start = end;
+    }
+    let above = format!("above = \"{}\"", rule.above);
+    let duration = format!("for = {}", rule.duration);
+    match table {
+        Some(keys) => {
+            // missing keys go after the table's last line that isn't blank
+            let mut end = lines[keys.clone()]
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(keys.start, |i| keys.start + i + 1);
+            for (key, setting) in [("above", above), ("for", duration)] {
+                match lines[keys.start..end]
+                    .iter()
+                    .position(|line| value(line, key).is_some())
+                {
+                    Some(i) => lines[keys.start + i] = setting,
+                    None => {
+                        lines.insert(end, setting);
+                        end += 1;
+                    }
+                }
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("[[alerts]]".to_string());
+            if let Some(interface) = &rule.interface {
+                lines.push(format!("interface = \"{interface}\""));
+            }
+            lines.push(format!("metric = \"{}\"", rule.metric));
+            lines.push(above);
+            lines.push(duration);
+        }
+    }
+    write_config_lines(&path, &lines)
+}
+
+fn read_config_lines() -> Result<(PathBuf, Vec<String>)> {
+    let path = config_path().ok_or_else(|| Error::config("no config directory"))?;
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).or_config(|| format!("failed to read {}", path.display())),
+    };
+    let lines = contents.lines().map(str::to_string).collect();
+    Ok((path, lines))
+}
+
+fn write_config_lines(path: &PathBuf, lines: &[String]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).or_config(|| format!("failed to create {}", dir.display()))?;
+    }
+    fs::write(path, lines.join("\n") + "\n")
+        .or_config(|| format!("failed to write {}", path.display()))
+}
+
+fn data_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+    Some(base.join("net-stat"))
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("history"))
+}
+
+pub fn notes_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("notes"))
+}
+
+pub fn bufferbloat_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("bufferbloat"))
+}
+
+pub fn slo_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("slo"))
+}
+
+pub fn usage_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("usage"))
+}
@@ -0,0 +1,450 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use ratatui::{
+    prelude::Constraint,
+    text::Span,
+    widgets::{Block, Borders, Row, Table},
+};
+
+use crate::{
+    app::{App, Screen},
+    error::Result,
+    sockdiag::SocketBytes,
+    theme::Theme,
+    units::format_rate,
+    users::{socket_key, SocketKey},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl Protocol {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::Tcp => "TCP",
+            Protocol::Udp => "UDP",
+        }
+    }
+}
+
+pub struct Connection {
+    pub protocol: Protocol,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub state: &'static str,
+    pub uid: u32,
+    pub inode: u64,
+    // country and network owner of the remote address, from the geoip databases
+    pub location: Option<String>,
+    // bytes per second sent and received over the last sample, TCP only
+    pub rate: Option<(f64, f64)>,
+}
+
+impl Connection {
+    fn key(&self) -> SocketKey {
+        (self.inode, self.local, self.remote)
+    }
+
+    fn total_rate(&self) -> f64 {
+        self.rate.map_or(0.0, |(sent, rec)| sent + rec)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionSort {
+    Protocol,
+    Local,
+    Remote,
+    Port,
+    State,
+    Uid,
+    // busiest first
+    Rate,
+}
+
+impl ConnectionSort {
+    pub fn from_name(name: &str) -> Option<ConnectionSort> {
+        match name {
+            "proto" | "protocol" => Some(ConnectionSort::Protocol),
+            "local" => Some(ConnectionSort::Local),
+            "remote" => Some(ConnectionSort::Remote),
+            "port" => Some(ConnectionSort::Port),
+            "state" => Some(ConnectionSort::State),
+            "uid" | "user" => Some(ConnectionSort::Uid),
+            "rate" | "bandwidth" => Some(ConnectionSort::Rate),
+            _ => None,
+        }
+    }
+}
+
+// Throughput of every TCP socket from the byte counters of the sock_diag
+// dump taken with each sample, so the busiest flow can be picked out
+#[derive(Default)]
+pub struct ConnectionRates {
+    previous: Option<HashMap<SocketKey, (u64, u64)>>,
+    pub rates: HashMap<SocketKey, (f64, f64)>,
+}
+
+impl ConnectionRates {
+    pub fn update(&mut self, sockets: &Result<Vec<SocketBytes>>, elapsed: f64) {
+        let Ok(sockets) = sockets else {
+            self.previous = None;
+            self.rates.clear();
+            return;
+        };
+        let current: HashMap<SocketKey, (u64, u64)> = sockets
+            .iter()
+            .map(|socket| (socket_key(socket), (socket.sent, socket.rec)))
+            .collect();
+        // the first sample only establishes the starting point, sockets
+        // opened since the last one count from zero
+        self.rates = match (&self.previous, elapsed > 0.0) {
+            (Some(previous), true) => current
+                .iter()
+                .map(|(key, (sent, rec))| {
+                    let (sent_before, rec_before) = previous.get(key).copied().unwrap_or((0, 0));
+                    let rate = (
+                        sent.saturating_sub(sent_before) as f64 / elapsed,
+                        rec.saturating_sub(rec_before) as f64 / elapsed,
+                    );
+                    (*key, rate)
+                })
+                .collect(),
+            _ => HashMap::new(),
+        };
+        self.previous = Some(current);
+    }
+}
+
+pub fn update_connection_rates(app: &mut App, sockets: &Result<Vec<SocketBytes>>, elapsed: f64) {
+    app.connection_rates.update(sockets, elapsed);
+}
+
+pub struct ProcessSockets {
+    pub pid: u32,
+    pub name: String,
+    pub tcp: usize,
+    pub udp: usize,
+    // its TCP sockets' rates summed, bytes per second sent and received
+    pub sent_rate: f64,
+    pub rec_rate: f64,
+}
+
+const SOURCES: [(&str, Protocol); 4] = [
+    ("/proc/net/tcp", Protocol::Tcp),
+    ("/proc/net/tcp6", Protocol::Tcp),
+    ("/proc/net/udp", Protocol::Udp),
+    ("/proc/net/udp6", Protocol::Udp),
+];
+
+// Reads the kernel socket tables. Only available on Linux, elsewhere the
+// list is simply empty.
+pub fn read_connections() -> Vec<Connection> {
+    SOURCES
+        .iter()
+        .filter_map(|(path, protocol)| {
+            fs::read_to_string(path)
+                .ok()
+                .map(|contents| parse_socket_table(&contents, *protocol))
+        })
+        .flatten()
+        .collect()
+}
+
+pub fn parse_socket_table(contents: &str, protocol: Protocol) -> Vec<Connection> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                return None;
+            }
+            Some(Connection {
+                protocol,
+                local: parse_address(fields[1])?,
+                remote: parse_address(fields[2])?,
+                state: tcp_state(protocol, u8::from_str_radix(fields[3], 16).ok()?),
+                uid: fields[7].parse().ok()?,
+                inode: fields[9].parse().ok()?,
+                location: None,
+                rate: None,
+            })
+        })
+        .collect()
+}
+
+// Addresses are hex encoded 32 bit words in host byte order, e.g. 0100007F:0035
+fn parse_address(field: &str) -> Option<SocketAddr> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let ip = match address.len() {
+        8 => IpAddr::V4(Ipv4Addr::from(
+            u32::from_str_radix(address, 16).ok()?.to_le_bytes(),
+        )),
+        32 => {
+            let mut octets = [0u8; 16];
+            for i in 0..4 {
+                let word = u32::from_str_radix(&address[i * 8..i * 8 + 8], 16).ok()?;
+                octets[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+fn tcp_state(protocol: Protocol, state: u8) -> &'static str {
+    if protocol == Protocol::Udp {
+        return if state == 0x07 { "UNCONN" } else { "ESTAB" };
+    }
+    match state {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+// Maps socket inodes to the owning process by walking /proc/<pid>/fd.
+// Sockets owned by other users are only visible when running as root.
+pub fn socket_owners() -> HashMap<u64, (u32, String)> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return owners;
+    };
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let name = fs::read_to_string(entry.path().join("comm"))
+            .map(|name| name.trim().to_string())
+            .unwrap_or_default();
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let inode = target
+                .to_str()
+                .and_then(|target| target.strip_prefix("socket:["))
+                .and_then(|target| target.strip_suffix(']'))
+                .and_then(|inode| inode.parse().ok());
+            if let Some(inode) = inode {
+                owners.insert(inode, (pid, name.clone()));
+            }
+        }
+    }
+    owners
+}
+
+pub fn update_connections(app: &mut App) {
+    let mut connections = read_connections();
+    for connection in &mut connections {
+        connection.rate = app.connection_rates.rates.get(&connection.key()).copied();
+    }
+    if app.screen == Screen::Processes {
+        app.processes = group_by_process(&connections);
+    }
+    if app.geoip.enabled() {
+        for connection in &mut connections {
+            connection.location = app.geoip.locate(connection.remote.ip());
+        }
+    }
+    if let Some(filter) = &app.connection_filter {
+        connections.retain(|connection| matches_filter(connection, filter));
+    }
+    if let Some(sort) = app.connection_sort {
+        sort_connections(&mut connections, sort);
+    }
+    app.connections = connections;
+}
+
+// A filter is either "port <n>", matching local or remote port, or a
+// fragment of the addresses or state
+pub fn matches_filter(connection: &Connection, filter: &str) -> bool {
+    if let Some(port) = filter
+        .strip_prefix("port")
+        .and_then(|port| port.trim().parse::<u16>().ok())
+    {
+        return connection.local.port() == port || connection.remote.port() == port;
+    }
+    let filter = filter.to_lowercase();
+    connection.local.to_string().contains(&filter)
+        || connection.remote.to_string().contains(&filter)
+        || connection.state.to_lowercase().contains(&filter)
+        || connection
+            .location
+            .as_ref()
+            .is_some_and(|location| location.to_lowercase().contains(&filter))
+}
+
+pub fn sort_connections(connections: &mut [Connection], sort: ConnectionSort) {
+    match sort {
+        ConnectionSort::Protocol => connections.sort_by_key(|c| c.protocol.name()),
+        ConnectionSort::Local => connections.sort_by_key(|c| c.local),
+        ConnectionSort::Remote => connections.sort_by_key(|c| c.remote),
+        ConnectionSort::Port => connections.sort_by_key(|c| c.local.port().min(c.remote.port())),
+        ConnectionSort::State => connections.sort_by_key(|c| c.state),
+        ConnectionSort::Uid => connections.sort_by_key(|c| c.uid),
+        ConnectionSort::Rate => {
+            connections.sort_by(|a, b| b.total_rate().total_cmp(&a.total_rate()))
+        }
+    }
+}
+
+pub fn group_by_process(connections: &[Connection]) -> Vec<ProcessSockets> {
+    let owners = socket_owners();
+    let mut processes: HashMap<u32, ProcessSockets> = HashMap::new();
+    connections.iter().for_each(|connection| {
+        if let Some((pid, name)) = owners.get(&connection.inode) {
+            let process = processes.entry(*pid).or_insert(ProcessSockets {
+                pid: *pid,
+                name: name.to_string(),
+                tcp: 0,
+                udp: 0,
+                sent_rate: 0.0,
+                rec_rate: 0.0,
+            });
+            if let Some((sent, rec)) = connection.rate {
+                process.sent_rate += sent;
+                process.rec_rate += rec;
+            }
+            match connection.protocol {
+                Protocol::Tcp => process.tcp += 1,
+                Protocol::Udp => process.udp += 1,
+            }
+        }
+    });
+    let mut processes: Vec<ProcessSockets> = processes.into_values().collect();
+    processes.sort_by(|a, b| {
+        (b.tcp + b.udp)
+            .cmp(&(a.tcp + a.udp))
+            .then(a.pid.cmp(&b.pid))
+    });
+    processes
+}
+
+const CONNECTION_WIDTHS: [Constraint; 7] = [
+    Constraint::Length(5),
+    Constraint::Percentage(30),
+    Constraint::Percentage(30),
+    Constraint::Length(12),
+    Constraint::Length(8),
+    Constraint::Length(11),
+    Constraint::Length(11),
+];
+const LOCATED_WIDTHS: [Constraint; 8] = [
+    Constraint::Length(5),
+    Constraint::Percentage(20),
+    Constraint::Percentage(20),
+    Constraint::Length(12),
+    Constraint::Length(8),
+    Constraint::Length(11),
+    Constraint::Length(11),
+    Constraint::Percentage(25),
+];
+
+pub fn create_connections_table<'a>(
+    connections: &[Connection],
+    filter: Option<&str>,
+    located: bool,
+    theme: &Theme,
+) -> Table<'a> {
+    let rows: Vec<Row> = connections
+        .iter()
+        .map(|connection| {
+            let mut cells = vec![
+                connection.protocol.name().to_string(),
+                connection.local.to_string(),
+                connection.remote.to_string(),
+                connection.state.to_string(),
+                connection.uid.to_string(),
+            ];
+            let (sent, rec) = match connection.rate {
+                Some((sent, rec)) => (format_rate(sent), format_rate(rec)),
+                None => (String::new(), String::new()),
+            };
+            cells.extend([sent, rec]);
+            if located {
+                cells.push(connection.location.clone().unwrap_or_default());
+            }
+            Row::new(cells)
+        })
+        .collect();
+    let mut header = vec!["Proto", "Local", "Remote", "State", "UID", "TX/s", "RX/s"];
+    if located {
+        header.push("Location");
+    }
+    let block = Block::default()
+        .title(Span::styled(
+            match filter {
+                Some(filter) => format!("Connections ({}, filter: {filter})", connections.len()),
+                None => format!("Connections ({})", connections.len()),
+            },
+            theme.title,
+        ))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Table::new(rows)
+        .header(Row::new(header).style(theme.title))
+        .style(theme.text)
+        .block(block)
+        .widths(if located {
+            &LOCATED_WIDTHS
+        } else {
+            &CONNECTION_WIDTHS
+        })
+}
+
+const PROCESS_WIDTHS: [Constraint; 6] = [
+    Constraint::Length(8),
+    Constraint::Percentage(40),
+    Constraint::Length(6),
+    Constraint::Length(6),
+    Constraint::Length(11),
+    Constraint::Length(11),
+];
+
+pub fn create_processes_table<'a>(processes: &[ProcessSockets], theme: &Theme) -> Table<'a> {
+    let rows: Vec<Row> = processes
+        .iter()
+        .map(|process| {
+            Row::new(vec![
+                process.pid.to_string(),
+                process.name.to_string(),
+                process.tcp.to_string(),
+                process.udp.to_string(),
+                format_rate(process.sent_rate),
+                format_rate(process.rec_rate),
+            ])
+        })
+        .collect();
+    let block = Block::default()
+        .title(Span::styled("Processes with open sockets", theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Table::new(rows)
+        .header(Row::new(vec!["PID", "Name", "TCP", "UDP", "TX/s", "RX/s"]).style(theme.title))
+        .style(theme.text)
+        .block(block)
+        .widths(&PROCESS_WIDTHS)
+}
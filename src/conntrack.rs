@@ -0,0 +1,198 @@
+use std::{collections::HashMap, fs};
+
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::app::{App, Screen};
+
+const COUNT_PATH: &str = "/proc/sys/net/netfilter/nf_conntrack_count";
+const MAX_PATH: &str = "/proc/sys/net/netfilter/nf_conntrack_max";
+// one line per tracked session, only with CONFIG_NF_CONNTRACK_PROCFS
+const TABLE_PATH: &str = "/proc/net/nf_conntrack";
+// a header line then one row of hex counters per CPU
+const STAT_PATH: &str = "/proc/net/stat/nf_conntrack";
+// from this share of the table on new sessions may be dropped
+const NEAR_FULL: f64 = 0.9;
+pub const CONNTRACK_HEIGHT: u16 = 4;
+
+// Counters that go up when the table is full or sessions are refused
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConntrackStats {
+    pub drop: u64,
+    pub early_drop: u64,
+    pub insert_failed: u64,
+    pub invalid: u64,
+}
+
+impl ConntrackStats {
+    fn since(&self, start: &ConntrackStats) -> ConntrackStats {
+        ConntrackStats {
+            drop: self.drop.saturating_sub(start.drop),
+            early_drop: self.early_drop.saturating_sub(start.early_drop),
+            insert_failed: self.insert_failed.saturating_sub(start.insert_failed),
+            invalid: self.invalid.saturating_sub(start.invalid),
+        }
+    }
+}
+
+// Netfilter connection tracking table usage, Linux only
+#[derive(Default)]
+pub struct Conntrack {
+    pub available: bool,
+    pub count: u64,
+    pub max: u64,
+    // sessions by protocol, most first, empty without the procfs table
+    pub protocols: Vec<(String, u64)>,
+    pub established: u64,
+    pub stats: ConntrackStats,
+    // counters when first read, the panel shows what happened since
+    initial: Option<ConntrackStats>,
+}
+
+impl Conntrack {
+    pub fn fill(&self) -> f64 {
+        self.count as f64 / self.max.max(1) as f64
+    }
+
+    pub fn near_full(&self) -> bool {
+        self.available && self.fill() >= NEAR_FULL
+    }
+
+    // e.g. "conntrack table 93% full"
+    pub fn warning(&self) -> Option<String> {
+        self.near_full()
+            .then(|| format!("conntrack table {:.0}% full", self.fill() * 100.0))
+    }
+}
+
+fn read_number(path: &str) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// Sums the per CPU rows of the columns by header name
+pub fn parse_stats(contents: &str) -> ConntrackStats {
+    let mut lines = contents.lines();
+    let header: Vec<&str> = lines
+        .next()
+        .map(|line| line.split_whitespace().collect())
+        .unwrap_or_default();
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    for line in lines {
+        for (name, value) in header.iter().zip(line.split_whitespace()) {
+            *totals.entry(name).or_default() += u64::from_str_radix(value, 16).unwrap_or(0);
+        }
+    }
+    let total = |name: &str| totals.get(name).copied().unwrap_or_default();
+    ConntrackStats {
+        drop: total("drop"),
+        early_drop: total("early_drop"),
+        insert_failed: total("insert_failed"),
+        invalid: total("invalid"),
+    }
+}
+
+// Lines look like "ipv4 2 tcp 6 431999 ESTABLISHED src=...", returns the
+// sessions by protocol and the established TCP ones
+pub fn parse_table(contents: &str) -> (Vec<(String, u64)>, u64) {
+    let mut protocols: HashMap<&str, u64> = HashMap::new();
+    let mut established = 0;
+    for line in contents.lines() {
+        let fields: Vec<&str> = line.split_whitespace().take(6).collect();
+        let Some(protocol) = fields.get(2) else {
+            continue;
+        };
+        *protocols.entry(protocol).or_default() += 1;
+        if *protocol == "tcp" && fields.get(5) == Some(&"ESTABLISHED") {
+            established += 1;
+        }
+    }
+    let mut protocols: Vec<(String, u64)> = protocols
+        .into_iter()
+        .map(|(name, count)| (name.to_string(), count))
+        .collect();
+    protocols.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    (protocols, established)
+}
+
+pub fn update_conntrack(app: &mut App) {
+    // the table belongs to this machine, not the one a remote session watches
+    // or a recording was made on
+    if app.is_remote() {
+        return;
+    }
+    let conntrack = &mut app.conntrack;
+    let (Some(count), Some(max)) = (read_number(COUNT_PATH), read_number(MAX_PATH)) else {
+        conntrack.available = false;
+        return;
+    };
+    conntrack.available = true;
+    conntrack.count = count;
+    conntrack.max = max;
+    if let Ok(contents) = fs::read_to_string(STAT_PATH) {
+        conntrack.stats = parse_stats(&contents);
+        conntrack.initial.get_or_insert(conntrack.stats);
+    }
+    // the full table can hold hundreds of thousands of lines, only read it
+    // while it's shown
+    if app.screen == Screen::Connections {
+        if let Ok(contents) = fs::read_to_string(TABLE_PATH) {
+            (conntrack.protocols, conntrack.established) = parse_table(&contents);
+        }
+    }
+}
+
+pub fn create_conntrack_paragraph<'a>(app: &App) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let conntrack = &app.conntrack;
+    let style = if conntrack.near_full() {
+        theme.alert
+    } else {
+        theme.text
+    };
+    let mut sessions = format!(
+        "Sessions: {} of {} ({:.0}%)",
+        conntrack.count,
+        conntrack.max,
+        conntrack.fill() * 100.0
+    );
+    if conntrack.near_full() {
+        sessions.push_str(", table nearly full, new connections may be dropped");
+    }
+    let protocols = if conntrack.protocols.is_empty() {
+        "By protocol: unavailable without /proc/net/nf_conntrack".to_string()
+    } else {
+        let counts: Vec<String> = conntrack
+            .protocols
+            .iter()
+            .map(|(name, count)| match name.as_str() {
+                "tcp" => format!("tcp {count} ({} established)", conntrack.established),
+                _ => format!("{name} {count}"),
+            })
+            .collect();
+        format!("By protocol: {}", counts.join(", "))
+    };
+    let stats = conntrack
+        .stats
+        .since(&conntrack.initial.unwrap_or_default());
+    let refused = stats.drop + stats.early_drop + stats.insert_failed;
+    let lines = vec![
+        Line::styled(sessions, style),
+        Line::from(vec![
+            Span::raw(protocols),
+            Span::styled(
+                format!(
+                    " | this session: {} dropped, {} early drops, {} insert failures, {} invalid",
+                    stats.drop, stats.early_drop, stats.insert_failed, stats.invalid
+                ),
+                if refused > 0 { theme.alert } else { theme.text },
+            ),
+        ]),
+    ];
+    let block = Block::default()
+        .title(Span::styled("Conntrack", theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Paragraph::new(lines).style(theme.text).block(block)
+}
@@ -0,0 +1,346 @@
+#[cfg(unix)]
+use std::time::Duration;
+use std::{collections::HashMap, fs, io};
+
+use ratatui::{
+    prelude::Constraint,
+    text::Span,
+    widgets::{Block, Borders, Row, Table},
+};
+use serde::Deserialize;
+
+use crate::{
+    app::{App, Screen},
+    collectors::procfs::parse_net_dev,
+    units::{format_bytes, format_rate},
+};
+
+#[cfg(unix)]
+const DOCKER_SOCKET: &str = "/var/run/docker.sock";
+// the daemon answers from memory, a slower one only loses the names
+#[cfg(unix)]
+const DOCKER_TIMEOUT: Duration = Duration::from_millis(200);
+// containers are looked for again every this many samples, their counters
+// are read every sample
+const DISCOVER_EVERY: u64 = 10;
+// prefixes runtimes give the cgroup of a container, e.g.
+// "docker-<id>.scope" under systemd or "/docker/<id>" without it
+const CGROUP_PREFIXES: [&str; 4] = ["docker-", "libpod-", "cri-containerd-", "crio-"];
+
+pub struct Container {
+    // the full id, shown shortened
+    pub id: String,
+    pub name: String,
+    // a process inside, its /proc/<pid>/net is the container's namespace
+    pub pid: u32,
+    // host ends of its veth pairs
+    pub veths: Vec<String>,
+    // what the container sent and received since it started, and per second
+    // over the last sample
+    pub sent_total: u64,
+    pub rec_total: u64,
+    pub sent_rate: f64,
+    pub rec_rate: f64,
+}
+
+// Running containers with their own network namespace, found through their
+// cgroups so Docker, Podman and Kubernetes ones all show. Docker's socket,
+// when there is one, names them. Containers on the host network have no
+// traffic of their own to show and are left out. Linux only.
+#[derive(Default)]
+pub struct Containers {
+    pub containers: Vec<Container>,
+    samples: u64,
+}
+
+impl Containers {
+    // The container the veth `interface` belongs to
+    pub fn owner(&self, interface: &str) -> Option<&str> {
+        self.containers
+            .iter()
+            .find(|container| container.veths.iter().any(|veth| veth == interface))
+            .map(|container| container.name.as_str())
+    }
+
+    pub fn update(&mut self, elapsed: f64) {
+        if self.samples.is_multiple_of(DISCOVER_EVERY) {
+            self.discover();
+        }
+        self.samples += 1;
+        for container in &mut self.containers {
+            // gone since it was found, dropped at the next discovery
+            let Some((sent, rec)) = namespace_totals(container.pid) else {
+                container.sent_rate = 0.0;
+                container.rec_rate = 0.0;
+                continue;
+            };
+            if elapsed > 0.0 {
+                container.sent_rate = sent.saturating_sub(container.sent_total) as f64 / elapsed;
+                container.rec_rate = rec.saturating_sub(container.rec_total) as f64 / elapsed;
+            }
+            container.sent_total = sent;
+            container.rec_total = rec;
+        }
+        self.containers.sort_by(|a, b| {
+            (b.sent_rate + b.rec_rate)
+                .total_cmp(&(a.sent_rate + a.rec_rate))
+                .then(a.name.cmp(&b.name))
+        });
+    }
+
+    fn discover(&mut self) {
+        let host = fs::read_link("/proc/self/ns/net").ok();
+        let names = docker_names().unwrap_or_default();
+        let veths = host_veths();
+        let mut found: HashMap<String, u32> = HashMap::new();
+        for pid in process_ids() {
+            let Some(id) = fs::read_to_string(format!("/proc/{pid}/cgroup"))
+                .ok()
+                .and_then(|cgroup| container_id(&cgroup))
+            else {
+                continue;
+            };
+            if found.contains_key(&id) {
+                continue;
+            }
+            let namespace = fs::read_link(format!("/proc/{pid}/ns/net")).ok();
+            if namespace.is_some() && namespace != host {
+                found.insert(id, pid);
+            }
+        }
+        let mut previous: HashMap<String, Container> = std::mem::take(&mut self.containers)
+            .into_iter()
+            .map(|container| (container.id.clone(), container))
+            .collect();
+        self.containers = found
+            .into_iter()
+            .map(|(id, pid)| {
+                let (sent_total, rec_total, sent_rate, rec_rate) = match previous.remove(&id) {
+                    Some(old) if old.pid == pid => {
+                        (old.sent_total, old.rec_total, old.sent_rate, old.rec_rate)
+                    }
+                    // new ones count from their current totals
+                    _ => namespace_totals(pid)
+                        .map_or((0, 0, 0.0, 0.0), |(sent, rec)| (sent, rec, 0.0, 0.0)),
+                };
+                Container {
+                    name: names
+                        .get(&id)
+                        .cloned()
+                        .or_else(|| hostname(pid))
+                        .unwrap_or_else(|| id[..12].to_string()),
+                    veths: container_veths(pid, &veths),
+                    id,
+                    pid,
+                    sent_total,
+                    rec_total,
+                    sent_rate,
+                    rec_rate,
+                }
+            })
+            .collect();
+    }
+}
+
+// The 64 hex digit id in the cgroup path of /proc/<pid>/cgroup, None for
+// processes outside a container
+pub fn container_id(cgroup: &str) -> Option<String> {
+    cgroup
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .flat_map(|path| path.split('/'))
+        .find_map(|segment| {
+            let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+            let id = CGROUP_PREFIXES
+                .iter()
+                .find_map(|prefix| segment.strip_prefix(prefix))
+                .unwrap_or(segment);
+            (id.len() == 64 && id.bytes().all(|byte| byte.is_ascii_hexdigit()))
+                .then(|| id.to_string())
+        })
+}
+
+#[derive(Deserialize)]
+struct DockerContainer {
+    #[serde(rename = "Id")]
+    id: String,
+    #[serde(rename = "Names", default)]
+    names: Vec<String>,
+}
+
+// The body of GET /containers/json, ids to names without the leading "/"
+pub fn parse_docker_names(json: &str) -> HashMap<String, String> {
+    let containers: Vec<DockerContainer> = serde_json::from_str(json).unwrap_or_default();
+    containers
+        .into_iter()
+        .filter_map(|container| {
+            let name = container.names.first()?.trim_start_matches('/').to_string();
+            Some((container.id, name))
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn docker_names() -> io::Result<HashMap<String, String>> {
+    use std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+    };
+
+    let mut stream = UnixStream::connect(DOCKER_SOCKET)?;
+    stream.set_read_timeout(Some(DOCKER_TIMEOUT))?;
+    stream.set_write_timeout(Some(DOCKER_TIMEOUT))?;
+    // HTTP/1.0 so the body isn't chunked and the daemon hangs up after it
+    stream.write_all(b"GET /containers/json HTTP/1.0\r\nHost: docker\r\n\r\n")?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+    Ok(parse_docker_names(body))
+}
+
+#[cfg(not(unix))]
+fn docker_names() -> io::Result<HashMap<String, String>> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+fn process_ids() -> Vec<u32> {
+    fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// The runtimes set it to the container's name or short id
+fn hostname(pid: u32) -> Option<String> {
+    let hostname = fs::read_to_string(format!("/proc/{pid}/root/etc/hostname")).ok()?;
+    Some(hostname.trim().to_string()).filter(|hostname| !hostname.is_empty())
+}
+
+// Bytes sent and received by all interfaces of the namespace but loopback
+fn namespace_totals(pid: u32) -> Option<(u64, u64)> {
+    let contents = fs::read_to_string(format!("/proc/{pid}/net/dev")).ok()?;
+    Some(
+        parse_net_dev(&contents)
+            .into_iter()
+            .filter(|(name, _)| name != "lo")
+            .fold((0, 0), |(sent, rec), (_, totals)| {
+                (sent + totals.tx_bytes, rec + totals.rx_bytes)
+            }),
+    )
+}
+
+fn read_index(path: String) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// Host interfaces by ifindex whose link is in another namespace, veth ends
+// and the like. A physical interface is its own link.
+fn host_veths() -> HashMap<u32, String> {
+    let Ok(entries) = fs::read_dir("/sys/class/net") else {
+        return HashMap::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().to_str()?.to_string();
+            let index = read_index(format!("/sys/class/net/{name}/ifindex"))?;
+            let link = read_index(format!("/sys/class/net/{name}/iflink"))?;
+            (index != link).then_some((index, name))
+        })
+        .collect()
+}
+
+// The iflink of each interface inside the container is the ifindex of its
+// peer on the host. Read through the container's own /sys, which shows its
+// namespace.
+fn container_veths(pid: u32, veths: &HashMap<u32, String>) -> Vec<String> {
+    let root = format!("/proc/{pid}/root/sys/class/net");
+    let Ok(entries) = fs::read_dir(&root) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().to_str()?.to_string();
+            let link = read_index(format!("{root}/{name}/iflink"))?;
+            veths.get(&link).cloned()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn update_containers(app: &mut App, elapsed: f64) {
+    // the containers belong to this machine, not the one a remote session
+    // watches or a recording was made on
+    if app.is_remote() {
+        return;
+    }
+    // only looked for while shown, or while there are veths to name
+    let veths = app
+        .all_interfaces
+        .iter()
+        .any(|interface| interface.name.starts_with("veth"));
+    if app.screen == Screen::Containers || veths || !app.containers.containers.is_empty() {
+        app.containers.update(elapsed);
+    }
+}
+
+const CONTAINER_WIDTHS: [Constraint; 7] = [
+    Constraint::Percentage(25),
+    Constraint::Length(12),
+    Constraint::Length(8),
+    Constraint::Percentage(25),
+    Constraint::Length(11),
+    Constraint::Length(11),
+    Constraint::Length(21),
+];
+
+pub fn create_containers_table<'a>(app: &App) -> Table<'a> {
+    let theme = &app.theme;
+    let containers = &app.containers.containers;
+    let rows: Vec<Row> = containers
+        .iter()
+        .map(|container| {
+            Row::new(vec![
+                container.name.clone(),
+                container.id[..12].to_string(),
+                container.pid.to_string(),
+                container.veths.join(", "),
+                format_rate(container.sent_rate),
+                format_rate(container.rec_rate),
+                format!(
+                    "{} / {}",
+                    format_bytes(container.sent_total as f64),
+                    format_bytes(container.rec_total as f64)
+                ),
+            ])
+        })
+        .collect();
+    let title = match (app.is_remote(), containers.len()) {
+        (true, _) => "Containers, only shown for this machine".to_string(),
+        (false, 0) => "Containers (none with their own network found)".to_string(),
+        (false, count) => format!("Containers ({count})"),
+    };
+    let block = Block::default()
+        .title(Span::styled(title, theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Table::new(rows)
+        .header(
+            Row::new(vec![
+                "Name",
+                "ID",
+                "PID",
+                "Interfaces",
+                "TX/s",
+                "RX/s",
+                "Sent / received",
+            ])
+            .style(theme.title),
+        )
+        .style(theme.text)
+        .block(block)
+        .widths(&CONTAINER_WIDTHS)
+}
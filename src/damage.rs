@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    app::{status_line, App, Screen},
+    networks::DISPLAY_SAMPLES,
+};
+
+// Longest an unchanged screen goes without a redraw, for what drifts without
+// any traffic, e.g. averages decaying and ages counting up
+pub const IDLE_REDRAW: Duration = Duration::from_secs(5);
+
+// What the last frame showed, enough to tell whether a sample changes it.
+// Input always redraws, samples only when they move something on screen.
+#[derive(Default)]
+pub struct Damage {
+    // a sample changed what's shown since the last draw
+    pub dirty: bool,
+    drawn: Option<Instant>,
+    names: Vec<String>,
+    status: String,
+}
+
+impl Damage {
+    pub fn needs_redraw(&self) -> bool {
+        self.dirty
+            || self
+                .drawn
+                .is_none_or(|drawn| drawn.elapsed() >= IDLE_REDRAW)
+    }
+}
+
+// Called after each sample
+pub fn update_damage(app: &mut App) {
+    let dirty = sample_visible(app);
+    app.damage.dirty |= dirty;
+}
+
+pub fn mark_drawn(app: &mut App) {
+    let names = app
+        .net_interfaces
+        .iter()
+        .map(|interface| interface.name.to_string())
+        .collect();
+    let status = status_line(app);
+    let damage = &mut app.damage;
+    damage.dirty = false;
+    damage.drawn = Some(Instant::now());
+    damage.names = names;
+    damage.status = status;
+}
+
+fn sample_visible(app: &App) -> bool {
+    // the other screens are live tables and charts, the strips change with
+    // every sample too
+    if app.screen != Screen::Overview
+        || app.system_strip
+        || app.socket_strip
+        || app.debug
+        || app.picker.is_some()
+    {
+        return true;
+    }
+    let names_changed = !app
+        .net_interfaces
+        .iter()
+        .map(|interface| &interface.name)
+        .eq(app.damage.names.iter());
+    // sparklines shift while anything non-zero is still in view
+    let window = app
+        .interface_areas
+        .iter()
+        .map(|area| area.width as usize)
+        .max()
+        .filter(|_| !app.chart)
+        .unwrap_or(DISPLAY_SAMPLES);
+    let moving = app.net_interfaces.iter().any(|interface| {
+        let errors = interface.errors;
+        let counters_moved = interface.sent + interface.rec > 0
+            || errors.rx_errors + errors.tx_errors + errors.rx_dropped + errors.tx_dropped > 0;
+        let in_view = |values: &[u64]| {
+            values[values.len().saturating_sub(window)..]
+                .iter()
+                .any(|value| *value > 0)
+        };
+        counters_moved
+            || app
+                .net_interface_graphs
+                .get(&interface.name)
+                .is_some_and(|graph| {
+                    in_view(&graph.sent)
+                        || in_view(&graph.rec)
+                        || in_view(&graph.sent_packets)
+                        || in_view(&graph.rec_packets)
+                })
+    });
+    names_changed || moving || status_line(app) != app.damage.status
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    alerts::Metric,
+    app::App,
+    error::{Error, Result},
+    networks::{create_interface_graph, effective_graph_style, Graph, DISPLAY_SAMPLES},
+    units::format_rate,
+};
+
+// A series worked out from the interface rates every sample, from the
+// [derived] table, e.g.
+//   [derived]
+//   wan_total = "eth0.rx + wg0.rx"
+//   upload_share = "100 * eth0.tx / max(eth0.total, 1)"
+// Terms are an interface and tx, rx or total in bytes per second, numbers,
+// + - * / and parentheses, and min, max and abs. Names that aren't plain
+// words are quoted, e.g. 'br-lan'.rx. Missing interfaces count as 0.
+pub struct DerivedSeries {
+    pub name: String,
+    expression: Expr,
+    pub value: f64,
+    pub peak: f64,
+    // values per sample for the graph, negative ones drawn as 0
+    pub values: Vec<u64>,
+}
+
+pub fn parse_derived(config: &BTreeMap<String, String>) -> Result<Vec<DerivedSeries>> {
+    config
+        .iter()
+        .map(|(name, source)| {
+            let expression = parse(source)
+                .map_err(|e| Error::config(format!("derived series {name} \"{source}\": {e}")))?;
+            Ok(DerivedSeries {
+                name: name.to_string(),
+                expression,
+                value: 0.0,
+                peak: 0.0,
+                values: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+// Evaluated against every interface of the sample, whatever the view
+// filters hide
+pub fn update_derived(app: &mut App, elapsed: f64) {
+    if elapsed <= 0.0 {
+        return;
+    }
+    let interfaces = &app.all_interfaces;
+    let rate = |name: &str, metric: Metric| {
+        interfaces
+            .iter()
+            .find(|interface| interface.name == name)
+            .map(|interface| {
+                metric.value(
+                    interface.sent as f64 / elapsed,
+                    interface.rec as f64 / elapsed,
+                )
+            })
+            .unwrap_or(0.0)
+    };
+    for series in &mut app.derived {
+        let value = series.expression.evaluate(&rate);
+        series.value = if value.is_finite() { value } else { 0.0 };
+        series.peak = series.peak.max(series.value);
+        series.values.push(series.value.max(0.0).round() as u64);
+        if series.values.len() > DISPLAY_SAMPLES {
+            series.values.remove(0);
+        }
+    }
+}
+
+// One graph per series side by side, each `width` columns wide
+pub fn create_derived_graphs(app: &App, width: usize) -> Vec<Graph<'_>> {
+    let theme = &app.theme;
+    let graph_style = effective_graph_style(app);
+    let samples = graph_style.samples(width);
+    app.derived
+        .iter()
+        .map(|series| {
+            let tail = series.values.len().saturating_sub(samples);
+            create_interface_graph(
+                format!(
+                    "{} {} (peak {})",
+                    series.name,
+                    format_rate(series.value),
+                    format_rate(series.peak)
+                ),
+                &series.values[tail..],
+                theme.tx_graph,
+                theme.tx_symbols.clone(),
+                graph_style,
+                app.capabilities.unicode,
+                theme,
+            )
+        })
+        .collect()
+}
+
+enum Expr {
+    Number(f64),
+    Rate(String, Metric),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Clone, Copy)]
+enum Function {
+    Min,
+    Max,
+    Abs,
+}
+
+impl Expr {
+    fn evaluate(&self, rate: &dyn Fn(&str, Metric) -> f64) -> f64 {
+        match self {
+            Expr::Number(value) => *value,
+            Expr::Rate(interface, metric) => rate(interface, *metric),
+            Expr::Negate(expr) => -expr.evaluate(rate),
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (left.evaluate(rate), right.evaluate(rate));
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    // an idle interface as the divisor shows as 0, not a gap
+                    _ if right == 0.0 => 0.0,
+                    _ => left / right,
+                }
+            }
+            Expr::Call(function, args) => {
+                let mut values = args.iter().map(|arg| arg.evaluate(rate));
+                match function {
+                    Function::Min => values.fold(f64::INFINITY, f64::min),
+                    Function::Max => values.fold(f64::NEG_INFINITY, f64::max),
+                    Function::Abs => values.next().unwrap_or_default().abs(),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Word(String),
+    Quoted(String),
+    Symbol(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                number.push(c);
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .map_err(|_| format!("invalid number {number}"))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        } else if c == '\'' || c == '"' {
+            chars.next();
+            let mut quoted = String::new();
+            loop {
+                match chars.next() {
+                    Some(next) if next == c => break,
+                    Some(next) => quoted.push(next),
+                    None => return Err("unterminated quote".to_string()),
+                }
+            }
+            tokens.push(Token::Quoted(quoted));
+        } else if "+-*/().,".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            return Err(format!("unexpected '{c}'"));
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse(source: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+    let expr = parser.sum()?;
+    match parser.next() {
+        None => Ok(expr),
+        Some(token) => Err(format!("unexpected {}", describe(&token))),
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(value) => value.to_string(),
+        Token::Word(word) => word.to_string(),
+        Token::Quoted(name) => format!("'{name}'"),
+        Token::Symbol(c) => format!("'{c}'"),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        let found = self.tokens.get(self.position) == Some(&Token::Symbol(symbol));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            return Ok(());
+        }
+        match self.tokens.get(self.position) {
+            Some(token) => Err(format!("expected '{symbol}', found {}", describe(token))),
+            None => Err(format!("expected '{symbol}' at the end")),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        loop {
+            let op = match () {
+                _ if self.eat('+') => '+',
+                _ if self.eat('-') => '-',
+                _ => return Ok(expr),
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.factor()?;
+        loop {
+            let op = match () {
+                _ if self.eat('*') => '*',
+                _ if self.eat('/') => '/',
+                _ => return Ok(expr),
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.factor()?));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Symbol('-')) => Ok(Expr::Negate(Box::new(self.factor()?))),
+            Some(Token::Symbol('(')) => {
+                let expr = self.sum()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Token::Word(word)) if self.eat('(') => {
+                let function = match word.as_str() {
+                    "min" => Function::Min,
+                    "max" => Function::Max,
+                    "abs" => Function::Abs,
+                    _ => return Err(format!("unknown function {word}, expected min, max or abs")),
+                };
+                let mut args = vec![self.sum()?];
+                while self.eat(',') {
+                    args.push(self.sum()?);
+                }
+                self.expect(')')?;
+                if matches!(function, Function::Abs) && args.len() > 1 {
+                    return Err("abs takes one argument".to_string());
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Some(Token::Word(interface) | Token::Quoted(interface)) => {
+                self.expect('.')?;
+                let metric = match self.next() {
+                    Some(Token::Word(metric)) => metric,
+                    _ => return Err(format!("expected tx, rx or total after {interface}.")),
+                };
+                match Metric::from_name(&metric) {
+                    Some(metric) => Ok(Expr::Rate(interface, metric)),
+                    None => Err(format!(
+                        "unknown metric {interface}.{metric}, expected tx, rx or total"
+                    )),
+                }
+            }
+            Some(token) => Err(format!("unexpected {}", describe(&token))),
+            None => Err("unexpected end".to_string()),
+        }
+    }
+}
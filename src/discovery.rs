@@ -0,0 +1,121 @@
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use sysinfo::{System, SystemExt};
+
+use crate::error::{Error, Result, ResultExt};
+
+// `serve --announce` broadcasts "net-stat <port> <hostname>" to this port,
+// `discover` listens on it. Broadcasts stay on the local network.
+pub const DISCOVERY_PORT: u16 = 9092;
+const ANNOUNCE_EVERY: Duration = Duration::from_secs(2);
+// long enough to hear every announcing agent at least twice
+const LISTEN_FOR: Duration = Duration::from_secs(5);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Peer {
+    // where the agent serves its samples, for --connect
+    pub address: SocketAddr,
+    pub name: String,
+}
+
+pub fn announcement(port: u16, name: &str) -> String {
+    format!("net-stat {port} {name}")
+}
+
+// The agent behind an announcement sent from `from`, it serves on the
+// announced port of the address it announced from
+pub fn parse_announcement(datagram: &[u8], from: IpAddr) -> Option<Peer> {
+    let text = std::str::from_utf8(datagram).ok()?;
+    let mut fields = text.trim_end().splitn(3, ' ');
+    if fields.next()? != "net-stat" {
+        return None;
+    }
+    let port = fields.next()?.parse().ok()?;
+    Some(Peer {
+        address: SocketAddr::new(from, port),
+        name: fields.next().unwrap_or_default().to_string(),
+    })
+}
+
+// Announces the agent serving on `address` until the process is stopped.
+// One on a loopback address can't be reached from elsewhere, so there is
+// nothing to announce.
+pub async fn announce(address: SocketAddr) -> Result<()> {
+    if address.ip().is_loopback() {
+        return Err(Error::config(
+            "--announce needs --bind on an address the network can reach, e.g. 0.0.0.0:9091",
+        ));
+    }
+    let socket = tokio::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .or_ui(|| "failed to open the announcement socket")?;
+    socket
+        .set_broadcast(true)
+        .or_ui(|| "failed to enable broadcasts")?;
+    let name = System::new().host_name().unwrap_or_default();
+    let message = announcement(address.port(), &name);
+    eprintln!("announcing on UDP port {DISCOVERY_PORT}");
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ANNOUNCE_EVERY);
+        loop {
+            interval.tick().await;
+            // no route for broadcasts yet, e.g. before the network is up
+            let _ = socket
+                .send_to(message.as_bytes(), (Ipv4Addr::BROADCAST, DISCOVERY_PORT))
+                .await;
+        }
+    });
+    Ok(())
+}
+
+// The agents heard announcing themselves within a few seconds, by address
+pub fn discover() -> Result<Vec<Peer>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))
+        .or_ui(|| format!("failed to listen on UDP port {DISCOVERY_PORT}"))?;
+    let deadline = Instant::now() + LISTEN_FOR;
+    let mut peers: Vec<Peer> = Vec::new();
+    let mut buffer = [0u8; 512];
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if left.is_zero() {
+            break;
+        }
+        socket
+            .set_read_timeout(Some(left))
+            .or_ui(|| "failed to wait for announcements")?;
+        let (length, from) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e).or_ui(|| "failed to receive announcements"),
+        };
+        if let Some(peer) = parse_announcement(&buffer[..length], from.ip()) {
+            if !peers.iter().any(|known| known.address == peer.address) {
+                peers.push(peer);
+            }
+        }
+    }
+    peers.sort_by_key(|peer| peer.address);
+    Ok(peers)
+}
+
+// `net-stat discover`
+pub fn print_peers() -> Result<()> {
+    eprintln!(
+        "listening for announcing agents for {}s",
+        LISTEN_FOR.as_secs()
+    );
+    let peers = discover()?;
+    if peers.is_empty() {
+        println!("no agents found, start them with serve --bind 0.0.0.0:9091 --announce");
+    }
+    for peer in peers {
+        println!(
+            "{:<24} {:<20} net-stat --connect {}",
+            peer.address, peer.name, peer.address
+        );
+    }
+    Ok(())
+}
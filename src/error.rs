@@ -0,0 +1,146 @@
+use std::{error::Error as StdError, fmt, sync::Arc};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+// shared so snapshots carrying an error can go to every pipeline consumer
+type Source = Arc<dyn StdError + Send + Sync>;
+
+// What went wrong, so embedders and the status line can tell a broken
+// config apart from a failing OS interface or a full disk
+#[derive(Debug, Clone)]
+pub enum Error {
+    // reading counters, sockets, netlink, pings and other OS interfaces
+    Backend(Detail),
+    // the config file, key bindings, rules, macros and command line
+    Config(Detail),
+    // exports and data files: history, notes, snapshots, images and alert
+    // outputs
+    Export(Detail),
+    // the terminal, attached sessions and the HTTP API
+    Ui(Detail),
+}
+
+#[derive(Debug, Clone)]
+pub struct Detail {
+    pub message: String,
+    pub source: Option<Source>,
+}
+
+impl Error {
+    pub fn backend(message: impl Into<String>) -> Error {
+        Error::Backend(Detail::new(message, None))
+    }
+
+    pub fn config(message: impl Into<String>) -> Error {
+        Error::Config(Detail::new(message, None))
+    }
+
+    pub fn export(message: impl Into<String>) -> Error {
+        Error::Export(Detail::new(message, None))
+    }
+
+    pub fn ui(message: impl Into<String>) -> Error {
+        Error::Ui(Detail::new(message, None))
+    }
+
+    pub fn class(&self) -> &'static str {
+        match self {
+            Error::Backend(_) => "backend",
+            Error::Config(_) => "config",
+            Error::Export(_) => "export",
+            Error::Ui(_) => "ui",
+        }
+    }
+
+    pub fn detail(&self) -> &Detail {
+        match self {
+            Error::Backend(detail)
+            | Error::Config(detail)
+            | Error::Export(detail)
+            | Error::Ui(detail) => detail,
+        }
+    }
+
+    // Prefix the message, keeping the class
+    pub fn context(self, message: impl fmt::Display) -> Error {
+        let prefix = |detail: Detail| Detail {
+            message: format!("{message}: {}", detail.message),
+            source: detail.source,
+        };
+        match self {
+            Error::Backend(detail) => Error::Backend(prefix(detail)),
+            Error::Config(detail) => Error::Config(prefix(detail)),
+            Error::Export(detail) => Error::Export(prefix(detail)),
+            Error::Ui(detail) => Error::Ui(prefix(detail)),
+        }
+    }
+}
+
+impl Detail {
+    fn new(message: impl Into<String>, source: Option<Source>) -> Detail {
+        Detail {
+            message: message.into(),
+            source,
+        }
+    }
+}
+
+// The message, and with {:#} each cause after it like "failed to read x: No
+// such file", the way the status line shows errors
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.detail().message)?;
+        if f.alternate() {
+            let mut source = self.source();
+            while let Some(error) = source {
+                write!(f, ": {error}")?;
+                source = error.source();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        let source = self.detail().source.as_deref()?;
+        Some(source as &(dyn StdError + 'static))
+    }
+}
+
+// Classify a foreign error where it crosses into the crate
+pub trait ResultExt<T> {
+    fn or_backend<M: Into<String>>(self, message: impl FnOnce() -> M) -> Result<T>;
+    fn or_config<M: Into<String>>(self, message: impl FnOnce() -> M) -> Result<T>;
+    fn or_export<M: Into<String>>(self, message: impl FnOnce() -> M) -> Result<T>;
+    fn or_ui<M: Into<String>>(self, message: impl FnOnce() -> M) -> Result<T>;
+}
+
+impl<T, E: StdError + Send + Sync + 'static> ResultExt<T> for std::result::Result<T, E> {
+    fn or_backend<M: Into<String>>(self, message: impl FnOnce() -> M) -> Result<T> {
+        self.map_err(|e| Error::Backend(Detail::new(message(), Some(Arc::new(e)))))
+    }
+
+    fn or_config<M: Into<String>>(self, message: impl FnOnce() -> M) -> Result<T> {
+        self.map_err(|e| Error::Config(Detail::new(message(), Some(Arc::new(e)))))
+    }
+
+    fn or_export<M: Into<String>>(self, message: impl FnOnce() -> M) -> Result<T> {
+        self.map_err(|e| Error::Export(Detail::new(message(), Some(Arc::new(e)))))
+    }
+
+    fn or_ui<M: Into<String>>(self, message: impl FnOnce() -> M) -> Result<T> {
+        self.map_err(|e| Error::Ui(Detail::new(message(), Some(Arc::new(e)))))
+    }
+}
+
+// Prefix errors that are already classified
+pub trait Context<T> {
+    fn context<M: fmt::Display>(self, message: impl FnOnce() -> M) -> Result<T>;
+}
+
+impl<T> Context<T> for Result<T> {
+    fn context<M: fmt::Display>(self, message: impl FnOnce() -> M) -> Result<T> {
+        self.map_err(|e| e.context(message()))
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    app::App,
+    collector::Subscription,
+    config::Config,
+    error::{Error, Result, ResultExt},
+    graphite::{GraphiteConfig, GraphiteSink},
+    mqtt::{MqttConfig, MqttSink},
+    networks::{collect_interfaces, InterfaceData},
+    units::parse_bytes,
+};
+
+// One exported measurement of an interface, using calibrated counters
+pub struct Sample {
+    pub timestamp: f64,
+    pub interface: String,
+    pub sent: u64,
+    pub rec: u64,
+    pub sent_total: u64,
+    pub rec_total: u64,
+    pub sent_rate: f64,
+    pub rec_rate: f64,
+}
+
+pub trait Sink: Send {
+    fn name(&self) -> &str;
+    fn write(&mut self, samples: &[Sample]) -> Result<()>;
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    pub csv: Option<CsvConfig>,
+    pub mqtt: Option<MqttConfig>,
+    pub graphite: Option<GraphiteConfig>,
+    // coarsen exported values so reports can be shared
+    pub privacy: Option<PrivacyConfig>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    // byte counts and rates are rounded to multiples of this, e.g. "1 MB"
+    pub bytes: Option<String>,
+    // seconds timestamps are rounded down to
+    pub timestamp: Option<u64>,
+    // replace interface names with if0, if1, ... in order of appearance
+    pub anonymize_interfaces: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CsvConfig {
+    pub path: String,
+    // "," ";" "tab", defaults to ";" when the decimal separator is a comma
+    pub delimiter: Option<String>,
+    // "." "," or "auto" to follow LC_NUMERIC
+    pub decimal: String,
+}
+
+impl Default for CsvConfig {
+    fn default() -> Self {
+        CsvConfig {
+            path: "net-stat.csv".to_string(),
+            delimiter: None,
+            decimal: "auto".to_string(),
+        }
+    }
+}
+
+impl ExportConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(csv) = &self.csv {
+            CsvFormat::from_config(csv)?;
+        }
+        if let Some(mqtt) = &self.mqtt {
+            mqtt.validate()?;
+        }
+        if let Some(graphite) = &self.graphite {
+            graphite.validate()?;
+        }
+        if let Some(privacy) = &self.privacy {
+            Privacy::from_config(privacy)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct Exporter {
+    sinks: Vec<Box<dyn Sink>>,
+    privacy: Option<Privacy>,
+    pub error: Option<String>,
+}
+
+impl Exporter {
+    pub fn from_config(config: &ExportConfig) -> Result<Exporter> {
+        let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+        if let Some(csv) = &config.csv {
+            sinks.push(Box::new(CsvSink::open(csv)?));
+        }
+        if let Some(mqtt) = &config.mqtt {
+            sinks.push(Box::new(MqttSink::open(mqtt)?));
+        }
+        if let Some(graphite) = &config.graphite {
+            sinks.push(Box::new(GraphiteSink::open(graphite)?));
+        }
+        let privacy = config
+            .privacy
+            .as_ref()
+            .map(Privacy::from_config)
+            .transpose()?;
+        Ok(Exporter {
+            sinks,
+            privacy,
+            error: None,
+        })
+    }
+
+    pub fn write(&mut self, samples: &[Sample]) {
+        self.error = None;
+        let coarsened;
+        let samples = match &mut self.privacy {
+            Some(privacy) => {
+                coarsened = privacy.apply(samples);
+                &coarsened
+            }
+            None => samples,
+        };
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.write(samples) {
+                self.error = Some(format!("{} export failed: {e:#}", sink.name()));
+            }
+        }
+    }
+
+    // Called before exiting so buffered sinks don't lose samples
+    pub fn flush(&mut self) {
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.flush() {
+                self.error = Some(format!("{} export failed: {e:#}", sink.name()));
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+}
+
+pub fn samples_from(app: &App, elapsed: f64) -> Vec<Sample> {
+    samples_for(&app.all_interfaces, elapsed)
+}
+
+// `interfaces` are already calibrated
+pub fn samples_for(interfaces: &[InterfaceData], elapsed: f64) -> Vec<Sample> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs_f64())
+        .unwrap_or_default();
+    interfaces
+        .iter()
+        .map(|interface| Sample {
+            timestamp,
+            interface: interface.name.to_string(),
+            sent: interface.sent,
+            rec: interface.rec,
+            sent_total: interface.sent_total,
+            rec_total: interface.rec_total,
+            sent_rate: rate(interface.sent, elapsed),
+            rec_rate: rate(interface.rec, elapsed),
+        })
+        .collect()
+}
+
+fn rate(bytes: u64, elapsed: f64) -> f64 {
+    if elapsed > 0.0 {
+        bytes as f64 / elapsed
+    } else {
+        0.0
+    }
+}
+
+pub fn export_samples(app: &mut App, elapsed: f64) {
+    if app.exporter.is_empty() {
+        return;
+    }
+    let samples = samples_from(app, elapsed);
+    app.exporter.write(&samples);
+}
+
+// An exporter fed by its own pipeline subscription, so slow sinks lag on
+// their own thread instead of stalling the UI
+pub struct ExportWorker {
+    error: Arc<Mutex<Option<String>>>,
+    thread: JoinHandle<()>,
+}
+
+impl ExportWorker {
+    pub fn spawn(mut exporter: Exporter, config: Config, mut subscription: Subscription) -> Self {
+        let error = Arc::new(Mutex::new(None));
+        let status = error.clone();
+        let thread = thread::spawn(move || {
+            let mut last_tick = Instant::now();
+            while let Some(snapshot) = subscription.blocking_recv() {
+                let elapsed = snapshot
+                    .taken
+                    .saturating_duration_since(last_tick)
+                    .as_secs_f64();
+                last_tick = snapshot.taken;
+                let interfaces = collect_interfaces(&config, snapshot.interfaces);
+                exporter.write(&samples_for(&interfaces, elapsed));
+                *status.lock().unwrap() = exporter.error.clone();
+            }
+            // the pipeline closed, nothing more is coming
+            exporter.flush();
+            *status.lock().unwrap() = exporter.error.clone();
+        });
+        ExportWorker { error, thread }
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    // Waits for the queued samples to be written once the pipeline is closed
+    pub fn finish(self) {
+        let _ = self.thread.join();
+    }
+}
+
+// Rounding and anonymization applied to samples before any sink sees them
+pub struct Privacy {
+    bytes: u64,
+    timestamp: u64,
+    anonymize_interfaces: bool,
+    names: HashMap<String, String>,
+}
+
+impl Privacy {
+    pub fn from_config(config: &PrivacyConfig) -> Result<Privacy> {
+        let bytes = match &config.bytes {
+            Some(bytes) => parse_bytes(bytes)
+                .filter(|bytes| *bytes > 0)
+                .ok_or_else(|| {
+                    Error::config(format!("invalid export privacy bytes \"{bytes}\""))
+                })?,
+            None => 1,
+        };
+        Ok(Privacy {
+            bytes,
+            timestamp: config.timestamp.unwrap_or(0),
+            anonymize_interfaces: config.anonymize_interfaces,
+            names: HashMap::new(),
+        })
+    }
+
+    pub fn apply(&mut self, samples: &[Sample]) -> Vec<Sample> {
+        let round = |value: u64| (value + self.bytes / 2) / self.bytes * self.bytes;
+        let round_rate = |value: f64| (value / self.bytes as f64).round() * self.bytes as f64;
+        samples
+            .iter()
+            .map(|sample| {
+                let interface = if self.anonymize_interfaces {
+                    let next = format!("if{}", self.names.len());
+                    self.names
+                        .entry(sample.interface.to_string())
+                        .or_insert(next)
+                        .to_string()
+                } else {
+                    sample.interface.to_string()
+                };
+                let timestamp = if self.timestamp > 0 {
+                    (sample.timestamp / self.timestamp as f64).floor() * self.timestamp as f64
+                } else {
+                    sample.timestamp
+                };
+                Sample {
+                    timestamp,
+                    interface,
+                    sent: round(sample.sent),
+                    rec: round(sample.rec),
+                    sent_total: round(sample.sent_total),
+                    rec_total: round(sample.rec_total),
+                    sent_rate: round_rate(sample.sent_rate),
+                    rec_rate: round_rate(sample.rec_rate),
+                }
+            })
+            .collect()
+    }
+}
+
+// Field delimiter and decimal separator, so output opens cleanly in
+// spreadsheet applications using a comma as decimal separator
+pub struct CsvFormat {
+    pub delimiter: char,
+    pub decimal: char,
+}
+
+// Locales that write decimals with a comma
+const COMMA_LOCALES: [&str; 24] = [
+    "de", "fr", "es", "it", "nl", "pt", "ru", "pl", "sv", "da", "fi", "nb", "nn", "no", "cs", "sk",
+    "tr", "el", "hu", "ro", "uk", "id", "is", "sl",
+];
+
+impl CsvFormat {
+    pub fn from_config(config: &CsvConfig) -> Result<CsvFormat> {
+        let decimal = match config.decimal.as_str() {
+            "auto" => locale_decimal(),
+            "." => '.',
+            "," => ',',
+            other => {
+                return Err(Error::config(format!(
+                    "invalid CSV decimal separator \"{other}\""
+                )))
+            }
+        };
+        let delimiter = match config.delimiter.as_deref() {
+            None if decimal == ',' => ';',
+            None | Some(",") => ',',
+            Some(";") => ';',
+            Some("tab") | Some("\t") => '\t',
+            Some(other) => return Err(Error::config(format!("invalid CSV delimiter \"{other}\""))),
+        };
+        if delimiter == decimal {
+            return Err(Error::config(format!(
+                "CSV delimiter and decimal separator must differ, both are \"{delimiter}\""
+            )));
+        }
+        Ok(CsvFormat { delimiter, decimal })
+    }
+
+    pub fn number(&self, value: f64) -> String {
+        let value = format!("{value:.3}");
+        if self.decimal == '.' {
+            value
+        } else {
+            value.replace('.', &self.decimal.to_string())
+        }
+    }
+
+    pub fn field(&self, value: &str) -> String {
+        if value.contains(self.delimiter) || value.contains('"') || value.contains('\n') {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    pub fn row(&self, fields: &[String]) -> String {
+        let delimiter = self.delimiter.to_string();
+        fields.join(&delimiter)
+    }
+}
+
+fn locale_decimal() -> char {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default();
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+    if COMMA_LOCALES.contains(&language) {
+        ','
+    } else {
+        '.'
+    }
+}
+
+pub struct CsvSink {
+    format: CsvFormat,
+    writer: BufWriter<File>,
+}
+
+const CSV_HEADER: [&str; 8] = [
+    "timestamp",
+    "interface",
+    "sent",
+    "recieved",
+    "sent_total",
+    "recieved_total",
+    "sent_rate",
+    "recieved_rate",
+];
+
+impl CsvSink {
+    pub fn open(config: &CsvConfig) -> Result<CsvSink> {
+        let format = CsvFormat::from_config(config)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.path)
+            .or_export(|| format!("failed to open CSV export {}", config.path))?;
+        let empty = file.metadata().map(|meta| meta.len() == 0).unwrap_or(true);
+        let mut writer = BufWriter::new(file);
+        if empty {
+            let header: Vec<String> = CSV_HEADER.iter().map(|name| name.to_string()).collect();
+            writeln!(writer, "{}", format.row(&header))
+                .or_export(|| format!("failed to write CSV export {}", config.path))?;
+        }
+        Ok(CsvSink { format, writer })
+    }
+}
+
+impl Sink for CsvSink {
+    fn name(&self) -> &str {
+        "CSV"
+    }
+
+    fn write(&mut self, samples: &[Sample]) -> Result<()> {
+        for sample in samples {
+            let fields = vec![
+                self.format.number(sample.timestamp),
+                self.format.field(&sample.interface),
+                sample.sent.to_string(),
+                sample.rec.to_string(),
+                sample.sent_total.to_string(),
+                sample.rec_total.to_string(),
+                self.format.number(sample.sent_rate),
+                self.format.number(sample.rec_rate),
+            ];
+            writeln!(self.writer, "{}", self.format.row(&fields))
+                .or_export(|| "failed to write CSV export")?;
+        }
+        self.writer
+            .flush()
+            .or_export(|| "failed to write CSV export")
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer
+            .flush()
+            .or_export(|| "failed to write CSV export")
+    }
+}
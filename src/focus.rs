@@ -0,0 +1,93 @@
+use crate::app::App;
+
+// A busier interface only takes over once it has out-paced the focused one
+// by this factor for HOLD seconds in a row, so focus doesn't flap between
+// links with similar traffic
+const FACTOR: f64 = 1.5;
+const HOLD: f64 = 3.0;
+// Below this combined rate an interface is considered idle
+const MIN_RATE: f64 = 1024.0;
+
+// Moves the selection to whichever interface currently has the highest rate
+#[derive(Default)]
+pub struct AutoFocus {
+    pub enabled: bool,
+    focused: Option<String>,
+    // the interface trying to take over and for how long it has been ahead
+    candidate: Option<(String, f64)>,
+}
+
+impl AutoFocus {
+    pub fn new(enabled: bool) -> AutoFocus {
+        AutoFocus {
+            enabled,
+            ..AutoFocus::default()
+        }
+    }
+
+    pub fn status(&self) -> Option<String> {
+        self.enabled.then(|| match &self.focused {
+            Some(name) => format!("auto-focus {name}"),
+            None => "auto-focus".to_string(),
+        })
+    }
+
+    // `rates` are combined sent and recieved rates by interface name, returns
+    // the interface to focus when it changes
+    pub fn update(&mut self, rates: &[(String, f64)], elapsed: f64) -> Option<String> {
+        let (busiest, rate) = rates
+            .iter()
+            .filter(|(_, rate)| *rate >= MIN_RATE)
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        let focused_rate = self
+            .focused
+            .as_ref()
+            .and_then(|name| rates.iter().find(|(other, _)| other == name))
+            .map(|(_, rate)| *rate);
+        let Some(focused_rate) = focused_rate else {
+            // nothing focused yet, or the focused interface went away
+            self.candidate = None;
+            self.focused = Some(busiest.to_string());
+            return self.focused.clone();
+        };
+        if Some(busiest) == self.focused.as_ref() || *rate < focused_rate * FACTOR {
+            self.candidate = None;
+            return None;
+        }
+        let ahead = match &self.candidate {
+            Some((name, ahead)) if name == busiest => ahead + elapsed,
+            _ => elapsed,
+        };
+        if ahead < HOLD {
+            self.candidate = Some((busiest.to_string(), ahead));
+            return None;
+        }
+        self.candidate = None;
+        self.focused = Some(busiest.to_string());
+        self.focused.clone()
+    }
+}
+
+pub fn toggle_auto_focus(app: &mut App) {
+    app.auto_focus = AutoFocus::new(!app.auto_focus.enabled);
+}
+
+pub fn update_auto_focus(app: &mut App, elapsed: f64) {
+    if !app.auto_focus.enabled {
+        return;
+    }
+    let rates: Vec<(String, f64)> = app
+        .net_interfaces
+        .iter()
+        .filter_map(|interface| {
+            let stats = app.stats.get(&interface.name)?;
+            let rate = stats.sent.current() + stats.rec.current();
+            Some((interface.name.to_string(), rate))
+        })
+        .collect();
+    if let Some(name) = app.auto_focus.update(&rates, elapsed) {
+        if let Some(i) = app.net_interfaces.iter().position(|i| i.name == name) {
+            app.selected = i;
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{app::App, networks::GraphData};
+
+// Seconds of recent samples the forecast is based on
+const WINDOW: f64 = 30.0;
+// Below this combined rate nothing counts as a transfer
+const MIN_RATE: f64 = 64.0 * 1024.0;
+
+// Projection for a sustained transfer, assuming the recent mean rate holds
+pub struct Forecast {
+    pub rate: f64,
+    pub size: u64,
+    // seconds until `size` more bytes are moved
+    pub target: f64,
+    // seconds until the configured quota is used up
+    pub quota: Option<f64>,
+}
+
+// A transfer is sustained when it covers most of the window and most samples
+// stay close to the mean, so short bursts don't produce a forecast
+fn sustained_rate(graph: &GraphData) -> Option<f64> {
+    let now = *graph.times.last()?;
+    // the sample before the window marks the time the first one started
+    let start = graph
+        .times
+        .iter()
+        .position(|time| now - time <= WINDOW)?
+        .max(1);
+    let first = *graph.times.get(start - 1)?;
+    if now - first < WINDOW / 2.0 {
+        return None;
+    }
+    let totals: Vec<u64> = graph.sent[start..]
+        .iter()
+        .zip(&graph.rec[start..])
+        .map(|(sent, rec)| sent + rec)
+        .collect();
+    let rate = totals.iter().sum::<u64>() as f64 / (now - first);
+    let mean = totals.iter().sum::<u64>() as f64 / totals.len() as f64;
+    let steady = totals
+        .iter()
+        .filter(|total| **total as f64 >= mean / 4.0)
+        .count();
+    (rate >= MIN_RATE && steady * 5 >= totals.len() * 4).then_some(rate)
+}
+
+pub fn update_forecasts(app: &mut App) {
+    let size = app.config.forecast_size();
+    app.forecasts = app
+        .all_interfaces
+        .iter()
+        .filter_map(|interface| {
+            let rate = sustained_rate(app.net_interface_graphs.get(&interface.name)?)?;
+            // against the usage of the quota's period, as the quota panel
+            // counts it, none until that is known
+            let quota = app
+                .config
+                .quota(&interface.name)
+                .zip(app.quotas.used(&interface.name))
+                .map(|(quota, used)| quota.saturating_sub(used) as f64 / rate);
+            let forecast = Forecast {
+                rate,
+                size,
+                target: size as f64 / rate,
+                quota,
+            };
+            Some((interface.name.to_string(), forecast))
+        })
+        .collect();
+}
@@ -0,0 +1,321 @@
+use std::{collections::HashMap, fs, net::IpAddr};
+
+use crate::error::{Error, Result, ResultExt};
+
+const METADATA_MARKER: &[u8] = b"\xab\xcd\xefMaxMind.com";
+// the data section follows the search tree after 16 zero bytes
+const DATA_SEPARATOR: usize = 16;
+const CACHE_SIZE: usize = 4096;
+// maps in real databases nest a few levels, deeper is a broken or crafted
+// file, e.g. one whose pointers loop
+const MAX_DEPTH: usize = 32;
+
+// A decoded MaxMind DB data section value
+#[derive(Debug)]
+enum Value {
+    String(String),
+    Uint(u64),
+    Int(i64),
+    Map(Vec<(String, Value)>),
+    // doubles, booleans, bytes and arrays, which lookups don't need
+    Other,
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    // e.g. ["country", "iso_code"]
+    fn path(&self, path: &[&str]) -> Option<&Value> {
+        path.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_uint(&self) -> Option<u64> {
+        match self {
+            Value::Uint(n) => Some(*n),
+            Value::Int(n) => u64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+}
+
+// One .mmdb file, e.g. GeoLite2-Country or GeoLite2-ASN, read into memory
+struct Database {
+    bytes: Vec<u8>,
+    node_count: usize,
+    record_size: usize,
+    data_start: usize,
+    // the node IPv4 addresses start from in an IPv6 tree
+    ipv4_start: usize,
+    ipv6: bool,
+}
+
+impl Database {
+    fn open(path: &str) -> Result<Database> {
+        let bytes = fs::read(path).or_export(|| format!("failed to read geoip database {path}"))?;
+        let invalid = || Error::export(format!("{path} is not a MaxMind DB file"));
+        let marker = bytes
+            .windows(METADATA_MARKER.len())
+            .rposition(|window| window == METADATA_MARKER)
+            .ok_or_else(invalid)?;
+        let (metadata, _) =
+            decode(&bytes, marker + METADATA_MARKER.len(), 0, 0).ok_or_else(invalid)?;
+        let field = |name: &str| metadata.get(name).and_then(Value::as_uint);
+        let node_count = field("node_count").ok_or_else(invalid)? as usize;
+        let record_size = field("record_size").ok_or_else(invalid)? as usize;
+        if ![24, 28, 32].contains(&record_size) {
+            return Err(Error::export(format!(
+                "{path} uses unsupported record size {record_size}"
+            )));
+        }
+        let tree_size = node_count
+            .checked_mul(record_size)
+            .map(|bits| bits / 4)
+            .filter(|size| size.saturating_add(DATA_SEPARATOR) <= marker)
+            .ok_or_else(invalid)?;
+        let mut database = Database {
+            bytes,
+            node_count,
+            record_size,
+            data_start: tree_size + DATA_SEPARATOR,
+            ipv4_start: 0,
+            ipv6: field("ip_version") == Some(6),
+        };
+        // IPv4 lives under ::/96 in an IPv6 tree
+        if database.ipv6 {
+            let mut node = 0;
+            for _ in 0..96 {
+                if node >= node_count {
+                    break;
+                }
+                node = database.record(node, 0).ok_or_else(invalid)?;
+            }
+            database.ipv4_start = node;
+        }
+        Ok(database)
+    }
+
+    fn record(&self, node: usize, bit: u8) -> Option<usize> {
+        let size = self.record_size * 2 / 8;
+        let b = self.bytes.get(node * size..node * size + size)?;
+        let be = |bytes: &[u8]| bytes.iter().fold(0usize, |n, byte| n << 8 | *byte as usize);
+        Some(match (self.record_size, bit) {
+            (24, 0) => be(&b[0..3]),
+            (24, _) => be(&b[3..6]),
+            (28, 0) => (b[3] as usize & 0xf0) << 20 | be(&b[0..3]),
+            (28, _) => (b[3] as usize & 0x0f) << 24 | be(&b[4..7]),
+            (_, 0) => be(&b[0..4]),
+            _ => be(&b[4..8]),
+        })
+    }
+
+    fn lookup(&self, address: IpAddr) -> Option<Value> {
+        let (bits, mut node) = match address {
+            IpAddr::V4(v4) if self.ipv6 => (v4.octets().to_vec(), self.ipv4_start),
+            IpAddr::V4(v4) => (v4.octets().to_vec(), 0),
+            IpAddr::V6(v6) if self.ipv6 => (v6.octets().to_vec(), 0),
+            IpAddr::V6(_) => return None,
+        };
+        for i in 0..bits.len() * 8 {
+            if node >= self.node_count {
+                break;
+            }
+            let bit = bits[i / 8] >> (7 - i % 8) & 1;
+            node = self.record(node, bit)?;
+        }
+        // equal to the node count means no data for this network
+        if node <= self.node_count {
+            return None;
+        }
+        let offset = self.data_start + (node - self.node_count).checked_sub(DATA_SEPARATOR)?;
+        decode(&self.bytes, offset, self.data_start, 0).map(|(value, _)| value)
+    }
+}
+
+fn be_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0u64, |n, byte| n << 8 | *byte as u64)
+}
+
+// Decodes the value at `offset`, returning it with the offset after it.
+// Pointers are relative to `base`, the start of the data section. `depth`
+// counts the maps, arrays and pointers the value is inside of.
+fn decode(bytes: &[u8], offset: usize, base: usize, depth: usize) -> Option<(Value, usize)> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let control = *bytes.get(offset)?;
+    let mut offset = offset + 1;
+    let mut kind = control >> 5;
+    if kind == 1 {
+        let size = (control >> 3 & 3) as usize;
+        let low = (control & 7) as usize;
+        let raw = be_uint(bytes.get(offset..offset + size + 1)?) as usize;
+        let pointer = match size {
+            0 => low << 8 | raw,
+            1 => (low << 16 | raw) + 2048,
+            2 => (low << 24 | raw) + 526336,
+            _ => raw,
+        };
+        // the format doesn't allow a pointer to point at another
+        if *bytes.get(base + pointer)? >> 5 == 1 {
+            return None;
+        }
+        let (value, _) = decode(bytes, base + pointer, base, depth + 1)?;
+        return Some((value, offset + size + 1));
+    }
+    if kind == 0 {
+        kind = 7 + *bytes.get(offset)?;
+        offset += 1;
+    }
+    let mut size = (control & 0x1f) as usize;
+    if size >= 29 {
+        let extra = size - 28;
+        let raw = be_uint(bytes.get(offset..offset + extra)?) as usize;
+        size = match extra {
+            1 => 29 + raw,
+            2 => 285 + raw,
+            _ => 65821 + raw,
+        };
+        offset += extra;
+    }
+    let data = match kind {
+        7 => {
+            // each entry takes at least two bytes
+            let mut entries = Vec::with_capacity(size.min(bytes.len() / 2));
+            for _ in 0..size {
+                let (key, next) = decode(bytes, offset, base, depth + 1)?;
+                let (value, next) = decode(bytes, next, base, depth + 1)?;
+                entries.push((key.as_str()?.to_string(), value));
+                offset = next;
+            }
+            return Some((Value::Map(entries), offset));
+        }
+        11 => {
+            for _ in 0..size {
+                let (_, next) = decode(bytes, offset, base, depth + 1)?;
+                offset = next;
+            }
+            return Some((Value::Other, offset));
+        }
+        // booleans keep their value in the size
+        14 => return Some((Value::Other, offset)),
+        _ => bytes.get(offset..offset + size)?,
+    };
+    let value = match kind {
+        2 => Value::String(String::from_utf8_lossy(data).into_owned()),
+        3 | 4 | 15 => Value::Other,
+        5 | 6 | 9 | 10 => Value::Uint(be_uint(&data[data.len().saturating_sub(8)..])),
+        8 => Value::Int(be_uint(data) as u32 as i32 as i64),
+        _ => return None,
+    };
+    Some((value, offset + size))
+}
+
+// Country and network owner of remote addresses from local MaxMind DB
+// files, looked up once per address
+#[derive(Default)]
+pub struct GeoIp {
+    paths: Vec<String>,
+    // read on the first lookup, city databases run to tens of megabytes
+    databases: Option<Vec<Database>>,
+    cache: HashMap<IpAddr, Option<String>>,
+    pub error: Option<String>,
+}
+
+impl GeoIp {
+    // Only checks the files are there, they're read when first needed
+    pub fn open(paths: &[String]) -> Result<GeoIp> {
+        for path in paths {
+            fs::metadata(path).or_export(|| format!("failed to read geoip database {path}"))?;
+        }
+        Ok(GeoIp {
+            paths: paths.to_vec(),
+            databases: None,
+            cache: HashMap::new(),
+            error: None,
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.paths.is_empty()
+    }
+
+    // e.g. "US AS15169 Google LLC", None for private and unknown addresses
+    pub fn locate(&mut self, address: IpAddr) -> Option<String> {
+        if let Some(location) = self.cache.get(&address) {
+            return location.clone();
+        }
+        if self.cache.len() >= CACHE_SIZE {
+            self.cache.clear();
+        }
+        if self.databases.is_none() {
+            let databases = self
+                .paths
+                .iter()
+                .map(|path| Database::open(path))
+                .collect::<Result<Vec<Database>>>();
+            self.databases = Some(databases.unwrap_or_else(|e| {
+                self.error = Some(format!("{e:#}"));
+                Vec::new()
+            }));
+        }
+        let location = self.describe(address);
+        self.cache.insert(address, location.clone());
+        location
+    }
+
+    fn describe(&self, address: IpAddr) -> Option<String> {
+        let mut country = None;
+        let mut city = None;
+        let mut asn = None;
+        let mut organization = None;
+        for value in self
+            .databases
+            .iter()
+            .flatten()
+            .filter_map(|db| db.lookup(address))
+        {
+            country = country.or_else(|| {
+                value
+                    .path(&["country", "iso_code"])
+                    .or_else(|| value.path(&["registered_country", "iso_code"]))
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            });
+            city = city.or_else(|| {
+                value
+                    .path(&["city", "names", "en"])
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            });
+            asn = asn.or_else(|| value.get("autonomous_system_number")?.as_uint());
+            organization = organization.or_else(|| {
+                value
+                    .get("autonomous_system_organization")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            });
+        }
+        let parts: Vec<String> = [
+            country,
+            city,
+            asn.map(|asn| format!("AS{asn}")),
+            organization,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
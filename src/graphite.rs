@@ -0,0 +1,277 @@
+use std::{
+    io::Write,
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    error::{Error, Result, ResultExt},
+    export::{Sample, Sink},
+};
+
+// Pushes samples to Graphite's plaintext protocol over TCP or to StatsD
+// over UDP, e.g.
+//   [export.graphite]
+//   address = "graphite.lan:2003"
+//   protocol = "graphite"
+//   prefix = "hosts.router.net"
+// Each interface gets tx_rate and rx_rate in bytes per second plus tx_total
+// and rx_total in bytes, sent to StatsD as gauges and tx_bytes and rx_bytes
+// as counters.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GraphiteConfig {
+    // host:port, 2003 for graphite and 8125 for statsd when left out
+    pub address: String,
+    // "graphite" or "statsd"
+    pub protocol: String,
+    // put in front of every metric path
+    pub prefix: String,
+    // seconds samples are collected for before they are sent
+    pub flush_interval: f64,
+}
+
+impl Default for GraphiteConfig {
+    fn default() -> Self {
+        GraphiteConfig {
+            address: "localhost".to_string(),
+            protocol: "graphite".to_string(),
+            prefix: "net-stat".to_string(),
+            flush_interval: 10.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Graphite,
+    Statsd,
+}
+
+impl GraphiteConfig {
+    fn protocol(&self) -> Result<Protocol> {
+        match self.protocol.as_str() {
+            "graphite" => Ok(Protocol::Graphite),
+            "statsd" => Ok(Protocol::Statsd),
+            other => Err(Error::config(format!(
+                "unknown metrics protocol \"{other}\", expected graphite or statsd"
+            ))),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.protocol()?;
+        if self.address.is_empty() {
+            return Err(Error::config("the graphite address is empty"));
+        }
+        if self.flush_interval < 0.0 {
+            return Err(Error::config("graphite flush_interval can't be negative"));
+        }
+        Ok(())
+    }
+
+    fn address(&self, protocol: Protocol) -> String {
+        match self.address.rsplit_once(':') {
+            Some((_, port)) if port.parse::<u16>().is_ok() => self.address.to_string(),
+            _ if protocol == Protocol::Statsd => format!("{}:8125", self.address),
+            _ => format!("{}:2003", self.address),
+        }
+    }
+}
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// wait between attempts while graphite is unreachable
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// keeps StatsD datagrams below a typical MTU
+const DATAGRAM_SIZE: usize = 1400;
+
+enum Connection {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+// Lines are buffered until the flush interval passes. Like the MQTT sink
+// the endpoint is connected on first use and again after failures.
+pub struct GraphiteSink {
+    config: GraphiteConfig,
+    protocol: Protocol,
+    connection: Option<Connection>,
+    pending: Vec<String>,
+    last_flush: Instant,
+    last_attempt: Option<Instant>,
+    failure: Option<String>,
+}
+
+impl GraphiteSink {
+    pub fn open(config: &GraphiteConfig) -> Result<GraphiteSink> {
+        config.validate()?;
+        Ok(GraphiteSink {
+            protocol: config.protocol()?,
+            config: config.clone(),
+            connection: None,
+            pending: Vec::new(),
+            last_flush: Instant::now(),
+            last_attempt: None,
+            failure: None,
+        })
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        let address = self.config.address(self.protocol);
+        let failed = || format!("failed to connect to {} {address}", self.config.protocol);
+        let socket = address
+            .to_socket_addrs()
+            .or_export(failed)?
+            .next()
+            .ok_or_else(|| Error::export(failed()))?;
+        match self.protocol {
+            Protocol::Graphite => Ok(Connection::Tcp(
+                TcpStream::connect_timeout(&socket, CONNECT_TIMEOUT).or_export(failed)?,
+            )),
+            Protocol::Statsd => {
+                let local = if socket.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                };
+                let udp = UdpSocket::bind(local).or_export(failed)?;
+                udp.connect(socket).or_export(failed)?;
+                Ok(Connection::Udp(udp))
+            }
+        }
+    }
+
+    fn lines(&self, sample: &Sample) -> Vec<String> {
+        let path = |metric: &str| {
+            format!(
+                "{}.{}.{metric}",
+                self.config.prefix,
+                metric_name(&sample.interface)
+            )
+        };
+        let values = [
+            ("tx_rate", format!("{:.1}", sample.sent_rate)),
+            ("rx_rate", format!("{:.1}", sample.rec_rate)),
+            ("tx_total", sample.sent_total.to_string()),
+            ("rx_total", sample.rec_total.to_string()),
+        ];
+        match self.protocol {
+            Protocol::Graphite => values
+                .iter()
+                .map(|(metric, value)| {
+                    format!("{} {value} {}\n", path(metric), sample.timestamp as u64)
+                })
+                .collect(),
+            Protocol::Statsd => {
+                let mut lines: Vec<String> = values
+                    .iter()
+                    .map(|(metric, value)| format!("{}:{value}|g", path(metric)))
+                    .collect();
+                lines.push(format!("{}:{}|c", path("tx_bytes"), sample.sent));
+                lines.push(format!("{}:{}|c", path("rx_bytes"), sample.rec));
+                lines
+            }
+        }
+    }
+
+    fn send(&mut self) -> Result<()> {
+        if self.connection.is_none() {
+            if self
+                .last_attempt
+                .is_some_and(|attempt| attempt.elapsed() < RECONNECT_DELAY)
+            {
+                return match &self.failure {
+                    Some(failure) => Err(Error::export(failure.to_string())),
+                    None => Ok(()),
+                };
+            }
+            self.last_attempt = Some(Instant::now());
+            match self.connect() {
+                Ok(connection) => {
+                    self.connection = Some(connection);
+                    self.failure = None;
+                }
+                Err(e) => {
+                    self.failure = Some(format!("{e:#}"));
+                    return Err(e);
+                }
+            }
+        }
+        let lines = std::mem::take(&mut self.pending);
+        let sent = match &mut self.connection {
+            Some(Connection::Tcp(stream)) => stream.write_all(lines.concat().as_bytes()),
+            Some(Connection::Udp(socket)) => datagrams(&lines)
+                .iter()
+                .try_for_each(|datagram| socket.send(datagram.as_bytes()).map(|_| ())),
+            None => Ok(()),
+        };
+        if let Err(e) = sent {
+            self.connection = None;
+            return Err(e).or_export(|| format!("lost {}", self.config.protocol));
+        }
+        Ok(())
+    }
+}
+
+impl Sink for GraphiteSink {
+    fn name(&self) -> &str {
+        match self.protocol {
+            Protocol::Graphite => "Graphite",
+            Protocol::Statsd => "StatsD",
+        }
+    }
+
+    fn write(&mut self, samples: &[Sample]) -> Result<()> {
+        for sample in samples {
+            let lines = self.lines(sample);
+            self.pending.extend(lines);
+        }
+        if self.last_flush.elapsed().as_secs_f64() < self.config.flush_interval {
+            return Ok(());
+        }
+        self.last_flush = Instant::now();
+        // points that couldn't be sent are dropped rather than piling up
+        // while the endpoint is down
+        let sent = self.send();
+        self.pending.clear();
+        sent
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        // a last attempt even within the reconnect delay
+        self.last_attempt = None;
+        self.send()
+    }
+}
+
+// Dots separate path components in both protocols and ':' '|' delimit
+// StatsD fields, so they can't appear in an interface's component
+fn metric_name(interface: &str) -> String {
+    interface
+        .chars()
+        .map(|c| match c {
+            '.' | ':' | '|' | '@' | ' ' | '/' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+// StatsD takes several newline separated metrics per datagram
+fn datagrams(lines: &[String]) -> Vec<String> {
+    let mut datagrams: Vec<String> = Vec::new();
+    for line in lines {
+        match datagrams.last_mut() {
+            Some(datagram) if datagram.len() + 1 + line.len() <= DATAGRAM_SIZE => {
+                datagram.push('\n');
+                datagram.push_str(line);
+            }
+            _ => datagrams.push(line.to_string()),
+        }
+    }
+    datagrams
+}
@@ -0,0 +1,207 @@
+use ratatui::{
+    buffer::Buffer,
+    prelude::Rect,
+    style::Style,
+    symbols::bar,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::{app::App, plot::Overlay, units::format_rate};
+
+// Buckets per doubling of the rate, so percentiles are within about 10%
+const STEPS: f64 = 4.0;
+// up to a petabyte a second
+const BUCKETS: usize = 200;
+
+// Every rate sampled this session counted into logarithmic buckets, so the
+// memory stays the same however long it runs
+#[derive(Clone)]
+pub struct RateHistogram {
+    // samples under 1 B/s
+    idle: u64,
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl Default for RateHistogram {
+    fn default() -> RateHistogram {
+        RateHistogram {
+            idle: 0,
+            counts: vec![0; BUCKETS],
+            total: 0,
+        }
+    }
+}
+
+impl RateHistogram {
+    pub fn push(&mut self, rate: f64) {
+        self.total += 1;
+        match bucket(rate) {
+            Some(index) => self.counts[index] += 1,
+            None => self.idle += 1,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    // The rate `fraction` of the samples are at or below, idle ones
+    // included, taken as the middle of its bucket
+    pub fn percentile(&self, fraction: f64) -> f64 {
+        let rank = (fraction * self.total as f64).ceil().max(1.0) as u64;
+        if rank <= self.idle {
+            return 0.0;
+        }
+        let mut seen = self.idle;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return middle(index);
+            }
+        }
+        0.0
+    }
+
+    // first and last bucket holding any samples
+    fn range(&self) -> Option<(usize, usize)> {
+        let first = self.counts.iter().position(|count| *count > 0)?;
+        let last = self.counts.iter().rposition(|count| *count > 0)?;
+        Some((first, last))
+    }
+
+    // The counts of buckets `first..=last` spread over `width` columns
+    fn columns(&self, (first, last): (usize, usize), width: usize) -> Vec<u64> {
+        let buckets = last + 1 - first;
+        (0..width)
+            .map(|column| {
+                let start = first + column * buckets / width;
+                let end = (first + (column + 1) * buckets / width).max(start + 1);
+                self.counts[start..end.min(last + 1)].iter().sum()
+            })
+            .collect()
+    }
+}
+
+fn bucket(rate: f64) -> Option<usize> {
+    (rate >= 1.0).then(|| ((rate.log2() * STEPS) as usize).min(BUCKETS - 1))
+}
+
+// geometric middle of a bucket
+fn middle(index: usize) -> f64 {
+    ((index as f64 + 0.5) / STEPS).exp2()
+}
+
+fn lower(index: usize) -> f64 {
+    (index as f64 / STEPS).exp2()
+}
+
+// "RX p50 1.2 MB/s, p95 8.0 MB/s, p99 9.5 MB/s"
+fn percentiles(direction: &str, histogram: &RateHistogram) -> String {
+    format!(
+        "{direction} p50 {}, p95 {}, p99 {}",
+        format_rate(histogram.percentile(0.5)),
+        format_rate(histogram.percentile(0.95)),
+        format_rate(histogram.percentile(0.99))
+    )
+}
+
+// How the rates of one interface spread over the session, RX and TX
+// overlaid on a logarithmic rate axis, for the detail view
+pub struct Histogram<'a> {
+    block: Block<'a>,
+    lines: Vec<Line<'a>>,
+    text: Style,
+    // first and last bucket with samples in either direction
+    range: Option<(usize, usize)>,
+    sent: RateHistogram,
+    rec: RateHistogram,
+    sent_style: Style,
+    rec_style: Style,
+    sent_symbols: bar::Set,
+    rec_symbols: bar::Set,
+}
+
+pub fn create_histogram<'a>(app: &App, name: &str) -> Histogram<'a> {
+    let theme = &app.theme;
+    let (sent, rec) = app
+        .stats
+        .get(name)
+        .map(|stats| (stats.sent_histogram.clone(), stats.rec_histogram.clone()))
+        .unwrap_or_default();
+    let range = match (sent.range(), rec.range()) {
+        (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.max(b.1))),
+        (a, b) => a.or(b),
+    };
+    let title = match range {
+        Some((first, last)) => format!(
+            "{name} rates this session, {} to {}",
+            format_rate(lower(first)),
+            format_rate(lower(last + 1))
+        ),
+        None => format!("{name} rates this session"),
+    };
+    let lines = match sent.total() {
+        0 => vec![Line::from("No samples yet")],
+        _ => vec![
+            Line::styled(percentiles("RX", &rec), theme.rx_graph),
+            Line::styled(percentiles("TX", &sent), theme.tx_graph),
+        ],
+    };
+    Histogram {
+        block: Block::default()
+            .title(Span::styled(title, theme.title))
+            .borders(Borders::ALL)
+            .border_style(theme.border),
+        lines,
+        text: theme.text,
+        range,
+        sent,
+        rec,
+        sent_style: theme.tx_graph,
+        rec_style: theme.rx_graph,
+        sent_symbols: theme.tx_symbols.clone(),
+        rec_symbols: theme.rx_symbols.clone(),
+    }
+}
+
+impl Widget for Histogram<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.render(area, buf);
+        let text_height = (self.lines.len() as u16).min(inner.height);
+        let text = Rect {
+            height: text_height,
+            ..inner
+        };
+        Paragraph::new(self.lines)
+            .style(self.text)
+            .render(text, buf);
+        let bars = Rect {
+            y: inner.y + text_height,
+            height: inner.height - text_height,
+            ..inner
+        };
+        let Some(range) = self.range.filter(|_| bars.height > 0 && bars.width > 0) else {
+            return;
+        };
+        let width = bars.width as usize;
+        let (sent, rec) = (
+            self.sent.columns(range, width),
+            self.rec.columns(range, width),
+        );
+        Overlay {
+            block: Block::default(),
+            sent: &sent,
+            rec: &rec,
+            sent_style: self.sent_style,
+            rec_style: self.rec_style,
+            sent_symbols: self.sent_symbols,
+            rec_symbols: self.rec_symbols,
+            mirrored: false,
+            max: None,
+        }
+        .render(bars, buf);
+    }
+}
@@ -0,0 +1,318 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    app::App,
+    error::{Context, Error, Result, ResultExt},
+};
+
+// Time range shown in the graphs: raw ticks or one of the aggregated series
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Raw,
+    Minute,
+    FiveMinutes,
+    Hour,
+}
+
+impl Resolution {
+    pub const AGGREGATED: [Resolution; 3] = [
+        Resolution::Minute,
+        Resolution::FiveMinutes,
+        Resolution::Hour,
+    ];
+
+    pub fn seconds(&self) -> f64 {
+        match self {
+            Resolution::Raw => 0.0,
+            Resolution::Minute => 60.0,
+            Resolution::FiveMinutes => 300.0,
+            Resolution::Hour => 3600.0,
+        }
+    }
+
+    // Buckets kept per resolution: a day of minutes, a week of five minute
+    // buckets and a month of hours
+    fn capacity(&self) -> usize {
+        match self {
+            Resolution::Raw => 0,
+            Resolution::Minute => 1440,
+            Resolution::FiveMinutes => 2016,
+            Resolution::Hour => 720,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resolution::Raw => "raw",
+            Resolution::Minute => "1m",
+            Resolution::FiveMinutes => "5m",
+            Resolution::Hour => "1h",
+        }
+    }
+
+    pub fn next(&self) -> Resolution {
+        match self {
+            Resolution::Raw => Resolution::Minute,
+            Resolution::Minute => Resolution::FiveMinutes,
+            Resolution::FiveMinutes => Resolution::Hour,
+            Resolution::Hour => Resolution::Raw,
+        }
+    }
+}
+
+// Aggregate of all samples whose time falls into [start, start + length)
+#[derive(Clone, Copy)]
+pub struct Bucket {
+    pub start: f64,
+    pub sent: u64,
+    pub rec: u64,
+    pub elapsed: f64,
+    pub sent_peak: f64,
+    pub rec_peak: f64,
+}
+
+impl Bucket {
+    pub fn sent_avg(&self) -> f64 {
+        rate(self.sent, self.elapsed)
+    }
+
+    pub fn rec_avg(&self) -> f64 {
+        rate(self.rec, self.elapsed)
+    }
+}
+
+fn rate(bytes: u64, elapsed: f64) -> f64 {
+    if elapsed > 0.0 {
+        bytes as f64 / elapsed
+    } else {
+        0.0
+    }
+}
+
+#[derive(Default)]
+pub struct InterfaceHistory {
+    buckets: [VecDeque<Bucket>; 3],
+}
+
+impl InterfaceHistory {
+    // `time` is when the sample was taken and `elapsed` the seconds it covers
+    pub fn push(&mut self, time: f64, sent: u64, rec: u64, elapsed: f64) {
+        let sent_rate = rate(sent, elapsed);
+        let rec_rate = rate(rec, elapsed);
+        for (buckets, resolution) in self.buckets.iter_mut().zip(Resolution::AGGREGATED) {
+            let length = resolution.seconds();
+            let start = (time / length).floor() * length;
+            match buckets.back_mut() {
+                Some(bucket) if bucket.start == start => {
+                    bucket.sent += sent;
+                    bucket.rec += rec;
+                    bucket.elapsed += elapsed;
+                    bucket.sent_peak = bucket.sent_peak.max(sent_rate);
+                    bucket.rec_peak = bucket.rec_peak.max(rec_rate);
+                }
+                _ => {
+                    if buckets.len() == resolution.capacity() {
+                        buckets.pop_front();
+                    }
+                    buckets.push_back(Bucket {
+                        start,
+                        sent,
+                        rec,
+                        elapsed,
+                        sent_peak: sent_rate,
+                        rec_peak: rec_rate,
+                    });
+                }
+            }
+        }
+    }
+
+    // Adds buckets from `other` whose start isn't recorded yet, so importing
+    // the same samples twice doesn't count them twice
+    pub fn merge(&mut self, other: InterfaceHistory) {
+        for ((buckets, imported), resolution) in self
+            .buckets
+            .iter_mut()
+            .zip(other.buckets)
+            .zip(Resolution::AGGREGATED)
+        {
+            let mut merged: Vec<Bucket> = buckets.drain(..).collect();
+            for bucket in imported {
+                if !merged.iter().any(|existing| existing.start == bucket.start) {
+                    merged.push(bucket);
+                }
+            }
+            merged.sort_by(|a, b| a.start.total_cmp(&b.start));
+            let excess = merged.len().saturating_sub(resolution.capacity());
+            buckets.extend(merged.into_iter().skip(excess));
+        }
+    }
+
+    pub fn buckets(&self, resolution: Resolution) -> Option<&VecDeque<Bucket>> {
+        Resolution::AGGREGATED
+            .iter()
+            .position(|r| *r == resolution)
+            .map(|i| &self.buckets[i])
+    }
+}
+
+// Buckets are keyed by wall clock time so they line up across restarts
+pub fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs_f64())
+        .unwrap_or_default()
+}
+
+pub fn update_history(app: &mut App, elapsed: f64) {
+    let time = unix_time();
+    // hidden interfaces keep their history too
+    app.all_interfaces.iter().for_each(|interface| {
+        app.history
+            .entry(interface.name.to_string())
+            .or_default()
+            .push(time, interface.sent, interface.rec, elapsed);
+    });
+}
+
+// Aggregated history saved under the XDG data dir so the longer ranges
+// survive restarts
+pub struct HistoryStore {
+    path: PathBuf,
+    last_saved: Instant,
+    pub error: Option<String>,
+}
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+const MAGIC: &[u8; 4] = b"NSH1";
+
+impl HistoryStore {
+    // Returns the store together with whatever history could be read, a
+    // damaged file is reported and replaced on the next save
+    pub fn open(path: PathBuf) -> (HistoryStore, HashMap<String, InterfaceHistory>) {
+        let (history, error) = match load(&path) {
+            Ok(history) => (history, None),
+            Err(e) => (HashMap::new(), Some(format!("{e:#}"))),
+        };
+        let store = HistoryStore {
+            path,
+            last_saved: Instant::now(),
+            error,
+        };
+        (store, history)
+    }
+
+    pub fn save(&mut self, history: &HashMap<String, InterfaceHistory>) {
+        self.last_saved = Instant::now();
+        if let Err(e) = save(&self.path, history) {
+            self.error = Some(format!("{e:#}"));
+        }
+    }
+}
+
+// Saves periodically so a crash loses at most a minute, `force` is used when
+// exiting
+pub fn save_history(app: &mut App, force: bool) {
+    let Some(store) = &mut app.history_store else {
+        return;
+    };
+    if force || store.last_saved.elapsed() >= SAVE_INTERVAL {
+        store.save(&app.history);
+    }
+}
+
+// File layout, little endian: magic, then per interface the name length (u16)
+// and name followed by each resolution as a bucket count (u32) and buckets of
+// start, sent, rec, elapsed, sent peak and rec peak
+pub fn save(path: &Path, history: &HashMap<String, InterfaceHistory>) -> Result<()> {
+    let mut bytes = MAGIC.to_vec();
+    for (name, interface) in history {
+        bytes.extend((name.len() as u16).to_le_bytes());
+        bytes.extend(name.as_bytes());
+        for buckets in &interface.buckets {
+            bytes.extend((buckets.len() as u32).to_le_bytes());
+            for bucket in buckets {
+                bytes.extend(bucket.start.to_le_bytes());
+                bytes.extend(bucket.sent.to_le_bytes());
+                bytes.extend(bucket.rec.to_le_bytes());
+                bytes.extend(bucket.elapsed.to_le_bytes());
+                bytes.extend(bucket.sent_peak.to_le_bytes());
+                bytes.extend(bucket.rec_peak.to_le_bytes());
+            }
+        }
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).or_export(|| format!("failed to create {}", dir.display()))?;
+    }
+    // write a temporary file first so an interrupted save keeps the old one
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, bytes)
+        .or_export(|| format!("failed to write history {}", temporary.display()))?;
+    fs::rename(&temporary, path)
+        .or_export(|| format!("failed to write history {}", path.display()))?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<HashMap<String, InterfaceHistory>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let bytes =
+        fs::read(path).or_export(|| format!("failed to read history {}", path.display()))?;
+    parse(&bytes).context(|| format!("failed to read history {}", path.display()))
+}
+
+fn parse(bytes: &[u8]) -> Result<HashMap<String, InterfaceHistory>> {
+    let mut reader = Reader(bytes);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(Error::export("not a net-stat history file"));
+    }
+    let mut history = HashMap::new();
+    while !reader.0.is_empty() {
+        let len = u16::from_le_bytes(reader.array()?) as usize;
+        let name =
+            String::from_utf8(reader.take(len)?.to_vec()).or_export(|| "invalid interface name")?;
+        let mut interface = InterfaceHistory::default();
+        for (buckets, resolution) in interface.buckets.iter_mut().zip(Resolution::AGGREGATED) {
+            let count = u32::from_le_bytes(reader.array()?) as usize;
+            for _ in 0..count {
+                buckets.push_back(Bucket {
+                    start: f64::from_le_bytes(reader.array()?),
+                    sent: u64::from_le_bytes(reader.array()?),
+                    rec: u64::from_le_bytes(reader.array()?),
+                    elapsed: f64::from_le_bytes(reader.array()?),
+                    sent_peak: f64::from_le_bytes(reader.array()?),
+                    rec_peak: f64::from_le_bytes(reader.array()?),
+                });
+            }
+            let excess = buckets.len().saturating_sub(resolution.capacity());
+            buckets.drain(..excess);
+        }
+        history.insert(name, interface);
+    }
+    Ok(history)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(Error::export("history file is truncated"));
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        self.take(N)?
+            .try_into()
+            .or_export(|| "history file is truncated")
+    }
+}
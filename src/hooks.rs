@@ -0,0 +1,170 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    alerts::{AlertEvent, AlertSink, AlertState},
+    error::{Error, Result, ResultExt},
+};
+
+// Events waiting for a hook, later ones are dropped while a hook hangs
+const QUEUE: usize = 32;
+// longest a hook may run before it's killed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Shell commands run on alert events, e.g.
+//   [hooks]
+//   on_threshold = "notify-send \"$NET_STAT_MESSAGE\""
+//   on_link_down = "logger -t net-stat link down on $NET_STAT_INTERFACE"
+// The event is passed as NET_STAT_* variables and as a JSON line on stdin.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    // a rule fired
+    pub on_threshold: Option<String>,
+    pub on_resolved: Option<String>,
+    pub on_link_down: Option<String>,
+    pub on_link_up: Option<String>,
+    pub on_anomaly: Option<String>,
+}
+
+impl HookConfig {
+    pub fn is_empty(&self) -> bool {
+        AlertState::ALL
+            .iter()
+            .all(|state| self.command(*state).is_none())
+    }
+
+    fn command(&self, state: AlertState) -> Option<&str> {
+        match state {
+            AlertState::Fired => self.on_threshold.as_deref(),
+            AlertState::Resolved => self.on_resolved.as_deref(),
+            AlertState::LinkDown => self.on_link_down.as_deref(),
+            AlertState::LinkUp => self.on_link_up.as_deref(),
+            AlertState::Anomaly => self.on_anomaly.as_deref(),
+        }
+        .filter(|command| !command.trim().is_empty())
+    }
+}
+
+struct Run {
+    command: String,
+    env: Vec<(&'static str, String)>,
+    json: String,
+}
+
+// Runs the hooks one at a time on their own thread so a slow command never
+// holds up sampling or drawing. Failures show up with the next event.
+pub struct HookSink {
+    config: HookConfig,
+    sender: SyncSender<Run>,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl HookSink {
+    pub fn spawn(config: &HookConfig) -> HookSink {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE);
+        let error = Arc::new(Mutex::new(None));
+        let status = error.clone();
+        thread::spawn(move || run_hooks(receiver, status));
+        HookSink {
+            config: config.clone(),
+            sender,
+            error,
+        }
+    }
+}
+
+impl AlertSink for HookSink {
+    fn name(&self) -> &str {
+        "hook"
+    }
+
+    fn send(&mut self, event: &AlertEvent) -> Result<()> {
+        if let Some(error) = self.error.lock().unwrap().take() {
+            return Err(Error::export(error));
+        }
+        let Some(command) = self.config.command(event.state) else {
+            return Ok(());
+        };
+        let run = Run {
+            command: command.to_string(),
+            env: vec![
+                ("NET_STAT_EVENT", event.state.name().to_string()),
+                ("NET_STAT_RULE", event.rule.clone()),
+                ("NET_STAT_INTERFACE", event.interface.clone()),
+                ("NET_STAT_VALUE", event.value.to_string()),
+                ("NET_STAT_THRESHOLD", event.threshold.to_string()),
+                ("NET_STAT_TIME", event.time.to_string()),
+                ("NET_STAT_SINCE", event.since.to_string()),
+                ("NET_STAT_MESSAGE", event.describe()),
+            ],
+            json: event.to_json()?,
+        };
+        match self.sender.try_send(run) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(Error::export(format!(
+                "{} hook still running, event dropped",
+                event.state.name()
+            ))),
+            Err(TrySendError::Disconnected(_)) => Err(Error::export("hook runner stopped")),
+        }
+    }
+}
+
+fn run_hooks(receiver: Receiver<Run>, status: Arc<Mutex<Option<String>>>) {
+    while let Ok(run) = receiver.recv() {
+        if let Err(e) = run_hook(&run) {
+            *status.lock().unwrap() = Some(format!("\"{}\": {e:#}", run.command));
+        }
+    }
+}
+
+fn run_hook(run: &Run) -> Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    let mut child = command
+        .arg(&run.command)
+        .envs(run.env.iter().map(|(name, value)| (name, value)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .or_export(|| "failed to start")?;
+    // a hook that doesn't read stdin closes it early, that's fine
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = writeln!(stdin, "{}", run.json);
+    }
+    let started = Instant::now();
+    loop {
+        match child.try_wait().or_export(|| "failed to wait for it")? {
+            Some(status) if status.success() => return Ok(()),
+            Some(status) => return Err(Error::export(format!("exited with {status}"))),
+            None if started.elapsed() >= HOOK_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(Error::export(format!(
+                    "killed after {}s",
+                    HOOK_TIMEOUT.as_secs()
+                )));
+            }
+            None => thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::{collections::HashSet, iter::zip};
+
+use ratatui::{
+    layout::Constraint,
+    text::Span,
+    widgets::{Block, Borders, Row, Table},
+};
+
+use crate::{
+    app::App,
+    history::unix_time,
+    layout::inline_sparkline,
+    networks::InterfaceData,
+    units::{format_bytes, format_clock, format_rate},
+};
+
+// Rows of the removed interfaces panel, the oldest are left out past that
+const PANEL_ROWS: usize = 4;
+const WIDTHS: [Constraint; 6] = [
+    Constraint::Length(16),
+    Constraint::Length(10),
+    Constraint::Length(10),
+    Constraint::Length(22),
+    Constraint::Length(22),
+    Constraint::Percentage(100),
+];
+
+// An interface that went away this session, e.g. an unplugged USB NIC or a
+// closed VPN tunnel. Its stats, history and graphs are kept by name.
+pub struct Departed {
+    pub interface: InterfaceData,
+    pub removed: f64,
+}
+
+#[derive(Default)]
+pub struct Hotplug {
+    // removed interfaces, most recent last
+    pub departed: Vec<Departed>,
+    // the panel is hidden until the next interface goes away
+    pub hidden: bool,
+    previous: Vec<InterfaceData>,
+}
+
+impl Hotplug {
+    pub fn shown(&self) -> bool {
+        !self.departed.is_empty() && !self.hidden
+    }
+
+    pub fn panel_height(&self) -> u16 {
+        if self.shown() {
+            self.departed.len().min(PANEL_ROWS) as u16 + 3
+        } else {
+            0
+        }
+    }
+}
+
+// Compares the interfaces of this sample with the previous one, before any
+// view filters so hiding an interface doesn't count as removing it
+pub fn update_hotplug(app: &mut App, interfaces: &[InterfaceData]) {
+    let hotplug = &mut app.hotplug;
+    let names: HashSet<&str> = interfaces.iter().map(|i| i.name.as_str()).collect();
+    // a tunnel coming back carries on where it left off
+    hotplug
+        .departed
+        .retain(|departed| !names.contains(departed.interface.name.as_str()));
+    let removed: Vec<InterfaceData> = std::mem::take(&mut hotplug.previous)
+        .into_iter()
+        .filter(|interface| !names.contains(interface.name.as_str()))
+        .collect();
+    if !removed.is_empty() {
+        let time = unix_time();
+        hotplug.hidden = false;
+        hotplug
+            .departed
+            .extend(removed.into_iter().map(|mut interface| {
+                interface.sent = 0;
+                interface.rec = 0;
+                Departed {
+                    interface,
+                    removed: time,
+                }
+            }));
+    }
+    hotplug.previous = interfaces.to_vec();
+}
+
+pub fn create_departed_table(app: &App, spark_width: usize) -> Table<'_> {
+    let theme = &app.theme;
+    let rows: Vec<Row> = app
+        .hotplug
+        .departed
+        .iter()
+        .rev()
+        .take(PANEL_ROWS)
+        .map(|departed| {
+            let interface = &departed.interface;
+            let stats = app.stats.get(&interface.name);
+            let (moved, peaks) = match stats {
+                Some(stats) => (
+                    format!(
+                        "{} / {}",
+                        format_bytes(stats.rec_bytes as f64),
+                        format_bytes(stats.sent_bytes as f64)
+                    ),
+                    format!(
+                        "{} / {}",
+                        format_rate(stats.rec.peak),
+                        format_rate(stats.sent.peak)
+                    ),
+                ),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            let traffic: Vec<u64> = app
+                .net_interface_graphs
+                .get(&interface.name)
+                .map(|graph| zip(&graph.sent, &graph.rec).map(|(s, r)| s + r).collect())
+                .unwrap_or_default();
+            Row::new(vec![
+                interface.name.to_string(),
+                interface.kind.label().to_string(),
+                format_clock(departed.removed),
+                moved,
+                peaks,
+                inline_sparkline(&traffic, spark_width, app.capabilities.unicode),
+            ])
+        })
+        .collect();
+    let hidden = app.hotplug.departed.len().saturating_sub(PANEL_ROWS);
+    let title = if hidden > 0 {
+        format!("Removed interfaces (+{hidden} earlier, esc to hide)")
+    } else {
+        "Removed interfaces (esc to hide)".to_string()
+    };
+    let block = Block::default()
+        .title(Span::styled(title, theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Table::new(rows)
+        .header(
+            Row::new(vec![
+                "Interface",
+                "Type",
+                "Removed",
+                "Session RX / TX",
+                "Peak RX / TX",
+                "Last traffic",
+            ])
+            .style(theme.title),
+        )
+        .style(theme.text)
+        .block(block)
+        .widths(&WIDTHS)
+}
+
+// Cells left for the sparkline once borders and the fixed columns are taken
+pub fn departed_spark_width(width: u16) -> usize {
+    let fixed: u16 = 16 + 10 + 10 + 22 + 22 + WIDTHS.len() as u16 - 1;
+    width.saturating_sub(2 + fixed) as usize
+}
@@ -0,0 +1,186 @@
+use std::{future, time::Duration};
+
+use serde::Serialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot},
+    time::timeout,
+};
+
+use crate::{
+    app::App,
+    error::{Result, ResultExt},
+    snapshot::{history_json, interfaces_json},
+};
+
+// Read-only JSON API served from the TUI process when `http_api` is set.
+// Requests are answered by the UI loop from the data it already collected:
+//   GET /interfaces                 latest sample, stats and alerts
+//   GET /interfaces/{name}/history  the same with raw samples and buckets
+//   GET /connections                open TCP and UDP sockets as of the last
+//                                   sample, filtered as the screen shows them
+pub struct HttpApi {
+    requests: mpsc::Receiver<HttpRequest>,
+}
+
+pub struct HttpRequest {
+    path: String,
+    reply: oneshot::Sender<Response>,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST: usize = 8192;
+
+impl HttpApi {
+    pub async fn start(bind: &str) -> Result<HttpApi> {
+        let listener = TcpListener::bind(bind)
+            .await
+            .or_ui(|| format!("failed to serve the HTTP API on {bind}"))?;
+        let (sender, requests) = mpsc::channel(16);
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(handle(socket, sender.clone()));
+            }
+        });
+        Ok(HttpApi { requests })
+    }
+}
+
+// Never resolves without an API, so it can sit in the event loop's select
+pub async fn next_request(api: &mut Option<HttpApi>) -> HttpRequest {
+    match api {
+        Some(api) => match api.requests.recv().await {
+            Some(request) => request,
+            None => future::pending().await,
+        },
+        None => future::pending().await,
+    }
+}
+
+#[derive(Serialize)]
+struct ConnectionJson {
+    protocol: &'static str,
+    local: String,
+    remote: String,
+    state: &'static str,
+    uid: u32,
+}
+
+pub fn answer(app: &App, request: HttpRequest) {
+    let path = request.path.trim_end_matches('/');
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    let result = match segments.as_slice() {
+        ["interfaces"] => Some(interfaces_json(app)),
+        ["interfaces", name, "history"] => history_json(app, &percent_decode(name)),
+        ["connections"] => {
+            let connections: Vec<ConnectionJson> = app
+                .connections
+                .iter()
+                .map(|connection| ConnectionJson {
+                    protocol: connection.protocol.name(),
+                    local: connection.local.to_string(),
+                    remote: connection.remote.to_string(),
+                    state: connection.state,
+                    uid: connection.uid,
+                })
+                .collect();
+            Some(serde_json::to_string(&connections).or_export(|| "failed to encode connections"))
+        }
+        _ => None,
+    };
+    let response = match result {
+        Some(Ok(body)) => Response { status: 200, body },
+        Some(Err(e)) => error(500, &format!("{e:#}")),
+        None => error(404, &format!("no such resource {}", request.path)),
+    };
+    let _ = request.reply.send(response);
+}
+
+fn error(status: u16, message: &str) -> Response {
+    Response {
+        status,
+        body: serde_json::json!({ "error": message }).to_string(),
+    }
+}
+
+// Interface names may be escaped, e.g. "br%2Dlan"
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+async fn handle(mut socket: TcpStream, requests: mpsc::Sender<HttpRequest>) {
+    let Ok(Some((method, path))) = timeout(REQUEST_TIMEOUT, read_request(&mut socket)).await else {
+        return;
+    };
+    let response = if method != "GET" {
+        error(405, "the API is read-only, only GET is supported")
+    } else {
+        let (reply, response) = oneshot::channel();
+        let request = HttpRequest { path, reply };
+        match requests.send(request).await {
+            Ok(()) => response
+                .await
+                .unwrap_or_else(|_| error(503, "net-stat is shutting down")),
+            Err(_) => error(503, "net-stat is shutting down"),
+        }
+    };
+    let reason = match response.status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.body.len()
+    );
+    let _ = socket.write_all(head.as_bytes()).await;
+    let _ = socket.write_all(response.body.as_bytes()).await;
+}
+
+// The method and path without the query string, None for anything that
+// isn't an HTTP request
+async fn read_request(socket: &mut TcpStream) -> Option<(String, String)> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = socket.read(&mut buffer).await.ok()?;
+        if n == 0 || request.len() + n > MAX_REQUEST {
+            return None;
+        }
+        request.extend_from_slice(&buffer[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.lines().next()?.split(' ');
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    Some((method, path))
+}
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{app::App, networks::show_interfaces};
+
+// What kind of link an interface is, from the platform where it says and
+// from the name otherwise
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterfaceType {
+    Ethernet,
+    Wifi,
+    // PPP and WWAN links, often the metered ones
+    Modem,
+    Loopback,
+    // bridges, tunnels, veths and other interfaces without hardware behind them
+    Virtual,
+    #[default]
+    Unknown,
+}
+
+impl InterfaceType {
+    pub const ALL: [InterfaceType; 6] = [
+        InterfaceType::Ethernet,
+        InterfaceType::Wifi,
+        InterfaceType::Modem,
+        InterfaceType::Loopback,
+        InterfaceType::Virtual,
+        InterfaceType::Unknown,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InterfaceType::Ethernet => "ethernet",
+            InterfaceType::Wifi => "wifi",
+            InterfaceType::Modem => "modem",
+            InterfaceType::Loopback => "loopback",
+            InterfaceType::Virtual => "virtual",
+            InterfaceType::Unknown => "unknown",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InterfaceType::Ethernet => "Ethernet",
+            InterfaceType::Wifi => "Wi-Fi",
+            InterfaceType::Modem => "Modem",
+            InterfaceType::Loopback => "Loopback",
+            InterfaceType::Virtual => "Virtual",
+            InterfaceType::Unknown => "Unknown",
+        }
+    }
+
+    pub fn icon(&self, unicode: bool) -> &'static str {
+        match (self, unicode) {
+            (InterfaceType::Ethernet, true) => "⇌",
+            (InterfaceType::Wifi, true) => "≋",
+            (InterfaceType::Modem, true) => "☏",
+            (InterfaceType::Loopback, true) => "↺",
+            (InterfaceType::Virtual, true) => "◇",
+            (InterfaceType::Ethernet, false) => "E",
+            (InterfaceType::Wifi, false) => "W",
+            (InterfaceType::Modem, false) => "M",
+            (InterfaceType::Loopback, false) => "L",
+            (InterfaceType::Virtual, false) => "V",
+            (InterfaceType::Unknown, _) => "?",
+        }
+    }
+
+    pub fn physical(&self) -> bool {
+        matches!(
+            self,
+            InterfaceType::Ethernet | InterfaceType::Wifi | InterfaceType::Modem
+        )
+    }
+
+    // Where the type places an interface when nothing else orders it: the
+    // links that carry real traffic first, loopback last
+    pub fn rank(&self) -> usize {
+        match self {
+            InterfaceType::Ethernet => 0,
+            InterfaceType::Wifi => 1,
+            InterfaceType::Modem => 2,
+            InterfaceType::Virtual => 3,
+            InterfaceType::Unknown => 4,
+            InterfaceType::Loopback => 5,
+        }
+    }
+
+    // A config filter entry, the type names or "physical" for ethernet, wifi
+    // and modems
+    pub fn parse_filter(name: &str) -> Option<Vec<InterfaceType>> {
+        if name == "physical" {
+            return Some(
+                InterfaceType::ALL
+                    .into_iter()
+                    .filter(InterfaceType::physical)
+                    .collect(),
+            );
+        }
+        InterfaceType::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .map(|kind| vec![kind])
+    }
+
+    // By the usual naming schemes of Linux, macOS and Windows, for interfaces
+    // the platform can't be asked about, e.g. remote ones
+    pub fn guess(name: &str) -> InterfaceType {
+        let name = name.to_lowercase();
+        let starts = |prefixes: &[&str]| prefixes.iter().any(|prefix| name.starts_with(prefix));
+        if name == "lo" || starts(&["lo0", "loopback"]) {
+            InterfaceType::Loopback
+        } else if starts(&["wl", "wi-fi", "wifi", "ath"]) {
+            InterfaceType::Wifi
+        } else if starts(&["ppp", "ww", "rmnet", "pdp_ip", "cellular"]) {
+            InterfaceType::Modem
+        } else if starts(&[
+            "docker",
+            "veth",
+            "br",
+            "virbr",
+            "vnet",
+            "vmnet",
+            "vethernet",
+            "tun",
+            "tap",
+            "utun",
+            "wg",
+            "tailscale",
+            "zt",
+            "ifb",
+            "dummy",
+            "awdl",
+            "llw",
+            "gif",
+            "stf",
+            "anpi",
+        ]) {
+            InterfaceType::Virtual
+        } else if starts(&["eth", "en", "em"]) {
+            InterfaceType::Ethernet
+        } else {
+            InterfaceType::Unknown
+        }
+    }
+
+    // Asks the platform first, falls back to the name
+    pub fn detect(name: &str) -> InterfaceType {
+        platform_type(name).unwrap_or_else(|| InterfaceType::guess(name))
+    }
+}
+
+pub fn toggle_physical_only(app: &mut App) {
+    app.physical_only = !app.physical_only;
+    show_interfaces(app);
+}
+
+#[cfg(target_os = "linux")]
+fn platform_type(name: &str) -> Option<InterfaceType> {
+    let dir = std::path::Path::new("/sys/class/net").join(name);
+    // ARPHRD_* from include/uapi/linux/if_arp.h
+    let arp_type: u32 = std::fs::read_to_string(dir.join("type"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(match arp_type {
+        772 => InterfaceType::Loopback,
+        _ if dir.join("wireless").exists() || dir.join("phy80211").exists() => InterfaceType::Wifi,
+        // ppp has no device of its own, WWAN drivers say so in the uevent
+        512 => InterfaceType::Modem,
+        _ if std::fs::read_to_string(dir.join("uevent"))
+            .is_ok_and(|uevent| uevent.lines().any(|line| line == "DEVTYPE=wwan")) =>
+        {
+            InterfaceType::Modem
+        }
+        // only hardware has a device behind it
+        _ if !dir.join("device").exists() => InterfaceType::Virtual,
+        1 => InterfaceType::Ethernet,
+        _ => InterfaceType::Unknown,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn platform_type(name: &str) -> Option<InterfaceType> {
+    // IANA ifType values as Windows reports them
+    crate::adapters::lookup(name).map(|adapter| match adapter.if_type {
+        24 => InterfaceType::Loopback,
+        71 => InterfaceType::Wifi,
+        // ppp, and WWAN over 3GPP and 3GPP2
+        23 | 243 | 244 => InterfaceType::Modem,
+        _ if !adapter.hardware => InterfaceType::Virtual,
+        6 => InterfaceType::Ethernet,
+        _ => InterfaceType::Unknown,
+    })
+}
@@ -0,0 +1,509 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    app::App,
+    error::{Error, Result, ResultExt},
+    history::{unix_time, Bucket, Resolution},
+    units::{format_clock, format_rate, format_timestamp},
+};
+
+const WIDTH: usize = 960;
+const HEIGHT: usize = 480;
+// plot area inside the image, the rest holds the title and axis labels
+const LEFT: usize = 100;
+const RIGHT: usize = 40;
+const TOP: usize = 50;
+const BOTTOM: usize = 50;
+const TICKS: usize = 4;
+
+// One palette shared by both formats, indices are what the PNG stores
+const PALETTE: [(u8, u8, u8); 6] = [
+    (255, 255, 255),
+    (225, 225, 225),
+    (60, 60, 60),
+    (214, 39, 40),
+    (31, 119, 180),
+    (160, 160, 160),
+];
+const BACKGROUND: u8 = 0;
+const GRID: u8 = 1;
+const TEXT: u8 = 2;
+const TX: u8 = 3;
+const RX: u8 = 4;
+const PEAK: u8 = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    fn from_path(path: &str) -> Result<ImageFormat> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".svg") {
+            Ok(ImageFormat::Svg)
+        } else if lower.ends_with(".png") {
+            Ok(ImageFormat::Png)
+        } else {
+            Err(Error::export(format!(
+                "image file \"{path}\" should end in .svg or .png"
+            )))
+        }
+    }
+}
+
+struct Series {
+    label: String,
+    color: u8,
+    // unix time and bytes per second
+    points: Vec<(f64, f64)>,
+}
+
+// Everything needed to draw the chart, in data coordinates
+struct ChartImage {
+    title: String,
+    series: Vec<Series>,
+    start: f64,
+    end: f64,
+    max: f64,
+}
+
+impl ChartImage {
+    fn x(&self, time: f64) -> f64 {
+        let width = (WIDTH - LEFT - RIGHT) as f64;
+        let span = (self.end - self.start).max(1.0);
+        LEFT as f64 + (time - self.start) / span * width
+    }
+
+    fn y(&self, rate: f64) -> f64 {
+        let height = (HEIGHT - TOP - BOTTOM) as f64;
+        (HEIGHT - BOTTOM) as f64 - rate / self.max * height
+    }
+}
+
+// The selected interface's history at the resolution shown in the graphs:
+// raw samples, or bucket averages with their peaks
+fn chart_image(app: &App) -> Result<ChartImage> {
+    let interface = app
+        .net_interfaces
+        .get(app.selected)
+        .ok_or_else(|| Error::export("no interface selected"))?;
+    let name = &interface.name;
+    let mut series = Vec::new();
+    match app.resolution {
+        Resolution::Raw => {
+            let graph = app
+                .net_interface_graphs
+                .get(name)
+                .ok_or_else(|| Error::export(format!("no samples for {name} yet")))?;
+            // raw sample times are relative to startup
+            let started = unix_time() - app.started.elapsed().as_secs_f64();
+            let rates = |values: &[u64]| -> Vec<(f64, f64)> {
+                (1..graph.times.len())
+                    .filter_map(|i| {
+                        let elapsed = graph.times[i] - graph.times[i - 1];
+                        (elapsed > 0.0)
+                            .then(|| (started + graph.times[i], values[i] as f64 / elapsed))
+                    })
+                    .collect()
+            };
+            series.push(Series {
+                label: "TX".to_string(),
+                color: TX,
+                points: rates(&graph.sent),
+            });
+            series.push(Series {
+                label: "RX".to_string(),
+                color: RX,
+                points: rates(&graph.rec),
+            });
+        }
+        resolution => {
+            let buckets = app
+                .history
+                .get(name)
+                .and_then(|history| history.buckets(resolution))
+                .ok_or_else(|| {
+                    Error::export(format!("no {} history for {name} yet", resolution.name()))
+                })?;
+            let points = |rate: fn(&Bucket) -> f64| {
+                buckets
+                    .iter()
+                    .map(|bucket| (bucket.start, rate(bucket)))
+                    .collect()
+            };
+            series.push(Series {
+                label: "TX peak".to_string(),
+                color: PEAK,
+                points: points(|bucket| bucket.sent_peak),
+            });
+            series.push(Series {
+                label: "RX peak".to_string(),
+                color: PEAK,
+                points: points(|bucket| bucket.rec_peak),
+            });
+            series.push(Series {
+                label: "TX".to_string(),
+                color: TX,
+                points: points(|bucket| bucket.sent_avg()),
+            });
+            series.push(Series {
+                label: "RX".to_string(),
+                color: RX,
+                points: points(|bucket| bucket.rec_avg()),
+            });
+        }
+    }
+    let times = series.iter().flat_map(|s| s.points.iter().map(|(t, _)| *t));
+    let (start, end) = times.fold((f64::MAX, f64::MIN), |(start, end), time| {
+        (start.min(time), end.max(time))
+    });
+    if start > end {
+        return Err(Error::export(format!("no samples for {name} yet")));
+    }
+    let max = series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|(_, rate)| *rate))
+        .fold(1024.0, f64::max)
+        * 1.1;
+    let range = match app.resolution {
+        Resolution::Raw => String::new(),
+        resolution => format!(", {} averages", resolution.name()),
+    };
+    Ok(ChartImage {
+        title: format!(
+            "{name} {} to {}{range}",
+            format_timestamp(start),
+            format_clock(end)
+        ),
+        series,
+        start,
+        end,
+        max,
+    })
+}
+
+fn hex(color: u8) -> String {
+    let (r, g, b) = PALETTE[color as usize];
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn to_svg(chart: &ChartImage) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{HEIGHT}\" font-family=\"sans-serif\" font-size=\"12\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n\
+         <text x=\"{LEFT}\" y=\"24\" font-size=\"16\" fill=\"{}\">{}</text>\n",
+        hex(BACKGROUND),
+        hex(TEXT),
+        escape(&chart.title)
+    );
+    for i in 0..=TICKS {
+        let rate = chart.max * i as f64 / TICKS as f64;
+        let y = chart.y(rate);
+        svg.push_str(&format!(
+            "<line x1=\"{LEFT}\" y1=\"{y:.1}\" x2=\"{}\" y2=\"{y:.1}\" stroke=\"{}\"/>\n\
+             <text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\" fill=\"{}\">{}</text>\n",
+            WIDTH - RIGHT,
+            hex(GRID),
+            LEFT - 8,
+            y + 4.0,
+            hex(TEXT),
+            escape(&format_rate(rate))
+        ));
+        let time = chart.start + (chart.end - chart.start) * i as f64 / TICKS as f64;
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\" fill=\"{}\">{}</text>\n",
+            chart.x(time),
+            HEIGHT - BOTTOM + 20,
+            hex(TEXT),
+            format_clock(time)
+        ));
+    }
+    for series in &chart.series {
+        let points: Vec<String> = series
+            .points
+            .iter()
+            .map(|(time, rate)| format!("{:.1},{:.1}", chart.x(*time), chart.y(*rate)))
+            .collect();
+        svg.push_str(&format!(
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>\n",
+            hex(series.color),
+            points.join(" ")
+        ));
+    }
+    for (i, series) in chart.series.iter().filter(|s| s.color != PEAK).enumerate() {
+        let x = WIDTH - RIGHT - 120 + i * 60;
+        svg.push_str(&format!(
+            "<rect x=\"{x}\" y=\"14\" width=\"12\" height=\"12\" fill=\"{}\"/>\n\
+             <text x=\"{}\" y=\"24\" fill=\"{}\">{}</text>\n",
+            hex(series.color),
+            x + 16,
+            hex(TEXT),
+            series.label
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// A palette image drawn with straight lines and a small bitmap font
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+// 3x5 glyphs, one row per entry with the leftmost pixel in the high bit.
+// Lower case letters are drawn as upper case.
+const FONT: [(char, [u8; 5]); 47] = [
+    ('0', [7, 5, 5, 5, 7]),
+    ('1', [2, 6, 2, 2, 7]),
+    ('2', [7, 1, 7, 4, 7]),
+    ('3', [7, 1, 7, 1, 7]),
+    ('4', [5, 5, 7, 1, 1]),
+    ('5', [7, 4, 7, 1, 7]),
+    ('6', [7, 4, 7, 5, 7]),
+    ('7', [7, 1, 1, 1, 1]),
+    ('8', [7, 5, 7, 5, 7]),
+    ('9', [7, 5, 7, 1, 7]),
+    ('A', [2, 5, 7, 5, 5]),
+    ('B', [6, 5, 6, 5, 6]),
+    ('C', [3, 4, 4, 4, 3]),
+    ('D', [6, 5, 5, 5, 6]),
+    ('E', [7, 4, 6, 4, 7]),
+    ('F', [7, 4, 6, 4, 4]),
+    ('G', [3, 4, 5, 5, 3]),
+    ('H', [5, 5, 7, 5, 5]),
+    ('I', [7, 2, 2, 2, 7]),
+    ('J', [1, 1, 1, 5, 2]),
+    ('K', [5, 5, 6, 5, 5]),
+    ('L', [4, 4, 4, 4, 7]),
+    ('M', [5, 7, 7, 5, 5]),
+    ('N', [6, 5, 5, 5, 5]),
+    ('O', [2, 5, 5, 5, 2]),
+    ('P', [6, 5, 6, 4, 4]),
+    ('Q', [2, 5, 5, 6, 3]),
+    ('R', [6, 5, 6, 5, 5]),
+    ('S', [3, 4, 2, 1, 6]),
+    ('T', [7, 2, 2, 2, 2]),
+    ('U', [5, 5, 5, 5, 7]),
+    ('V', [5, 5, 5, 5, 2]),
+    ('W', [5, 5, 7, 7, 5]),
+    ('X', [5, 5, 2, 5, 5]),
+    ('Y', [5, 5, 2, 2, 2]),
+    ('Z', [7, 1, 2, 4, 7]),
+    (' ', [0, 0, 0, 0, 0]),
+    ('.', [0, 0, 0, 0, 2]),
+    (':', [0, 2, 0, 2, 0]),
+    ('/', [1, 1, 2, 4, 4]),
+    ('-', [0, 0, 7, 0, 0]),
+    ('(', [1, 2, 2, 2, 1]),
+    (')', [4, 2, 2, 2, 4]),
+    ('_', [0, 0, 0, 0, 7]),
+    (',', [0, 0, 0, 2, 4]),
+    ('+', [0, 2, 7, 2, 0]),
+    ('?', [7, 1, 2, 0, 2]),
+];
+const SCALE: usize = 2;
+const GLYPH_WIDTH: usize = 4 * SCALE;
+
+impl Canvas {
+    fn new() -> Canvas {
+        Canvas {
+            pixels: vec![BACKGROUND; WIDTH * HEIGHT],
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, color: u8) {
+        if (0..WIDTH as i64).contains(&x) && (0..HEIGHT as i64).contains(&y) {
+            self.pixels[y as usize * WIDTH + x as usize] = color;
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: u8) {
+        for y in y..y + height {
+            for x in x..x + width {
+                self.set(x as i64, y as i64, color);
+            }
+        }
+    }
+
+    // Two pixels wide so lines stay visible when the image is scaled down
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: u8) {
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil() as usize;
+        for step in 0..=steps {
+            let t = if steps > 0 {
+                step as f64 / steps as f64
+            } else {
+                0.0
+            };
+            let x = (from.0 + (to.0 - from.0) * t).round() as i64;
+            let y = (from.1 + (to.1 - from.1) * t).round() as i64;
+            self.set(x, y, color);
+            self.set(x, y + 1, color);
+        }
+    }
+
+    fn text_width(text: &str) -> usize {
+        text.chars().count() * GLYPH_WIDTH
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, color: u8) {
+        for (i, c) in text.chars().enumerate() {
+            let c = c.to_ascii_uppercase();
+            let glyph = FONT
+                .iter()
+                .find(|(glyph, _)| *glyph == c)
+                .or_else(|| FONT.iter().find(|(glyph, _)| *glyph == '?'))
+                .map(|(_, rows)| rows)
+                .unwrap();
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (4 >> column) != 0 {
+                        let x = x + i * GLYPH_WIDTH + column * SCALE;
+                        self.fill(x, y + row * SCALE, SCALE, SCALE, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn to_png(chart: &ChartImage) -> Vec<u8> {
+    let mut canvas = Canvas::new();
+    canvas.text(LEFT, 18, &chart.title, TEXT);
+    for i in 0..=TICKS {
+        let rate = chart.max * i as f64 / TICKS as f64;
+        let y = chart.y(rate);
+        canvas.line((LEFT as f64, y), ((WIDTH - RIGHT) as f64, y), GRID);
+        let label = format_rate(rate);
+        let x = (LEFT - 8).saturating_sub(Canvas::text_width(&label));
+        canvas.text(x, y as usize - 4, &label, TEXT);
+        let time = chart.start + (chart.end - chart.start) * i as f64 / TICKS as f64;
+        let label = format_clock(time);
+        let x = chart.x(time) as usize - Canvas::text_width(&label) / 2;
+        canvas.text(x, HEIGHT - BOTTOM + 12, &label, TEXT);
+    }
+    for series in &chart.series {
+        for pair in series.points.windows(2) {
+            let from = (chart.x(pair[0].0), chart.y(pair[0].1));
+            let to = (chart.x(pair[1].0), chart.y(pair[1].1));
+            canvas.line(from, to, series.color);
+        }
+    }
+    for (i, series) in chart.series.iter().filter(|s| s.color != PEAK).enumerate() {
+        let x = WIDTH - RIGHT - 120 + i * 60;
+        canvas.fill(x, 16, 12, 12, series.color);
+        canvas.text(x + 16, 18, &series.label, TEXT);
+    }
+    encode_png(&canvas.pixels)
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+// An 8 bit palette PNG. The image data is stored in uncompressed deflate
+// blocks, a chart of a few flat colors is small enough without compression.
+fn encode_png(pixels: &[u8]) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    let mut header = Vec::new();
+    header.extend((WIDTH as u32).to_be_bytes());
+    header.extend((HEIGHT as u32).to_be_bytes());
+    // bit depth 8, palette, default compression, filter and no interlace
+    header.extend([8, 3, 0, 0, 0]);
+    chunk(&mut png, b"IHDR", &header);
+    let palette: Vec<u8> = PALETTE.iter().flat_map(|(r, g, b)| [*r, *g, *b]).collect();
+    chunk(&mut png, b"PLTE", &palette);
+
+    // every row starts with filter type 0
+    let mut raw = Vec::with_capacity((WIDTH + 1) * HEIGHT);
+    for row in pixels.chunks(WIDTH) {
+        raw.push(0);
+        raw.extend(row);
+    }
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = raw.chunks(0xffff).collect();
+    for (i, block) in blocks.iter().enumerate() {
+        zlib.push((i == blocks.len() - 1) as u8);
+        let len = block.len() as u16;
+        zlib.extend(len.to_le_bytes());
+        zlib.extend((!len).to_le_bytes());
+        zlib.extend(*block);
+    }
+    zlib.extend(adler32(&raw).to_be_bytes());
+    chunk(&mut png, b"IDAT", &zlib);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+// e.g. net-stat-eth0-2024-05-01_140327.svg
+fn default_path(app: &App) -> String {
+    let name = app
+        .net_interfaces
+        .get(app.selected)
+        .map(|interface| interface.name.as_str())
+        .unwrap_or("graph");
+    let stamp = format_timestamp(unix_time())
+        .replace(' ', "_")
+        .replace(':', "");
+    format!("net-stat-{name}-{stamp}.svg")
+}
+
+pub fn write_image(app: &App, path: &str) -> Result<PathBuf> {
+    let path = match path.trim() {
+        "" => default_path(app),
+        path => path.to_string(),
+    };
+    let format = ImageFormat::from_path(&path)?;
+    let chart = chart_image(app)?;
+    let contents = match format {
+        ImageFormat::Svg => to_svg(&chart).into_bytes(),
+        ImageFormat::Png => to_png(&chart),
+    };
+    fs::write(&path, contents).or_export(|| format!("failed to write {path}"))?;
+    Ok(PathBuf::from(path))
+}
+
+// Render the selected interface's graph to an image file, the outcome is
+// shown in the status line
+pub fn export_image(app: &mut App, path: String) {
+    app.file_status = Some(match write_image(app, &path) {
+        Ok(path) => format!("graph saved to {}", path.display()),
+        Err(e) => format!("{e:#}"),
+    });
+}
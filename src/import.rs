@@ -0,0 +1,148 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    config::{history_path, Config},
+    error::{Context, Error, Result, ResultExt},
+    history::{load, save, InterfaceHistory},
+};
+
+pub struct ImportedSample {
+    pub timestamp: f64,
+    pub interface: String,
+    pub sent: u64,
+    pub rec: u64,
+    pub sent_rate: f64,
+    pub rec_rate: f64,
+}
+
+// Reads files written by the CSV sink. The delimiter is taken from the
+// header, decimals may use either separator.
+fn parse_csv(contents: &str) -> Result<Vec<ImportedSample>> {
+    let mut lines = contents.lines();
+    let header = lines.next().ok_or_else(|| Error::export("file is empty"))?;
+    let delimiter = header
+        .strip_prefix("timestamp")
+        .and_then(|rest| rest.chars().next())
+        .ok_or_else(|| Error::export("not a net-stat CSV export"))?;
+    lines
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            parse_row(line, delimiter).context(|| format!("invalid sample on line {}", i + 2))
+        })
+        .collect()
+}
+
+const CSV_FIELDS: [&str; 8] = [
+    "timestamp",
+    "interface",
+    "sent",
+    "recieved",
+    "sent total",
+    "recieved total",
+    "sent rate",
+    "recieved rate",
+];
+
+fn parse_row(line: &str, delimiter: char) -> Result<ImportedSample> {
+    let fields = split_fields(line, delimiter);
+    if fields.len() < 8 {
+        return Err(Error::export(format!(
+            "expected 8 fields, found {}",
+            fields.len()
+        )));
+    }
+    let invalid = |i: usize| format!("invalid {} \"{}\"", CSV_FIELDS[i], fields[i]);
+    let number =
+        |i: usize| -> Result<f64> { fields[i].replace(',', ".").parse().or_export(|| invalid(i)) };
+    Ok(ImportedSample {
+        timestamp: number(0)?,
+        interface: fields[1].to_string(),
+        sent: fields[2].parse().or_export(|| invalid(2))?,
+        rec: fields[3].parse().or_export(|| invalid(3))?,
+        sent_rate: number(6)?,
+        rec_rate: number(7)?,
+    })
+}
+
+// Undoes the quoting applied by `CsvFormat::field`
+fn split_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+// Seconds covered by a sample, recovered from its rate or, for idle samples,
+// from the previous sample of the same interface
+fn sample_elapsed(sample: &ImportedSample, previous: Option<f64>) -> f64 {
+    if sample.sent_rate > 0.0 {
+        sample.sent as f64 / sample.sent_rate
+    } else if sample.rec_rate > 0.0 {
+        sample.rec as f64 / sample.rec_rate
+    } else {
+        previous
+            .map(|previous| sample.timestamp - previous)
+            .unwrap_or(0.0)
+    }
+}
+
+// Samples of a CSV export in time order, without duplicate rows
+pub fn read_samples(file: &Path) -> Result<Vec<ImportedSample>> {
+    let contents =
+        fs::read_to_string(file).or_export(|| format!("failed to read {}", file.display()))?;
+    let mut samples =
+        parse_csv(&contents).context(|| format!("failed to import {}", file.display()))?;
+    samples.sort_by(|a, b| {
+        a.timestamp
+            .total_cmp(&b.timestamp)
+            .then_with(|| a.interface.cmp(&b.interface))
+    });
+    // the same row appearing twice, e.g. from concatenated exports
+    samples.dedup_by(|a, b| a.timestamp == b.timestamp && a.interface == b.interface);
+    Ok(samples)
+}
+
+pub fn import_csv(file: &Path) -> Result<()> {
+    let samples = read_samples(file)?;
+
+    let mut imported: HashMap<String, InterfaceHistory> = HashMap::new();
+    let mut previous: HashMap<String, f64> = HashMap::new();
+    for sample in &samples {
+        let elapsed = sample_elapsed(sample, previous.get(&sample.interface).copied());
+        previous.insert(sample.interface.to_string(), sample.timestamp);
+        imported
+            .entry(sample.interface.to_string())
+            .or_default()
+            .push(sample.timestamp, sample.sent, sample.rec, elapsed);
+    }
+
+    let path = history_path().ok_or_else(|| Error::export("could not determine the data dir"))?;
+    let mut history = load(&path)?;
+    let interfaces = imported.len();
+    for (name, interface) in imported {
+        history.entry(name).or_default().merge(interface);
+    }
+    save(&path, &history)?;
+
+    println!(
+        "imported {} samples for {interfaces} interfaces into {}",
+        samples.len(),
+        path.display()
+    );
+    if !Config::load()?.persist_history {
+        println!("set persist_history = true in the config to show them in net-stat");
+    }
+    Ok(())
+}
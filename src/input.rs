@@ -0,0 +1,223 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    alerts::{current_threshold, set_threshold},
+    app::{update, App},
+    image::export_image,
+    keymap::KeyBinding,
+    notes::add_note,
+    route::lookup_route,
+    traceroute::{current_target, start_traceroute},
+    Action,
+};
+
+// A bare digit is a count prefix, if no motion follows within this time it
+// falls back to whatever the digit is bound to (the screen by default)
+const PREFIX_TIMEOUT: Duration = Duration::from_millis(600);
+// counted motions are repeated as that many actions, more digits than this
+// are ignored
+const MAX_COUNT: usize = 999;
+
+// Single line text entry shown at the bottom of the screen, line editing
+// keys go to it instead of the keymap while it is open
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Route,
+    Note,
+    Image,
+    Threshold,
+    Traceroute,
+    // filters the interfaces as it's typed, enter keeps the filter
+    Search,
+}
+
+impl PromptKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            PromptKind::Route => "Route to host or IP",
+            PromptKind::Note => {
+                "Note for the selected interface, -30m to cover the last 30 minutes"
+            }
+            PromptKind::Image => "Save the selected graph as .svg or .png, empty for an SVG here",
+            PromptKind::Threshold => {
+                "Alert when the selected interface goes above, e.g. rx 10 MB for 30"
+            }
+            PromptKind::Traceroute => "Traceroute to host or IP",
+            PromptKind::Search => "Show interfaces matching, enter to keep, esc to clear",
+        }
+    }
+}
+
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub text: String,
+}
+
+pub fn open_prompt(app: &mut App, kind: PromptKind) {
+    app.input.clear();
+    let text = match kind {
+        PromptKind::Threshold => current_threshold(app),
+        PromptKind::Traceroute => current_target(app),
+        PromptKind::Search => app.search.clone().unwrap_or_default(),
+        _ => String::new(),
+    };
+    app.prompt = Some(Prompt { kind, text });
+}
+
+pub fn edit_prompt(app: &mut App, code: KeyCode) {
+    let Some(prompt) = &mut app.prompt else {
+        return;
+    };
+    match code {
+        KeyCode::Char(c) => prompt.text.push(c),
+        KeyCode::Backspace => {
+            prompt.text.pop();
+        }
+        KeyCode::Esc => {
+            let kind = prompt.kind;
+            app.prompt = None;
+            if kind == PromptKind::Search {
+                update(app, Action::SearchInterfaces(None));
+            }
+            return;
+        }
+        KeyCode::Enter => {
+            let Some(prompt) = app.prompt.take() else {
+                return;
+            };
+            match prompt.kind {
+                PromptKind::Route => lookup_route(app, prompt.text),
+                PromptKind::Note => add_note(app, prompt.text),
+                PromptKind::Image => export_image(app, prompt.text),
+                PromptKind::Threshold => set_threshold(app, prompt.text),
+                PromptKind::Traceroute => start_traceroute(app, prompt.text),
+                // already applied while typing
+                PromptKind::Search => {}
+            }
+            return;
+        }
+        _ => return,
+    }
+    if prompt.kind == PromptKind::Search {
+        let fragment = prompt.text.clone();
+        update(app, Action::SearchInterfaces(Some(fragment)));
+    }
+}
+
+// Pending vim style prefixes: a count like the 5 in `5j` and the first g of
+// `gg` or `gt`
+#[derive(Default)]
+pub struct InputState {
+    count: Option<usize>,
+    // the digits as typed, replayed through the keymap when the count is unused
+    digits: Vec<KeyBinding>,
+    g: bool,
+    since: Option<Instant>,
+}
+
+impl InputState {
+    pub fn pending(&self) -> Option<String> {
+        let count = self
+            .count
+            .map(|count| count.to_string())
+            .unwrap_or_default();
+        let g = if self.g { "g" } else { "" };
+        (self.count.is_some() || self.g).then(|| format!("{count}{g}"))
+    }
+
+    fn clear(&mut self) -> Vec<KeyBinding> {
+        let digits = std::mem::take(&mut self.digits);
+        *self = InputState::default();
+        digits
+    }
+}
+
+fn is_motion(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::SelectNext
+            | Action::SelectPrev
+            | Action::PageDown
+            | Action::PageUp
+            | Action::NextScreen
+            | Action::PrevScreen
+    )
+}
+
+// Actions bound to the digits of an unused count
+fn replay_digits(app: &App, digits: &[KeyBinding]) -> Vec<Action> {
+    digits
+        .iter()
+        .filter_map(|digit| app.keymap.get(digit).cloned())
+        .collect()
+}
+
+pub fn key_action(app: &mut App, key: KeyEvent) -> Action {
+    if app.prompt.is_some() {
+        return Action::PromptInput(key.code);
+    }
+    // ctrl-c still quits
+    if app.picker.is_some() && !key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::PickerInput(key.code);
+    }
+    let binding = KeyBinding::from_event(&key);
+    let plain = binding.modifiers == KeyModifiers::NONE;
+
+    if let KeyCode::Char(c @ '0'..='9') = key.code {
+        // a leading zero isn't a count
+        if plain && !app.input.g && (c != '0' || app.input.count.is_some()) {
+            let digit = c.to_digit(10).unwrap() as usize;
+            let count = app.input.count.unwrap_or(0);
+            app.input.count = Some((count * 10 + digit).min(MAX_COUNT));
+            app.input.digits.push(binding);
+            app.input.since = Some(Instant::now());
+            return Action::None;
+        }
+    }
+
+    let count = app.input.count;
+    if app.input.g {
+        app.input.clear();
+        match key.code {
+            KeyCode::Char('g') => return Action::SelectIndex(count.unwrap_or(1) - 1),
+            KeyCode::Char('t') => {
+                return match count {
+                    Some(count) => Action::SelectScreen(count.saturating_sub(1)),
+                    None => Action::NextScreen,
+                }
+            }
+            KeyCode::Char('T') => return Action::PrevScreen,
+            _ => {}
+        }
+    }
+    if plain && key.code == KeyCode::Char('g') && !app.keymap.contains_key(&binding) {
+        app.input.g = true;
+        app.input.since = Some(Instant::now());
+        return Action::None;
+    }
+
+    let digits = app.input.clear();
+    let action = app.keymap.get(&binding).cloned().unwrap_or(Action::None);
+    match (count, action) {
+        (Some(count), Action::SelectIndex(usize::MAX)) => Action::SelectIndex(count - 1),
+        (Some(count), action) if is_motion(&action) => Action::Sequence(vec![action; count]),
+        (Some(_), action) => {
+            let mut actions = replay_digits(app, &digits);
+            actions.push(action);
+            Action::Sequence(actions)
+        }
+        (None, action) => action,
+    }
+}
+
+// Called on every tick, resolves prefixes nobody completed
+pub fn expire_prefix(app: &mut App) -> Option<Action> {
+    let since = app.input.since?;
+    if since.elapsed() < PREFIX_TIMEOUT {
+        return None;
+    }
+    let digits = app.input.clear();
+    Some(Action::Sequence(replay_digits(app, &digits)))
+}
@@ -0,0 +1,123 @@
+#[cfg(not(unix))]
+use crate::alerts::{AlertEvent, AlertSink};
+use crate::error::{Error, Result};
+
+// Where alert events are logged outside the TUI, from `alert_log` in the
+// config: "journal" for systemd-journald's native protocol, so each event
+// keeps its rule and interface as fields, or "syslog" for /dev/log
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LogTarget {
+    Journal,
+    Syslog,
+}
+
+impl LogTarget {
+    pub fn parse(name: &str) -> Result<LogTarget> {
+        match name {
+            "journal" => Ok(LogTarget::Journal),
+            "syslog" => Ok(LogTarget::Syslog),
+            other => Err(Error::config(format!(
+                "unknown alert_log \"{other}\", expected journal or syslog"
+            ))),
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use unix::JournalSink;
+
+#[cfg(not(unix))]
+pub struct JournalSink;
+
+#[cfg(not(unix))]
+impl JournalSink {
+    pub fn open(_target: LogTarget) -> Result<JournalSink> {
+        Err(Error::config(
+            "the systemd journal and syslog are only available on Unix",
+        ))
+    }
+}
+
+#[cfg(not(unix))]
+impl AlertSink for JournalSink {
+    fn name(&self) -> &str {
+        "journal"
+    }
+
+    fn send(&mut self, _event: &AlertEvent) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::os::unix::net::UnixDatagram;
+
+    use super::LogTarget;
+    use crate::{
+        alerts::{AlertEvent, AlertSink},
+        error::{Result, ResultExt},
+    };
+
+    const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+    const SYSLOG_SOCKET: &str = "/dev/log";
+    const IDENTIFIER: &str = "net-stat";
+    // syslog facility "user"
+    const FACILITY: u8 = 1;
+
+    pub struct JournalSink {
+        target: LogTarget,
+        socket: UnixDatagram,
+    }
+
+    impl JournalSink {
+        pub fn open(target: LogTarget) -> Result<JournalSink> {
+            let path = match target {
+                LogTarget::Journal => JOURNAL_SOCKET,
+                LogTarget::Syslog => SYSLOG_SOCKET,
+            };
+            let socket = UnixDatagram::unbound()
+                .and_then(|socket| socket.connect(path).map(|_| socket))
+                .or_export(|| format!("failed to connect to {path}"))?;
+            Ok(JournalSink { target, socket })
+        }
+
+        fn message(&self, event: &AlertEvent) -> String {
+            // newlines would start a new field or record
+            let field = |value: &str| value.replace('\n', " ");
+            match self.target {
+                LogTarget::Journal => format!(
+                    "MESSAGE={}\nPRIORITY={}\nSYSLOG_IDENTIFIER={IDENTIFIER}\nNET_STAT_RULE={}\nNET_STAT_INTERFACE={}\nNET_STAT_STATE={}\n",
+                    field(&event.describe()),
+                    event.state.priority(),
+                    field(&event.rule),
+                    field(&event.interface),
+                    event.state.name()
+                ),
+                LogTarget::Syslog => format!(
+                    "<{}>{IDENTIFIER}[{}]: {}",
+                    FACILITY * 8 + event.state.priority(),
+                    std::process::id(),
+                    field(&event.describe())
+                ),
+            }
+        }
+    }
+
+    impl AlertSink for JournalSink {
+        fn name(&self) -> &str {
+            match self.target {
+                LogTarget::Journal => "journal",
+                LogTarget::Syslog => "syslog",
+            }
+        }
+
+        fn send(&mut self, event: &AlertEvent) -> Result<()> {
+            let message = self.message(event);
+            self.socket
+                .send(message.as_bytes())
+                .or_export(|| format!("failed to log to {}", self.name()))?;
+            Ok(())
+        }
+    }
+}
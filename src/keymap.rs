@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::{
+    app::Screen,
+    connections::ConnectionSort,
+    error::{Error, Result},
+    input::PromptKind,
+    macros::parse_key,
+    Action,
+};
+
+// A key together with the modifiers that matter for bindings
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    // Shift is already part of the character for printable keys
+    pub fn from_event(event: &KeyEvent) -> KeyBinding {
+        KeyBinding {
+            code: event.code,
+            modifiers: event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+}
+
+// One key or a list of keys for an action in the [keys] config table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn keys(&self) -> Vec<&str> {
+        match self {
+            KeyList::One(key) => vec![key.as_str()],
+            KeyList::Many(keys) => keys.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+pub type Keymap = HashMap<KeyBinding, Action>;
+
+// Action names usable in the [keys] table with their default keys
+fn default_bindings() -> Vec<(String, Action, Vec<&'static str>)> {
+    let mut bindings = vec![
+        ("quit", Action::Quit, vec!["q", "ctrl-c"]),
+        ("toggle-debug", Action::ToggleDebug, vec!["d"]),
+        ("toggle-baseline", Action::ToggleBaseline, vec!["b"]),
+        ("toggle-smoothing", Action::ToggleSmoothing, vec!["s"]),
+        ("toggle-log", Action::ToggleLogScale, vec!["L"]),
+        ("toggle-chart", Action::ToggleChart, vec!["c"]),
+        ("cycle-layout", Action::CycleLayout, vec!["v"]),
+        ("cycle-graph-style", Action::CycleGraphStyle, vec!["p"]),
+        ("cycle-overlay", Action::CycleOverlay, vec!["o"]),
+        ("toggle-auto-focus", Action::ToggleAutoFocus, vec!["f"]),
+        (
+            "route-lookup",
+            Action::OpenPrompt(PromptKind::Route),
+            vec!["R"],
+        ),
+        ("add-note", Action::OpenPrompt(PromptKind::Note), vec!["n"]),
+        (
+            "export-image",
+            Action::OpenPrompt(PromptKind::Image),
+            vec!["E"],
+        ),
+        (
+            "set-threshold",
+            Action::OpenPrompt(PromptKind::Threshold),
+            vec!["!"],
+        ),
+        (
+            "traceroute",
+            Action::OpenPrompt(PromptKind::Traceroute),
+            vec!["ctrl-t"],
+        ),
+        ("search", Action::OpenPrompt(PromptKind::Search), vec!["/"]),
+        ("dismiss", Action::Dismiss, vec!["esc"]),
+        ("wake-host", Action::WakeHost, vec!["w"]),
+        ("toggle-zoom", Action::ToggleZoom, vec!["z"]),
+        ("mark-compare", Action::ToggleCompareMark, vec!["x"]),
+        ("lock-scale", Action::ToggleScaleLock, vec!["Y"]),
+        ("toggle-packets", Action::TogglePacketGraphs, vec!["P"]),
+        ("toggle-aggregate", Action::ToggleAggregate, vec!["A"]),
+        ("toggle-stacked", Action::ToggleStacked, vec!["K"]),
+        ("toggle-physical", Action::TogglePhysicalOnly, vec!["i"]),
+        ("toggle-system", Action::ToggleSystemStrip, vec!["m"]),
+        ("toggle-sockets", Action::ToggleSocketStrip, vec!["W"]),
+        ("bufferbloat-test", Action::ToggleBufferbloat, vec!["B"]),
+        ("speed-test", Action::ToggleSpeedTest, vec!["g"]),
+        ("cable-test", Action::CableTest, vec!["C"]),
+        ("save-snapshot", Action::SaveSnapshot, vec!["S"]),
+        ("cycle-range", Action::CycleResolution, vec!["t"]),
+        ("toggle-cumulative", Action::ToggleCumulative, vec!["a"]),
+        (
+            "toggle-interface-cumulative",
+            Action::ToggleInterfaceCumulative,
+            vec!["T"],
+        ),
+        ("reset-cumulative", Action::ResetCumulative, vec!["r"]),
+        ("select-next", Action::SelectNext, vec!["down", "j"]),
+        ("select-prev", Action::SelectPrev, vec!["up", "k"]),
+        ("first-interface", Action::SelectIndex(0), vec!["home"]),
+        (
+            "last-interface",
+            Action::SelectIndex(usize::MAX),
+            vec!["end", "G"],
+        ),
+        ("page-down", Action::PageDown, vec!["pagedown", "ctrl-f"]),
+        ("page-up", Action::PageUp, vec!["pageup", "ctrl-b"]),
+        ("replay-pause", Action::ToggleReplayPause, vec!["space"]),
+        ("replay-faster", Action::ReplaySpeed(true), vec![">"]),
+        ("replay-slower", Action::ReplaySpeed(false), vec!["<"]),
+        ("seek-forward", Action::ReplaySeek(10), vec!["]"]),
+        ("seek-back", Action::ReplaySeek(-10), vec!["["]),
+        (
+            "sort-by-rate",
+            Action::SortConnections(ConnectionSort::Rate),
+            vec!["O"],
+        ),
+        ("next-screen", Action::NextScreen, vec!["tab", "l"]),
+        ("prev-screen", Action::PrevScreen, vec!["backtab", "h"]),
+    ]
+    .into_iter()
+    .map(|(name, action, keys)| (name.to_string(), action, keys))
+    .collect::<Vec<_>>();
+    const DIGITS: [&str; 10] = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "0"];
+    for (i, digit) in DIGITS.iter().enumerate().take(Screen::ALL.len()) {
+        bindings.push((
+            format!("screen-{}", i + 1),
+            Action::SelectScreen(i),
+            vec![*digit],
+        ));
+    }
+    bindings
+}
+
+// Accepts single characters, F1-F12 and named keys, optionally prefixed with
+// ctrl- and alt-, e.g. "q", "ctrl-c", "space", "pagedown"
+pub fn parse_binding(key: &str) -> Result<KeyBinding> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = key;
+    loop {
+        let lower = rest.to_ascii_lowercase();
+        if rest.len() > 1 && lower.starts_with("ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = &rest[5..];
+        } else if rest.len() > 1 && lower.starts_with("alt-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = &rest[4..];
+        } else {
+            break;
+        }
+    }
+    let code = match rest.to_ascii_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => parse_key(rest).map_err(|_| Error::config(format!("unknown key \"{key}\"")))?,
+    };
+    Ok(KeyBinding { code, modifiers })
+}
+
+// Defaults with configured actions replacing their default keys. A key may
+// only trigger one action.
+pub fn build_keymap(config: &HashMap<String, KeyList>) -> Result<Keymap> {
+    let defaults = default_bindings();
+    for name in config.keys() {
+        if !defaults.iter().any(|(action, _, _)| action == name) {
+            let names: Vec<&str> = defaults.iter().map(|(name, _, _)| name.as_str()).collect();
+            return Err(Error::config(format!(
+                "unknown action \"{name}\" in [keys], expected one of {}",
+                names.join(", ")
+            )));
+        }
+    }
+    let mut keymap = Keymap::new();
+    let mut owners: HashMap<KeyBinding, (&str, &str)> = HashMap::new();
+    for (name, action, keys) in &defaults {
+        let keys = match config.get(name) {
+            Some(configured) => configured.keys(),
+            None => keys.clone(),
+        };
+        for key in keys {
+            let binding = parse_binding(key).map_err(|e| e.context(format!("[keys] {name}")))?;
+            if let Some((other, other_key)) = owners.insert(binding, (name, key)) {
+                let hint = if config.contains_key(other) {
+                    String::new()
+                } else {
+                    format!(", {other} uses it by default so rebind {other} as well")
+                };
+                return Err(Error::config(format!(
+                    "[keys] \"{key}\" is bound to both {other} (\"{other_key}\") and {name}{hint}"
+                )));
+            }
+            keymap.insert(binding, action.clone());
+        }
+    }
+    Ok(keymap)
+}
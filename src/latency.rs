@@ -0,0 +1,827 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    net::{IpAddr, Ipv4Addr},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    prelude::Constraint,
+    style::Style,
+    symbols::Marker,
+    text::Span,
+    widgets::{Axis, Block, Borders, Cell, Chart, Dataset, GraphType, Row, Table},
+};
+use serde::Deserialize;
+
+use crate::{
+    alerts::{dispatch_alerts, AlertEvent, AlertState},
+    app::App,
+    error::{Error, Result},
+    history::unix_time,
+    networks::InterfaceData,
+    route::{resolve, route_get},
+    theme::Theme,
+    units::{format_duration, format_rate},
+};
+
+// Seconds after which a missing reply counts as lost
+const REPLY_TIMEOUT: f64 = 2.0;
+// probes a target needs before its loss and jitter are judged
+const MIN_SAMPLES: usize = 10;
+// Names the breaches are raised under in place of a rule
+pub const LOSS_RULE: &str = "packet loss";
+pub const JITTER_RULE: &str = "jitter";
+const TARGET_WIDTHS: [Constraint; 5] = [
+    Constraint::Percentage(20),
+    Constraint::Percentage(35),
+    Constraint::Percentage(15),
+    Constraint::Percentage(15),
+    Constraint::Percentage(15),
+];
+
+// Objectives every pinged target is held to over the stats window, e.g.
+//   [latency_slo]
+//   loss = 1
+//   jitter = 20
+// Targets over either are highlighted on the Latency screen and raise an
+// alert until they're back under.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LatencySlo {
+    // percent of probes lost
+    pub loss: f64,
+    // milliseconds of mean difference between consecutive round trips
+    pub jitter: f64,
+}
+
+impl Default for LatencySlo {
+    fn default() -> Self {
+        LatencySlo {
+            loss: 5.0,
+            jitter: 30.0,
+        }
+    }
+}
+
+impl LatencySlo {
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=100.0).contains(&self.loss) {
+            return Err(Error::config(format!(
+                "latency_slo loss {} is not a percentage",
+                self.loss
+            )));
+        }
+        if self.jitter < 0.0 {
+            return Err(Error::config("latency_slo jitter can't be negative"));
+        }
+        Ok(())
+    }
+}
+
+// One probe: round trip time in milliseconds, None when the echo was lost,
+// and the throughput of the probed interface at the time
+#[derive(Clone, Copy)]
+pub struct LatencySample {
+    pub time: f64,
+    pub rtt: Option<f64>,
+    pub throughput: f64,
+}
+
+// The host being pinged and the interface the probes leave through
+pub struct LatencyTarget {
+    pub address: IpAddr,
+    pub interface: String,
+}
+
+// Round trip times to the default gateway, or `latency_target`, measured
+// with one ICMP echo per tick. Probing starts when the Latency screen is
+// first shown, or at startup with `latency_probe = true`. Each of the
+// `latency_targets` gets a probe of its own alongside.
+#[derive(Default)]
+pub struct LatencyProbe {
+    pub active: bool,
+    // the host as configured, or "gateway"
+    pub name: String,
+    pub target: Option<LatencyTarget>,
+    pinger: Option<Pinger>,
+    seq: u16,
+    // send time of each unanswered echo
+    pending: HashMap<u16, f64>,
+    pub samples: VecDeque<LatencySample>,
+    pub error: Option<String>,
+    // when the loss and jitter breaches fired, while they last
+    loss_breach: Option<f64>,
+    jitter_breach: Option<f64>,
+}
+
+impl LatencyProbe {
+    fn start(&mut self, target: Option<&str>) {
+        self.active = true;
+        self.name = target.unwrap_or("gateway").to_string();
+        let started = find_target(target).and_then(|target| {
+            let pinger = Pinger::open(target.address)?;
+            Ok((target, pinger))
+        });
+        match started {
+            Ok((target, pinger)) => {
+                self.target = Some(target);
+                self.pinger = Some(pinger);
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    // Collects replies, expires lost probes and sends the next echo. Returns
+    // the round trip time if a reply arrived, Some(None) if one was lost.
+    // Replies are only read once per tick, so the kernel's receive timestamp
+    // is used rather than the time they are read.
+    fn probe(&mut self) -> Option<Option<f64>> {
+        let pinger = self.pinger.as_ref()?;
+        let mut result = None;
+        loop {
+            match pinger.recv() {
+                Ok(Some((seq, received))) => {
+                    if let Some(sent) = self.pending.remove(&seq) {
+                        result = Some(Some((received - sent).max(0.0) * 1000.0));
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    self.error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+        let now = unix_time();
+        let before = self.pending.len();
+        self.pending.retain(|_, sent| now - *sent < REPLY_TIMEOUT);
+        if self.pending.len() < before && result.is_none() {
+            result = Some(None);
+        }
+
+        self.seq = self.seq.wrapping_add(1);
+        match pinger.send(self.seq) {
+            Ok(()) => {
+                self.pending.insert(self.seq, unix_time());
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("ping failed: {e}")),
+        }
+        result
+    }
+
+    // percent of the probes in the window that went unanswered
+    pub fn loss(&self) -> Option<f64> {
+        let lost = self
+            .samples
+            .iter()
+            .filter(|sample| sample.rtt.is_none())
+            .count();
+        (self.samples.len() >= MIN_SAMPLES).then(|| lost as f64 * 100.0 / self.samples.len() as f64)
+    }
+
+    // Mean difference between consecutive round trips in milliseconds, as
+    // RFC 3550 has it without the smoothing. Lost probes are skipped.
+    pub fn jitter(&self) -> Option<f64> {
+        let rtts: Vec<f64> = self
+            .samples
+            .iter()
+            .filter_map(|sample| sample.rtt)
+            .collect();
+        if rtts.len() < MIN_SAMPLES {
+            return None;
+        }
+        let total: f64 = rtts.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum();
+        Some(total / (rtts.len() - 1) as f64)
+    }
+
+    pub fn breached(&self, slo: &LatencySlo) -> (bool, bool) {
+        (
+            self.loss().is_some_and(|loss| loss > slo.loss),
+            self.jitter().is_some_and(|jitter| jitter > slo.jitter),
+        )
+    }
+
+    // Fired and resolved events for the objectives crossed since last time
+    fn judge(&mut self, slo: &LatencySlo, time: f64) -> Vec<AlertEvent> {
+        let (loss, jitter) = self.breached(slo);
+        let values = (self.loss(), self.jitter());
+        let name = &self.name;
+        let checks = [
+            (LOSS_RULE, loss, values.0, slo.loss, &mut self.loss_breach),
+            (
+                JITTER_RULE,
+                jitter,
+                values.1,
+                slo.jitter,
+                &mut self.jitter_breach,
+            ),
+        ];
+        let mut events = Vec::new();
+        for (rule, breached, value, threshold, fired_at) in checks {
+            let state = match (breached, *fired_at) {
+                (true, None) => AlertState::Fired,
+                (false, Some(_)) => AlertState::Resolved,
+                _ => continue,
+            };
+            events.push(AlertEvent {
+                rule: rule.to_string(),
+                interface: name.to_string(),
+                state,
+                value: value.unwrap_or_default(),
+                threshold,
+                time,
+                since: fired_at.unwrap_or(time),
+            });
+            *fired_at = breached.then_some(time);
+        }
+        events
+    }
+}
+
+fn find_target(target: Option<&str>) -> io::Result<LatencyTarget> {
+    let address = match target {
+        Some(target) => resolve(target)?,
+        // any off-link address gives the default route, nothing is sent to it
+        None => route_get(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)))?
+            .gateway
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "no default gateway, set latency_target",
+                )
+            })?,
+    };
+    let interface = route_get(address)?.interface;
+    Ok(LatencyTarget { address, interface })
+}
+
+pub fn start_latency(app: &mut App) {
+    if !app.latency.active {
+        let target = app.config.latency_target.clone();
+        app.latency.start(target.as_deref());
+        app.latency_targets = app
+            .config
+            .latency_targets
+            .iter()
+            .map(|target| {
+                let mut probe = LatencyProbe::default();
+                probe.start(Some(target));
+                probe
+            })
+            .collect();
+    }
+}
+
+pub fn update_latency(app: &mut App, elapsed: f64) {
+    if !app.latency.active || elapsed <= 0.0 {
+        return;
+    }
+    let window = app.config.stats_window.max(1);
+    let time = unix_time();
+    let mut events = Vec::new();
+    for probe in std::iter::once(&mut app.latency).chain(&mut app.latency_targets) {
+        if sample(probe, &app.all_interfaces, elapsed, window) {
+            events.extend(probe.judge(&app.config.latency_slo, time));
+        }
+    }
+    for event in &events {
+        app.alerts.record(event);
+    }
+    dispatch_alerts(app, &events);
+}
+
+// Adds the result of the probe due this tick, if any came in
+fn sample(
+    probe: &mut LatencyProbe,
+    interfaces: &[InterfaceData],
+    elapsed: f64,
+    window: usize,
+) -> bool {
+    let Some(rtt) = probe.probe() else {
+        return false;
+    };
+    let throughput = probe
+        .target
+        .as_ref()
+        .and_then(|target| {
+            interfaces
+                .iter()
+                .find(|interface| interface.name == target.interface)
+        })
+        .map_or(0.0, |interface| {
+            (interface.sent + interface.rec) as f64 / elapsed
+        });
+    let samples = &mut probe.samples;
+    if samples.len() == window {
+        samples.pop_front();
+    }
+    samples.push_back(LatencySample {
+        time: unix_time(),
+        rtt,
+        throughput,
+    });
+    true
+}
+
+// Loss and jitter of every target, those over the objectives highlighted
+pub fn create_latency_table<'a>(app: &App) -> Table<'a> {
+    let theme = &app.theme;
+    let slo = &app.config.latency_slo;
+    let rows = std::iter::once(&app.latency)
+        .chain(&app.latency_targets)
+        .map(|probe| {
+            let (loss_high, jitter_high) = probe.breached(slo);
+            let style = |high: bool| if high { theme.alert } else { theme.text };
+            let address = match (&probe.target, &probe.error) {
+                (_, Some(error)) => error.to_string(),
+                (Some(target), None) => format!("{} via {}", target.address, target.interface),
+                (None, None) => String::new(),
+            };
+            let rtt = probe
+                .samples
+                .back()
+                .and_then(|sample| sample.rtt)
+                .map_or("-".to_string(), format_ms);
+            let pending = || "-".to_string();
+            Row::new(vec![
+                Cell::from(probe.name.to_string()),
+                Cell::from(address),
+                Cell::from(rtt),
+                Cell::from(
+                    probe
+                        .loss()
+                        .map_or_else(pending, |loss| format!("{loss:.1}%")),
+                )
+                .style(style(loss_high)),
+                Cell::from(probe.jitter().map_or_else(pending, format_ms))
+                    .style(style(jitter_high)),
+            ])
+        });
+    Table::new(rows)
+        .header(Row::new(vec!["Target", "Address", "RTT", "Loss", "Jitter"]).style(theme.title))
+        .style(theme.text)
+        .block(
+            Block::default()
+                .title(Span::styled(
+                    format!(
+                        "Targets (loss over {}%, jitter over {} ms alert)",
+                        slo.loss, slo.jitter
+                    ),
+                    theme.title,
+                ))
+                .borders(Borders::ALL)
+                .border_style(theme.border),
+        )
+        .widths(&TARGET_WIDTHS)
+}
+
+// Pearson correlation of RTT and throughput, close to 1 when latency rises
+// with load, the signature of bufferbloat
+pub fn correlation(samples: &[(f64, f64)]) -> Option<f64> {
+    let n = samples.len() as f64;
+    if samples.len() < 3 {
+        return None;
+    }
+    let (mean_x, mean_y) = samples
+        .iter()
+        .fold((0.0, 0.0), |(x, y), (sx, sy)| (x + sx / n, y + sy / n));
+    let (mut covariance, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in samples {
+        covariance += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    (var_x > 0.0 && var_y > 0.0).then(|| covariance / (var_x * var_y).sqrt())
+}
+
+// Points for the charts: (throughput, rtt) for the scatter and both series
+// against seconds before now
+pub struct LatencySeries {
+    pub scatter: Vec<(f64, f64)>,
+    pub rtt: Vec<(f64, f64)>,
+    pub throughput: Vec<(f64, f64)>,
+    pub lost: usize,
+    pub window: f64,
+}
+
+impl LatencySeries {
+    pub fn from(probe: &LatencyProbe, now: f64) -> LatencySeries {
+        let mut series = LatencySeries {
+            scatter: Vec::new(),
+            rtt: Vec::new(),
+            throughput: Vec::new(),
+            lost: 0,
+            window: probe
+                .samples
+                .front()
+                .map_or(60.0, |sample| (now - sample.time).max(1.0)),
+        };
+        for sample in &probe.samples {
+            let time = sample.time - now;
+            series.throughput.push((time, sample.throughput));
+            match sample.rtt {
+                Some(rtt) => {
+                    series.rtt.push((time, rtt));
+                    series.scatter.push((sample.throughput, rtt));
+                }
+                None => series.lost += 1,
+            }
+        }
+        series
+    }
+}
+
+fn max(points: &[(f64, f64)], value: impl Fn(&(f64, f64)) -> f64, floor: f64) -> f64 {
+    points.iter().map(value).fold(floor, f64::max) * 1.1
+}
+
+fn labels<'a>(max: f64, theme: &Theme, format: impl Fn(f64) -> String) -> Vec<Span<'a>> {
+    [0.0, max / 2.0, max]
+        .iter()
+        .map(|value| Span::styled(format(*value), theme.text))
+        .collect()
+}
+
+fn format_ms(ms: f64) -> String {
+    format!("{ms:.1} ms")
+}
+
+fn time_axis<'a>(window: f64, theme: &Theme) -> Axis<'a> {
+    Axis::default()
+        .style(theme.border)
+        .bounds([-window, 0.0])
+        .labels(vec![
+            Span::styled(format!("-{}", format_duration(window)), theme.text),
+            Span::styled(format!("-{}", format_duration(window / 2.0)), theme.text),
+            Span::styled("now", theme.text),
+        ])
+}
+
+fn dataset<'a>(
+    points: &'a [(f64, f64)],
+    graph_type: GraphType,
+    style: Style,
+    marker: Marker,
+) -> Dataset<'a> {
+    Dataset::default()
+        .marker(marker)
+        .graph_type(graph_type)
+        .style(style)
+        .data(points)
+}
+
+// RTT against throughput, and both over time sharing the time axis
+pub fn create_latency_charts<'a>(
+    app: &App,
+    series: &'a LatencySeries,
+) -> (Chart<'a>, Chart<'a>, Chart<'a>) {
+    let theme = &app.theme;
+    let marker = if app.capabilities.braille {
+        Marker::Braille
+    } else {
+        Marker::Dot
+    };
+    let block = |title: String| {
+        Block::default()
+            .title(Span::styled(title, theme.title))
+            .borders(Borders::ALL)
+            .border_style(theme.border)
+    };
+    let target = match (&app.latency.target, &app.latency.error) {
+        (_, Some(error)) => error.to_string(),
+        (Some(target), None) => format!("{} via {}", target.address, target.interface),
+        (None, None) => "no target".to_string(),
+    };
+    let r = correlation(&series.scatter)
+        .map(|r| format!("r = {r:.2}"))
+        .unwrap_or_else(|| "r = n/a".to_string());
+    let rtt_max = max(&series.rtt, |(_, rtt)| *rtt, 1.0);
+    let throughput_max = max(&series.throughput, |(_, rate)| *rate, 1024.0);
+
+    let scatter = Chart::new(vec![dataset(
+        &series.scatter,
+        GraphType::Scatter,
+        theme.tx_graph,
+        marker,
+    )])
+    .block(block(format!("RTT vs throughput, {target}, {r}")))
+    .x_axis(
+        Axis::default()
+            .style(theme.border)
+            .bounds([0.0, throughput_max])
+            .labels(labels(throughput_max, theme, format_rate)),
+    )
+    .y_axis(
+        Axis::default()
+            .style(theme.border)
+            .bounds([0.0, rtt_max])
+            .labels(labels(rtt_max, theme, format_ms)),
+    );
+    let rtt = Chart::new(vec![dataset(
+        &series.rtt,
+        GraphType::Line,
+        theme.tx_graph,
+        marker,
+    )])
+    .block(block(format!("RTT ({} lost)", series.lost)))
+    .x_axis(time_axis(series.window, theme))
+    .y_axis(
+        Axis::default()
+            .style(theme.border)
+            .bounds([0.0, rtt_max])
+            .labels(labels(rtt_max, theme, format_ms)),
+    );
+    let throughput = Chart::new(vec![dataset(
+        &series.throughput,
+        GraphType::Line,
+        theme.rx_graph,
+        marker,
+    )])
+    .block(block("Throughput".to_string()))
+    .x_axis(time_axis(series.window, theme))
+    .y_axis(
+        Axis::default()
+            .style(theme.border)
+            .bounds([0.0, throughput_max])
+            .labels(labels(throughput_max, theme, format_rate)),
+    );
+    (scatter, rtt, throughput)
+}
+
+// ICMP echo over an unprivileged ping socket, falling back to a raw socket
+// when ping sockets are disabled (net.ipv4.ping_group_range)
+#[cfg(target_os = "linux")]
+pub use icmp::Pinger;
+
+#[cfg(not(target_os = "linux"))]
+pub struct Pinger;
+
+#[cfg(not(target_os = "linux"))]
+impl Pinger {
+    pub fn open(_address: IpAddr) -> io::Result<Pinger> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "latency probes are only available on Linux",
+        ))
+    }
+
+    pub fn send(&self, _seq: u16) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn recv(&self) -> io::Result<Option<(u16, f64)>> {
+        Ok(None)
+    }
+}
+
+// Round trip time in milliseconds of a single echo, None when no reply came
+// within `timeout`. Blocks, for probes running on their own thread.
+pub fn ping_once(address: IpAddr, timeout: Duration) -> io::Result<Option<f64>> {
+    let pinger = Pinger::open(address)?;
+    let sent = unix_time();
+    pinger.send(1)?;
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if let Some((_, received)) = pinger.recv()? {
+            return Ok(Some((received - sent).max(0.0) * 1000.0));
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    Ok(None)
+}
+
+#[cfg(target_os = "linux")]
+mod icmp {
+    use std::{
+        mem,
+        net::{SocketAddr, SocketAddrV4, SocketAddrV6},
+    };
+
+    use super::*;
+
+    const ECHO_REQUEST_V4: u8 = 8;
+    const ECHO_REPLY_V4: u8 = 0;
+    const ECHO_REQUEST_V6: u8 = 128;
+    const ECHO_REPLY_V6: u8 = 129;
+    const SIOCGSTAMP: libc::c_ulong = 0x8906;
+
+    pub struct Pinger {
+        fd: libc::c_int,
+        address: IpAddr,
+        // raw IPv4 sockets see every ICMP packet including the IP header
+        raw: bool,
+        id: u16,
+    }
+
+    impl Pinger {
+        pub fn open(address: IpAddr) -> io::Result<Pinger> {
+            let (family, protocol) = match address {
+                IpAddr::V4(_) => (libc::AF_INET, libc::IPPROTO_ICMP),
+                IpAddr::V6(_) => (libc::AF_INET6, libc::IPPROTO_ICMPV6),
+            };
+            let open = |kind: libc::c_int| unsafe {
+                libc::socket(
+                    family,
+                    kind | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                    protocol,
+                )
+            };
+            let (fd, raw) = match open(libc::SOCK_DGRAM) {
+                fd if fd >= 0 => (fd, false),
+                _ => (open(libc::SOCK_RAW), true),
+            };
+            if fd < 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "can't open an ICMP socket ({}), allow ping sockets with net.ipv4.ping_group_range",
+                        io::Error::last_os_error()
+                    ),
+                ));
+            }
+            let pinger = Pinger {
+                fd,
+                address,
+                raw,
+                id: std::process::id() as u16,
+            };
+            // the kernel only starts timestamping packets after the first
+            // SIOCGSTAMP, which fails here as nothing was received yet
+            pinger.received();
+            Ok(pinger)
+        }
+
+        pub fn send(&self, seq: u16) -> io::Result<()> {
+            let kind = match self.address {
+                IpAddr::V4(_) => ECHO_REQUEST_V4,
+                IpAddr::V6(_) => ECHO_REQUEST_V6,
+            };
+            // type, code, checksum, identifier, sequence, then a short payload
+            let mut packet = [0u8; 16];
+            packet[0] = kind;
+            packet[4..6].copy_from_slice(&self.id.to_be_bytes());
+            packet[6..8].copy_from_slice(&seq.to_be_bytes());
+            packet[8..].copy_from_slice(b"net-stat");
+            // the kernel fills in the ICMPv6 checksum
+            if self.address.is_ipv4() {
+                let checksum = checksum(&packet);
+                packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+            }
+
+            let (storage, len) = socket_address(self.address);
+            let sent = unsafe {
+                libc::sendto(
+                    self.fd,
+                    packet.as_ptr() as *const libc::c_void,
+                    packet.len(),
+                    0,
+                    &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+                    len,
+                )
+            };
+            if sent < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        // Sequence number and receive time of the next echo reply from the
+        // target, None when nothing is waiting
+        pub fn recv(&self) -> io::Result<Option<(u16, f64)>> {
+            let mut buffer = [0u8; 1500];
+            loop {
+                let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+                let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+                let received = unsafe {
+                    libc::recvfrom(
+                        self.fd,
+                        buffer.as_mut_ptr() as *mut libc::c_void,
+                        buffer.len(),
+                        0,
+                        &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+                        &mut len,
+                    )
+                };
+                if received < 0 {
+                    let error = io::Error::last_os_error();
+                    return match error.kind() {
+                        io::ErrorKind::WouldBlock => Ok(None),
+                        _ => Err(error),
+                    };
+                }
+                let mut packet = &buffer[..received as usize];
+                if self.raw && self.address.is_ipv4() {
+                    let header = packet.first().map_or(0, |byte| (byte & 0x0f) as usize * 4);
+                    packet = &packet[header.min(packet.len())..];
+                }
+                if packet.len() < 8 || source(&storage) != Some(self.address) {
+                    continue;
+                }
+                let reply = match self.address {
+                    IpAddr::V4(_) => ECHO_REPLY_V4,
+                    IpAddr::V6(_) => ECHO_REPLY_V6,
+                };
+                // ping sockets rewrite the identifier and only deliver our
+                // own replies
+                let id = u16::from_be_bytes([packet[4], packet[5]]);
+                if packet[0] == reply && (!self.raw || id == self.id) {
+                    let seq = u16::from_be_bytes([packet[6], packet[7]]);
+                    return Ok(Some((seq, self.received())));
+                }
+            }
+        }
+    }
+
+    impl Pinger {
+        // Kernel timestamp of the last packet read, falling back to now
+        fn received(&self) -> f64 {
+            let mut time: libc::timeval = unsafe { mem::zeroed() };
+            let result = unsafe { libc::ioctl(self.fd, SIOCGSTAMP as _, &mut time) };
+            if result < 0 {
+                return unix_time();
+            }
+            time.tv_sec as f64 + time.tv_usec as f64 / 1e6
+        }
+    }
+
+    impl Drop for Pinger {
+        fn drop(&mut self) {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+    }
+
+    fn checksum(packet: &[u8]) -> u16 {
+        let mut sum: u32 = packet
+            .chunks(2)
+            .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]) as u32)
+            .sum();
+        while sum > 0xffff {
+            sum = (sum & 0xffff) + (sum >> 16);
+        }
+        !(sum as u16)
+    }
+
+    fn socket_address(address: IpAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let len = match address {
+            IpAddr::V4(address) => {
+                let sockaddr = libc::sockaddr_in {
+                    sin_family: libc::AF_INET as libc::sa_family_t,
+                    sin_port: 0,
+                    sin_addr: libc::in_addr {
+                        s_addr: u32::from_ne_bytes(address.octets()),
+                    },
+                    sin_zero: [0; 8],
+                };
+                unsafe {
+                    *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in) =
+                        sockaddr;
+                }
+                mem::size_of::<libc::sockaddr_in>()
+            }
+            IpAddr::V6(address) => {
+                let mut sockaddr: libc::sockaddr_in6 = unsafe { mem::zeroed() };
+                sockaddr.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                sockaddr.sin6_addr.s6_addr = address.octets();
+                unsafe {
+                    *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in6) =
+                        sockaddr;
+                }
+                mem::size_of::<libc::sockaddr_in6>()
+            }
+        };
+        (storage, len as libc::socklen_t)
+    }
+
+    fn source(storage: &libc::sockaddr_storage) -> Option<IpAddr> {
+        let address = match storage.ss_family as libc::c_int {
+            libc::AF_INET => {
+                let sockaddr = unsafe {
+                    *(storage as *const libc::sockaddr_storage as *const libc::sockaddr_in)
+                };
+                SocketAddr::V4(SocketAddrV4::new(
+                    sockaddr.sin_addr.s_addr.to_ne_bytes().into(),
+                    0,
+                ))
+            }
+            libc::AF_INET6 => {
+                let sockaddr = unsafe {
+                    *(storage as *const libc::sockaddr_storage as *const libc::sockaddr_in6)
+                };
+                SocketAddr::V6(SocketAddrV6::new(
+                    sockaddr.sin6_addr.s6_addr.into(),
+                    0,
+                    0,
+                    0,
+                ))
+            }
+            _ => return None,
+        };
+        Some(address.ip())
+    }
+}
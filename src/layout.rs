@@ -0,0 +1,190 @@
+use std::iter::zip;
+
+use ratatui::{
+    layout::Alignment,
+    prelude::Constraint,
+    style::Modifier,
+    text::Span,
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Row, Table,
+    },
+};
+use serde::Deserialize;
+
+use crate::{
+    app::App,
+    config::save_setting,
+    units::{format_bytes, format_rate},
+};
+
+// How interface blocks are arranged on the overview screen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutMode {
+    #[default]
+    List,
+    Grid,
+    Compact,
+}
+
+impl LayoutMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LayoutMode::List => "list",
+            LayoutMode::Grid => "grid",
+            LayoutMode::Compact => "compact",
+        }
+    }
+
+    pub fn next(&self) -> LayoutMode {
+        match self {
+            LayoutMode::List => LayoutMode::Grid,
+            LayoutMode::Grid => LayoutMode::Compact,
+            LayoutMode::Compact => LayoutMode::List,
+        }
+    }
+}
+
+// Narrowest a grid cell gets before the grid drops a column
+const GRID_COLUMN_WIDTH: u16 = 60;
+const GRID_MAX_COLUMNS: u16 = 3;
+
+pub fn columns(layout: LayoutMode, width: u16) -> usize {
+    match layout {
+        LayoutMode::Grid => (width / GRID_COLUMN_WIDTH).clamp(1, GRID_MAX_COLUMNS) as usize,
+        LayoutMode::List | LayoutMode::Compact => 1,
+    }
+}
+
+// Switch to the next layout and remember it as the default for next time
+pub fn cycle_layout(app: &mut App) {
+    app.layout = app.layout.next();
+    app.settings_error = save_setting("layout", app.layout.name())
+        .err()
+        .map(|e| format!("layout not saved: {e:#}"));
+}
+
+// Borders and header above the first row of the compact table
+pub const COMPACT_HEADER_HEIGHT: u16 = 2;
+
+const COMPACT_WIDTHS: [Constraint; 5] = [
+    Constraint::Length(16),
+    Constraint::Length(12),
+    Constraint::Length(12),
+    Constraint::Length(22),
+    Constraint::Percentage(100),
+];
+
+// Cells left for the inline sparkline once borders, the fixed columns and
+// the spacing between them are taken
+pub fn sparkline_width(width: u16) -> usize {
+    let fixed: u16 = 16 + 12 + 12 + 22 + COMPACT_WIDTHS.len() as u16 - 1;
+    width.saturating_sub(2 + fixed) as usize
+}
+
+// idle samples are left blank, like the sparkline widget does
+const SPARK_BARS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARK_ASCII: [char; 9] = [' ', '_', '.', '-', ':', '=', '+', '*', '#'];
+
+// The most recent `width` values as a single line of bar characters, scaled
+// to the largest of them
+pub fn inline_sparkline(values: &[u64], width: usize, unicode: bool) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = values.iter().copied().max().unwrap_or(0);
+    scaled_sparkline(values, width, max, unicode)
+}
+
+// Like inline_sparkline against a fixed top, e.g. 100 for percentages
+pub fn scaled_sparkline(values: &[u64], width: usize, max: u64, unicode: bool) -> String {
+    let bars = if unicode { SPARK_BARS } else { SPARK_ASCII };
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = max.max(1);
+    values
+        .iter()
+        .map(|value| bars[(*value.min(&max) * (bars.len() as u64 - 1)).div_ceil(max) as usize])
+        .collect()
+}
+
+// Interfaces scrolled past on either side of the `shown` ones, e.g.
+// "↑ 2 above · 3 more interfaces ↓", None when everything fits
+pub fn overflow_text(app: &App, shown: usize) -> Option<String> {
+    let above = app.scroll.min(app.net_interfaces.len());
+    let below = app.net_interfaces.len().saturating_sub(above + shown);
+    let (up, down) = if app.capabilities.unicode {
+        ("↑", "↓")
+    } else {
+        ("^", "v")
+    };
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let parts: Vec<String> = [
+        (above > 0).then(|| format!("{up} {above} above")),
+        (below > 0).then(|| format!("{below} more interface{} {down}", plural(below))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    (!parts.is_empty()).then(|| parts.join(" · "))
+}
+
+pub fn create_compact_table(app: &App, visible: usize, spark_width: usize) -> Table<'_> {
+    let rows: Vec<Row> = app
+        .net_interfaces
+        .iter()
+        .enumerate()
+        .skip(app.scroll)
+        .take(visible)
+        .map(|(i, interface)| {
+            let (sent, rec) = app
+                .stats
+                .get(&interface.name)
+                .map(|stats| (stats.sent.current(), stats.rec.current()))
+                .unwrap_or_default();
+            let traffic: Vec<u64> = app
+                .net_interface_display
+                .get(&interface.name)
+                .map(|data| zip(&data.sent, &data.rec).map(|(s, r)| s + r).collect())
+                .unwrap_or_default();
+            let row = Row::new(vec![
+                format!(
+                    "{} {}",
+                    interface.kind.icon(app.capabilities.unicode),
+                    interface.name
+                ),
+                format_rate(rec),
+                format_rate(sent),
+                format!(
+                    "{} / {}",
+                    format_bytes(interface.rec_total as f64),
+                    format_bytes(interface.sent_total as f64)
+                ),
+                inline_sparkline(&traffic, spark_width, app.capabilities.unicode),
+            ]);
+            if i == app.selected {
+                row.style(app.theme.title.add_modifier(Modifier::REVERSED))
+            } else {
+                row
+            }
+        })
+        .collect();
+    let shown = rows.len();
+    let mut block = Block::default()
+        .title(Span::styled("Interfaces", app.theme.title))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border);
+    if let Some(overflow) = overflow_text(app, shown) {
+        block = block.title(
+            Title::from(Span::styled(format!(" {overflow} "), app.theme.title))
+                .position(Position::Bottom)
+                .alignment(Alignment::Right),
+        );
+    }
+    Table::new(rows)
+        .header(
+            Row::new(vec!["Interface", "RX", "TX", "Total RX / TX", "Traffic"])
+                .style(app.theme.title),
+        )
+        .style(app.theme.text)
+        .block(block)
+        .widths(&COMPACT_WIDTHS)
+}
@@ -0,0 +1,176 @@
+pub mod accessible;
+pub mod adapters;
+pub mod aggregate;
+pub mod alerts;
+pub mod anomaly;
+pub mod api;
+pub mod app;
+pub mod attach;
+pub mod baseline;
+pub mod broadcast;
+pub mod bufferbloat;
+pub mod canary;
+pub mod capabilities;
+pub mod carrier;
+pub mod cli;
+pub mod collector;
+pub mod collectors;
+pub mod compare;
+pub mod config;
+pub mod connections;
+pub mod conntrack;
+pub mod containers;
+pub mod damage;
+pub mod derived;
+pub mod discovery;
+pub mod error;
+pub mod export;
+pub mod focus;
+pub mod forecast;
+pub mod geoip;
+pub mod graphite;
+pub mod histogram;
+pub mod history;
+pub mod hooks;
+pub mod hotplug;
+pub mod http;
+pub mod iftype;
+pub mod image;
+pub mod import;
+pub mod input;
+pub mod journal;
+pub mod keymap;
+pub mod latency;
+pub mod layout;
+pub mod macros;
+pub mod mqtt;
+pub mod netlink;
+pub mod networks;
+pub mod notes;
+pub mod picker;
+pub mod plot;
+pub mod quota;
+pub mod replay;
+pub mod route;
+pub mod rules;
+pub mod scale;
+pub mod share;
+pub mod slo;
+pub mod smoothing;
+pub mod snapshot;
+pub mod sockdiag;
+pub mod sockstates;
+pub mod speedtest;
+pub mod stats;
+pub mod stream;
+pub mod summary;
+pub mod sysload;
+pub mod tcp;
+pub mod theme;
+pub mod throughput;
+pub mod traceroute;
+pub mod tui;
+pub mod units;
+pub mod users;
+pub mod wire;
+pub mod wol;
+pub mod zoom;
+
+use collector::Snapshot;
+use connections::ConnectionSort;
+use crossterm::event::KeyCode;
+use input::PromptKind;
+use ratatui::prelude::CrosstermBackend;
+
+pub type Frame<'a, B = CrosstermBackend<std::io::Stderr>> = ratatui::Frame<'a, B>;
+
+// App actions
+#[derive(Clone)]
+pub enum Action {
+    // a new sample from the collector
+    Tick(Snapshot),
+    Quit,
+    ToggleDebug,
+    ToggleBaseline,
+    ToggleSmoothing,
+    ToggleLogScale,
+    ToggleChart,
+    CycleResolution,
+    ToggleCumulative,
+    // cumulative totals for the selected interface only
+    ToggleInterfaceCumulative,
+    // restart the cumulative totals from zero
+    ResetCumulative,
+    SelectNext,
+    SelectPrev,
+    PageDown,
+    PageUp,
+    Click(u16, u16),
+    NextScreen,
+    SelectScreen(usize),
+    SelectInterface(String),
+    // show only the interfaces matching a name fragment, None shows them all
+    SearchInterfaces(Option<String>),
+    // select by position, clamped to the last interface
+    SelectIndex(usize),
+    // playback controls of --replay, nothing otherwise
+    ToggleReplayPause,
+    // true for the next faster speed
+    ReplaySpeed(bool),
+    // seconds forward, or back when negative
+    ReplaySeek(i64),
+    PrevScreen,
+    CycleLayout,
+    CycleGraphStyle,
+    // separate TX and RX graphs, both in one, or TX mirrored under RX
+    CycleOverlay,
+    ToggleAutoFocus,
+    OpenPrompt(PromptKind),
+    PromptInput(KeyCode),
+    // keys go to the --pick checklist while it's open
+    PickerInput(KeyCode),
+    // show only the selected interface, with more detail
+    ToggleZoom,
+    // hold the selected interface's graph scale at its peaks, or release it
+    ToggleScaleLock,
+    // mark the selected interface, two marked ones are shown side by side
+    ToggleCompareMark,
+    // packets per second graphs under the byte graphs
+    TogglePacketGraphs,
+    // pinned panel summing all interfaces
+    ToggleAggregate,
+    // the aggregate panel as every interface's traffic stacked in one graph
+    ToggleStacked,
+    // only ethernet, wifi and modem interfaces
+    TogglePhysicalOnly,
+    // CPU and memory strip under the tabs
+    ToggleSystemStrip,
+    // TCP socket states strip under the tabs
+    ToggleSocketStrip,
+    // start or cancel a bufferbloat test
+    ToggleBufferbloat,
+    // start or cancel a throughput test against the [speedtest] URLs
+    ToggleSpeedTest,
+    // write the current state to a JSON file
+    SaveSnapshot,
+    // add or adjust the selected interface's alert rule, e.g. "rx 10 MB"
+    SetThreshold(String),
+    // trace the path to a host or IP, shown on the Latency screen
+    Traceroute(String),
+    // cable diagnostics on the selected interface's PHY
+    CableTest,
+    // send a Wake-on-LAN packet to the selected host
+    WakeHost,
+    // close whatever popup is open
+    Dismiss,
+    // several actions at once, e.g. a motion repeated by a count prefix
+    Sequence(Vec<Action>),
+    FilterConnections(Option<String>),
+    SortConnections(ConnectionSort),
+    RunMacro(KeyCode),
+    // the layout has to be redrawn, nothing else changed
+    Resize,
+    // the controlling terminal went away (SSH drop, closed window)
+    Detach,
+    None,
+}
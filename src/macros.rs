@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use crossterm::event::KeyCode;
+
+use crate::{
+    app::Screen,
+    connections::ConnectionSort,
+    error::{Error, Result},
+    Action,
+};
+
+// Composite actions bound to a key, run one after another before the next
+// frame is drawn
+pub type Macros = HashMap<KeyCode, Vec<Action>>;
+
+pub fn parse_macros(config: &HashMap<String, Vec<String>>) -> Result<Macros> {
+    config
+        .iter()
+        .map(|(key, steps)| {
+            let code = parse_key(key)?;
+            let actions = steps
+                .iter()
+                .map(|step| parse_step(step))
+                .collect::<Result<Vec<Action>>>()
+                .map_err(|e| e.context(format!("macro {key}")))?;
+            Ok((code, actions))
+        })
+        .collect()
+}
+
+pub fn parse_key(key: &str) -> Result<KeyCode> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(KeyCode::Char(c)),
+        _ => key
+            .strip_prefix(['F', 'f'])
+            .and_then(|n| n.parse().ok())
+            .filter(|n| (1..=12).contains(n))
+            .map(KeyCode::F)
+            .ok_or_else(|| Error::config(format!("unknown key \"{key}\""))),
+    }
+}
+
+fn parse_step(step: &str) -> Result<Action> {
+    let (command, argument) = step
+        .trim()
+        .split_once(' ')
+        .map(|(command, argument)| (command, argument.trim()))
+        .unwrap_or((step.trim(), ""));
+    match (command, argument) {
+        ("screen", name) => Screen::ALL
+            .iter()
+            .position(|screen| screen.title().eq_ignore_ascii_case(name))
+            .or_else(|| name.parse::<usize>().ok().map(|n| n.saturating_sub(1)))
+            .filter(|i| *i < Screen::ALL.len())
+            .map(Action::SelectScreen)
+            .ok_or_else(|| Error::config(format!("unknown screen \"{name}\""))),
+        ("search", "") => Ok(Action::SearchInterfaces(None)),
+        ("search", fragment) => Ok(Action::SearchInterfaces(Some(fragment.to_string()))),
+        ("select", name) if !name.is_empty() => Ok(Action::SelectInterface(name.to_string())),
+        ("toggle", "debug") => Ok(Action::ToggleDebug),
+        ("toggle", "baseline") => Ok(Action::ToggleBaseline),
+        ("toggle", "smoothing") => Ok(Action::ToggleSmoothing),
+        ("toggle", "log") => Ok(Action::ToggleLogScale),
+        ("toggle", "chart") => Ok(Action::ToggleChart),
+        ("toggle", "auto-focus") => Ok(Action::ToggleAutoFocus),
+        ("toggle", "zoom") => Ok(Action::ToggleZoom),
+        ("mark", "compare") => Ok(Action::ToggleCompareMark),
+        ("lock", "scale") => Ok(Action::ToggleScaleLock),
+        ("toggle", "packets") => Ok(Action::TogglePacketGraphs),
+        ("toggle", "aggregate") => Ok(Action::ToggleAggregate),
+        ("toggle", "stacked") => Ok(Action::ToggleStacked),
+        ("toggle", "physical") => Ok(Action::TogglePhysicalOnly),
+        ("toggle", "system") => Ok(Action::ToggleSystemStrip),
+        ("toggle", "sockets") => Ok(Action::ToggleSocketStrip),
+        ("toggle", "bufferbloat") => Ok(Action::ToggleBufferbloat),
+        ("speed", "test") => Ok(Action::ToggleSpeedTest),
+        ("cable", "test") => Ok(Action::CableTest),
+        ("save", "snapshot") => Ok(Action::SaveSnapshot),
+        ("threshold", spec) if !spec.is_empty() => Ok(Action::SetThreshold(spec.to_string())),
+        ("traceroute", target) if !target.is_empty() => Ok(Action::Traceroute(target.to_string())),
+        ("range", "") => Ok(Action::CycleResolution),
+        ("layout", "") => Ok(Action::CycleLayout),
+        ("graph", "style") => Ok(Action::CycleGraphStyle),
+        ("graph", "overlay") => Ok(Action::CycleOverlay),
+        ("toggle", "cumulative") => Ok(Action::ToggleCumulative),
+        ("toggle", "interface-cumulative") => Ok(Action::ToggleInterfaceCumulative),
+        ("reset", "cumulative") => Ok(Action::ResetCumulative),
+        ("replay", "pause") => Ok(Action::ToggleReplayPause),
+        ("replay", "faster") => Ok(Action::ReplaySpeed(true)),
+        ("replay", "slower") => Ok(Action::ReplaySpeed(false)),
+        ("seek", seconds) => seconds
+            .parse()
+            .map(Action::ReplaySeek)
+            .map_err(|_| Error::config(format!("invalid seek \"{seconds}\", expected seconds"))),
+        ("filter", "") => Ok(Action::FilterConnections(None)),
+        ("filter", filter) => Ok(Action::FilterConnections(Some(filter.to_string()))),
+        ("sort", field) => ConnectionSort::from_name(field)
+            .map(Action::SortConnections)
+            .ok_or_else(|| Error::config(format!("unknown sort field \"{field}\""))),
+        _ => Err(Error::config(format!("unknown macro step \"{step}\""))),
+    }
+}
@@ -1,114 +1,76 @@
 use anyhow::Result;
-use app::{ui, update, App};
-use std::collections::HashMap;
-mod app;
-mod networks;
-use crossterm::{
-    event::{self, Event::Key, KeyCode::Char},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+use net_stat::{
+    accessible, attach,
+    capabilities::Capabilities,
+    cli::{self, Command, TuiOptions},
+    config::config_path,
+    discovery,
+    error::Error,
+    import, rules, stream,
+    summary::session_summary,
+    tui,
 };
-use networks::InterfaceData;
-use ratatui::prelude::{CrosstermBackend, Terminal};
-use sysinfo::{System, SystemExt};
 
-pub type Frame<'a> = ratatui::Frame<'a, CrosstermBackend<std::io::Stderr>>;
-
-fn startup() -> Result<()> {
-    enable_raw_mode()?;
-    execute!(std::io::stderr(), EnterAlternateScreen)?;
-    Ok(())
-}
-
-fn shutdown() -> Result<()> {
-    execute!(std::io::stderr(), LeaveAlternateScreen)?;
-    disable_raw_mode()?;
-    Ok(())
-}
-
-// App state
-
-// App actions
-pub enum Action {
-    Tick,
-    Quit,
-    None,
-}
+#[tokio::main]
+async fn main() -> Result<()> {
+    let (panel, options) = match cli::parse(std::env::args().skip(1))? {
+        Command::Tui { options } => (None, options),
+        Command::Attach { panel } => (Some(panel), TuiOptions::default()),
+        Command::DbImport { csv } => return Ok(import::import_csv(&csv)?),
+        Command::RulesTest { samples } => return Ok(rules::test_rules(samples.as_deref())?),
+        Command::Serve {
+            bind,
+            backend,
+            announce,
+        } => return Ok(stream::serve(&bind, backend, announce).await?),
+        Command::Discover => return Ok(discovery::print_peers()?),
+    };
+    let attached = panel.is_some();
 
-// App ui render function
+    let capabilities = Capabilities::detect();
 
-fn get_action(_app: &App) -> Action {
-    let tick_rate = std::time::Duration::from_millis(250);
-    if event::poll(tick_rate).unwrap() {
-        if let Key(key) = event::read().unwrap() {
-            match key.code {
-                Char('q') => Action::Quit,
-                _ => Action::None,
-            }
-        } else {
-            Action::None
-        }
-    } else {
-        Action::Tick
+    // no terminal to set up, the sentences go to stdout as they come
+    if options.accessible {
+        let app = accessible::run(capabilities, options)
+            .await
+            .map_err(explain)?;
+        println!("{}", session_summary(&app));
+        return Ok(());
     }
-}
-
-fn run() -> Result<()> {
-    // ratatui terminal
-    let mut t = Terminal::new(CrosstermBackend::new(std::io::stderr()))?;
-
-    let mut sys = System::new_all();
-    sys.refresh_all();
 
-    let interfaces: Vec<InterfaceData> = sys
-        .networks()
-        .into_iter()
-        .map(|(name, data)| InterfaceData::from(name, data))
-        .collect();
-    // application state
-
-    let mut set = HashMap::new();
-    interfaces.iter().for_each(|x| {
-        set.insert(x.name.to_string(), Vec::new());
-    });
+    // setup terminal
+    tui::startup(&capabilities)?;
 
-    let mut app = App {
-        should_quit: false,
-        sys: sys,
-        net_interfaces: interfaces,
-        net_interface_graphs: set,
+    let result = match panel {
+        Some(panel) => attach::run(capabilities, panel).await,
+        None => tui::run(capabilities, options).await,
     };
 
-    loop {
-        let action = get_action(&mut app);
-
-        // application update
-        update(&mut app, action);
-
-        // application render
-        t.draw(|f| {
-            ui(f, &app);
-        })?;
+    // teardown terminal before unwrapping Result of app run. Restoring a
+    // terminal that has been hung up on fails, which is expected.
+    let teardown = tui::shutdown(&capabilities);
+    let detached = matches!(&result, Ok(app) if app.detached);
+    if !detached {
+        teardown?;
+    }
 
-        // application exit
-        if app.should_quit {
-            break;
-        }
+    let app = result.map_err(explain)?;
+    // stdout went away together with the terminal when detached, and the
+    // session belongs to the primary instance when attached
+    if !detached && !attached {
+        println!("{}", session_summary(&app));
     }
 
     Ok(())
 }
 
-fn main() -> Result<()> {
-    // setup terminal
-    startup()?;
-
-    let result = run();
-
-    // teardown terminal before unwrapping Result of app run
-    shutdown()?;
-
-    result?;
-
-    Ok(())
+// Config mistakes are the one class of error the user fixes by hand, so
+// point at the file
+fn explain(error: Error) -> anyhow::Error {
+    match (&error, config_path()) {
+        (Error::Config(_), Some(path)) => {
+            anyhow::Error::new(error).context(format!("check {}", path.display()))
+        }
+        _ => error.into(),
+    }
 }
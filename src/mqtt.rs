@@ -0,0 +1,267 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    error::{Error, Result, ResultExt},
+    export::{Sample, Sink},
+};
+
+// Publishes every sample to an MQTT broker, one topic per interface and
+// metric, e.g.
+//   [export.mqtt]
+//   broker = "homeassistant.local:1883"
+//   topic = "net-stat/{interface}/{metric}"
+// Metrics are tx_rate and rx_rate in bytes per second and tx_total and
+// rx_total in bytes, each published as a plain number.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    // host:port, port 1883 when left out
+    pub broker: String,
+    // {interface} and {metric} are replaced
+    pub topic: String,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // keep the last value at the broker for subscribers that join later
+    pub retain: bool,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        MqttConfig {
+            broker: "localhost:1883".to_string(),
+            topic: "net-stat/{interface}/{metric}".to_string(),
+            client_id: "net-stat".to_string(),
+            username: None,
+            password: None,
+            retain: false,
+        }
+    }
+}
+
+impl MqttConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.broker.is_empty() {
+            return Err(Error::config("the MQTT broker address is empty"));
+        }
+        if !self.topic.contains("{interface}") || !self.topic.contains("{metric}") {
+            return Err(Error::config(format!(
+                "MQTT topic \"{}\" needs both {{interface}} and {{metric}}",
+                self.topic
+            )));
+        }
+        if self.password.is_some() && self.username.is_none() {
+            return Err(Error::config("an MQTT password needs a username"));
+        }
+        Ok(())
+    }
+
+    // The topic for one interface's metric. + and # are wildcards to
+    // subscribers and / separates levels, so the interface's level has none.
+    pub fn topic(&self, interface: &str, metric: &str) -> String {
+        let level: String = interface
+            .chars()
+            .map(|c| match c {
+                '+' | '#' | '/' | '\0' => '_',
+                c => c,
+            })
+            .collect();
+        self.topic
+            .replace("{interface}", &level)
+            .replace("{metric}", metric)
+    }
+
+    fn address(&self) -> String {
+        match self.broker.rsplit_once(':') {
+            Some((_, port)) if port.parse::<u16>().is_ok() => self.broker.to_string(),
+            _ => format!("{}:1883", self.broker),
+        }
+    }
+}
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// wait between attempts while the broker is unreachable
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// samples are published every tick, well within this
+const KEEP_ALIVE: u16 = 60;
+
+// MQTT 3.1.1 with QoS 0, so nothing is read back after the handshake. The
+// broker is connected on the first sample and again after failures, so an
+// unreachable broker doesn't keep net-stat from starting.
+pub struct MqttSink {
+    config: MqttConfig,
+    stream: Option<TcpStream>,
+    last_attempt: Option<Instant>,
+    // why the last connection attempt failed, reported until one succeeds
+    failure: Option<String>,
+}
+
+impl MqttSink {
+    pub fn open(config: &MqttConfig) -> Result<MqttSink> {
+        config.validate()?;
+        Ok(MqttSink {
+            config: config.clone(),
+            stream: None,
+            last_attempt: None,
+            failure: None,
+        })
+    }
+
+    fn connect(&self) -> Result<TcpStream> {
+        let address = self.config.address();
+        let failed = || format!("failed to connect to MQTT broker {address}");
+        let socket = address
+            .to_socket_addrs()
+            .or_export(failed)?
+            .next()
+            .ok_or_else(|| Error::export(failed()))?;
+        let mut stream = TcpStream::connect_timeout(&socket, CONNECT_TIMEOUT).or_export(failed)?;
+        stream
+            .set_read_timeout(Some(CONNECT_TIMEOUT))
+            .or_export(failed)?;
+        stream
+            .write_all(&connect_packet(&self.config))
+            .or_export(failed)?;
+        let mut ack = [0u8; 4];
+        stream.read_exact(&mut ack).or_export(failed)?;
+        match ack {
+            [0x20, 2, _, 0] => Ok(stream),
+            [0x20, 2, _, code] => Err(Error::export(format!(
+                "MQTT broker {address} refused the connection: {}",
+                refusal(code)
+            ))),
+            _ => Err(Error::export(format!(
+                "{address} didn't answer like an MQTT broker"
+            ))),
+        }
+    }
+}
+
+impl Sink for MqttSink {
+    fn name(&self) -> &str {
+        "MQTT"
+    }
+
+    fn write(&mut self, samples: &[Sample]) -> Result<()> {
+        if self.stream.is_none() {
+            if self
+                .last_attempt
+                .is_some_and(|attempt| attempt.elapsed() < RECONNECT_DELAY)
+            {
+                return match &self.failure {
+                    Some(failure) => Err(Error::export(failure.to_string())),
+                    None => Ok(()),
+                };
+            }
+            self.last_attempt = Some(Instant::now());
+            match self.connect() {
+                Ok(stream) => {
+                    self.stream = Some(stream);
+                    self.failure = None;
+                }
+                Err(e) => {
+                    self.failure = Some(format!("{e:#}"));
+                    return Err(e);
+                }
+            }
+        }
+        let mut packets = Vec::new();
+        for sample in samples {
+            let metrics = [
+                ("tx_rate", format!("{:.1}", sample.sent_rate)),
+                ("rx_rate", format!("{:.1}", sample.rec_rate)),
+                ("tx_total", sample.sent_total.to_string()),
+                ("rx_total", sample.rec_total.to_string()),
+            ];
+            for (metric, value) in metrics {
+                let topic = self.config.topic(&sample.interface, metric);
+                packets.extend(publish_packet(&topic, value.as_bytes(), self.config.retain));
+            }
+        }
+        let Some(stream) = &mut self.stream else {
+            return Ok(());
+        };
+        if let Err(e) = stream.write_all(&packets) {
+            self.stream = None;
+            return Err(e).or_export(|| "lost the MQTT broker");
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some(stream) = &mut self.stream {
+            // DISCONNECT, so the broker doesn't treat the exit as a failure
+            stream
+                .write_all(&[0xe0, 0])
+                .or_export(|| "lost the MQTT broker")?;
+        }
+        Ok(())
+    }
+}
+
+fn refusal(code: u8) -> &'static str {
+    match code {
+        1 => "unsupported protocol version",
+        2 => "client id rejected",
+        3 => "server unavailable",
+        4 => "bad username or password",
+        5 => "not authorized",
+        _ => "unknown reason",
+    }
+}
+
+fn connect_packet(config: &MqttConfig) -> Vec<u8> {
+    let mut flags = 0x02; // clean session
+    let mut payload = string(&config.client_id);
+    if let Some(username) = &config.username {
+        flags |= 0x80;
+        payload.extend(string(username));
+    }
+    if let Some(password) = &config.password {
+        flags |= 0x40;
+        payload.extend(string(password));
+    }
+    let mut body = string("MQTT");
+    body.push(4); // protocol level 3.1.1
+    body.push(flags);
+    body.extend(KEEP_ALIVE.to_be_bytes());
+    body.extend(payload);
+    packet(0x10, body)
+}
+
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = string(topic);
+    body.extend_from_slice(payload);
+    packet(0x30 | retain as u8, body)
+}
+
+fn packet(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![header];
+    // remaining length, 7 bits per byte with a continuation bit
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+fn string(text: &str) -> Vec<u8> {
+    let mut encoded = (text.len() as u16).to_be_bytes().to_vec();
+    encoded.extend_from_slice(text.as_bytes());
+    encoded
+}
@@ -0,0 +1,255 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+// Netlink messages and their attributes, shared by the route, sock_diag,
+// link and ethtool requests. The parsing is plain bytes and works anywhere,
+// the socket is Linux only.
+
+pub const NLMSG_HEADER_LEN: usize = 16;
+pub const NLA_F_NESTED: u16 = 0x8000;
+
+// One message of a datagram, the payload is what follows its header
+pub struct Message<'a> {
+    pub kind: u16,
+    pub sequence: u32,
+    pub payload: &'a [u8],
+}
+
+// Messages and attributes start on 4 byte boundaries
+pub fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+// Native endian fields of the kernel's structs, the caller checks the length
+pub fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+pub fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+pub fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_ne_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+// An address in network order, IPv4 or IPv6 by its length
+pub fn address(bytes: &[u8]) -> Option<IpAddr> {
+    match bytes.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?))),
+        16 => Some(IpAddr::V6(Ipv6Addr::from(
+            <[u8; 16]>::try_from(bytes).ok()?,
+        ))),
+        _ => None,
+    }
+}
+
+// A request of `kind` with its header filled in
+pub fn request(kind: u16, flags: u16, sequence: u32, payload: &[u8]) -> Vec<u8> {
+    let len = NLMSG_HEADER_LEN + payload.len();
+    let mut message = Vec::with_capacity(len);
+    message.extend((len as u32).to_ne_bytes());
+    message.extend(kind.to_ne_bytes());
+    message.extend(flags.to_ne_bytes());
+    message.extend(sequence.to_ne_bytes());
+    // the port id, the kernel fills in ours
+    message.extend(0u32.to_ne_bytes());
+    message.extend(payload);
+    message
+}
+
+pub fn messages(mut bytes: &[u8]) -> Vec<Message<'_>> {
+    let mut messages = Vec::new();
+    while bytes.len() >= NLMSG_HEADER_LEN {
+        let len = read_u32(bytes, 0) as usize;
+        if len < NLMSG_HEADER_LEN || len > bytes.len() {
+            break;
+        }
+        messages.push(Message {
+            kind: read_u16(bytes, 4),
+            sequence: read_u32(bytes, 8),
+            payload: &bytes[NLMSG_HEADER_LEN..len],
+        });
+        bytes = &bytes[align(len).min(bytes.len())..];
+    }
+    messages
+}
+
+// The outcome an NLMSG_ERROR carries, error 0 is an ack
+pub fn outcome(payload: &[u8]) -> io::Result<()> {
+    if payload.len() < 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "truncated netlink error",
+        ));
+    }
+    match read_u32(payload, 0) as i32 {
+        0 => Ok(()),
+        errno => Err(io::Error::from_raw_os_error(-errno)),
+    }
+}
+
+pub fn attribute(kind: u16, value: &[u8]) -> Vec<u8> {
+    let len = 4 + value.len();
+    let mut bytes = Vec::with_capacity(align(len));
+    bytes.extend((len as u16).to_ne_bytes());
+    bytes.extend(kind.to_ne_bytes());
+    bytes.extend(value);
+    bytes.resize(align(len), 0);
+    bytes
+}
+
+// Attribute kinds with the nested flag cleared, and their payloads
+pub fn attributes(mut bytes: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attributes = Vec::new();
+    while bytes.len() >= 4 {
+        let len = read_u16(bytes, 0) as usize;
+        let kind = read_u16(bytes, 2) & !NLA_F_NESTED;
+        if len < 4 || len > bytes.len() {
+            break;
+        }
+        attributes.push((kind, &bytes[4..len]));
+        bytes = &bytes[align(len).min(bytes.len())..];
+    }
+    attributes
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::Socket;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{
+        os::fd::{AsRawFd, FromRawFd, OwnedFd},
+        time::Duration,
+    };
+
+    use super::*;
+
+    const SOL_NETLINK: libc::c_int = 270;
+    const NETLINK_ADD_MEMBERSHIP: libc::c_int = 1;
+
+    pub struct Socket {
+        fd: OwnedFd,
+        sequence: u32,
+    }
+
+    impl Socket {
+        // A socket talking to the kernel over NETLINK_ROUTE and the like
+        pub fn open(protocol: libc::c_int) -> io::Result<Socket> {
+            let fd = unsafe {
+                libc::socket(
+                    libc::AF_NETLINK,
+                    libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                    protocol,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Socket {
+                fd: unsafe { OwnedFd::from_raw_fd(fd) },
+                sequence: 0,
+            })
+        }
+
+        // How long recv() waits before failing with WouldBlock
+        pub fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+            let timeout = libc::timeval {
+                tv_sec: timeout.as_secs() as libc::time_t,
+                tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+            };
+            self.set_option(libc::SOL_SOCKET, libc::SO_RCVTIMEO, &timeout)
+        }
+
+        // Also receive the multicast `group`, e.g. a family's notifications
+        pub fn join(&self, group: u32) -> io::Result<()> {
+            self.set_option(SOL_NETLINK, NETLINK_ADD_MEMBERSHIP, &group)
+        }
+
+        fn set_option<T>(
+            &self,
+            level: libc::c_int,
+            name: libc::c_int,
+            value: &T,
+        ) -> io::Result<()> {
+            let result = unsafe {
+                libc::setsockopt(
+                    self.fd.as_raw_fd(),
+                    level,
+                    name,
+                    value as *const T as *const libc::c_void,
+                    std::mem::size_of::<T>() as libc::socklen_t,
+                )
+            };
+            if result < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub fn send(&self, message: &[u8]) -> io::Result<()> {
+            let mut kernel: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+            kernel.nl_family = libc::AF_NETLINK as u16;
+            let sent = unsafe {
+                libc::sendto(
+                    self.fd.as_raw_fd(),
+                    message.as_ptr() as *const libc::c_void,
+                    message.len(),
+                    0,
+                    &kernel as *const libc::sockaddr_nl as *const libc::sockaddr,
+                    std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+                )
+            };
+            if sent < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        // One datagram, it holds one or more messages
+        pub fn recv<'a>(&self, buffer: &'a mut [u8]) -> io::Result<&'a [u8]> {
+            let len = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                    0,
+                )
+            };
+            if len < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(&buffer[..len as usize])
+        }
+
+        // Sends a dump request of `kind` and hands every message of the
+        // answer to `each` until NLMSG_DONE
+        pub fn dump(
+            &mut self,
+            kind: u16,
+            payload: &[u8],
+            mut each: impl FnMut(&Message),
+        ) -> io::Result<()> {
+            self.sequence = self.sequence.wrapping_add(1);
+            let flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+            self.send(&request(kind, flags, self.sequence, payload))?;
+            let mut buffer = vec![0u8; 32 * 1024];
+            loop {
+                for message in messages(self.recv(&mut buffer)?) {
+                    // skip what's left of an earlier dump that failed half way
+                    if message.sequence != self.sequence {
+                        continue;
+                    }
+                    match message.kind as libc::c_int {
+                        libc::NLMSG_DONE => return Ok(()),
+                        libc::NLMSG_ERROR => return outcome(message.payload),
+                        _ => each(&message),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,32 @@
+use std::{collections::HashMap, iter::zip};
+
 use ratatui::{
-    text::{Line, Text},
-    widgets::{Block, Borders, Paragraph, Sparkline},
+    buffer::Buffer,
+    prelude::Rect,
+    style::{Modifier, Style},
+    symbols::{bar, Marker},
+    text::{Line, Span, Text},
+    widgets::{
+        Axis, Block, BorderType, Borders, Chart, Dataset, GraphType, Paragraph, Sparkline, Widget,
+    },
 };
-use sysinfo::{MacAddr, NetworkData, NetworkExt, SystemExt};
+use sysinfo::{MacAddr, NetworkData, NetworkExt};
 
-use crate::app::App;
+use crate::{
+    app::App,
+    config::{Calibration, Config},
+    history::{unix_time, Bucket, Resolution},
+    hotplug::update_hotplug,
+    iftype::InterfaceType,
+    plot::{GraphStyle, Overlay, OverlayMode, Plot, BLOCKS},
+    scale::{chart_max, scale_label, scale_lock, sparkline_max, value_label},
+    speedtest::Direction,
+    stats::RateStats,
+    theme::Theme,
+    units::{format_bytes, format_duration, format_rate},
+};
 
+#[derive(Clone)]
 pub struct InterfaceData {
     pub name: String,
     pub sent_total: u64,
@@ -13,80 +34,1053 @@ pub struct InterfaceData {
     pub sent: u64,
     pub rec: u64,
     pub mac: MacAddr,
+    pub raw: RawCounters,
+    pub errors: PacketErrors,
+    pub packets: PacketCounters,
+    // None when the backend doesn't report them
+    pub carrier_changes: Option<u64>,
+    // what the adapter calls itself, e.g. "Intel(R) Ethernet Connection
+    // I219-V", only on Windows for now
+    pub description: Option<String>,
+    pub kind: InterfaceType,
+}
+
+// Packets moved since the previous sample and since boot
+#[derive(Clone, Copy, Default)]
+pub struct PacketCounters {
+    pub sent: u64,
+    pub rec: u64,
+    pub sent_total: u64,
+    pub rec_total: u64,
+    // received multicast, and broadcast where the driver counts it
+    pub multicast: u64,
+    pub multicast_total: u64,
+    pub broadcast: Option<u64>,
+    pub broadcast_total: Option<u64>,
+}
+
+// Packet errors and drops, totals since boot and the change since the
+// previous sample
+#[derive(Clone, Copy, Default)]
+pub struct PacketErrors {
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_errors_total: u64,
+    pub tx_errors_total: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+    pub rx_dropped_total: u64,
+    pub tx_dropped_total: u64,
+}
+
+// sysinfo has no drop or multicast counters, Linux keeps them next to the
+// other statistics in sysfs
+pub fn read_statistic(name: &str, counter: &str) -> Option<u64> {
+    std::fs::read_to_string(format!("/sys/class/net/{name}/statistics/{counter}"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+pub fn read_dropped(name: &str) -> Option<(u64, u64)> {
+    Some((
+        read_statistic(name, "rx_dropped")?,
+        read_statistic(name, "tx_dropped")?,
+    ))
+}
+
+// Counters exactly as reported by the OS, before any calibration
+#[derive(Clone, Copy, Default)]
+pub struct RawCounters {
+    pub sent_total: u64,
+    pub rec_total: u64,
+    pub sent: u64,
+    pub rec: u64,
 }
 
 impl InterfaceData {
-    pub fn from(name: &String, data: &NetworkData) -> InterfaceData {
-        InterfaceData {
-            name: name.to_string(),
+    pub fn from(name: &str, data: &NetworkData) -> InterfaceData {
+        let raw = RawCounters {
             sent_total: data.total_transmitted(),
             rec_total: data.total_received(),
             sent: data.transmitted(),
             rec: data.received(),
+        };
+        InterfaceData {
+            name: name.to_string(),
+            sent_total: raw.sent_total,
+            rec_total: raw.rec_total,
+            sent: raw.sent,
+            rec: raw.rec,
             mac: data.mac_address(),
+            raw,
+            errors: PacketErrors {
+                rx_errors: data.errors_on_received(),
+                tx_errors: data.errors_on_transmitted(),
+                rx_errors_total: data.total_errors_on_received(),
+                tx_errors_total: data.total_errors_on_transmitted(),
+                ..PacketErrors::default()
+            },
+            packets: PacketCounters {
+                sent: data.packets_transmitted(),
+                rec: data.packets_received(),
+                sent_total: data.total_packets_transmitted(),
+                rec_total: data.total_packets_received(),
+                ..PacketCounters::default()
+            },
+            carrier_changes: None,
+            description: None,
+            kind: InterfaceType::Unknown,
+        }
+    }
+
+    // Case insensitive match of a fragment of the name or description, for
+    // picking an interface by what the user knows it as
+    pub fn matches(&self, fragment: &str) -> bool {
+        let fragment = fragment.to_lowercase();
+        self.name.to_lowercase().contains(&fragment)
+            || self
+                .description
+                .as_ref()
+                .is_some_and(|description| description.to_lowercase().contains(&fragment))
+    }
+
+    pub fn calibrate(mut self, calibration: Calibration) -> InterfaceData {
+        self.sent_total = calibration.total(self.raw.sent_total, calibration.sent_offset);
+        self.rec_total = calibration.total(self.raw.rec_total, calibration.rec_offset);
+        self.sent = calibration.scale(self.raw.sent);
+        self.rec = calibration.scale(self.raw.rec);
+        self
+    }
+}
+
+// Sent (TX) and received (RX) history for a single interface, `times` holds
+// when each sample was taken in seconds since startup
+#[derive(Default)]
+pub struct GraphData {
+    pub sent: Vec<u64>,
+    pub rec: Vec<u64>,
+    pub times: Vec<f64>,
+    // packets per sample
+    pub sent_packets: Vec<u64>,
+    pub rec_packets: Vec<u64>,
+}
+
+// Series as shown in the graphs: per-tick values for sparklines, and
+// (seconds ago, rate) points for charts. Aggregated resolutions show bucket
+// averages and also chart the peak rate of each bucket
+#[derive(Default)]
+pub struct DisplayData {
+    pub sent: Vec<u64>,
+    pub rec: Vec<u64>,
+    pub sent_points: Vec<(f64, f64)>,
+    pub rec_points: Vec<(f64, f64)>,
+    pub sent_peaks: Vec<(f64, f64)>,
+    pub rec_peaks: Vec<(f64, f64)>,
+}
+
+static NO_DISPLAY: DisplayData = DisplayData {
+    sent: Vec::new(),
+    rec: Vec::new(),
+    sent_points: Vec::new(),
+    rec_points: Vec::new(),
+    sent_peaks: Vec::new(),
+    rec_peaks: Vec::new(),
+};
+
+pub enum Graph<'a> {
+    Sparkline(Sparkline<'a>),
+    Plot(Plot<'a>),
+    Chart(Chart<'a>),
+    Overlay(Overlay<'a>),
+}
+
+impl Widget for Graph<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        match self {
+            Graph::Sparkline(sparkline) => sparkline.render(area, buf),
+            Graph::Plot(plot) => plot.render(area, buf),
+            Graph::Chart(chart) => chart.render(area, buf),
+            Graph::Overlay(overlay) => overlay.render(area, buf),
+        }
+    }
+}
+
+impl<'a> Graph<'a> {
+    // Scaled to `max` rather than the largest value shown, so graphs side by
+    // side compare. Charts keep the axis of their own data.
+    pub fn with_max(self, max: u64) -> Graph<'a> {
+        match self {
+            Graph::Sparkline(sparkline) => Graph::Sparkline(sparkline.max(max.max(1))),
+            Graph::Plot(plot) => Graph::Plot(Plot {
+                max: Some(max),
+                ..plot
+            }),
+            Graph::Overlay(overlay) => Graph::Overlay(Overlay {
+                max: Some(max),
+                ..overlay
+            }),
+            chart => chart,
         }
     }
 }
 
-pub fn to_network_stat_widgets(app: &App) -> (Vec<Paragraph>, Vec<Sparkline>) {
+pub type GraphPair<'a> = (Graph<'a>, Graph<'a>);
+
+// Samples kept in the display series, enough for a very wide terminal
+pub const DISPLAY_SAMPLES: usize = 1024;
+
+// Raw samples kept per interface, longer ranges come from the aggregated
+// history instead
+pub const HISTORY_SAMPLES: usize = 4 * 3600;
+
+// `width` is the number of columns available to each graph, only the most
+// recent samples that fit are shown
+pub fn to_network_stat_widgets(
+    app: &App,
+    width: usize,
+) -> (Vec<Paragraph<'_>>, Vec<GraphPair<'_>>) {
     let mut network_data = Vec::new();
     let mut network_spark = Vec::new();
+    let graph_style = effective_graph_style(app);
 
-    app.net_interfaces.iter().for_each(|interface| {
-        let paragraph = create_interface_paragraph(interface);
-        let spark = app
-            .net_interface_graphs
-            .get(&interface.name)
-            .and_then(|data| Some(create_interface_graph(&interface.name, data)))
-            .unwrap();
-        network_data.push(paragraph);
-        network_spark.push(spark);
-    });
+    app.net_interfaces
+        .iter()
+        .enumerate()
+        .for_each(|(i, interface)| {
+            let theme = &interface_theme(app, &interface.name);
+            let log_scale = app.log_scale.contains(&interface.name);
+            let cumulative = is_cumulative(app, &interface.name);
+            let scale = scale_title(app, &interface.name);
+            let paragraph = create_interface_paragraph(app, interface, theme, i == app.selected);
+            // empty until its first sample, e.g. shown again after appearing
+            // while filtered out
+            let data = app
+                .net_interface_display
+                .get(&interface.name)
+                .unwrap_or(&NO_DISPLAY);
+            let spark = {
+                let speed_test = &app.speed_test;
+                let lock = scale_lock(app, &interface.name);
+                // values only label sparklines, charts have their axis
+                let title = |direction: Direction, values: &[u64], lock: Option<f64>| {
+                    format!(
+                        "{} {}{}{scale}{}{}",
+                        interface.name,
+                        match direction {
+                            Direction::Upload => "TX",
+                            Direction::Download => "RX",
+                        },
+                        match app.chart {
+                            true => String::new(),
+                            false => value_label(app, &interface.name, values),
+                        },
+                        lock.map(scale_label).unwrap_or_default(),
+                        speed_test.title(&interface.name, direction)
+                    )
+                };
+                let samples = graph_style.samples(width);
+                let (sent, rec) = (tail(&data.sent, samples), tail(&data.rec, samples));
+                let tx_title = title(Direction::Upload, sent, lock.map(|lock| lock.sent));
+                let rx_title = title(Direction::Download, rec, lock.map(|lock| lock.rec));
+                if app.chart {
+                    let line = |direction| match cumulative {
+                        true => &[][..],
+                        false => speed_test.line(&interface.name, direction),
+                    };
+                    let (sent_line, rec_line) =
+                        (line(Direction::Upload), line(Direction::Download));
+                    let chart = |title, points, peaks, line, style, max: Option<f64>| {
+                        let options = ChartOptions {
+                            log_scale,
+                            cumulative,
+                            window: chart_window(app),
+                            marker: app.capabilities.braille,
+                            max: max.map(|rate| chart_max(app, &interface.name, rate)),
+                        };
+                        Graph::Chart(create_interface_chart(
+                            title, points, peaks, line, style, options, theme,
+                        ))
+                    };
+                    (
+                        chart(
+                            tx_title,
+                            &data.sent_points,
+                            &data.sent_peaks,
+                            sent_line,
+                            theme.tx_graph,
+                            lock.map(|lock| lock.sent),
+                        ),
+                        chart(
+                            rx_title,
+                            &data.rec_points,
+                            &data.rec_peaks,
+                            rec_line,
+                            theme.rx_graph,
+                            lock.map(|lock| lock.rec),
+                        ),
+                    )
+                } else {
+                    let graphs = (
+                        create_interface_graph(
+                            tx_title,
+                            sent,
+                            theme.tx_graph,
+                            theme.tx_symbols.clone(),
+                            graph_style,
+                            app.capabilities.unicode,
+                            theme,
+                        ),
+                        create_interface_graph(
+                            rx_title,
+                            rec,
+                            theme.rx_graph,
+                            theme.rx_symbols.clone(),
+                            graph_style,
+                            app.capabilities.unicode,
+                            theme,
+                        ),
+                    );
+                    match lock {
+                        Some(lock) => (
+                            graphs
+                                .0
+                                .with_max(sparkline_max(app, &interface.name, lock.sent)),
+                            graphs
+                                .1
+                                .with_max(sparkline_max(app, &interface.name, lock.rec)),
+                        ),
+                        None => graphs,
+                    }
+                }
+            };
+            network_data.push(paragraph);
+            network_spark.push(spark);
+        });
     (network_data, network_spark)
 }
 
-pub fn update_net_data(app: &mut App) {
+// e.g. " (total) (log) [1m]"
+fn scale_title(app: &App, name: &str) -> String {
+    let mut scale = String::new();
+    if is_cumulative(app, name) {
+        scale.push_str(" (total)");
+    }
+    if app.log_scale.contains(name) {
+        scale.push_str(" (log)");
+    }
+    if app.resolution != Resolution::Raw {
+        scale.push_str(&format!(" [{}]", app.resolution.name()));
+    }
+    scale
+}
+
+// Charts keep their own axis per direction, only sparklines overlay
+pub fn is_overlaid(app: &App) -> bool {
+    app.overlay != OverlayMode::Off && !app.chart
+}
+
+// One graph per interface with RX and TX together, in place of the pairs
+// from to_network_stat_widgets when overlaid. Always drawn as bars, the
+// braille and dot styles can't tell two series apart.
+pub fn to_overlay_graphs(app: &App, width: usize) -> Vec<Graph<'_>> {
+    app.net_interfaces
+        .iter()
+        .map(|interface| {
+            let name = &interface.name;
+            let theme = &interface_theme(app, name);
+            let (sent, rec) = app
+                .net_interface_display
+                .get(name)
+                .map_or((&[][..], &[][..]), |data| {
+                    (tail(&data.sent, width), tail(&data.rec, width))
+                });
+            let lock = scale_lock(app, name);
+            let title = format!(
+                "{name} RX{} TX{}{}{}",
+                value_label(app, name, rec),
+                value_label(app, name, sent),
+                scale_title(app, name),
+                lock.map(|lock| scale_label(lock.sent.max(lock.rec)))
+                    .unwrap_or_default()
+            );
+            let block = Block::default()
+                .title(Span::styled(title, theme.title))
+                .borders(Borders::all())
+                .border_style(theme.border);
+            let symbols = |symbols: &bar::Set| match effective_graph_style(app) {
+                GraphStyle::Block if app.capabilities.unicode => BLOCKS,
+                _ => symbols.clone(),
+            };
+            Graph::Overlay(Overlay {
+                block,
+                sent,
+                rec,
+                sent_style: theme.tx_graph,
+                rec_style: theme.rx_graph,
+                sent_symbols: symbols(&theme.tx_symbols),
+                rec_symbols: symbols(&theme.rx_symbols),
+                mirrored: app.overlay == OverlayMode::Mirrored,
+                max: lock.map(|lock| {
+                    sparkline_max(app, name, lock.sent).max(sparkline_max(app, name, lock.rec))
+                }),
+            })
+        })
+        .collect()
+}
+
+// Braille falls back to bars where the terminal font lacks it
+pub fn effective_graph_style(app: &App) -> GraphStyle {
+    match app.graph_style {
+        GraphStyle::Braille if !app.capabilities.braille => GraphStyle::Bar,
+        style => style,
+    }
+}
+
+// Packets per sample for TX and RX, shown under the byte graphs when
+// enabled. Small packet floods barely register in bytes but stand out here.
+pub fn to_packet_graphs(app: &App, width: usize) -> Vec<GraphPair<'_>> {
+    let graph_style = effective_graph_style(app);
+    let samples = graph_style.samples(width);
+    app.net_interfaces
+        .iter()
+        .map(|interface| {
+            let theme = &interface_theme(app, &interface.name);
+            let (sent, rec) = match app.net_interface_graphs.get(&interface.name) {
+                Some(graph) => (&graph.sent_packets[..], &graph.rec_packets[..]),
+                None => (&[][..], &[][..]),
+            };
+            (
+                create_interface_graph(
+                    format!("{} TX packets", interface.name),
+                    tail(sent, samples),
+                    theme.tx_graph,
+                    theme.tx_symbols.clone(),
+                    graph_style,
+                    app.capabilities.unicode,
+                    theme,
+                ),
+                create_interface_graph(
+                    format!("{} RX packets", interface.name),
+                    tail(rec, samples),
+                    theme.rx_graph,
+                    theme.rx_symbols.clone(),
+                    graph_style,
+                    app.capabilities.unicode,
+                    theme,
+                ),
+            )
+        })
+        .collect()
+}
+
+// Seconds of history shown in chart mode, aggregated resolutions show the
+// last 60 buckets
+pub fn chart_window(app: &App) -> f64 {
+    match app.resolution {
+        Resolution::Raw => app.config.chart_window,
+        resolution => resolution.seconds() * 60.0,
+    }
+}
+
+// Either every interface or just this one graphs running totals
+pub fn is_cumulative(app: &App, name: &str) -> bool {
+    app.cumulative || app.cumulative_interfaces.contains(name)
+}
+
+fn tail<T>(values: &[T], width: usize) -> &[T] {
+    &values[values.len().saturating_sub(width)..]
+}
+
+fn interface_theme(app: &App, name: &str) -> Theme {
+    if !app.config.interface_colors {
+        return app.theme.clone();
+    }
+    let configured = app
+        .config
+        .color(name)
+        .map(|color| app.capabilities.color(color));
+    match app.theme.interface_color(name, configured) {
+        Some(color) => app.theme.accented(color),
+        None => app.theme.clone(),
+    }
+}
+
+pub fn update_net_data(app: &mut App, interfaces: Vec<InterfaceData>) {
+    let interfaces = collect_interfaces(&app.config, interfaces);
+    update_hotplug(app, &interfaces);
+    app.all_interfaces = interfaces;
+    show_interfaces(app);
+}
+
+pub fn search_interfaces(app: &mut App, fragment: Option<String>) {
+    app.search = fragment.filter(|fragment| !fragment.is_empty());
+    show_interfaces(app);
+}
+
+// Applies the view filters to the last sample, right away when they change
+// rather than on the next sample
+pub fn show_interfaces(app: &mut App) {
     let interfaces = app
-        .sys
-        .networks()
-        .into_iter()
-        .map(|(name, data)| InterfaceData::from(name, data))
+        .all_interfaces
+        .iter()
+        .filter(|interface| !app.physical_only || interface.kind.physical())
+        .filter(|interface| {
+            app.search
+                .as_ref()
+                .is_none_or(|fragment| interface.matches(fragment))
+        })
+        .cloned()
         .collect();
+    // keep the selection on the same interface as others come and go
+    let selected = app
+        .net_interfaces
+        .get(app.selected)
+        .map(|interface| interface.name.to_string());
     app.net_interfaces = interfaces;
+    if let Some(i) = selected.and_then(|name| {
+        app.net_interfaces
+            .iter()
+            .position(|interface| interface.name == name)
+    }) {
+        app.selected = i;
+    }
+}
+
+// Calibrate and order the interfaces of a collector snapshot
+pub fn collect_interfaces(config: &Config, interfaces: Vec<InterfaceData>) -> Vec<InterfaceData> {
+    let mut interfaces: Vec<InterfaceData> = interfaces
+        .into_iter()
+        .map(|mut interface| {
+            if let Some(kind) = config.interface_type(&interface.name) {
+                interface.kind = kind;
+            }
+            let calibration = config.calibration(&interface.name);
+            interface.calibrate(calibration)
+        })
+        .filter(|interface| config.monitors(&interface.name) && config.shows_type(interface.kind))
+        .collect();
+    interfaces.sort_by(|a, b| {
+        config
+            .order_key(&a.name, a.kind)
+            .cmp(&config.order_key(&b.name, b.kind))
+    });
+    interfaces
 }
 
 pub fn update_graph_data(app: &mut App) {
-    app.net_interfaces.iter().for_each(|interface| {
-        app.net_interface_graphs
+    let time = app.started.elapsed().as_secs_f64();
+    // hidden interfaces too, so they have their graphs when shown again
+    app.all_interfaces.iter().for_each(|interface| {
+        let graph = app
+            .net_interface_graphs
             .entry(interface.name.to_string())
-            .and_modify(|l| {
-                l.push(interface.sent);
-            })
-            .or_insert(vec![interface.sent]);
+            .or_default();
+        graph.sent.push(interface.sent);
+        graph.rec.push(interface.rec);
+        graph.times.push(time);
+        graph.sent_packets.push(interface.packets.sent);
+        graph.rec_packets.push(interface.packets.rec);
+        if graph.times.len() > HISTORY_SAMPLES {
+            let excess = graph.times.len() - HISTORY_SAMPLES;
+            graph.sent.drain(..excess);
+            graph.rec.drain(..excess);
+            graph.times.drain(..excess);
+            graph.sent_packets.drain(..excess);
+            graph.rec_packets.drain(..excess);
+        }
     });
 }
 
-fn create_interface_paragraph(interface: &InterfaceData) -> Paragraph {
-    let lines = vec![
-        Line::from(format!("Interface: {}", interface.name)),
-        Line::from(format!(
-            "Sent/Recieved: {} / {}",
-            interface.sent, interface.rec
-        )),
+// Rebuild the series shown in the graphs from the recorded history
+pub fn update_display_data(app: &mut App) {
+    if app.cumulative {
+        app.net_interface_display = cumulative_display(app);
+        return;
+    }
+    if app.resolution != Resolution::Raw {
+        update_aggregated_display(app, unix_time());
+    } else {
+        update_raw_display(app);
+    }
+    if !app.cumulative_interfaces.is_empty() {
+        let totals = cumulative_display(app);
+        app.net_interface_display.extend(totals);
+    }
+}
+
+fn update_raw_display(app: &mut App) {
+    let now = app.started.elapsed().as_secs_f64();
+    let display = app
+        .net_interface_graphs
+        .iter()
+        .map(|(name, data)| {
+            let baseline = app.baseline.get(name).unwrap_or_default();
+            let log_scale = app.log_scale.contains(name);
+            let times = tail(&data.times, DISPLAY_SAMPLES);
+            let series = |values: &[u64], base: u64| -> (Vec<u64>, Vec<(f64, f64)>) {
+                let values: Vec<u64> = tail(values, DISPLAY_SAMPLES)
+                    .iter()
+                    .map(|value| value.saturating_sub(base))
+                    .collect();
+                let values = if app.smoothed {
+                    app.config.smoothing.apply(&values)
+                } else {
+                    values
+                };
+                let points = if app.chart {
+                    chart_points(&values, times, now, app.config.chart_window, log_scale)
+                } else {
+                    Vec::new()
+                };
+                if log_scale {
+                    (values.into_iter().map(log_value).collect(), points)
+                } else {
+                    (values, points)
+                }
+            };
+            let (sent, sent_points) = series(&data.sent, baseline.sent);
+            let (rec, rec_points) = series(&data.rec, baseline.rec);
+            let data = DisplayData {
+                sent,
+                rec,
+                sent_points,
+                rec_points,
+                ..Default::default()
+            };
+            (name.to_string(), data)
+        })
+        .collect();
+    app.net_interface_display = display;
+}
+
+// Running byte totals since the last reset, from raw samples or buckets
+// depending on the resolution
+fn cumulative_display(app: &App) -> HashMap<String, DisplayData> {
+    let now = unix_time();
+    // raw sample times are relative to startup
+    let started = now - app.started.elapsed().as_secs_f64();
+    let window = chart_window(app);
+    let resolution = app.resolution;
+    app.net_interface_graphs
+        .iter()
+        .filter(|(name, _)| is_cumulative(app, name))
+        .filter_map(|(name, data)| {
+            let (times, sent, rec): (Vec<f64>, Vec<u64>, Vec<u64>) = match resolution {
+                Resolution::Raw => (
+                    data.times.iter().map(|time| started + time).collect(),
+                    data.sent.clone(),
+                    data.rec.clone(),
+                ),
+                resolution => {
+                    let buckets = app.history.get(name)?.buckets(resolution)?;
+                    (
+                        buckets.iter().map(|bucket| bucket.start).collect(),
+                        buckets.iter().map(|bucket| bucket.sent).collect(),
+                        buckets.iter().map(|bucket| bucket.rec).collect(),
+                    )
+                }
+            };
+            let log_scale = app.log_scale.contains(name);
+            let series = |values: &[u64]| -> (Vec<u64>, Vec<(f64, f64)>) {
+                let mut total = 0u64;
+                let totals: Vec<(f64, u64)> = zip(&times, values)
+                    // buckets count once any part of them is after the reset
+                    .filter(|(time, _)| **time + resolution.seconds() > app.cumulative_since)
+                    .map(|(time, value)| {
+                        total += value;
+                        (*time, total)
+                    })
+                    .collect();
+                let totals = tail(&totals, DISPLAY_SAMPLES);
+                let points = if app.chart {
+                    totals
+                        .iter()
+                        .filter(|(time, _)| now - time <= window)
+                        .map(|(time, total)| {
+                            let total = *total as f64;
+                            let y = if log_scale { total.ln_1p() } else { total };
+                            (time - now, y)
+                        })
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                let values = totals
+                    .iter()
+                    .map(|(_, total)| if log_scale { log_value(*total) } else { *total })
+                    .collect();
+                (values, points)
+            };
+            let (sent, sent_points) = series(&sent);
+            let (rec, rec_points) = series(&rec);
+            let data = DisplayData {
+                sent,
+                rec,
+                sent_points,
+                rec_points,
+                ..Default::default()
+            };
+            Some((name.to_string(), data))
+        })
+        .collect()
+}
+
+// Bucket averages in bytes per second, baselines only apply to raw samples
+fn update_aggregated_display(app: &mut App, now: f64) {
+    let resolution = app.resolution;
+    let window = chart_window(app);
+    let display = app
+        .history
+        .iter()
+        .filter_map(|(name, history)| {
+            let buckets: Vec<&Bucket> = history.buckets(resolution)?.iter().collect();
+            let buckets = tail(&buckets, DISPLAY_SAMPLES);
+            let log_scale = app.log_scale.contains(name);
+            let series = |average: fn(&Bucket) -> f64, peak: fn(&Bucket) -> f64| {
+                let values: Vec<u64> = buckets.iter().map(|b| average(b) as u64).collect();
+                let values = if app.smoothed {
+                    app.config.smoothing.apply(&values)
+                } else {
+                    values
+                };
+                let y = |rate: f64| if log_scale { rate.ln_1p() } else { rate };
+                let (points, peaks) = if app.chart {
+                    let visible: Vec<usize> = (0..buckets.len())
+                        .filter(|i| now - buckets[*i].start <= window)
+                        .collect();
+                    let at = |i: &usize| buckets[*i].start - now;
+                    (
+                        visible
+                            .iter()
+                            .map(|i| (at(i), y(values[*i] as f64)))
+                            .collect(),
+                        visible
+                            .iter()
+                            .map(|i| (at(i), y(peak(buckets[*i]))))
+                            .collect(),
+                    )
+                } else {
+                    (Vec::new(), Vec::new())
+                };
+                let values = if log_scale {
+                    values.into_iter().map(log_value).collect()
+                } else {
+                    values
+                };
+                (values, points, peaks)
+            };
+            let (sent, sent_points, sent_peaks) = series(Bucket::sent_avg, |b| b.sent_peak);
+            let (rec, rec_points, rec_peaks) = series(Bucket::rec_avg, |b| b.rec_peak);
+            let data = DisplayData {
+                sent,
+                rec,
+                sent_points,
+                rec_points,
+                sent_peaks,
+                rec_peaks,
+            };
+            Some((name.to_string(), data))
+        })
+        .collect();
+    app.net_interface_display = display;
+}
+
+// Convert per-tick byte counts into (seconds ago, bytes per second) points
+// within the chart window
+fn chart_points(
+    values: &[u64],
+    times: &[f64],
+    now: f64,
+    window: f64,
+    log_scale: bool,
+) -> Vec<(f64, f64)> {
+    let times = tail(times, values.len());
+    let values = tail(values, times.len());
+    (0..values.len())
+        .filter(|i| now - times[*i] <= window)
+        .map(|i| {
+            let elapsed = if i > 0 {
+                times[i] - times[i - 1]
+            } else {
+                times.get(1).map(|next| next - times[0]).unwrap_or(0.0)
+            };
+            let rate = if elapsed > 0.0 {
+                values[i] as f64 / elapsed
+            } else {
+                0.0
+            };
+            let rate = if log_scale { rate.ln_1p() } else { rate };
+            (times[i] - now, rate)
+        })
+        .collect()
+}
+
+// Sparklines only take integers, so the logarithm is scaled up to keep
+// enough resolution between small values
+pub const LOG_SCALE: f64 = 1000.0;
+
+pub fn log_value(value: u64) -> u64 {
+    ((value as f64).ln_1p() * LOG_SCALE) as u64
+}
+
+fn create_interface_paragraph<'a>(
+    app: &App,
+    interface: &'a InterfaceData,
+    theme: &Theme,
+    selected: bool,
+) -> Paragraph<'a> {
+    let baseline = app.baseline.get(&interface.name);
+    let stats = app.stats.get(&interface.name);
+    let forecast = app.forecasts.get(&interface.name);
+    let container = app.containers.owner(&interface.name);
+    let rates = match baseline {
+        Some(baseline) => format!(
+            "Sent/Recieved: {} / {} (baseline {} / {})",
+            interface.sent.saturating_sub(baseline.sent),
+            interface.rec.saturating_sub(baseline.rec),
+            baseline.sent,
+            baseline.rec
+        ),
+        None => format!("Sent/Recieved: {} / {}", interface.sent, interface.rec),
+    };
+    let mut name = vec![Span::raw(match container {
+        Some(container) => format!(
+            "Interface: {} ({}, container {container})",
+            interface.name,
+            interface.kind.label()
+        ),
+        None => format!("Interface: {} ({})", interface.name, interface.kind.label()),
+    })];
+    if let Some(forecast) = forecast {
+        let mut projection = format!(
+            " | at {}, {} in ~{}",
+            format_rate(forecast.rate),
+            format_bytes(forecast.size as f64),
+            format_duration(forecast.target)
+        );
+        if let Some(quota) = forecast.quota {
+            projection.push_str(&format!(", quota used up in ~{}", format_duration(quota)));
+        }
+        name.push(Span::styled(projection, theme.alert));
+    }
+    let errors = interface.errors;
+    // new errors are what matters, old ones may be from long before startup
+    let error_style = if errors.rx_errors + errors.tx_errors > 0 {
+        theme.alert
+    } else {
+        theme.text
+    };
+    let mut lines = vec![
+        Line::from(name),
+        Line::from(rates),
         Line::from(format!(
             "Total Send/Recieved {} / {}",
             interface.sent_total, interface.rec_total
         )),
         Line::from(format!("Mac Address {}", interface.mac)),
+        Line::styled(
+            format!(
+                "Errors RX/TX: {} / {} (+{} / +{}) Dropped RX/TX: {} / {} (+{} / +{})",
+                errors.rx_errors_total,
+                errors.tx_errors_total,
+                errors.rx_errors,
+                errors.tx_errors,
+                errors.rx_dropped_total,
+                errors.tx_dropped_total,
+                errors.rx_dropped,
+                errors.tx_dropped
+            ),
+            error_style,
+        ),
     ];
+    if let Some(stats) = stats {
+        lines.push(Line::from(format!(
+            "Packets/s sent/recieved: {:.0} / {:.0} (peak {:.0} / {:.0}, total {} / {})",
+            stats.sent_packets.current(),
+            stats.rec_packets.current(),
+            stats.sent_packets.peak,
+            stats.rec_packets.peak,
+            interface.packets.sent_total,
+            interface.packets.rec_total
+        )));
+        let summary = |label: &str, rates: &RateStats| {
+            Line::from(format!(
+                "{label} peak/avg/min: {} / {} / {} (recent {} / {} / {})",
+                format_rate(rates.peak),
+                format_rate(rates.avg()),
+                format_rate(rates.min()),
+                format_rate(rates.window_peak()),
+                format_rate(rates.window_avg()),
+                format_rate(rates.window_min())
+            ))
+        };
+        lines.push(summary("Sent", &stats.sent));
+        lines.push(summary("Recieved", &stats.rec));
+    }
+    if app.debug {
+        // highlight raw counters when calibration changes what is displayed
+        let calibrated = interface.raw.sent_total != interface.sent_total
+            || interface.raw.rec_total != interface.rec_total;
+        let style = if calibrated { theme.alert } else { theme.text };
+        lines.push(Line::styled(
+            format!(
+                "Raw Sent/Recieved: {} / {} (total {} / {})",
+                interface.raw.sent,
+                interface.raw.rec,
+                interface.raw.sent_total,
+                interface.raw.rec_total
+            ),
+            style,
+        ));
+    }
     let text = Text::from(lines);
-    let block = Block::default().borders(Borders::ALL);
-    return Paragraph::new(text).block(block);
+    let block = if selected {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Thick)
+            .border_style(theme.title)
+    } else {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border)
+    };
+    Paragraph::new(text).style(theme.text).block(block)
 }
 
-fn create_interface_graph<'a>(name: &'a String, val: &'a Vec<u64>) -> Sparkline<'a> {
+pub struct ChartOptions {
+    pub log_scale: bool,
+    // values are byte totals rather than rates
+    pub cumulative: bool,
+    pub window: f64,
+    // draw with braille dots when the terminal supports them
+    pub marker: bool,
+    // top of the Y axis instead of following the data
+    pub max: Option<f64>,
+}
+
+// `reference` is a line drawn across the chart, e.g. a speed test result
+fn create_interface_chart<'a>(
+    title: String,
+    points: &'a [(f64, f64)],
+    peaks: &'a [(f64, f64)],
+    reference: &'a [(f64, f64)],
+    style: Style,
+    options: ChartOptions,
+    theme: &Theme,
+) -> Chart<'a> {
     let block = Block::default()
-        .title(name.to_string())
-        .borders(Borders::all());
-    return Sparkline::default().block(block).data(val);
+        .title(Span::styled(title, theme.title))
+        .borders(Borders::all())
+        .border_style(theme.border);
+    let floor = if options.log_scale { 1.0 } else { 1024.0 };
+    let max = match options.max {
+        Some(max) => max,
+        None => {
+            points
+                .iter()
+                .chain(peaks)
+                .chain(reference)
+                .map(|(_, y)| *y)
+                .fold(floor, f64::max)
+                * 1.1
+        }
+    };
+    let label = |y: f64| {
+        let value = if options.log_scale { y.exp_m1() } else { y };
+        if options.cumulative {
+            Span::styled(format_bytes(value), theme.text)
+        } else {
+            Span::styled(format_rate(value), theme.text)
+        }
+    };
+    let marker = if options.marker {
+        Marker::Braille
+    } else {
+        Marker::Dot
+    };
+    let mut datasets = Vec::new();
+    if !peaks.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .marker(marker)
+                .graph_type(GraphType::Line)
+                .style(style.add_modifier(Modifier::DIM))
+                .data(peaks),
+        );
+    }
+    if !reference.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .marker(marker)
+                .graph_type(GraphType::Line)
+                .style(theme.text.add_modifier(Modifier::DIM))
+                .data(reference),
+        );
+    }
+    datasets.push(
+        Dataset::default()
+            .marker(marker)
+            .graph_type(GraphType::Line)
+            .style(style)
+            .data(points),
+    );
+    Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .style(theme.border)
+                .bounds([-options.window, 0.0])
+                .labels(vec![
+                    Span::styled(format!("-{}", format_duration(options.window)), theme.text),
+                    Span::styled(
+                        format!("-{}", format_duration(options.window / 2.0)),
+                        theme.text,
+                    ),
+                    Span::styled("now", theme.text),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(theme.border)
+                .bounds([0.0, max])
+                .labels(vec![label(0.0), label(max / 2.0), label(max)]),
+        )
+}
+
+pub fn create_interface_graph<'a>(
+    title: String,
+    val: &'a [u64],
+    style: Style,
+    symbols: bar::Set,
+    graph_style: GraphStyle,
+    unicode: bool,
+    theme: &Theme,
+) -> Graph<'a> {
+    let block = Block::default()
+        .title(Span::styled(title, theme.title))
+        .borders(Borders::all())
+        .border_style(theme.border);
+    let symbols = match graph_style {
+        GraphStyle::Block if unicode => BLOCKS,
+        GraphStyle::Braille | GraphStyle::Dot => {
+            return Graph::Plot(Plot {
+                block,
+                data: val,
+                style,
+                graph_style,
+                ascii: !unicode,
+                max: None,
+            })
+        }
+        _ => symbols,
+    };
+    Graph::Sparkline(
+        Sparkline::default()
+            .block(block)
+            .style(style)
+            .bar_set(symbols)
+            .data(val),
+    )
 }
@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::App,
+    error::{Error, Result, ResultExt},
+    history::unix_time,
+    units::{format_clock, format_timestamp},
+};
+
+// Free text attached to an interface, either at a point in time or over a
+// range such as an outage
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub interface: String,
+    pub start: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<f64>,
+    pub text: String,
+}
+
+impl Note {
+    pub fn overlaps(&self, from: f64, to: f64) -> bool {
+        self.start <= to && self.end.unwrap_or(self.start) >= from
+    }
+
+    // "14:03:27 text" or "14:03:27-14:33:27 text", with the date when the
+    // note isn't from today
+    pub fn describe(&self) -> String {
+        let now = unix_time();
+        let time = |unix: f64| {
+            if now - unix < 24.0 * 3600.0 {
+                format_clock(unix)
+            } else {
+                format_timestamp(unix)
+            }
+        };
+        match self.end {
+            Some(end) => format!("{}-{} {}", time(self.start), format_clock(end), self.text),
+            None => format!("{} {}", time(self.start), self.text),
+        }
+    }
+}
+
+// Notes kept next to the history in the XDG data dir, one JSON object per
+// line. They are saved as soon as one is added.
+#[derive(Default)]
+pub struct NoteStore {
+    path: Option<PathBuf>,
+    pub notes: Vec<Note>,
+    pub error: Option<String>,
+}
+
+impl NoteStore {
+    pub fn open(path: Option<PathBuf>) -> NoteStore {
+        let (notes, error) = match path.as_deref().map(load).unwrap_or(Ok(Vec::new())) {
+            Ok(notes) => (notes, None),
+            Err(e) => (Vec::new(), Some(format!("{e:#}"))),
+        };
+        NoteStore { path, notes, error }
+    }
+
+    pub fn add(&mut self, note: Note) {
+        self.notes.push(note);
+        self.notes.sort_by(|a, b| a.start.total_cmp(&b.start));
+        self.error = match &self.path {
+            Some(path) => save(path, &self.notes).err().map(|e| format!("{e:#}")),
+            None => Some("no data directory, note not saved".to_string()),
+        };
+    }
+
+    pub fn for_interface<'a>(&'a self, name: &'a str) -> impl DoubleEndedIterator<Item = &'a Note> {
+        self.notes.iter().filter(move |note| note.interface == name)
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<Note>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(path)
+        .or_export(|| format!("failed to read notes {}", path.display()))?;
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .or_export(|| format!("failed to read notes {} line {}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+pub fn save(path: &Path, notes: &[Note]) -> Result<()> {
+    let mut contents = String::new();
+    for note in notes {
+        contents.push_str(&serde_json::to_string(note).or_export(|| "failed to encode note")?);
+        contents.push('\n');
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).or_export(|| format!("failed to create {}", dir.display()))?;
+    }
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, contents)
+        .or_export(|| format!("failed to write notes {}", temporary.display()))?;
+    fs::rename(&temporary, path).or_export(|| format!("failed to write notes {}", path.display()))
+}
+
+// A leading "-30m" makes the note cover the last 30 minutes, otherwise it
+// marks the current time
+pub fn parse_note(interface: &str, text: &str, now: f64) -> Result<Note> {
+    let text = text.trim();
+    let (start, end, text) = match text.strip_prefix('-') {
+        Some(rest) => {
+            let (range, text) = rest.split_once(' ').unwrap_or((rest, ""));
+            let seconds = parse_range(range)
+                .ok_or_else(|| Error::ui(format!("invalid note range \"-{range}\", e.g. -30m")))?;
+            (now - seconds, Some(now), text.trim())
+        }
+        None => (now, None, text),
+    };
+    if text.is_empty() {
+        return Err(Error::ui("note is empty"));
+    }
+    Ok(Note {
+        interface: interface.to_string(),
+        start,
+        end,
+        text: text.to_string(),
+    })
+}
+
+fn parse_range(range: &str) -> Option<f64> {
+    let unit = range.chars().last()?;
+    let number = &range[..range.len() - unit.len_utf8()];
+    let number: f64 = number.parse().ok().filter(|number: &f64| *number > 0.0)?;
+    let unit = match unit {
+        's' => 1.0,
+        'm' => 60.0,
+        'h' => 3600.0,
+        'd' => 86400.0,
+        _ => return None,
+    };
+    Some(number * unit)
+}
+
+// Attach a note to the selected interface
+pub fn add_note(app: &mut App, text: String) {
+    let Some(interface) = app.net_interfaces.get(app.selected) else {
+        return;
+    };
+    match parse_note(&interface.name, &text, unix_time()) {
+        Ok(note) => app.notes.add(note),
+        Err(e) => app.notes.error = Some(format!("{e:#}")),
+    }
+}
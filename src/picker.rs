@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    app::{update, App},
+    config::save_value,
+    iftype::InterfaceType,
+    networks::{show_interfaces, InterfaceData},
+    Action,
+};
+
+const HELP: &str = "space toggle · a all/none · enter start · s save and start · esc skip";
+
+// Checklist of the detected interfaces shown before the dashboard with
+// --pick, the checked ones are the only ones monitored
+pub struct Picker {
+    pub interfaces: Vec<(String, InterfaceType)>,
+    pub checked: HashSet<String>,
+    pub cursor: usize,
+    // interfaces that turn up later start checked when nothing was picked yet
+    check_new: bool,
+    pub error: Option<String>,
+}
+
+pub fn open_picker(app: &mut App) {
+    let monitor = &app.config.monitor;
+    app.picker = Some(Picker {
+        interfaces: Vec::new(),
+        checked: monitor.iter().cloned().collect(),
+        cursor: 0,
+        check_new: monitor.is_empty(),
+        error: None,
+    });
+}
+
+// Adds interfaces as the collector finds them, before the monitor filter
+pub fn update_picker(app: &mut App, interfaces: &[InterfaceData]) {
+    let config = &app.config;
+    let Some(picker) = &mut app.picker else {
+        return;
+    };
+    for interface in interfaces {
+        if picker
+            .interfaces
+            .iter()
+            .any(|(name, _)| *name == interface.name)
+        {
+            continue;
+        }
+        let kind = config
+            .interface_type(&interface.name)
+            .unwrap_or(interface.kind);
+        picker.interfaces.push((interface.name.to_string(), kind));
+        if picker.check_new {
+            picker.checked.insert(interface.name.to_string());
+        }
+    }
+    picker.interfaces.sort_by(|a, b| {
+        config
+            .order_key(&a.0, a.1)
+            .cmp(&config.order_key(&b.0, b.1))
+    });
+}
+
+pub fn edit_picker(app: &mut App, code: KeyCode) {
+    let Some(picker) = &mut app.picker else {
+        return;
+    };
+    match code {
+        KeyCode::Up | KeyCode::Char('k') => picker.cursor = picker.cursor.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => {
+            picker.cursor = (picker.cursor + 1).min(picker.interfaces.len().saturating_sub(1));
+        }
+        KeyCode::Char(' ') => {
+            if let Some((name, _)) = picker.interfaces.get(picker.cursor) {
+                if !picker.checked.remove(name) {
+                    picker.checked.insert(name.to_string());
+                }
+                picker.check_new = false;
+            }
+        }
+        KeyCode::Char('a') => {
+            if picker.checked.len() == picker.interfaces.len() {
+                picker.checked.clear();
+            } else {
+                picker.checked = picker
+                    .interfaces
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect();
+            }
+            picker.check_new = false;
+        }
+        KeyCode::Enter => apply_picker(app, false),
+        KeyCode::Char('s') => apply_picker(app, true),
+        KeyCode::Esc => app.picker = None,
+        KeyCode::Char('q') => update(app, Action::Quit),
+        _ => {}
+    }
+}
+
+fn apply_picker(app: &mut App, save: bool) {
+    let Some(picker) = &mut app.picker else {
+        return;
+    };
+    if picker.checked.is_empty() {
+        picker.error = Some("pick at least one interface".to_string());
+        return;
+    }
+    // everything checked monitors interfaces added later too
+    let monitor: Vec<String> = if picker.checked.len() == picker.interfaces.len() {
+        Vec::new()
+    } else {
+        picker
+            .interfaces
+            .iter()
+            .map(|(name, _)| name)
+            .filter(|name| picker.checked.contains(*name))
+            .cloned()
+            .collect()
+    };
+    if save {
+        let value = toml::Value::Array(monitor.iter().cloned().map(toml::Value::String).collect());
+        if let Err(e) = save_value("monitor", value) {
+            picker.error = Some(format!("{e:#}"));
+            return;
+        }
+    }
+    app.picker = None;
+    app.all_interfaces
+        .retain(|interface| monitor.is_empty() || monitor.contains(&interface.name));
+    app.config.monitor = monitor;
+    app.selected = 0;
+    app.scroll = 0;
+    show_interfaces(app);
+}
+
+// `height` of the area it's drawn in, the list scrolls to keep the cursor in it
+pub fn create_picker<'a>(app: &App, picker: &Picker, height: u16) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let unicode = app.capabilities.unicode;
+    let mut lines: Vec<Line> = picker
+        .interfaces
+        .iter()
+        .enumerate()
+        .map(|(i, (name, kind))| {
+            let mark = if picker.checked.contains(name) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let text = format!(" {mark} {} {name:<16} {}", kind.icon(unicode), kind.label());
+            if i == picker.cursor {
+                Line::styled(text, theme.title.add_modifier(Modifier::REVERSED))
+            } else {
+                Line::from(text)
+            }
+        })
+        .collect();
+    if picker.interfaces.is_empty() {
+        lines.push(Line::from(" Looking for interfaces..."));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::styled(format!(" {HELP}"), theme.border));
+    if let Some(error) = &picker.error {
+        lines.push(Line::styled(format!(" {error}"), theme.alert));
+    }
+    let block = Block::default()
+        .title(Span::styled("Interfaces to monitor", theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.title);
+    // borders and the help below the list
+    let rows = (height as usize).saturating_sub(5).max(1);
+    let offset = (picker.cursor + 1).saturating_sub(rows) as u16;
+    Paragraph::new(lines)
+        .style(theme.text)
+        .block(block)
+        .scroll((offset, 0))
+}
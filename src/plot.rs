@@ -0,0 +1,352 @@
+use ratatui::{
+    buffer::Buffer,
+    prelude::Rect,
+    style::Style,
+    symbols::bar,
+    widgets::{Block, Widget},
+};
+use serde::Deserialize;
+
+// How sparkline mode draws the TX/RX series
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphStyle {
+    // eighth height bars, the sparkline default
+    #[default]
+    Bar,
+    // two samples and four levels per cell
+    Braille,
+    // a single dot at the top of each sample
+    Dot,
+    // whole cells only, coarse but high contrast
+    Block,
+}
+
+impl GraphStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraphStyle::Bar => "bar",
+            GraphStyle::Braille => "braille",
+            GraphStyle::Dot => "dot",
+            GraphStyle::Block => "block",
+        }
+    }
+
+    pub fn next(&self) -> GraphStyle {
+        match self {
+            GraphStyle::Bar => GraphStyle::Braille,
+            GraphStyle::Braille => GraphStyle::Dot,
+            GraphStyle::Dot => GraphStyle::Block,
+            GraphStyle::Block => GraphStyle::Bar,
+        }
+    }
+
+    // Samples that fit in `width` columns
+    pub fn samples(&self, width: usize) -> usize {
+        match self {
+            GraphStyle::Braille => width * 2,
+            _ => width,
+        }
+    }
+}
+
+// Whether each interface gets separate TX and RX graphs or one graph with
+// both, `overlay` in the config
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayMode {
+    #[default]
+    Off,
+    // RX and TX rising from the bottom in their own colors
+    Overlay,
+    // RX above a midline, TX below it
+    Mirrored,
+}
+
+impl OverlayMode {
+    pub fn next(&self) -> OverlayMode {
+        match self {
+            OverlayMode::Off => OverlayMode::Overlay,
+            OverlayMode::Overlay => OverlayMode::Mirrored,
+            OverlayMode::Mirrored => OverlayMode::Off,
+        }
+    }
+
+    pub fn status(&self) -> Option<String> {
+        match self {
+            OverlayMode::Off => None,
+            OverlayMode::Overlay => Some("RX and TX overlaid".to_string()),
+            OverlayMode::Mirrored => Some("TX mirrored below RX".to_string()),
+        }
+    }
+}
+
+pub const BLOCKS: bar::Set = bar::Set {
+    full: "█",
+    seven_eighths: "█",
+    three_quarters: "█",
+    five_eighths: "█",
+    half: "█",
+    three_eighths: " ",
+    one_quarter: " ",
+    one_eighth: " ",
+    empty: " ",
+};
+
+// Dot patterns of the left and right braille column, top row first
+const BRAILLE_LEFT: [u32; 4] = [0x01, 0x02, 0x04, 0x40];
+const BRAILLE_RIGHT: [u32; 4] = [0x08, 0x10, 0x20, 0x80];
+
+// Sparkline replacement for the braille and dot styles. Like the sparkline,
+// values are scaled to the largest one and drawn from the left.
+pub struct Plot<'a> {
+    pub block: Block<'a>,
+    pub data: &'a [u64],
+    pub style: Style,
+    pub graph_style: GraphStyle,
+    // plain characters for terminals without unicode
+    pub ascii: bool,
+    // the value drawn full height, the largest in `data` when None
+    pub max: Option<u64>,
+}
+
+impl Widget for Plot<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.clone().render(area, buf);
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+        let max = self
+            .max
+            .unwrap_or_else(|| self.data.iter().copied().max().unwrap_or(0))
+            .max(1);
+        match self.graph_style {
+            GraphStyle::Braille => self.render_braille(inner, buf, max),
+            _ => self.render_dots(inner, buf, max),
+        }
+    }
+}
+
+impl Plot<'_> {
+    fn render_braille(&self, area: Rect, buf: &mut Buffer, max: u64) {
+        let rows = area.height as u64 * 4;
+        let mut cells = vec![0u32; area.width as usize * area.height as usize];
+        for (i, value) in self.data.iter().take(area.width as usize * 2).enumerate() {
+            let column = i / 2;
+            let dots = if i % 2 == 0 {
+                BRAILLE_LEFT
+            } else {
+                BRAILLE_RIGHT
+            };
+            let level = (value * rows).div_ceil(max);
+            // fill from the bottom row of dots up to the level
+            for dot in 0..level {
+                let row = rows - 1 - dot;
+                let cell = (row / 4) as usize * area.width as usize + column;
+                cells[cell] |= dots[(row % 4) as usize];
+            }
+        }
+        for (i, bits) in cells.into_iter().enumerate() {
+            if bits == 0 {
+                continue;
+            }
+            let x = area.x + (i % area.width as usize) as u16;
+            let y = area.y + (i / area.width as usize) as u16;
+            let symbol = char::from_u32(0x2800 + bits).unwrap_or(' ');
+            buf.get_mut(x, y).set_char(symbol).set_style(self.style);
+        }
+    }
+
+    fn render_dots(&self, area: Rect, buf: &mut Buffer, max: u64) {
+        let symbol = if self.ascii { '.' } else { '•' };
+        let rows = area.height as u64;
+        for (i, value) in self.data.iter().take(area.width as usize).enumerate() {
+            let level = (value * rows).div_ceil(max);
+            if level == 0 {
+                continue;
+            }
+            let y = area.y + (rows - level) as u16;
+            buf.get_mut(area.x + i as u16, y)
+                .set_char(symbol)
+                .set_style(self.style);
+        }
+    }
+}
+
+// RX and TX of one interface as bars in a single graph. Overlaid, the
+// shorter bar of each sample is drawn in front of the taller one. Both are
+// scaled to the largest value of either.
+pub struct Overlay<'a> {
+    pub block: Block<'a>,
+    pub sent: &'a [u64],
+    pub rec: &'a [u64],
+    pub sent_style: Style,
+    pub rec_style: Style,
+    pub sent_symbols: bar::Set,
+    pub rec_symbols: bar::Set,
+    pub mirrored: bool,
+    // the value drawn full height, the largest in the data when None
+    pub max: Option<u64>,
+}
+
+impl Widget for Overlay<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.clone().render(area, buf);
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+        let max = self
+            .max
+            .unwrap_or_else(|| self.sent.iter().chain(self.rec).copied().max().unwrap_or(0))
+            .max(1);
+        let columns = inner.width as usize;
+        let samples = self.sent.iter().zip(self.rec).take(columns).enumerate();
+        if self.mirrored {
+            let up = inner.height.div_ceil(2);
+            let down = inner.height - up;
+            for (i, (sent, rec)) in samples {
+                let x = inner.x + i as u16;
+                let rec = (rec * up as u64 * 8).div_ceil(max);
+                bar_up(
+                    buf,
+                    x,
+                    inner.y + up,
+                    up,
+                    rec,
+                    &self.rec_symbols,
+                    self.rec_style,
+                );
+                let sent = (sent * down as u64 * 8).div_ceil(max);
+                let symbol = self.sent_symbols.full;
+                bar_down(buf, x, inner.y + up, down, sent, symbol, self.sent_style);
+            }
+            return;
+        }
+        let rows = inner.height;
+        let bottom = inner.bottom();
+        for (i, (sent, rec)) in samples {
+            let x = inner.x + i as u16;
+            let sent = (
+                (sent * rows as u64 * 8).div_ceil(max),
+                &self.sent_symbols,
+                self.sent_style,
+            );
+            let rec = (
+                (rec * rows as u64 * 8).div_ceil(max),
+                &self.rec_symbols,
+                self.rec_style,
+            );
+            let (back, front) = if rec.0 >= sent.0 {
+                (rec, sent)
+            } else {
+                (sent, rec)
+            };
+            for (eighths, symbols, style) in [back, front] {
+                bar_up(buf, x, bottom, rows, eighths, symbols, style);
+            }
+        }
+    }
+}
+
+// Series stacked on top of one another, the first at the bottom, scaled so
+// the tallest column fills the height. All series end at the latest sample.
+pub struct Stacked<'a> {
+    pub block: Block<'a>,
+    pub layers: Vec<(&'a [u64], Style, bar::Set)>,
+}
+
+impl Widget for Stacked<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.clone().render(area, buf);
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+        let samples = self
+            .layers
+            .iter()
+            .map(|(values, ..)| values.len())
+            .max()
+            .unwrap_or(0)
+            .min(inner.width as usize);
+        // the cumulative value of each layer per column
+        let columns: Vec<Vec<u64>> = (0..samples)
+            .map(|i| {
+                let mut total = 0;
+                self.layers
+                    .iter()
+                    .map(|(values, ..)| {
+                        let index = values.len().checked_sub(samples - i);
+                        total += index.and_then(|i| values.get(i)).copied().unwrap_or(0);
+                        total
+                    })
+                    .collect()
+            })
+            .collect();
+        let max = columns
+            .iter()
+            .filter_map(|column| column.last())
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let rows = inner.height as u64;
+        for (i, column) in columns.iter().enumerate() {
+            let x = inner.x + i as u16;
+            // the highest first, the ones below drawn over it
+            for (top, (_, style, symbols)) in column.iter().zip(&self.layers).rev() {
+                let eighths = (top * rows * 8).div_ceil(max);
+                bar_up(
+                    buf,
+                    x,
+                    inner.bottom(),
+                    inner.height,
+                    eighths,
+                    symbols,
+                    *style,
+                );
+            }
+        }
+    }
+}
+
+// `eighths` of a cell filled upwards from the row above `bottom`
+fn bar_up(
+    buf: &mut Buffer,
+    x: u16,
+    bottom: u16,
+    rows: u16,
+    eighths: u64,
+    symbols: &bar::Set,
+    style: Style,
+) {
+    for row in 0..rows {
+        let symbol = match eighths.saturating_sub(row as u64 * 8).min(8) {
+            0 => return,
+            1 => symbols.one_eighth,
+            2 => symbols.one_quarter,
+            3 => symbols.three_eighths,
+            4 => symbols.half,
+            5 => symbols.five_eighths,
+            6 => symbols.three_quarters,
+            7 => symbols.seven_eighths,
+            _ => symbols.full,
+        };
+        buf.get_mut(x, bottom - 1 - row)
+            .set_symbol(symbol)
+            .set_style(style);
+    }
+}
+
+// Hanging down from `top`, whole cells only as there are no upper eighths,
+// rounded to the nearest
+fn bar_down(buf: &mut Buffer, x: u16, top: u16, rows: u16, eighths: u64, full: &str, style: Style) {
+    for row in 0..rows {
+        if eighths.saturating_sub(row as u64 * 8) < 4 {
+            return;
+        }
+        buf.get_mut(x, top + row).set_symbol(full).set_style(style);
+    }
+}
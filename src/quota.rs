@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::App,
+    error::{Result, ResultExt},
+    history::unix_time,
+    units::monthly_period,
+};
+
+// Traffic counted against an interface's allowance since its period started
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Usage {
+    pub period_start: f64,
+    pub used: u64,
+    // sent + recieved totals when last counted, so traffic while net-stat
+    // wasn't running is picked up on the next start
+    last_total: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StoredUsage {
+    boot_id: Option<String>,
+    interfaces: HashMap<String, Usage>,
+}
+
+// Where an interface stands in its current period, `overage` is when the
+// allowance runs out at the average rate of the period so far
+pub struct QuotaStatus {
+    pub quota: u64,
+    pub used: u64,
+    pub start: f64,
+    pub end: f64,
+    pub projected: u64,
+    pub overage: Option<f64>,
+}
+
+// Monthly usage of interfaces with a quota, saved under the XDG data dir
+pub struct Quotas {
+    path: Option<PathBuf>,
+    boot_id: Option<String>,
+    // the last session's usage, taken over when an interface shows up
+    stored: HashMap<String, Usage>,
+    same_boot: bool,
+    usage: HashMap<String, Usage>,
+    last_saved: Instant,
+    pub error: Option<String>,
+}
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+// projections from the first minutes of a period say little
+const MIN_ELAPSED: f64 = 3600.0;
+
+// Counters restart with the kernel, the boot id tells whether the saved
+// totals still apply
+fn boot_id() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/random/boot_id")
+        .ok()
+        .map(|id| id.trim().to_string())
+}
+
+fn load(path: &Path) -> Result<StoredUsage> {
+    if !path.exists() {
+        return Ok(StoredUsage::default());
+    }
+    let contents =
+        fs::read_to_string(path).or_export(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&contents).or_export(|| format!("failed to read {}", path.display()))
+}
+
+impl Quotas {
+    // `path` is None when no interface has a quota or there is no data dir,
+    // a damaged file is reported and replaced on the next save
+    pub fn open(path: Option<PathBuf>) -> Quotas {
+        let boot_id = boot_id();
+        let (stored, error) = match path.as_deref().map(load) {
+            Some(Ok(stored)) => (stored, None),
+            Some(Err(e)) => (StoredUsage::default(), Some(format!("{e:#}"))),
+            None => (StoredUsage::default(), None),
+        };
+        Quotas {
+            path,
+            same_boot: stored.boot_id == boot_id,
+            boot_id,
+            stored: stored.interfaces,
+            usage: HashMap::new(),
+            last_saved: Instant::now(),
+            error,
+        }
+    }
+
+    // Keeps the usage that was loaded but never writes it back, for instances
+    // mirroring one that does
+    pub fn stop_saving(&mut self) {
+        self.path = None;
+    }
+
+    pub fn used(&self, name: &str) -> Option<u64> {
+        self.usage.get(name).map(|usage| usage.used)
+    }
+
+    pub fn save(&mut self) {
+        self.last_saved = Instant::now();
+        let Some(path) = &self.path else {
+            return;
+        };
+        // interfaces that didn't show up this session keep their usage
+        let mut interfaces = self.stored.clone();
+        interfaces.extend(
+            self.usage
+                .iter()
+                .map(|(name, usage)| (name.clone(), *usage)),
+        );
+        let stored = StoredUsage {
+            boot_id: self.boot_id.clone(),
+            interfaces,
+        };
+        if let Err(e) = write(path, &stored) {
+            self.error = Some(format!("{e:#}"));
+        }
+    }
+}
+
+fn write(path: &Path, stored: &StoredUsage) -> Result<()> {
+    let json = serde_json::to_string(stored).or_export(|| "failed to encode usage")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).or_export(|| format!("failed to create {}", dir.display()))?;
+    }
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, json).or_export(|| format!("failed to write {}", temporary.display()))?;
+    fs::rename(&temporary, path).or_export(|| format!("failed to write {}", path.display()))
+}
+
+pub fn update_quotas(app: &mut App) {
+    // the totals of a remote session or recording belong to another machine
+    if app.is_remote() {
+        return;
+    }
+    let now = unix_time();
+    let quotas = &mut app.quotas;
+    for interface in &app.all_interfaces {
+        let name = &interface.name;
+        if app.config.quota(name).is_none() {
+            continue;
+        }
+        let total = interface.sent_total + interface.rec_total;
+        let (start, _) = monthly_period(now, app.config.quota_day(name));
+        let usage = match quotas.usage.get_mut(name) {
+            Some(usage) => {
+                usage.used += interface.sent + interface.rec;
+                usage
+            }
+            None => {
+                let usage = match quotas.stored.remove(name) {
+                    // after a reboot or a recreated interface the totals
+                    // start over
+                    Some(mut usage) => {
+                        usage.used += if quotas.same_boot && total >= usage.last_total {
+                            total - usage.last_total
+                        } else {
+                            total
+                        };
+                        usage
+                    }
+                    None => Usage {
+                        period_start: start,
+                        used: 0,
+                        last_total: total,
+                    },
+                };
+                quotas.usage.entry(name.to_string()).or_insert(usage)
+            }
+        };
+        if usage.period_start < start {
+            usage.period_start = start;
+            usage.used = 0;
+        }
+        usage.last_total = total;
+    }
+}
+
+// Saves periodically so a crash loses at most a minute, `force` is used when
+// exiting
+pub fn save_quotas(app: &mut App, force: bool) {
+    if force || app.quotas.last_saved.elapsed() >= SAVE_INTERVAL {
+        app.quotas.save();
+    }
+}
+
+pub fn quota_status(app: &App, name: &str) -> Option<QuotaStatus> {
+    let quota = app.config.quota(name)?;
+    let used = app.quotas.used(name)?;
+    let now = unix_time();
+    let (start, end) = monthly_period(now, app.config.quota_day(name));
+    let rate = used as f64 / (now - start).max(MIN_ELAPSED);
+    let projected = used + (rate * (end - now).max(0.0)) as u64;
+    let overage = if used >= quota {
+        Some(now)
+    } else {
+        let at = now + (quota - used) as f64 / rate;
+        (at < end).then_some(at)
+    };
+    Some(QuotaStatus {
+        quota,
+        used,
+        start,
+        end,
+        projected,
+        overage,
+    })
+}
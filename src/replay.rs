@@ -0,0 +1,228 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    collector::{Pipeline, Snapshot, Subscription},
+    error::{Error, Result, ResultExt},
+    history::unix_time,
+    wire,
+};
+
+// Playback speeds stepped through with < and >
+const SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+// how often the replay thread checks the keys and the clock
+const POLL: Duration = Duration::from_millis(50);
+
+// Writes every sample to a file for --replay, one JSON line each in the
+// format `net-stat serve` streams. Lines are written as they come so a
+// recording cut short by a crash is still readable.
+pub struct Recorder {
+    error: Arc<Mutex<Option<String>>>,
+    thread: JoinHandle<()>,
+}
+
+impl Recorder {
+    pub fn spawn(path: &Path, mut subscription: Subscription) -> Result<Recorder> {
+        let mut file =
+            File::create(path).or_export(|| format!("failed to create {}", path.display()))?;
+        let error = Arc::new(Mutex::new(None));
+        let status = error.clone();
+        let thread = thread::spawn(move || {
+            while let Some(snapshot) = subscription.blocking_recv() {
+                // when it was sampled, not when it got through the queue
+                let time = unix_time() - snapshot.taken.elapsed().as_secs_f64();
+                let written = wire::encode_at(&snapshot, time).and_then(|line| {
+                    file.write_all(format!("{line}\n").as_bytes())
+                        .or_export(|| "failed to write the recording")
+                });
+                if let Err(e) = written {
+                    *status.lock().unwrap() = Some(format!("recording stopped: {e:#}"));
+                    return;
+                }
+            }
+        });
+        Ok(Recorder { error, thread })
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    // Waits for the queued samples to be written once the pipeline is closed
+    pub fn finish(self) {
+        let _ = self.thread.join();
+    }
+}
+
+// A recorded sample and when it was taken, in seconds since the first one
+pub struct Frame {
+    pub offset: f64,
+    pub snapshot: Snapshot,
+}
+
+pub fn open(path: &Path) -> Result<Vec<Frame>> {
+    let text =
+        fs::read_to_string(path).or_backend(|| format!("failed to read {}", path.display()))?;
+    let mut frames = Vec::new();
+    let mut start = None;
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let wire = wire::parse(line)
+            .map_err(|e| e.context(format!("line {} of {}", i + 1, path.display())))?;
+        let start = *start.get_or_insert(wire.time);
+        frames.push(Frame {
+            offset: (wire.time - start).max(0.0),
+            snapshot: Snapshot {
+                taken: Instant::now(),
+                recorded: Some(wire.time),
+                interfaces: wire.interfaces.into_iter().map(Into::into).collect(),
+                sockets: Err(Error::backend("socket statistics are not recorded")),
+            },
+        });
+    }
+    if frames.is_empty() {
+        return Err(Error::backend(format!(
+            "{} holds no samples",
+            path.display()
+        )));
+    }
+    Ok(frames)
+}
+
+struct ReplayState {
+    // offset of the last sample played
+    position: f64,
+    length: f64,
+    speed: f64,
+    paused: bool,
+    // offset to jump to
+    seek: Option<f64>,
+    finished: bool,
+}
+
+// The playback position and what the keys change, shared with the thread
+// publishing the samples
+#[derive(Clone)]
+pub struct ReplayControl {
+    state: Arc<Mutex<ReplayState>>,
+}
+
+impl ReplayControl {
+    pub fn toggle_pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = !state.paused;
+    }
+
+    pub fn change_speed(&self, faster: bool) {
+        let mut state = self.state.lock().unwrap();
+        let current = SPEEDS
+            .iter()
+            .position(|speed| *speed >= state.speed)
+            .unwrap_or(SPEEDS.len() - 1);
+        let next = match faster {
+            true => (current + 1).min(SPEEDS.len() - 1),
+            false => current.saturating_sub(1),
+        };
+        state.speed = SPEEDS[next];
+    }
+
+    // Relative to the last sample played, clamped to the recording
+    pub fn seek(&self, seconds: f64) {
+        let mut state = self.state.lock().unwrap();
+        let from = state.seek.unwrap_or(state.position);
+        state.seek = Some((from + seconds).clamp(0.0, state.length));
+    }
+
+    pub fn status(&self) -> String {
+        let state = self.state.lock().unwrap();
+        if state.finished {
+            return format!("replay ended at {}", clock(state.length));
+        }
+        let mut status = format!(
+            "replay {} of {} at {}x",
+            clock(state.position),
+            clock(state.length),
+            state.speed
+        );
+        if state.paused {
+            status.push_str(", paused");
+        }
+        status
+    }
+}
+
+// e.g. 75 -> "1:15"
+fn clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// Publishes the frames into `pipeline` spaced as they were recorded. The
+// samples count as taken that far apart whatever the speed, so rates come out
+// as recorded, and a seek only leaves a jump in the graphs. The last sample
+// stays up once the recording ends.
+pub fn spawn(pipeline: &Pipeline, frames: Vec<Frame>) -> ReplayControl {
+    let control = ReplayControl {
+        state: Arc::new(Mutex::new(ReplayState {
+            position: 0.0,
+            length: frames.last().map(|frame| frame.offset).unwrap_or_default(),
+            speed: 1.0,
+            paused: false,
+            seek: None,
+            finished: false,
+        })),
+    };
+    let shared = control.state.clone();
+    let pipeline = pipeline.clone();
+    thread::spawn(move || {
+        let gap = |index: usize| match index {
+            0 => 0.0,
+            _ => frames[index].offset - frames[index - 1].offset,
+        };
+        let mut index = 0;
+        // recording time passed since the last sample played
+        let mut progress = 0.0;
+        let mut clock = Instant::now();
+        let mut taken = Instant::now();
+        while !pipeline.is_closed() {
+            let now = Instant::now();
+            let real = now.duration_since(clock).as_secs_f64();
+            clock = now;
+            let mut state = shared.lock().unwrap();
+            if let Some(target) = state.seek.take() {
+                index = frames
+                    .partition_point(|frame| frame.offset < target)
+                    .min(frames.len() - 1);
+                progress = gap(index);
+            } else if !state.paused && index < frames.len() {
+                progress += real * state.speed;
+            }
+            state.finished = index >= frames.len();
+            let due = !state.finished && progress >= gap(index);
+            if due {
+                state.position = frames[index].offset;
+            }
+            drop(state);
+
+            if !due {
+                thread::sleep(POLL);
+                continue;
+            }
+            taken += Duration::from_secs_f64(gap(index));
+            progress -= gap(index);
+            let mut snapshot = frames[index].snapshot.clone();
+            snapshot.taken = taken;
+            pipeline.publish(&snapshot);
+            index += 1;
+        }
+    });
+    control
+}
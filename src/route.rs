@@ -0,0 +1,234 @@
+use std::{
+    io,
+    net::{IpAddr, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    app::App,
+    error::{Result, ResultExt},
+    units::{format_bytes, format_rate},
+};
+
+// Where the kernel would send traffic for a destination, as `ip route get`
+// reports it
+pub struct Route {
+    pub destination: IpAddr,
+    pub interface: String,
+    pub gateway: Option<IpAddr>,
+    pub source: Option<IpAddr>,
+}
+
+// Names are resolved on their own thread, the route arrives with the ticks
+pub struct RouteLookup {
+    pub query: String,
+    // None while the lookup runs
+    pub route: Option<Result<Route>>,
+    running: Option<Receiver<Result<Route>>>,
+}
+
+pub fn resolve(destination: &str) -> io::Result<IpAddr> {
+    if let Ok(address) = destination.parse() {
+        return Ok(address);
+    }
+    (destination, 0)
+        .to_socket_addrs()?
+        .next()
+        .map(|address| address.ip())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address found"))
+}
+
+pub fn lookup_route(app: &mut App, query: String) {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return;
+    }
+    let (sender, receiver) = mpsc::channel();
+    let destination = query.clone();
+    thread::spawn(move || {
+        let route = resolve(&destination)
+            .and_then(route_get)
+            .or_backend(|| destination.clone());
+        let _ = sender.send(route);
+    });
+    app.route = Some(RouteLookup {
+        query,
+        route: None,
+        running: Some(receiver),
+    });
+}
+
+pub fn update_route(app: &mut App) {
+    let Some(lookup) = &mut app.route else {
+        return;
+    };
+    let Some(receiver) = &lookup.running else {
+        return;
+    };
+    let route = match receiver.try_recv() {
+        Ok(route) => route,
+        Err(TryRecvError::Empty) => return,
+        Err(TryRecvError::Disconnected) => {
+            lookup.running = None;
+            return;
+        }
+    };
+    lookup.running = None;
+    if let Ok(route) = &route {
+        if let Some(i) = app
+            .net_interfaces
+            .iter()
+            .position(|interface| interface.name == route.interface)
+        {
+            app.selected = i;
+        }
+    }
+    lookup.route = Some(route);
+}
+
+pub fn create_route_paragraph<'a>(app: &App, lookup: &RouteLookup) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let block = Block::default()
+        .title(Span::styled("Route lookup (esc to close)", theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    let route = match &lookup.route {
+        Some(Ok(route)) => route,
+        None => {
+            return Paragraph::new(Line::from(format!("Looking up {}", lookup.query)))
+                .style(theme.text)
+                .block(block)
+        }
+        Some(Err(e)) => {
+            return Paragraph::new(Line::styled(format!("{e:#}"), theme.alert))
+                .style(theme.text)
+                .block(block)
+        }
+    };
+    let destination = if lookup.query == route.destination.to_string() {
+        lookup.query.to_string()
+    } else {
+        format!("{} ({})", lookup.query, route.destination)
+    };
+    let mut path = format!("{destination} dev {}", route.interface);
+    if let Some(gateway) = route.gateway {
+        path.push_str(&format!(" via {gateway}"));
+    }
+    if let Some(source) = route.source {
+        path.push_str(&format!(" src {source}"));
+    }
+    let mut lines = vec![Line::from(path)];
+    let interface = app
+        .net_interfaces
+        .iter()
+        .find(|interface| interface.name == route.interface);
+    if let (Some(interface), Some(stats)) = (interface, app.stats.get(&route.interface)) {
+        lines.push(Line::from(format!(
+            "Sent/Recieved: {} / {} (total {} / {})",
+            format_rate(stats.sent.current()),
+            format_rate(stats.rec.current()),
+            format_bytes(interface.sent_total as f64),
+            format_bytes(interface.rec_total as f64)
+        )));
+    }
+    Paragraph::new(lines).style(theme.text).block(block)
+}
+
+#[cfg(target_os = "linux")]
+pub fn route_get(destination: IpAddr) -> io::Result<Route> {
+    linux::route_get(destination)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn route_get(_destination: IpAddr) -> io::Result<Route> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "route lookups are only available on Linux",
+    ))
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CStr;
+
+    use super::*;
+    use crate::netlink::{self, Socket};
+
+    const RTM_NEWROUTE: u16 = 24;
+    const RTM_GETROUTE: u16 = 26;
+    const RTA_DST: u16 = 1;
+    const RTA_OIF: u16 = 4;
+    const RTA_GATEWAY: u16 = 5;
+    const RTA_PREFSRC: u16 = 7;
+    const RTMSG_LEN: usize = 12;
+
+    // A single RTM_GETROUTE request, answered with the route the kernel
+    // picked for the destination
+    pub fn route_get(destination: IpAddr) -> io::Result<Route> {
+        let socket = Socket::open(libc::NETLINK_ROUTE)?;
+        let (family, address) = match destination {
+            IpAddr::V4(address) => (libc::AF_INET as u8, address.octets().to_vec()),
+            IpAddr::V6(address) => (libc::AF_INET6 as u8, address.octets().to_vec()),
+        };
+        // rtmsg: family, dst_len, then nothing else set
+        let mut payload = vec![0u8; RTMSG_LEN];
+        payload[0] = family;
+        payload[1] = (address.len() * 8) as u8;
+        payload.extend(netlink::attribute(RTA_DST, &address));
+        let flags = libc::NLM_F_REQUEST as u16;
+        socket.send(&netlink::request(RTM_GETROUTE, flags, 0, &payload))?;
+        let mut buffer = vec![0u8; 8 * 1024];
+        parse_reply(socket.recv(&mut buffer)?, destination)
+    }
+
+    fn parse_reply(reply: &[u8], destination: IpAddr) -> io::Result<Route> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed route reply");
+        let messages = netlink::messages(reply);
+        let message = messages.first().ok_or_else(invalid)?;
+        match message.kind {
+            RTM_NEWROUTE if message.payload.len() >= RTMSG_LEN => {}
+            kind if kind as libc::c_int == libc::NLMSG_ERROR => {
+                netlink::outcome(message.payload)?;
+                return Err(invalid());
+            }
+            _ => return Err(invalid()),
+        }
+
+        let mut route = Route {
+            destination,
+            interface: String::new(),
+            gateway: None,
+            source: None,
+        };
+        for (kind, payload) in netlink::attributes(&message.payload[RTMSG_LEN..]) {
+            match kind {
+                RTA_OIF if payload.len() >= 4 => {
+                    route.interface = interface_name(netlink::read_u32(payload, 0))?;
+                }
+                RTA_GATEWAY => route.gateway = netlink::address(payload),
+                RTA_PREFSRC => route.source = netlink::address(payload),
+                _ => {}
+            }
+        }
+        if route.interface.is_empty() {
+            return Err(invalid());
+        }
+        Ok(route)
+    }
+
+    fn interface_name(index: u32) -> io::Result<String> {
+        let mut name = [0 as libc::c_char; libc::IF_NAMESIZE];
+        let result = unsafe { libc::if_indextoname(index, name.as_mut_ptr()) };
+        if result.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let name = unsafe { CStr::from_ptr(name.as_ptr()) };
+        Ok(name.to_string_lossy().into_owned())
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use crate::{
+    alerts::AlertEngine,
+    config::Config,
+    error::{Error, Result},
+    history::unix_time,
+    import::{read_samples, ImportedSample},
+    units::{format_rate, format_timestamp},
+};
+
+const MB: f64 = 1024.0 * 1024.0;
+
+// Built-in traffic patterns at one sample per second, for checking rules
+// without recorded data
+fn demo_scenarios() -> Vec<(&'static str, Vec<ImportedSample>)> {
+    let scenario = |seconds: usize, rate: &dyn Fn(usize) -> (f64, f64)| {
+        let start = (unix_time() - seconds as f64).floor();
+        (0..seconds)
+            .map(|i| {
+                let (sent_rate, rec_rate) = rate(i);
+                ImportedSample {
+                    timestamp: start + i as f64,
+                    interface: "demo0".to_string(),
+                    sent: sent_rate as u64,
+                    rec: rec_rate as u64,
+                    sent_rate,
+                    rec_rate,
+                }
+            })
+            .collect()
+    };
+    vec![
+        ("idle", scenario(120, &|_| (2.0 * 1024.0, 8.0 * 1024.0))),
+        (
+            "burst",
+            scenario(120, &|i| {
+                if (60..65).contains(&i) {
+                    (MB, 50.0 * MB)
+                } else {
+                    (0.01 * MB, 0.1 * MB)
+                }
+            }),
+        ),
+        (
+            "sustained download",
+            scenario(300, &|i| {
+                if (30..270).contains(&i) {
+                    (0.5 * MB, 20.0 * MB)
+                } else {
+                    (0.01 * MB, 0.1 * MB)
+                }
+            }),
+        ),
+        (
+            "sustained upload",
+            scenario(300, &|i| {
+                if (30..270).contains(&i) {
+                    (15.0 * MB, 0.5 * MB)
+                } else {
+                    (0.01 * MB, 0.1 * MB)
+                }
+            }),
+        ),
+        (
+            "flapping",
+            scenario(180, &|i| {
+                if i / 10 % 2 == 1 {
+                    (0.1 * MB, 30.0 * MB)
+                } else {
+                    (0.01 * MB, 0.1 * MB)
+                }
+            }),
+        ),
+    ]
+}
+
+fn evaluate(config: &Config, label: &str, samples: &[ImportedSample]) -> Result<()> {
+    let mut engine = AlertEngine::from_config(&config.alerts)?;
+    let duration = match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) => last.timestamp - first.timestamp,
+        _ => 0.0,
+    };
+    println!("{label}: {} samples over {duration:.0}s", samples.len());
+    for sample in samples {
+        for event in engine.evaluate(
+            sample.timestamp,
+            &sample.interface,
+            sample.sent_rate,
+            sample.rec_rate,
+        ) {
+            let state = event.state.name();
+            println!(
+                "  {} {state:<8} {} on {}: {} (threshold {})",
+                format_timestamp(event.time),
+                event.rule,
+                event.interface,
+                format_rate(event.value),
+                format_rate(event.threshold)
+            );
+        }
+    }
+    for rule in engine.rules() {
+        match engine.fired_count(&rule.name) {
+            0 => println!("  \"{}\" never fired", rule.name),
+            1 => println!("  \"{}\" fired once", rule.name),
+            count => println!("  \"{}\" fired {count} times", rule.name),
+        }
+    }
+    Ok(())
+}
+
+// `net-stat rules test`, evaluates the configured rules against a CSV export
+// or, without a file, against the demo scenarios
+pub fn test_rules(file: Option<&Path>) -> Result<()> {
+    let config = Config::load()?;
+    if config.alerts.is_empty() {
+        return Err(Error::config(
+            "no alert rules configured, add [[alerts]] to the config",
+        ));
+    }
+    match file {
+        Some(file) => evaluate(&config, &file.display().to_string(), &read_samples(file)?),
+        None => demo_scenarios()
+            .iter()
+            .try_for_each(|(name, samples)| evaluate(&config, name, samples)),
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    app::App,
+    history::Resolution,
+    networks::{is_cumulative, log_value, LOG_SCALE},
+    units::{format_bytes, format_rate},
+};
+
+// Lowest scale a lock holds, an idle interface would otherwise draw any
+// traffic full height
+const MIN_SCALE: f64 = 1024.0;
+
+// Top of an interface's TX and RX graphs in bytes per second, held instead
+// of following the largest value shown so the graphs stop jumping. From
+// `y_max` in the config, or the observed peaks when locked with Y.
+#[derive(Clone, Copy)]
+pub struct ScaleLock {
+    pub sent: f64,
+    pub rec: f64,
+}
+
+// Locks the selected interface's scale at its peaks so far, or goes back to
+// following the data
+pub fn toggle_scale_lock(app: &mut App) {
+    let Some(interface) = app.net_interfaces.get(app.selected) else {
+        return;
+    };
+    let name = interface.name.to_string();
+    if app.scale_locks.remove(&name).is_some() {
+        return;
+    }
+    let lock = app.stats.get(&name).map_or(
+        ScaleLock {
+            sent: MIN_SCALE,
+            rec: MIN_SCALE,
+        },
+        |stats| ScaleLock {
+            sent: stats.sent.peak.max(MIN_SCALE),
+            rec: stats.rec.peak.max(MIN_SCALE),
+        },
+    );
+    app.scale_locks.insert(name, lock);
+}
+
+// The lock on `name`'s graphs, totals keep growing so they aren't held
+pub fn scale_lock(app: &App, name: &str) -> Option<ScaleLock> {
+    app.scale_locks
+        .get(name)
+        .copied()
+        .filter(|_| !is_cumulative(app, name))
+}
+
+// `rate` in the units the sparklines of `name` hold: bytes per sample at
+// the raw resolution, bucket averages per second otherwise
+pub fn sparkline_max(app: &App, name: &str, rate: f64) -> u64 {
+    let value = match app.resolution {
+        Resolution::Raw => rate * app.sample_interval,
+        _ => rate,
+    };
+    match app.log_scale.contains(name) {
+        true => log_value(value as u64),
+        false => value as u64,
+    }
+}
+
+// `rate` as the chart Y axis holds it
+pub fn chart_max(app: &App, name: &str, rate: f64) -> f64 {
+    match app.log_scale.contains(name) {
+        true => rate.ln_1p(),
+        false => rate,
+    }
+}
+
+// Latest and largest value of a sparkline's visible `values`, e.g.
+// " 1.2 MB/s (max 3.4 MB/s)", or the running total of a cumulative one
+pub fn value_label(app: &App, name: &str, values: &[u64]) -> String {
+    let (Some(last), Some(max)) = (values.last(), values.iter().max()) else {
+        return String::new();
+    };
+    if is_cumulative(app, name) {
+        return format!(" {}", format_bytes(unlogged(app, name, *last)));
+    }
+    format!(
+        " {} (max {})",
+        format_rate(sparkline_rate(app, name, *last)),
+        format_rate(sparkline_rate(app, name, *max))
+    )
+}
+
+// The inverse of sparkline_max
+fn sparkline_rate(app: &App, name: &str, value: u64) -> f64 {
+    let value = unlogged(app, name, value);
+    match app.resolution {
+        Resolution::Raw => value / app.sample_interval,
+        _ => value,
+    }
+}
+
+fn unlogged(app: &App, name: &str, value: u64) -> f64 {
+    match app.log_scale.contains(name) {
+        true => (value as f64 / LOG_SCALE).exp_m1(),
+        false => value as f64,
+    }
+}
+
+// Shown on the block of a locked graph
+pub fn scale_label(rate: f64) -> String {
+    format!(" [scale {}]", format_rate(rate))
+}
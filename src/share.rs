@@ -0,0 +1,130 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{app::App, theme::Theme, units::format_rate};
+
+// Fraction of the combined throughput an interface or user accounts for
+pub struct Share {
+    pub name: String,
+    pub rate: f64,
+    pub fraction: f64,
+}
+
+// Busiest first, entities without traffic are left out
+pub fn shares(rates: Vec<(String, f64)>) -> Vec<Share> {
+    let total: f64 = rates.iter().map(|(_, rate)| rate).sum();
+    let mut shares: Vec<Share> = rates
+        .into_iter()
+        .filter(|(_, rate)| *rate > 0.0)
+        .map(|(name, rate)| Share {
+            name,
+            rate,
+            fraction: rate / total,
+        })
+        .collect();
+    shares.sort_by(|a, b| b.rate.total_cmp(&a.rate));
+    shares
+}
+
+// Segments differ in fill pattern as well as color, so they stay apart on
+// monochrome terminals
+const SEGMENTS: [char; 4] = ['█', '▓', '▒', '░'];
+const SEGMENTS_ASCII: [char; 4] = ['#', '=', '+', '-'];
+
+fn segment_style(theme: &Theme, i: usize) -> (Style, usize) {
+    let style = match theme.palette.len() {
+        0 => theme.text,
+        len => theme.text.fg(theme.palette[i % len]),
+    };
+    (style, i % SEGMENTS.len())
+}
+
+// One line split into segments proportional to each share. Rounding is
+// carried over so the segments always add up to `width`.
+fn stacked_bar<'a>(shares: &[Share], width: usize, theme: &Theme, unicode: bool) -> Line<'a> {
+    let symbols = if unicode { SEGMENTS } else { SEGMENTS_ASCII };
+    let mut used = 0;
+    let mut covered = 0.0;
+    let spans = shares
+        .iter()
+        .enumerate()
+        .map(|(i, share)| {
+            covered += share.fraction;
+            let end = (covered * width as f64).round() as usize;
+            let cells = end.saturating_sub(used);
+            used = end;
+            let (style, symbol) = segment_style(theme, i);
+            Span::styled(symbols[symbol].to_string().repeat(cells), style)
+        })
+        .collect::<Vec<Span>>();
+    Line::from(spans)
+}
+
+fn create_share_paragraph<'a>(
+    app: &App,
+    title: &str,
+    shares: &[Share],
+    width: u16,
+) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let unicode = app.capabilities.unicode;
+    let mut lines = vec![
+        stacked_bar(shares, width.saturating_sub(2) as usize, theme, unicode),
+        Line::from(""),
+    ];
+    let symbols = if unicode { SEGMENTS } else { SEGMENTS_ASCII };
+    lines.extend(shares.iter().enumerate().map(|(i, share)| {
+        let (style, symbol) = segment_style(theme, i);
+        Line::from(vec![
+            Span::styled(format!("{} ", symbols[symbol]), style),
+            Span::raw(format!(
+                "{:<16} {:>12} {:>5.1}%",
+                share.name,
+                format_rate(share.rate),
+                share.fraction * 100.0
+            )),
+        ])
+    }));
+    if shares.is_empty() {
+        lines.push(Line::from("no traffic"));
+    }
+    let block = Block::default()
+        .title(Span::styled(title.to_string(), theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Paragraph::new(lines).style(theme.text).block(block)
+}
+
+// Throughput split by interface and by user. Loopback is left out as it
+// doesn't compete for link bandwidth.
+pub fn create_share_paragraphs<'a>(app: &App, width: u16) -> (Paragraph<'a>, Paragraph<'a>) {
+    let interfaces = shares(
+        app.net_interfaces
+            .iter()
+            .filter(|interface| interface.name != "lo")
+            .filter_map(|interface| {
+                let stats = app.stats.get(&interface.name)?;
+                let rate = stats.sent.current() + stats.rec.current();
+                Some((interface.name.to_string(), rate))
+            })
+            .collect(),
+    );
+    let users = shares(
+        app.user_traffic
+            .users
+            .iter()
+            .map(|user| (user.name.to_string(), user.sent_rate + user.rec_rate))
+            .collect(),
+    );
+    let users_title = match &app.user_traffic.error {
+        Some(error) => format!("Share by user ({error})"),
+        None => "Share by user (TCP)".to_string(),
+    };
+    (
+        create_share_paragraph(app, "Share by interface", &interfaces, width),
+        create_share_paragraph(app, &users_title, &users, width),
+    )
+}
@@ -0,0 +1,510 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, OpenOptions},
+    io::{Read, Write},
+    net::ToSocketAddrs,
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    prelude::Constraint,
+    style::Style,
+    text::Span,
+    widgets::{Block, Borders, Cell, Row, Table},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::App,
+    error::{Error, Result, ResultExt},
+    history::unix_time,
+    latency::ping_once,
+    route::resolve,
+    speedtest::{connect, HttpUrl},
+    theme::Theme,
+};
+
+// A probe whose results are scored against objectives, e.g.
+//   [[probes]]
+//   name = "website"
+//   kind = "http"
+//   target = "http://example.com/"
+//   latency = 300
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProbeConfig {
+    // defaults to the target
+    pub name: Option<String>,
+    // "ping", "http" or "dns"
+    pub kind: String,
+    // host or IP to ping, http:// URL to fetch, or name to resolve
+    pub target: String,
+    // seconds between probes
+    pub interval: f64,
+    // milliseconds a response may take to count as fast
+    pub latency: f64,
+    // percent of probes that have to succeed
+    pub availability_objective: f64,
+    // percent of successful probes that have to be fast
+    pub latency_objective: f64,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        ProbeConfig {
+            name: None,
+            kind: "ping".to_string(),
+            target: String::new(),
+            interval: 30.0,
+            latency: 200.0,
+            availability_objective: 99.9,
+            latency_objective: 99.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ProbeKind {
+    Ping,
+    Http,
+    Dns,
+}
+
+impl ProbeConfig {
+    pub fn validate(&self) -> Result<ProbeKind> {
+        let kind = match self.kind.as_str() {
+            "ping" => ProbeKind::Ping,
+            "http" => ProbeKind::Http,
+            "dns" => ProbeKind::Dns,
+            other => {
+                return Err(Error::config(format!(
+                    "unknown probe kind \"{other}\", expected ping, http or dns"
+                )))
+            }
+        };
+        if self.target.is_empty() {
+            return Err(Error::config(format!(
+                "{} probe without a target",
+                self.kind
+            )));
+        }
+        if kind == ProbeKind::Http {
+            HttpUrl::parse(&self.target)?;
+        }
+        if self.interval <= 0.0 {
+            return Err(Error::config(format!(
+                "probe interval for {} must be above 0",
+                self.target
+            )));
+        }
+        for objective in [self.availability_objective, self.latency_objective] {
+            if !(0.0..=100.0).contains(&objective) {
+                return Err(Error::config(format!(
+                    "probe objective {objective} for {} is not a percentage",
+                    self.target
+                )));
+            }
+        }
+        Ok(kind)
+    }
+}
+
+// Probe results of one minute, the unit the windows are summed from
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct Bucket {
+    minute: u64,
+    probes: u32,
+    ok: u32,
+    fast: u32,
+}
+
+// Rolling windows of the scorecard, the last one decides how long buckets
+// are kept
+pub const WINDOWS: [(&str, f64); 3] = [("1h", 3600.0), ("24h", 86400.0), ("7d", 604800.0)];
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Milliseconds the probe took, or why it failed
+type Outcome = Result<f64>;
+
+struct Probe {
+    config: ProbeConfig,
+    kind: ProbeKind,
+    name: String,
+    buckets: VecDeque<Bucket>,
+    running: Option<JoinHandle<Outcome>>,
+    next: f64,
+    last: Option<Outcome>,
+    // buckets loaded from disk are already stored, so the first result of
+    // this run starts a bucket of its own even within the same minute
+    recorded: bool,
+}
+
+// Availability and latency compliance of a probe over one window, in percent
+pub struct Compliance {
+    pub availability: f64,
+    pub fast: Option<f64>,
+}
+
+impl Probe {
+    fn record(&mut self, outcome: Outcome, now: f64) -> Option<Bucket> {
+        let minute = (now / 60.0) as u64;
+        let mut closed = None;
+        if !self.recorded
+            || self
+                .buckets
+                .back()
+                .is_none_or(|bucket| bucket.minute != minute)
+        {
+            closed = self.buckets.back().copied().filter(|_| self.recorded);
+            self.recorded = true;
+            self.buckets.push_back(Bucket {
+                minute,
+                probes: 0,
+                ok: 0,
+                fast: 0,
+            });
+        }
+        if let Some(bucket) = self.buckets.back_mut() {
+            bucket.probes += 1;
+            if let Ok(ms) = outcome {
+                bucket.ok += 1;
+                bucket.fast += (ms <= self.config.latency) as u32;
+            }
+        }
+        let oldest = ((now - WINDOWS[2].1) / 60.0) as u64;
+        while self
+            .buckets
+            .front()
+            .is_some_and(|bucket| bucket.minute < oldest)
+        {
+            self.buckets.pop_front();
+        }
+        self.last = Some(outcome);
+        closed
+    }
+
+    pub fn compliance(&self, window: f64, now: f64) -> Option<Compliance> {
+        let since = ((now - window) / 60.0) as u64;
+        let (probes, ok, fast) = self
+            .buckets
+            .iter()
+            .filter(|bucket| bucket.minute >= since)
+            .fold((0, 0, 0), |(probes, ok, fast), bucket| {
+                (probes + bucket.probes, ok + bucket.ok, fast + bucket.fast)
+            });
+        (probes > 0).then(|| Compliance {
+            availability: 100.0 * ok as f64 / probes as f64,
+            fast: (ok > 0).then(|| 100.0 * fast as f64 / ok as f64),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredBucket {
+    probe: String,
+    #[serde(flatten)]
+    bucket: Bucket,
+}
+
+// Probes configured under [[probes]] and their per-minute results, which
+// are appended to the data directory as each minute ends so the 24h and 7d
+// windows survive restarts
+#[derive(Default)]
+pub struct Slo {
+    probes: Vec<Probe>,
+    path: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+impl Slo {
+    pub fn open(configs: &[ProbeConfig], path: Option<PathBuf>) -> Result<Slo> {
+        let mut stored = match path.as_deref() {
+            Some(path) if !configs.is_empty() => load(path)?,
+            _ => HashMap::new(),
+        };
+        let probes = configs
+            .iter()
+            .map(|config| {
+                let kind = config.validate()?;
+                let name = config.name.clone().unwrap_or_else(|| config.target.clone());
+                Ok(Probe {
+                    kind,
+                    buckets: stored.remove(&name).unwrap_or_default(),
+                    name,
+                    config: config.clone(),
+                    running: None,
+                    next: 0.0,
+                    last: None,
+                    recorded: false,
+                })
+            })
+            .collect::<Result<Vec<Probe>>>()?;
+        let slo = Slo {
+            probes,
+            path,
+            error: None,
+        };
+        // compacted on every start, leaving out expired buckets
+        if let Some(path) = &slo.path {
+            if !slo.probes.is_empty() {
+                slo.rewrite(path)?;
+            }
+        }
+        Ok(slo)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+
+    // Stores the minute in progress, called before exiting
+    pub fn close(&mut self) {
+        let open: Vec<(String, Bucket)> = self
+            .probes
+            .iter()
+            .filter(|probe| probe.recorded)
+            .filter_map(|probe| Some((probe.name.clone(), *probe.buckets.back()?)))
+            .collect();
+        self.append(&open);
+    }
+
+    fn append(&mut self, buckets: &[(String, Bucket)]) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if buckets.is_empty() {
+            return;
+        }
+        let mut lines = String::new();
+        for (probe, bucket) in buckets {
+            let stored = StoredBucket {
+                probe: probe.to_string(),
+                bucket: *bucket,
+            };
+            if let Ok(line) = serde_json::to_string(&stored) {
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+        }
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(path))
+            .and_then(|mut file| file.write_all(lines.as_bytes()));
+        self.error = written
+            .err()
+            .map(|e| format!("failed to write {}: {e}", path.display()));
+    }
+
+    fn rewrite(&self, path: &Path) -> Result<()> {
+        let mut contents = String::new();
+        for probe in &self.probes {
+            for bucket in &probe.buckets {
+                let stored = StoredBucket {
+                    probe: probe.name.clone(),
+                    bucket: *bucket,
+                };
+                contents.push_str(
+                    &serde_json::to_string(&stored)
+                        .or_export(|| "failed to encode probe results")?,
+                );
+                contents.push('\n');
+            }
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).or_export(|| format!("failed to create {}", dir.display()))?;
+        }
+        fs::write(path, contents).or_export(|| format!("failed to write {}", path.display()))
+    }
+}
+
+// Buckets by probe name. The minute in progress may have been stored
+// before a restart and again later, such duplicates are summed.
+fn load(path: &Path) -> Result<HashMap<String, VecDeque<Bucket>>> {
+    let mut probes: HashMap<String, VecDeque<Bucket>> = HashMap::new();
+    if !path.exists() {
+        return Ok(probes);
+    }
+    let contents =
+        fs::read_to_string(path).or_export(|| format!("failed to read {}", path.display()))?;
+    let oldest = ((unix_time() - WINDOWS[2].1) / 60.0) as u64;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let stored: StoredBucket = serde_json::from_str(line)
+            .or_export(|| format!("failed to read {}", path.display()))?;
+        if stored.bucket.minute < oldest {
+            continue;
+        }
+        let buckets = probes.entry(stored.probe).or_default();
+        match buckets
+            .iter_mut()
+            .find(|bucket| bucket.minute == stored.bucket.minute)
+        {
+            Some(bucket) => {
+                bucket.probes += stored.bucket.probes;
+                bucket.ok += stored.bucket.ok;
+                bucket.fast += stored.bucket.fast;
+            }
+            None => buckets.push_back(stored.bucket),
+        }
+    }
+    for buckets in probes.values_mut() {
+        buckets
+            .make_contiguous()
+            .sort_by_key(|bucket| bucket.minute);
+    }
+    Ok(probes)
+}
+
+fn run_probe(kind: ProbeKind, target: &str) -> Outcome {
+    let started = Instant::now();
+    let elapsed = || started.elapsed().as_secs_f64() * 1000.0;
+    match kind {
+        ProbeKind::Ping => {
+            let address = resolve(target).or_backend(|| "no address")?;
+            match ping_once(address, PROBE_TIMEOUT).or_backend(|| "ping failed")? {
+                Some(rtt) => Ok(rtt),
+                None => Err(Error::backend("no reply")),
+            }
+        }
+        ProbeKind::Dns => match (target, 0).to_socket_addrs() {
+            Ok(addresses) if addresses.len() > 0 => Ok(elapsed()),
+            Ok(_) => Err(Error::backend("no addresses")),
+            Err(e) => Err(e).or_backend(|| "lookup failed"),
+        },
+        // time to the first byte of a successful response
+        ProbeKind::Http => {
+            let url = HttpUrl::parse(target)?;
+            let mut stream = connect(&url, PROBE_TIMEOUT).or_backend(|| "connect failed")?;
+            write!(
+                stream,
+                "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                url.path, url.host
+            )
+            .or_backend(|| "request failed")?;
+            let mut response = [0u8; 64];
+            let len = stream.read(&mut response).or_backend(|| "no response")?;
+            let ms = elapsed();
+            let head = String::from_utf8_lossy(&response[..len]);
+            match head.split(' ').nth(1) {
+                Some(status) if status.starts_with('2') || status.starts_with('3') => Ok(ms),
+                _ => Err(Error::backend(format!(
+                    "unexpected response \"{}\"",
+                    head.lines().next().unwrap_or_default()
+                ))),
+            }
+        }
+    }
+}
+
+// Collects finished probes and starts those that are due
+pub fn update_slo(app: &mut App) {
+    let now = unix_time();
+    let mut closed = Vec::new();
+    for probe in &mut app.slo.probes {
+        if let Some(running) = probe.running.take() {
+            if running.is_finished() {
+                let outcome = running
+                    .join()
+                    .unwrap_or_else(|_| Err(Error::backend("probe crashed")));
+                if let Some(bucket) = probe.record(outcome, now) {
+                    closed.push((probe.name.clone(), bucket));
+                }
+            } else {
+                probe.running = Some(running);
+                continue;
+            }
+        }
+        if now >= probe.next {
+            probe.next = now + probe.config.interval;
+            let (kind, target) = (probe.kind, probe.config.target.clone());
+            probe.running = Some(thread::spawn(move || run_probe(kind, &target)));
+        }
+    }
+    app.slo.append(&closed);
+}
+
+fn percent(value: Option<f64>, objective: f64, theme: &Theme) -> Cell<'static> {
+    match value {
+        Some(value) => {
+            let style = if value < objective {
+                theme.alert
+            } else {
+                Style::default()
+            };
+            Cell::from(format!("{value:.2}%")).style(style)
+        }
+        None => Cell::from("-"),
+    }
+}
+
+const SLO_WIDTHS: [Constraint; 8] = [
+    Constraint::Percentage(25),
+    Constraint::Length(14),
+    Constraint::Length(9),
+    Constraint::Length(9),
+    Constraint::Length(9),
+    Constraint::Length(9),
+    Constraint::Length(9),
+    Constraint::Length(9),
+];
+
+// One row per probe: the last result, then availability and the share of
+// fast responses over each window, highlighted when below the objective
+pub fn create_slo_table<'a>(slo: &Slo, theme: &Theme) -> Table<'a> {
+    let now = unix_time();
+    let rows: Vec<Row> = slo
+        .probes
+        .iter()
+        .map(|probe| {
+            let last = match &probe.last {
+                Some(Ok(ms)) => Cell::from(format!("{ms:.1} ms")),
+                Some(Err(e)) => Cell::from(format!("{e:#}")).style(theme.alert),
+                None => Cell::from("waiting"),
+            };
+            let compliance: Vec<Option<Compliance>> = WINDOWS
+                .iter()
+                .map(|(_, window)| probe.compliance(*window, now))
+                .collect();
+            let mut cells = vec![
+                Cell::from(format!("{} ({})", probe.name, probe.config.kind)),
+                last,
+            ];
+            cells.extend(compliance.iter().map(|compliance| {
+                percent(
+                    compliance.as_ref().map(|c| c.availability),
+                    probe.config.availability_objective,
+                    theme,
+                )
+            }));
+            cells.extend(compliance.iter().map(|compliance| {
+                percent(
+                    compliance.as_ref().and_then(|c| c.fast),
+                    probe.config.latency_objective,
+                    theme,
+                )
+            }));
+            Row::new(cells)
+        })
+        .collect();
+    let title = match (&slo.error, slo.probes.is_empty()) {
+        (Some(error), _) => format!("SLOs ({error})"),
+        (None, true) => "SLOs (no [[probes]] configured)".to_string(),
+        (None, false) => {
+            "SLOs (availability, then responses within the latency target)".to_string()
+        }
+    };
+    let block = Block::default()
+        .title(Span::styled(title, theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    let mut header = vec!["Probe".to_string(), "Last".to_string()];
+    header.extend(WINDOWS.iter().map(|(name, _)| format!("up {name}")));
+    header.extend(WINDOWS.iter().map(|(name, _)| format!("fast {name}")));
+    Table::new(rows)
+        .header(Row::new(header).style(theme.title))
+        .style(theme.text)
+        .block(block)
+        .widths(&SLO_WIDTHS)
+}
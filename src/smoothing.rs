@@ -0,0 +1,79 @@
+use serde::Deserialize;
+
+// Smoothing applied to graph series before they are rendered
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "method", rename_all = "lowercase")]
+pub enum Smoothing {
+    // simple moving average over the last `window` samples
+    Sma {
+        #[serde(default = "default_window")]
+        window: usize,
+    },
+    // exponentially weighted moving average, higher alpha follows the raw
+    // series more closely
+    Ewma {
+        #[serde(default = "default_alpha")]
+        alpha: f64,
+    },
+}
+
+fn default_window() -> usize {
+    5
+}
+
+fn default_alpha() -> f64 {
+    0.3
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Smoothing::Ewma {
+            alpha: default_alpha(),
+        }
+    }
+}
+
+impl Smoothing {
+    pub fn apply(&self, values: &[u64]) -> Vec<u64> {
+        match *self {
+            Smoothing::Sma { window } => {
+                let window = window.max(1);
+                let mut sum = 0u64;
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        sum += value;
+                        if i >= window {
+                            sum -= values[i - window];
+                        }
+                        sum / (i + 1).min(window) as u64
+                    })
+                    .collect()
+            }
+            Smoothing::Ewma { alpha } => {
+                let alpha = alpha.clamp(0.0, 1.0);
+                let mut average: Option<f64> = None;
+                values
+                    .iter()
+                    .map(|value| {
+                        let value = *value as f64;
+                        let next = match average {
+                            Some(average) => alpha * value + (1.0 - alpha) * average,
+                            None => value,
+                        };
+                        average = Some(next);
+                        next.round() as u64
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Smoothing::Sma { window } => format!("SMA({window})"),
+            Smoothing::Ewma { alpha } => format!("EWMA({alpha})"),
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::{fs, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::{
+    app::App,
+    error::{Result, ResultExt},
+    history::{unix_time, Bucket, Resolution},
+    networks::InterfaceData,
+    speedtest::Direction,
+    stats::RateStats,
+    units::format_timestamp,
+};
+
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    time: f64,
+    started: f64,
+    interfaces: Vec<InterfaceSnapshot<'a>>,
+    alerts_firing: usize,
+    canary: Vec<CanarySnapshot<'a>>,
+}
+
+// A canary transfer next to the interface's passive rate before it started,
+// both in bytes per second
+#[derive(Serialize)]
+struct CanarySnapshot<'a> {
+    time: f64,
+    direction: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interface: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    passive_rate: f64,
+}
+
+#[derive(Serialize)]
+struct InterfaceSnapshot<'a> {
+    name: &'a str,
+    mac: String,
+    sent: u64,
+    rec: u64,
+    sent_total: u64,
+    rec_total: u64,
+    rx_errors_total: u64,
+    tx_errors_total: u64,
+    rx_dropped_total: u64,
+    tx_dropped_total: u64,
+    sent_packets_total: u64,
+    rec_packets_total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<StatsSnapshot>,
+    firing: Vec<&'a str>,
+    notes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    samples: Vec<SampleSnapshot>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    history: Vec<HistorySnapshot>,
+}
+
+#[derive(Serialize)]
+struct HistorySnapshot {
+    resolution: &'static str,
+    buckets: Vec<BucketSnapshot>,
+}
+
+#[derive(Serialize)]
+struct StatsSnapshot {
+    sent: RatesSnapshot,
+    rec: RatesSnapshot,
+    sent_packets: RatesSnapshot,
+    rec_packets: RatesSnapshot,
+    sent_bytes: u64,
+    rec_bytes: u64,
+}
+
+// bytes or packets per second
+#[derive(Serialize)]
+struct RatesSnapshot {
+    current: f64,
+    peak: f64,
+    avg: f64,
+    min: f64,
+    window_peak: f64,
+    window_avg: f64,
+    window_min: f64,
+}
+
+impl From<&RateStats> for RatesSnapshot {
+    fn from(rates: &RateStats) -> Self {
+        RatesSnapshot {
+            current: rates.current(),
+            peak: rates.peak,
+            avg: rates.avg(),
+            min: rates.min(),
+            window_peak: rates.window_peak(),
+            window_avg: rates.window_avg(),
+            window_min: rates.window_min(),
+        }
+    }
+}
+
+// bytes and packets moved during the sample ending at `time`
+#[derive(Serialize)]
+struct SampleSnapshot {
+    time: f64,
+    sent: u64,
+    rec: u64,
+    sent_packets: u64,
+    rec_packets: u64,
+}
+
+#[derive(Serialize)]
+struct BucketSnapshot {
+    start: f64,
+    sent: u64,
+    rec: u64,
+    elapsed: f64,
+    sent_peak: f64,
+    rec_peak: f64,
+}
+
+impl From<&Bucket> for BucketSnapshot {
+    fn from(bucket: &Bucket) -> Self {
+        BucketSnapshot {
+            start: bucket.start,
+            sent: bucket.sent,
+            rec: bucket.rec,
+            elapsed: bucket.elapsed,
+            sent_peak: bucket.sent_peak,
+            rec_peak: bucket.rec_peak,
+        }
+    }
+}
+
+const RESOLUTIONS: [Resolution; 3] = [
+    Resolution::Minute,
+    Resolution::FiveMinutes,
+    Resolution::Hour,
+];
+
+fn interface_snapshot<'a>(
+    app: &'a App,
+    interface: &'a InterfaceData,
+    started: f64,
+    with_history: bool,
+) -> InterfaceSnapshot<'a> {
+    let name = interface.name.as_str();
+    let samples = app
+        .net_interface_graphs
+        .get(name)
+        .filter(|_| with_history)
+        .map(|graph| {
+            (0..graph.times.len())
+                .map(|i| SampleSnapshot {
+                    time: started + graph.times[i],
+                    sent: graph.sent[i],
+                    rec: graph.rec[i],
+                    sent_packets: graph.sent_packets[i],
+                    rec_packets: graph.rec_packets[i],
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let history = app
+        .history
+        .get(name)
+        .filter(|_| with_history)
+        .map(|history| {
+            RESOLUTIONS
+                .iter()
+                .filter_map(|resolution| {
+                    let buckets = history.buckets(*resolution)?;
+                    Some(HistorySnapshot {
+                        resolution: resolution.name(),
+                        buckets: buckets.iter().map(Into::into).collect(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let stats = app.stats.get(name).map(|stats| StatsSnapshot {
+        sent: (&stats.sent).into(),
+        rec: (&stats.rec).into(),
+        sent_packets: (&stats.sent_packets).into(),
+        rec_packets: (&stats.rec_packets).into(),
+        sent_bytes: stats.sent_bytes,
+        rec_bytes: stats.rec_bytes,
+    });
+    InterfaceSnapshot {
+        name,
+        mac: interface.mac.to_string(),
+        sent: interface.sent,
+        rec: interface.rec,
+        sent_total: interface.sent_total,
+        rec_total: interface.rec_total,
+        rx_errors_total: interface.errors.rx_errors_total,
+        tx_errors_total: interface.errors.tx_errors_total,
+        rx_dropped_total: interface.errors.rx_dropped_total,
+        tx_dropped_total: interface.errors.tx_dropped_total,
+        sent_packets_total: interface.packets.sent_total,
+        rec_packets_total: interface.packets.rec_total,
+        stats,
+        firing: app.alerts.firing(name),
+        notes: app
+            .notes
+            .for_interface(name)
+            .map(|note| note.describe())
+            .collect(),
+        samples,
+        history,
+    }
+}
+
+// raw sample times are relative to startup
+fn started(app: &App, now: f64) -> f64 {
+    now - app.started.elapsed().as_secs_f64()
+}
+
+fn snapshot(app: &App, now: f64) -> Snapshot<'_> {
+    let started = started(app, now);
+    let interfaces = app
+        .all_interfaces
+        .iter()
+        .map(|interface| interface_snapshot(app, interface, started, true))
+        .collect();
+    let canary = app
+        .canary
+        .results
+        .iter()
+        .map(|result| CanarySnapshot {
+            time: result.time,
+            direction: match result.direction {
+                Direction::Download => "download",
+                Direction::Upload => "upload",
+            },
+            interface: result.interface.as_deref(),
+            rate: result.rate.as_ref().ok().copied(),
+            error: result.rate.as_ref().err().map(|e| format!("{e:#}")),
+            passive_rate: result.passive,
+        })
+        .collect();
+    Snapshot {
+        time: now,
+        started,
+        interfaces,
+        alerts_firing: app.alerts.active(),
+        canary,
+    }
+}
+
+// e.g. net-stat-snapshot-2024-05-01_140327.json
+fn snapshot_path(now: f64) -> PathBuf {
+    let stamp = format_timestamp(now).replace(' ', "_").replace(':', "");
+    PathBuf::from(format!("net-stat-snapshot-{stamp}.json"))
+}
+
+pub fn write_snapshot(app: &App) -> Result<PathBuf> {
+    let now = unix_time();
+    let path = snapshot_path(now);
+    let json = serde_json::to_string_pretty(&snapshot(app, now))
+        .or_export(|| "failed to encode snapshot")?;
+    fs::write(&path, json).or_export(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+// The latest sample, stats and alerts of every interface, without history
+pub fn interfaces_json(app: &App) -> Result<String> {
+    let started = started(app, unix_time());
+    let interfaces: Vec<InterfaceSnapshot> = app
+        .all_interfaces
+        .iter()
+        .map(|interface| interface_snapshot(app, interface, started, false))
+        .collect();
+    serde_json::to_string(&interfaces).or_export(|| "failed to encode interfaces")
+}
+
+// One interface with its raw samples and history buckets, None when there
+// is no such interface
+pub fn history_json(app: &App, name: &str) -> Option<Result<String>> {
+    let interface = app
+        .all_interfaces
+        .iter()
+        .find(|interface| interface.name == name)?;
+    let snapshot = interface_snapshot(app, interface, started(app, unix_time()), true);
+    Some(serde_json::to_string(&snapshot).or_export(|| "failed to encode history"))
+}
+
+// Dump the current state to the working directory, the outcome is shown in
+// the status line
+pub fn save_snapshot(app: &mut App) {
+    app.file_status = Some(match write_snapshot(app) {
+        Ok(path) => format!("snapshot saved to {}", path.display()),
+        Err(e) => format!("{e:#}"),
+    });
+}
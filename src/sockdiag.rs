@@ -0,0 +1,88 @@
+use std::{io, net::SocketAddr};
+
+// Per-socket byte counters for TCP sockets, read through the kernel's
+// sock_diag netlink interface (INET_DIAG with tcp_info)
+#[derive(Clone)]
+pub struct SocketBytes {
+    pub inode: u64,
+    pub uid: u32,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    // TCP_ESTABLISHED and so on, as numbered in the kernel
+    pub state: u8,
+    pub sent: u64,
+    pub rec: u64,
+}
+
+#[cfg(target_os = "linux")]
+pub fn tcp_socket_bytes() -> io::Result<Vec<SocketBytes>> {
+    let mut socket = crate::netlink::Socket::open(libc::NETLINK_SOCK_DIAG)?;
+    let mut sockets = diag::dump(&mut socket, libc::AF_INET as u8)?;
+    sockets.extend(diag::dump(&mut socket, libc::AF_INET6 as u8)?);
+    Ok(sockets)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn tcp_socket_bytes() -> io::Result<Vec<SocketBytes>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "socket statistics are only available on Linux",
+    ))
+}
+
+#[cfg(target_os = "linux")]
+mod diag {
+    use super::*;
+    use crate::netlink::{self, Socket};
+
+    const SOCK_DIAG_BY_FAMILY: u16 = 20;
+    const INET_DIAG_INFO: u16 = 2;
+    const INET_DIAG_REQ_LEN: usize = 56;
+    const INET_DIAG_MSG_LEN: usize = 72;
+    // offsets of tcpi_bytes_acked and tcpi_bytes_received in struct tcp_info
+    const TCPI_BYTES_ACKED: usize = 120;
+    const TCPI_BYTES_RECEIVED: usize = 128;
+
+    pub fn dump(socket: &mut Socket, family: u8) -> io::Result<Vec<SocketBytes>> {
+        // inet_diag_req_v2: family, protocol, extensions, pad, states
+        let mut request = [0u8; INET_DIAG_REQ_LEN];
+        request[0] = family;
+        request[1] = libc::IPPROTO_TCP as u8;
+        request[2] = 1 << (INET_DIAG_INFO - 1);
+        request[4..8].copy_from_slice(&u32::MAX.to_ne_bytes());
+        let mut sockets = Vec::new();
+        socket.dump(SOCK_DIAG_BY_FAMILY, &request, |message| {
+            sockets.extend(parse_diag_message(message.payload));
+        })?;
+        Ok(sockets)
+    }
+
+    fn parse_diag_message(message: &[u8]) -> Option<SocketBytes> {
+        if message.len() < INET_DIAG_MSG_LEN {
+            return None;
+        }
+        // inet_diag_sockid: ports are big endian, addresses in network order
+        let local_port = u16::from_be_bytes([message[4], message[5]]);
+        let remote_port = u16::from_be_bytes([message[6], message[7]]);
+        let len = match message[0] as libc::c_int {
+            libc::AF_INET => 4,
+            _ => 16,
+        };
+        let mut socket = SocketBytes {
+            local: SocketAddr::new(netlink::address(&message[8..8 + len])?, local_port),
+            remote: SocketAddr::new(netlink::address(&message[24..24 + len])?, remote_port),
+            uid: netlink::read_u32(message, 64),
+            inode: netlink::read_u32(message, 68) as u64,
+            state: message[1],
+            sent: 0,
+            rec: 0,
+        };
+        for (kind, payload) in netlink::attributes(&message[INET_DIAG_MSG_LEN..]) {
+            if kind == INET_DIAG_INFO && payload.len() >= TCPI_BYTES_RECEIVED + 8 {
+                socket.sent = netlink::read_u64(payload, TCPI_BYTES_ACKED);
+                socket.rec = netlink::read_u64(payload, TCPI_BYTES_RECEIVED);
+            }
+        }
+        Some(socket)
+    }
+}
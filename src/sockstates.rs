@@ -0,0 +1,108 @@
+use ratatui::{
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+use crate::{app::App, error::Result, layout::inline_sparkline, sockdiag::SocketBytes};
+
+// The states shown and their number in the kernel's enum, the ones that pile
+// up when something goes wrong
+pub const STATES: [(u8, &str); 4] = [
+    (1, "ESTABLISHED"),
+    (6, "TIME_WAIT"),
+    (8, "CLOSE_WAIT"),
+    (3, "SYN_RECV"),
+];
+// counts kept per state for the trend sparklines
+const SAMPLES: usize = 60;
+const SPARK_WIDTH: usize = 10;
+// a count that rose this many samples in a row is drawn as an alert, the
+// shape of a leak or a TIME_WAIT explosion
+const GROWING: usize = 10;
+
+// TCP sockets by state at every sample, counted from the sockets the
+// backend reports
+#[derive(Default)]
+pub struct SocketStates {
+    // why there are no counts, e.g. on a remote session
+    pub error: Option<String>,
+    // per entry of STATES, oldest first
+    pub counts: [Vec<u64>; 4],
+}
+
+impl SocketStates {
+    pub fn update(&mut self, sockets: &Result<Vec<SocketBytes>>) {
+        let sockets = match sockets {
+            Ok(sockets) => sockets,
+            Err(e) => {
+                self.error = Some(format!("{e:#}"));
+                return;
+            }
+        };
+        self.error = None;
+        for ((state, _), counts) in STATES.iter().zip(&mut self.counts) {
+            let count = sockets
+                .iter()
+                .filter(|socket| socket.state == *state)
+                .count();
+            counts.push(count as u64);
+            if counts.len() > SAMPLES {
+                counts.remove(0);
+            }
+        }
+    }
+
+    // The latest count of STATES[index]
+    pub fn count(&self, index: usize) -> u64 {
+        self.counts[index].last().copied().unwrap_or_default()
+    }
+
+    pub fn growing(&self, index: usize) -> bool {
+        let counts = &self.counts[index];
+        counts.len() > GROWING
+            && counts[counts.len() - GROWING - 1..]
+                .windows(2)
+                .all(|pair| pair[1] > pair[0])
+    }
+}
+
+pub fn update_socket_states(app: &mut App, sockets: &Result<Vec<SocketBytes>>) {
+    app.socket_states.update(sockets);
+}
+
+pub fn toggle_socket_strip(app: &mut App) {
+    app.socket_strip = !app.socket_strip;
+}
+
+pub fn socket_strip_height(app: &App) -> u16 {
+    app.socket_strip as u16
+}
+
+// e.g. "ESTABLISHED 42 ▁▂▂▃ | TIME_WAIT 1203 ▁▃▅█ | CLOSE_WAIT 3 ▁▁▁▁ | .."
+pub fn create_socket_strip<'a>(app: &App) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let states = &app.socket_states;
+    if let Some(error) = &states.error {
+        return Paragraph::new(format!("Socket states unavailable: {error}")).style(theme.text);
+    }
+    let mut spans = Vec::new();
+    for (index, (_, name)) in STATES.iter().enumerate() {
+        if index > 0 {
+            spans.push(Span::raw(" | "));
+        }
+        let style = if states.growing(index) {
+            theme.alert
+        } else {
+            theme.text
+        };
+        spans.push(Span::styled(
+            format!("{name} {} ", states.count(index)),
+            style,
+        ));
+        spans.push(Span::styled(
+            inline_sparkline(&states.counts[index], SPARK_WIDTH, app.capabilities.unicode),
+            theme.tx_graph,
+        ));
+    }
+    Paragraph::new(Line::from(spans)).style(theme.text)
+}
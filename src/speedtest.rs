@@ -0,0 +1,227 @@
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SpeedTestConfig {
+    // plain http:// URL of a large file, fetched repeatedly to load the
+    // download direction
+    pub download: Option<String>,
+    // http:// URL accepting POST bodies, for the upload direction
+    pub upload: Option<String>,
+    // parallel connections per direction
+    pub streams: usize,
+}
+
+impl Default for SpeedTestConfig {
+    fn default() -> Self {
+        SpeedTestConfig {
+            download: None,
+            upload: None,
+            streams: 4,
+        }
+    }
+}
+
+impl SpeedTestConfig {
+    pub fn validate(&self) -> Result<()> {
+        for url in self.download.iter().chain(&self.upload) {
+            HttpUrl::parse(url)?;
+        }
+        if self.streams == 0 {
+            return Err(Error::config("speed test streams must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpUrl {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl HttpUrl {
+    pub fn parse(url: &str) -> Result<HttpUrl> {
+        let invalid = || {
+            Error::config(format!(
+                "invalid URL \"{url}\", expected http://host[:port]/path"
+            ))
+        };
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        // a colon inside [brackets] belongs to an IPv6 address
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                (host, port.parse().map_err(|_| invalid())?)
+            }
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(HttpUrl {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Download,
+    Upload,
+}
+
+// Connections moving as much data as they can in one direction until
+// stopped, counting the bytes transferred
+pub struct Load {
+    stop: Arc<AtomicBool>,
+    bytes: Arc<AtomicU64>,
+    error: Arc<Mutex<Option<String>>>,
+    started: Instant,
+    threads: Vec<JoinHandle<()>>,
+}
+
+const CHUNK: usize = 64 * 1024;
+// upload bodies are announced this large and cut off when stopped
+const UPLOAD_SIZE: u64 = 1 << 40;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// short read and write timeouts so a stop request is noticed promptly
+const STOP_POLL: Duration = Duration::from_millis(500);
+
+impl Load {
+    pub fn start(url: &HttpUrl, direction: Direction, streams: usize) -> Load {
+        let stop = Arc::new(AtomicBool::new(false));
+        let bytes = Arc::new(AtomicU64::new(0));
+        let error = Arc::new(Mutex::new(None));
+        let threads = (0..streams.max(1))
+            .map(|_| {
+                let (url, stop, bytes, error) =
+                    (url.clone(), stop.clone(), bytes.clone(), error.clone());
+                thread::spawn(move || {
+                    // reconnect whenever a transfer ends before the load is stopped
+                    while !stop.load(Ordering::Relaxed) {
+                        if let Err(e) = transfer(&url, direction, &stop, &bytes) {
+                            *error.lock().unwrap() = Some(format!("{}: {e}", url.host));
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+        Load {
+            stop,
+            bytes,
+            error,
+            started: Instant::now(),
+            threads,
+        }
+    }
+
+    pub fn rate(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.bytes.load(Ordering::Relaxed) as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    // Stops the connections and returns the average rate
+    pub fn stop(self) -> f64 {
+        self.stop.store(true, Ordering::Relaxed);
+        let rate = self.rate();
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+        rate
+    }
+}
+
+// `timeout` applies to each read and write
+pub fn connect(url: &HttpUrl, timeout: Duration) -> io::Result<TcpStream> {
+    let address = (url.host.trim_matches(['[', ']']), url.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address found"))?;
+    let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+fn transfer(
+    url: &HttpUrl,
+    direction: Direction,
+    stop: &AtomicBool,
+    bytes: &AtomicU64,
+) -> io::Result<()> {
+    let mut stream = connect(url, STOP_POLL)?;
+    let mut buffer = vec![0u8; CHUNK];
+    match direction {
+        Direction::Download => {
+            write!(
+                stream,
+                "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                url.path, url.host
+            )?;
+            while !stop.load(Ordering::Relaxed) {
+                match stream.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(len) => {
+                        bytes.fetch_add(len as u64, Ordering::Relaxed);
+                    }
+                    Err(e) if is_timeout(&e) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Direction::Upload => {
+            write!(
+                stream,
+                "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/octet-stream\r\nContent-Length: {UPLOAD_SIZE}\r\nConnection: close\r\n\r\n",
+                url.path, url.host
+            )?;
+            while !stop.load(Ordering::Relaxed) {
+                match stream.write(&buffer) {
+                    Ok(0) => break,
+                    Ok(len) => {
+                        bytes.fetch_add(len as u64, Ordering::Relaxed);
+                    }
+                    Err(e) if is_timeout(&e) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+
+use crate::{app::App, histogram::RateHistogram};
+
+// Running statistics over a rate series, both since start and over a
+// sliding window of the most recent samples
+pub struct RateStats {
+    pub peak: f64,
+    pub min: f64,
+    sum: f64,
+    count: usize,
+    window: VecDeque<f64>,
+    window_size: usize,
+}
+
+impl RateStats {
+    pub fn new(window_size: usize) -> RateStats {
+        RateStats {
+            peak: 0.0,
+            min: f64::INFINITY,
+            sum: 0.0,
+            count: 0,
+            window: VecDeque::with_capacity(window_size),
+            window_size: window_size.max(1),
+        }
+    }
+
+    pub fn push(&mut self, rate: f64) {
+        self.peak = self.peak.max(rate);
+        self.min = self.min.min(rate);
+        self.sum += rate;
+        self.count += 1;
+        if self.window.len() == self.window_size {
+            self.window.pop_front();
+        }
+        self.window.push_back(rate);
+    }
+
+    // the most recent sample
+    pub fn current(&self) -> f64 {
+        self.window.back().copied().unwrap_or(0.0)
+    }
+
+    pub fn avg(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum / self.count as f64
+    }
+
+    pub fn min(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.min
+    }
+
+    pub fn window_peak(&self) -> f64 {
+        self.window.iter().copied().fold(0.0, f64::max)
+    }
+
+    pub fn window_avg(&self) -> f64 {
+        if self.window.is_empty() {
+            return 0.0;
+        }
+        self.window.iter().sum::<f64>() / self.window.len() as f64
+    }
+
+    pub fn window_min(&self) -> f64 {
+        self.window.iter().copied().reduce(f64::min).unwrap_or(0.0)
+    }
+}
+
+pub struct InterfaceStats {
+    pub sent: RateStats,
+    pub rec: RateStats,
+    // packets per second
+    pub sent_packets: RateStats,
+    pub rec_packets: RateStats,
+    // received multicast and broadcast packets per second, broadcasts only
+    // where the driver counts them
+    pub multicast: RateStats,
+    pub broadcast: Option<RateStats>,
+    // every rate of the session, for the detail view's histogram
+    pub sent_histogram: RateHistogram,
+    pub rec_histogram: RateHistogram,
+    // bytes moved since net-stat started
+    pub sent_bytes: u64,
+    pub rec_bytes: u64,
+}
+
+impl InterfaceStats {
+    pub fn new(window_size: usize) -> InterfaceStats {
+        InterfaceStats {
+            sent: RateStats::new(window_size),
+            rec: RateStats::new(window_size),
+            sent_packets: RateStats::new(window_size),
+            rec_packets: RateStats::new(window_size),
+            multicast: RateStats::new(window_size),
+            broadcast: None,
+            sent_histogram: RateHistogram::default(),
+            rec_histogram: RateHistogram::default(),
+            sent_bytes: 0,
+            rec_bytes: 0,
+        }
+    }
+}
+
+// `elapsed` is the time in seconds covered by the current samples
+pub fn update_stats(app: &mut App, elapsed: f64) {
+    if elapsed <= 0.0 {
+        return;
+    }
+    let window_size = app.config.stats_window;
+    app.all_interfaces.iter().for_each(|interface| {
+        let stats = app
+            .stats
+            .entry(interface.name.to_string())
+            .or_insert_with(|| InterfaceStats::new(window_size));
+        let (sent, rec) = (
+            interface.sent as f64 / elapsed,
+            interface.rec as f64 / elapsed,
+        );
+        stats.sent.push(sent);
+        stats.rec.push(rec);
+        stats.sent_histogram.push(sent);
+        stats.rec_histogram.push(rec);
+        stats
+            .sent_packets
+            .push(interface.packets.sent as f64 / elapsed);
+        stats
+            .rec_packets
+            .push(interface.packets.rec as f64 / elapsed);
+        stats
+            .multicast
+            .push(interface.packets.multicast as f64 / elapsed);
+        if let Some(broadcast) = interface.packets.broadcast {
+            stats
+                .broadcast
+                .get_or_insert_with(|| RateStats::new(window_size))
+                .push(broadcast as f64 / elapsed);
+        }
+        stats.sent_bytes += interface.sent;
+        stats.rec_bytes += interface.rec;
+    });
+}
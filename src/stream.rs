@@ -0,0 +1,337 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{tcp::OwnedReadHalf, TcpListener, TcpStream},
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+    time::timeout,
+};
+
+use crate::{
+    collector::{self, Collector, Pipeline},
+    collectors::BackendKind,
+    config::Config,
+    discovery,
+    error::{Error, Result, ResultExt},
+    tui::TICK_RATE,
+    wire,
+};
+
+// Clients that say nothing within this time get plain JSON lines, WebSocket
+// clients open with their upgrade request straight away
+const HANDSHAKE_WAIT: Duration = Duration::from_millis(500);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST: usize = 8192;
+// WebSocket clients only listen, a longer message of theirs ends the
+// connection
+const MAX_CLIENT_FRAME: usize = 8192;
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+// Samples this machine and streams every sample to whoever connects to
+// `bind`, as JSON lines over TCP or as WebSocket text messages. Runs until
+// the process is stopped.
+pub async fn serve(bind: &str, backend: Option<BackendKind>, announce: bool) -> Result<()> {
+    let mut config = Config::load()?;
+    if let Some(backend) = backend {
+        config.backend = backend;
+    }
+    let listener = TcpListener::bind(bind)
+        .await
+        .or_ui(|| format!("failed to listen on {bind}"))?;
+    let address = listener
+        .local_addr()
+        .or_ui(|| format!("failed to listen on {bind}"))?;
+    if announce {
+        discovery::announce(address).await?;
+    }
+    eprintln!("streaming samples on {address}, ctrl-c to stop");
+
+    let pipeline = Pipeline::default();
+    let mut samples = pipeline.subscribe("serve", config.pipeline_queue);
+    collector::spawn(&pipeline, TICK_RATE, Collector::new(config.backend)?);
+
+    let (sender, _) = broadcast::channel(config.pipeline_queue);
+    let clients = sender.clone();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            tokio::spawn(stream_to(socket, clients.subscribe()));
+        }
+    });
+    while let Some(snapshot) = samples.recv().await {
+        if sender.receiver_count() > 0 {
+            let _ = sender.send(Arc::new(wire::encode(&snapshot)?));
+        }
+    }
+    Err(Error::backend("the collector thread stopped"))
+}
+
+// Samples flow one way. Of what a WebSocket client sends, pings are
+// answered and a close is returned before hanging up, anything else is
+// read and dropped.
+async fn stream_to(mut socket: TcpStream, mut messages: broadcast::Receiver<Arc<String>>) {
+    let websocket = match timeout(HANDSHAKE_WAIT, socket.peek(&mut [0u8; 1])).await {
+        Err(_) => false,
+        Ok(Ok(n)) if n > 0 => {
+            match timeout(REQUEST_TIMEOUT, upgrade(&mut socket)).await {
+                Ok(Ok(())) => true,
+                // not an upgrade request, or it never finished
+                _ => return,
+            }
+        }
+        Ok(_) => return,
+    };
+    let (reader, mut socket) = socket.into_split();
+    let (replies, mut control) = mpsc::channel(4);
+    if websocket {
+        tokio::spawn(read_frames(reader, replies));
+    }
+    loop {
+        let bytes = tokio::select! {
+            message = messages.recv() => match message {
+                Ok(message) if websocket => frame(OPCODE_TEXT, message.as_bytes()),
+                Ok(message) => format!("{message}\n").into_bytes(),
+                // a slow client just misses samples
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            // the client hung up or closed once this is answered
+            reply = control.recv(), if websocket => match reply {
+                Some(reply) => reply,
+                None => break,
+            },
+        };
+        if socket.write_all(&bytes).await.is_err() {
+            break;
+        }
+    }
+}
+
+// Replies to the client's control frames, until it closes the connection
+async fn read_frames(mut reader: OwnedReadHalf, replies: mpsc::Sender<Vec<u8>>) {
+    let mut received = Vec::new();
+    let mut buffer = [0u8; 1024];
+    loop {
+        while let Some((opcode, payload, len)) = client_frame(&received) {
+            received.drain(..len);
+            let reply = match opcode {
+                OPCODE_PING => frame(OPCODE_PONG, &payload),
+                OPCODE_CLOSE => {
+                    // echo the status code, if any, then the writer stops
+                    let code = payload.get(..2).unwrap_or_default();
+                    let _ = replies.send(frame(OPCODE_CLOSE, code)).await;
+                    return;
+                }
+                _ => continue,
+            };
+            if replies.send(reply).await.is_err() {
+                return;
+            }
+        }
+        if received.len() > MAX_CLIENT_FRAME {
+            let _ = replies
+                .send(frame(OPCODE_CLOSE, &1009u16.to_be_bytes()))
+                .await;
+            return;
+        }
+        match reader.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => received.extend_from_slice(&buffer[..n]),
+        }
+    }
+}
+
+// The frame at the start of `bytes` as its opcode, unmasked payload and
+// length, None until all of it has arrived
+pub fn client_frame(bytes: &[u8]) -> Option<(u8, Vec<u8>, usize)> {
+    let (first, second) = (*bytes.first()?, *bytes.get(1)?);
+    let (len, mut at) = match second & 0x7f {
+        126 => (
+            u16::from_be_bytes(bytes.get(2..4)?.try_into().ok()?) as usize,
+            4,
+        ),
+        127 => (
+            usize::try_from(u64::from_be_bytes(bytes.get(2..10)?.try_into().ok()?)).ok()?,
+            10,
+        ),
+        len => (len as usize, 2),
+    };
+    // clients mask every frame
+    let mut mask = [0u8; 4];
+    if second & 0x80 != 0 {
+        mask.copy_from_slice(bytes.get(at..at + 4)?);
+        at += 4;
+    }
+    let end = at.checked_add(len)?;
+    let payload = bytes
+        .get(at..end)?
+        .iter()
+        .zip(mask.iter().cycle())
+        .map(|(byte, mask)| byte ^ mask)
+        .collect();
+    Some((first & 0x0f, payload, end))
+}
+
+// Answers a WebSocket upgrade request, anything else gets a 400
+async fn upgrade(socket: &mut TcpStream) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        let n = socket
+            .read(&mut buffer)
+            .await
+            .or_ui(|| "failed to read the request")?;
+        if n == 0 || request.len() + n > MAX_REQUEST {
+            return Err(Error::ui("incomplete request"));
+        }
+        request.extend_from_slice(&buffer[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let key = request.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("sec-websocket-key")
+            .then(|| value.trim().to_string())
+    });
+    let Some(key) = key else {
+        let response = "HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\nconnect with a WebSocket client or plain TCP\n";
+        let _ = socket.write_all(response.as_bytes()).await;
+        return Err(Error::ui("not a WebSocket request"));
+    };
+    let accept = accept_key(&key);
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+    );
+    socket
+        .write_all(response.as_bytes())
+        .await
+        .or_ui(|| "failed to answer the upgrade request")
+}
+
+// The Sec-WebSocket-Accept answer to a client's Sec-WebSocket-Key
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(
+        format!("{key}258EAFA5-E914-47DA-95CA-C5AB0DC85B11").as_bytes(),
+    ))
+}
+
+// Unmasked and unfragmented, as a server sends them
+pub fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xffff => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// Only needed for the handshake's accept key
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+    let mut digest = [0u8; 20];
+    for (bytes, word) in digest.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// Feeds the samples of a `net-stat serve` instance into `pipeline` in place
+// of the local collector
+pub async fn connect(pipeline: &Pipeline, address: &str) -> Result<()> {
+    let socket = TcpStream::connect(address)
+        .await
+        .or_backend(|| format!("failed to connect to {address}"))?;
+    let publisher = pipeline.clone();
+    let address = address.to_string();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(socket).lines();
+        let reason = loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => match wire::decode(&line) {
+                    Ok(snapshot) => publisher.publish(&snapshot),
+                    Err(e) => break format!("{e:#}"),
+                },
+                Ok(None) => break format!("{address} closed the connection"),
+                Err(e) => break format!("lost the connection to {address}: {e}"),
+            }
+            if publisher.is_closed() {
+                return;
+            }
+        };
+        publisher.stop(reason);
+    });
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use crate::{
+    app::App,
+    history::unix_time,
+    units::{format_bytes, format_duration, format_rate},
+};
+
+// Printed to stdout after the terminal is restored
+pub fn session_summary(app: &App) -> String {
+    let mut lines = vec![format!(
+        "net-stat session: {}",
+        format_duration(app.started.elapsed().as_secs_f64())
+    )];
+    if !app.alerts.rules().is_empty() {
+        lines.push(format!("  alerts fired: {}", app.alerts.fired));
+    }
+    let now = unix_time();
+    let started = now - app.started.elapsed().as_secs_f64();
+    app.all_interfaces.iter().for_each(|interface| {
+        let Some(stats) = app.stats.get(&interface.name) else {
+            return;
+        };
+        lines.push(format!(
+            "  {}: sent {} (peak {}), recieved {} (peak {})",
+            interface.name,
+            format_bytes(stats.sent_bytes as f64),
+            format_rate(stats.sent.peak),
+            format_bytes(stats.rec_bytes as f64),
+            format_rate(stats.rec.peak)
+        ));
+        // notes covering any part of the session
+        app.notes
+            .for_interface(&interface.name)
+            .filter(|note| note.overlaps(started, now))
+            .for_each(|note| lines.push(format!("    note: {}", note.describe())));
+    });
+    lines.join("\n")
+}
@@ -0,0 +1,113 @@
+use ratatui::{
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use sysinfo::{CpuExt, CpuRefreshKind, RefreshKind, System, SystemExt};
+
+use crate::{app::App, layout::scaled_sparkline, units::format_bytes};
+
+// CPU percentages kept for the strip's sparkline
+const CPU_SAMPLES: usize = 60;
+// from this CPU or memory use on the strip is drawn as an alert
+const BUSY: f64 = 90.0;
+
+// CPU and memory of this machine, refreshed only while the strip is shown
+#[derive(Default)]
+pub struct SystemLoad {
+    // set up when the strip is first shown
+    sys: Option<System>,
+    pub cpu: Vec<u64>,
+    pub used_memory: u64,
+    pub total_memory: u64,
+    pub used_swap: u64,
+    pub total_swap: u64,
+    pub load: [f64; 3],
+}
+
+impl SystemLoad {
+    fn refresh(&mut self) {
+        let sys = self.sys.get_or_insert_with(|| {
+            // nothing but CPU and memory, the process list is expensive to keep
+            let refreshes = RefreshKind::new()
+                .with_cpu(CpuRefreshKind::new().with_cpu_usage())
+                .with_memory();
+            System::new_with_specifics(refreshes)
+        });
+        sys.refresh_cpu();
+        sys.refresh_memory();
+        self.cpu
+            .push(sys.global_cpu_info().cpu_usage().round() as u64);
+        if self.cpu.len() > CPU_SAMPLES {
+            self.cpu.remove(0);
+        }
+        self.used_memory = sys.used_memory();
+        self.total_memory = sys.total_memory();
+        self.used_swap = sys.used_swap();
+        self.total_swap = sys.total_swap();
+        let load = sys.load_average();
+        self.load = [load.one, load.five, load.fifteen];
+    }
+
+    fn memory_percent(&self) -> f64 {
+        self.used_memory as f64 * 100.0 / self.total_memory.max(1) as f64
+    }
+}
+
+pub fn toggle_system_strip(app: &mut App) {
+    app.system_strip = !app.system_strip;
+    if app.system_strip {
+        update_system_load(app);
+    }
+}
+
+pub fn update_system_load(app: &mut App) {
+    // the load shown would be this machine's, not the one being watched
+    if !app.system_strip || app.is_remote() {
+        return;
+    }
+    app.system_load.refresh();
+}
+
+pub fn system_strip_height(app: &App) -> u16 {
+    (app.system_strip && !app.is_remote()) as u16
+}
+
+// e.g. "CPU  12% ▁▂▁▃ | Mem 3.1 GB / 7.8 GB (40%) | Swap 0 B / 2.0 GB | Load 0.52 0.43 0.40"
+pub fn create_system_strip<'a>(app: &App) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let load = &app.system_load;
+    let cpu = load.cpu.last().copied().unwrap_or_default();
+    let style = |busy: bool| if busy { theme.alert } else { theme.text };
+    let memory = load.memory_percent();
+    let mut spans = vec![
+        Span::styled(format!("CPU {cpu:>3}% "), style(cpu as f64 >= BUSY)),
+        Span::styled(
+            scaled_sparkline(&load.cpu, 20, 100, app.capabilities.unicode),
+            theme.tx_graph,
+        ),
+        Span::raw(" | "),
+        Span::styled(
+            format!(
+                "Mem {} / {} ({memory:.0}%)",
+                format_bytes(load.used_memory as f64),
+                format_bytes(load.total_memory as f64)
+            ),
+            style(memory >= BUSY),
+        ),
+    ];
+    if load.total_swap > 0 {
+        spans.push(Span::raw(format!(
+            " | Swap {} / {}",
+            format_bytes(load.used_swap as f64),
+            format_bytes(load.total_swap as f64)
+        )));
+    }
+    // Windows has no load average, sysinfo reports zeros
+    if load.load.iter().any(|load| *load > 0.0) {
+        spans.push(Span::raw(format!(
+            " | Load {:.2} {:.2} {:.2}",
+            load.load[0], load.load[1], load.load[2]
+        )));
+    }
+    Paragraph::new(Line::from(spans)).style(theme.text)
+}
@@ -0,0 +1,257 @@
+use std::{collections::HashMap, fs};
+
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    app::App,
+    networks::{create_interface_graph, effective_graph_style, Graph, DISPLAY_SAMPLES},
+};
+
+const SNMP_PATH: &str = "/proc/net/snmp";
+const NETSTAT_PATH: &str = "/proc/net/netstat";
+// from this share of the segments sent being retransmits the status line
+// warns, a healthy path stays well under 1%
+const RETRANSMIT_WARNING: f64 = 2.0;
+pub const TCP_SUMMARY_HEIGHT: u16 = 4;
+
+// System wide TCP counters since boot, the ones behind "throughput is
+// fine but everything feels slow"
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TcpCounters {
+    pub out_segs: u64,
+    pub retrans_segs: u64,
+    pub in_errs: u64,
+    // resets sent, and established connections reset either way
+    pub out_rsts: u64,
+    pub estab_resets: u64,
+    pub attempt_fails: u64,
+    pub listen_overflows: u64,
+    pub listen_drops: u64,
+}
+
+impl TcpCounters {
+    fn since(&self, start: &TcpCounters) -> TcpCounters {
+        TcpCounters {
+            out_segs: self.out_segs.saturating_sub(start.out_segs),
+            retrans_segs: self.retrans_segs.saturating_sub(start.retrans_segs),
+            in_errs: self.in_errs.saturating_sub(start.in_errs),
+            out_rsts: self.out_rsts.saturating_sub(start.out_rsts),
+            estab_resets: self.estab_resets.saturating_sub(start.estab_resets),
+            attempt_fails: self.attempt_fails.saturating_sub(start.attempt_fails),
+            listen_overflows: self.listen_overflows.saturating_sub(start.listen_overflows),
+            listen_drops: self.listen_drops.saturating_sub(start.listen_drops),
+        }
+    }
+}
+
+// Both files hold pairs of lines, the names then the values, each starting
+// with the same "Tcp:" or "TcpExt:" prefix. Keys are e.g. "Tcp.RetransSegs".
+pub fn parse_pairs(contents: &str) -> HashMap<String, u64> {
+    let mut counters = HashMap::new();
+    let lines: Vec<&str> = contents.lines().collect();
+    for pair in lines.chunks(2) {
+        let [names, values] = pair else {
+            continue;
+        };
+        let (Some((prefix, names)), Some((value_prefix, values))) =
+            (names.split_once(':'), values.split_once(':'))
+        else {
+            continue;
+        };
+        if prefix != value_prefix {
+            continue;
+        }
+        for (name, value) in names.split_whitespace().zip(values.split_whitespace()) {
+            // MaxConn is -1 on Linux, nothing here reads it
+            if let Ok(value) = value.parse() {
+                counters.insert(format!("{prefix}.{name}"), value);
+            }
+        }
+    }
+    counters
+}
+
+// None without a Tcp line, the netstat counters count as 0 when missing
+pub fn parse_counters(snmp: &str, netstat: &str) -> Option<TcpCounters> {
+    let mut counters = parse_pairs(snmp);
+    counters.get("Tcp.OutSegs")?;
+    counters.extend(parse_pairs(netstat));
+    let counter = |name: &str| counters.get(name).copied().unwrap_or_default();
+    Some(TcpCounters {
+        out_segs: counter("Tcp.OutSegs"),
+        retrans_segs: counter("Tcp.RetransSegs"),
+        in_errs: counter("Tcp.InErrs"),
+        out_rsts: counter("Tcp.OutRsts"),
+        estab_resets: counter("Tcp.EstabResets"),
+        attempt_fails: counter("Tcp.AttemptFails"),
+        listen_overflows: counter("TcpExt.ListenOverflows"),
+        listen_drops: counter("TcpExt.ListenDrops"),
+    })
+}
+
+// What the counters did per second over the session, Linux only
+#[derive(Default)]
+pub struct TcpHealth {
+    pub available: bool,
+    pub counters: TcpCounters,
+    // the change per second at the last sample
+    pub rates: [f64; 4],
+    // per second, for the graphs
+    pub retransmits: Vec<u64>,
+    pub resets: Vec<u64>,
+    pub listen_drops: Vec<u64>,
+    // counters when first read, the panel shows what happened since
+    initial: Option<TcpCounters>,
+}
+
+impl TcpHealth {
+    // Retransmitted segments as a percentage of all segments sent over the
+    // last sample
+    pub fn retransmit_share(&self) -> f64 {
+        let [segments, retransmits, ..] = self.rates;
+        match segments > 0.0 {
+            true => retransmits * 100.0 / segments,
+            false => 0.0,
+        }
+    }
+
+    // e.g. "TCP retransmitting 4.2% of segments"
+    pub fn warning(&self) -> Option<String> {
+        (self.available && self.retransmit_share() >= RETRANSMIT_WARNING).then(|| {
+            format!(
+                "TCP retransmitting {:.1}% of segments",
+                self.retransmit_share()
+            )
+        })
+    }
+
+    // Takes in the counters of a new sample `elapsed` seconds after the last
+    pub fn push(&mut self, counters: TcpCounters, elapsed: f64) {
+        self.available = true;
+        let previous = std::mem::replace(&mut self.counters, counters);
+        if self.initial.is_none() {
+            self.initial = Some(counters);
+            return;
+        }
+        if elapsed <= 0.0 {
+            return;
+        }
+        let moved = counters.since(&previous);
+        let rate = |count: u64| count as f64 / elapsed;
+        self.rates = [
+            rate(moved.out_segs),
+            rate(moved.retrans_segs),
+            rate(moved.out_rsts + moved.estab_resets),
+            rate(moved.listen_drops),
+        ];
+        for (series, value) in [
+            (&mut self.retransmits, self.rates[1]),
+            (&mut self.resets, self.rates[2]),
+            (&mut self.listen_drops, self.rates[3]),
+        ] {
+            series.push(value.round() as u64);
+            if series.len() > DISPLAY_SAMPLES {
+                series.remove(0);
+            }
+        }
+    }
+}
+
+pub fn update_tcp(app: &mut App, elapsed: f64) {
+    // the counters belong to this machine, not the one a remote session
+    // watches or a recording was made on
+    if app.is_remote() {
+        return;
+    }
+    let snmp = fs::read_to_string(SNMP_PATH).unwrap_or_default();
+    let netstat = fs::read_to_string(NETSTAT_PATH).unwrap_or_default();
+    match parse_counters(&snmp, &netstat) {
+        Some(counters) => app.tcp.push(counters, elapsed),
+        None => app.tcp.available = false,
+    }
+}
+
+pub fn create_tcp_paragraph<'a>(app: &App) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let tcp = &app.tcp;
+    if !tcp.available {
+        return Paragraph::new("TCP counters are only read from /proc/net/snmp on Linux")
+            .style(theme.text)
+            .block(
+                Block::default()
+                    .title(Span::styled("TCP health", theme.title))
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            );
+    }
+    let [_, retransmits, resets, listen_drops] = tcp.rates;
+    let style = |bad: bool| if bad { theme.alert } else { theme.text };
+    let session = tcp.counters.since(&tcp.initial.unwrap_or_default());
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(
+                format!(
+                    "Retransmits: {retransmits:.0}/s ({:.1}% of segments sent)",
+                    tcp.retransmit_share()
+                ),
+                style(tcp.warning().is_some()),
+            ),
+            Span::raw(format!(" | Resets: {resets:.0}/s")),
+            Span::styled(
+                format!(" | Listen drops: {listen_drops:.0}/s"),
+                style(listen_drops > 0.0),
+            ),
+        ]),
+        Line::styled(
+            format!(
+                "This session: {} retransmitted, {} resets sent, {} connections reset, {} failed opens, {} listen overflows, {} listen drops, {} bad segments",
+                session.retrans_segs,
+                session.out_rsts,
+                session.estab_resets,
+                session.attempt_fails,
+                session.listen_overflows,
+                session.listen_drops,
+                session.in_errs
+            ),
+            style(session.listen_drops + session.in_errs > 0),
+        ),
+    ];
+    let block = Block::default()
+        .title(Span::styled("TCP health, all sockets", theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Paragraph::new(lines).style(theme.text).block(block)
+}
+
+// Retransmits, resets and listen drops per second, top to bottom, each
+// `width` columns wide
+pub fn create_tcp_graphs(app: &App, width: usize) -> Vec<Graph<'_>> {
+    let theme = &app.theme;
+    let graph_style = effective_graph_style(app);
+    let samples = graph_style.samples(width);
+    let tcp = &app.tcp;
+    [
+        ("Retransmitted segments", &tcp.retransmits),
+        ("Resets", &tcp.resets),
+        ("Listen drops", &tcp.listen_drops),
+    ]
+    .into_iter()
+    .map(|(name, values)| {
+        let tail = values.len().saturating_sub(samples);
+        let latest = values.last().copied().unwrap_or_default();
+        let peak = values.iter().copied().max().unwrap_or_default();
+        create_interface_graph(
+            format!("{name} {latest}/s (peak {peak}/s)"),
+            &values[tail..],
+            theme.tx_graph,
+            theme.tx_symbols.clone(),
+            graph_style,
+            app.capabilities.unicode,
+            theme,
+        )
+    })
+    .collect()
+}
@@ -0,0 +1,281 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    symbols::bar,
+};
+
+use crate::capabilities::Capabilities;
+
+#[derive(Clone)]
+pub struct Theme {
+    pub border: Style,
+    pub title: Style,
+    pub text: Style,
+    pub tx_graph: Style,
+    pub rx_graph: Style,
+    pub tx_symbols: bar::Set,
+    pub rx_symbols: bar::Set,
+    pub alert: Style,
+    pub palette: Vec<Color>,
+}
+
+// Shaded bar set so RX can be told apart from TX by texture, not just color
+pub const PATTERN: bar::Set = bar::Set {
+    full: "▓",
+    seven_eighths: "▓",
+    three_quarters: "▒",
+    five_eighths: "▒",
+    half: "▒",
+    three_eighths: "░",
+    one_quarter: "░",
+    one_eighth: "░",
+    empty: " ",
+};
+
+const DARK_PALETTE: &[Color] = &[
+    Color::LightCyan,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightMagenta,
+    Color::LightBlue,
+    Color::LightRed,
+];
+
+const LIGHT_PALETTE: &[Color] = &[
+    Color::Blue,
+    Color::Green,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Red,
+    Color::Yellow,
+];
+
+const SOLARIZED_PALETTE: &[Color] = &[
+    Color::Rgb(0x26, 0x8b, 0xd2),
+    Color::Rgb(0x2a, 0xa1, 0x98),
+    Color::Rgb(0x85, 0x99, 0x00),
+    Color::Rgb(0xb5, 0x89, 0x00),
+    Color::Rgb(0xcb, 0x4b, 0x16),
+    Color::Rgb(0x6c, 0x71, 0xc4),
+    Color::Rgb(0xd3, 0x36, 0x82),
+];
+
+const OKABE_ITO_PALETTE: &[Color] = &[
+    Color::Rgb(0x00, 0x72, 0xb2),
+    Color::Rgb(0xe6, 0x9f, 0x00),
+    Color::Rgb(0x00, 0x9e, 0x73),
+    Color::Rgb(0xcc, 0x79, 0xa7),
+    Color::Rgb(0x56, 0xb4, 0xe9),
+    Color::Rgb(0xd5, 0x5e, 0x00),
+    Color::Rgb(0xf0, 0xe4, 0x42),
+];
+
+// Plain ASCII fallbacks for terminals without unicode support
+pub const ASCII: bar::Set = bar::Set {
+    full: "#",
+    seven_eighths: "#",
+    three_quarters: "=",
+    five_eighths: "=",
+    half: "-",
+    three_eighths: "-",
+    one_quarter: ".",
+    one_eighth: ".",
+    empty: " ",
+};
+
+pub const ASCII_PATTERN: bar::Set = bar::Set {
+    full: "@",
+    seven_eighths: "@",
+    three_quarters: "o",
+    five_eighths: "o",
+    half: "+",
+    three_eighths: "+",
+    one_quarter: ",",
+    one_eighth: ",",
+    empty: " ",
+};
+
+impl Theme {
+    pub fn by_name(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "solarized" => Some(Theme::solarized()),
+            "monochrome" => Some(Theme::monochrome()),
+            "colorblind" => Some(Theme::colorblind()),
+            "colorblind-light" => Some(Theme::colorblind_light()),
+            _ => None,
+        }
+    }
+
+    pub fn dark() -> Theme {
+        Theme {
+            border: Style::default().fg(Color::DarkGray),
+            title: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            text: Style::default().fg(Color::Gray),
+            tx_graph: Style::default().fg(Color::Green),
+            rx_graph: Style::default().fg(Color::Yellow),
+            tx_symbols: bar::NINE_LEVELS,
+            rx_symbols: bar::NINE_LEVELS,
+            alert: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            palette: DARK_PALETTE.to_vec(),
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            border: Style::default().fg(Color::Gray),
+            title: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            text: Style::default().fg(Color::Black),
+            tx_graph: Style::default().fg(Color::Blue),
+            rx_graph: Style::default().fg(Color::Magenta),
+            tx_symbols: bar::NINE_LEVELS,
+            rx_symbols: bar::NINE_LEVELS,
+            alert: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            palette: LIGHT_PALETTE.to_vec(),
+        }
+    }
+
+    pub fn solarized() -> Theme {
+        Theme {
+            border: Style::default().fg(Color::Rgb(0x58, 0x6e, 0x75)),
+            title: Style::default()
+                .fg(Color::Rgb(0x26, 0x8b, 0xd2))
+                .add_modifier(Modifier::BOLD),
+            text: Style::default().fg(Color::Rgb(0x93, 0xa1, 0xa1)),
+            tx_graph: Style::default().fg(Color::Rgb(0x85, 0x99, 0x00)),
+            rx_graph: Style::default().fg(Color::Rgb(0xb5, 0x89, 0x00)),
+            tx_symbols: bar::NINE_LEVELS,
+            rx_symbols: bar::NINE_LEVELS,
+            alert: Style::default()
+                .fg(Color::Rgb(0xdc, 0x32, 0x2f))
+                .add_modifier(Modifier::BOLD),
+            palette: SOLARIZED_PALETTE.to_vec(),
+        }
+    }
+
+    pub fn monochrome() -> Theme {
+        Theme {
+            border: Style::default(),
+            title: Style::default().add_modifier(Modifier::BOLD),
+            text: Style::default(),
+            tx_graph: Style::default(),
+            rx_graph: Style::default(),
+            tx_symbols: bar::NINE_LEVELS,
+            rx_symbols: PATTERN,
+            alert: Style::default().add_modifier(Modifier::REVERSED),
+            palette: Vec::new(),
+        }
+    }
+
+    // Okabe-Ito palette, distinguishable under the common color vision deficiencies
+    pub fn colorblind() -> Theme {
+        Theme {
+            border: Style::default().fg(Color::Rgb(0x99, 0x99, 0x99)),
+            title: Style::default()
+                .fg(Color::Rgb(0x56, 0xb4, 0xe9))
+                .add_modifier(Modifier::BOLD),
+            text: Style::default().fg(Color::Rgb(0xdd, 0xdd, 0xdd)),
+            tx_graph: Style::default().fg(Color::Rgb(0x00, 0x72, 0xb2)),
+            rx_graph: Style::default().fg(Color::Rgb(0xe6, 0x9f, 0x00)),
+            tx_symbols: bar::NINE_LEVELS,
+            rx_symbols: PATTERN,
+            alert: Style::default()
+                .fg(Color::Rgb(0xd5, 0x5e, 0x00))
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            palette: OKABE_ITO_PALETTE.to_vec(),
+        }
+    }
+
+    pub fn colorblind_light() -> Theme {
+        Theme {
+            border: Style::default().fg(Color::Rgb(0x60, 0x60, 0x60)),
+            title: Style::default()
+                .fg(Color::Rgb(0x00, 0x72, 0xb2))
+                .add_modifier(Modifier::BOLD),
+            text: Style::default().fg(Color::Black),
+            tx_graph: Style::default().fg(Color::Rgb(0x00, 0x72, 0xb2)),
+            rx_graph: Style::default().fg(Color::Rgb(0xd5, 0x5e, 0x00)),
+            tx_symbols: bar::NINE_LEVELS,
+            rx_symbols: PATTERN,
+            alert: Style::default()
+                .fg(Color::Rgb(0xcc, 0x79, 0xa7))
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            palette: OKABE_ITO_PALETTE.to_vec(),
+        }
+    }
+
+    // Deterministic per-interface color so an interface keeps its color across
+    // sessions, unless the config maps it explicitly
+    pub fn interface_color(&self, name: &str, configured: Option<Color>) -> Option<Color> {
+        if configured.is_some() {
+            return configured;
+        }
+        if self.palette.is_empty() {
+            return None;
+        }
+        let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Some(self.palette[(hash % self.palette.len() as u64) as usize])
+    }
+
+    // Theme variant using a single accent color for one interface, with RX
+    // falling back to the shaded pattern to stay distinguishable from TX
+    pub fn accented(&self, color: Color) -> Theme {
+        let rx_symbols = if self.rx_symbols.full == self.tx_symbols.full {
+            PATTERN
+        } else {
+            self.rx_symbols.clone()
+        };
+        Theme {
+            border: self.border.fg(color),
+            title: self.title.fg(color),
+            tx_graph: self.tx_graph.fg(color),
+            rx_graph: self.rx_graph.fg(color),
+            rx_symbols,
+            ..self.clone()
+        }
+    }
+
+    // Degrade colors and symbols to what the terminal can actually display
+    pub fn adapt(mut self, capabilities: &Capabilities) -> Theme {
+        let style = |style: Style| Style {
+            fg: style.fg.map(|color| capabilities.color(color)),
+            bg: style.bg.map(|color| capabilities.color(color)),
+            ..style
+        };
+        self.border = style(self.border);
+        self.title = style(self.title);
+        self.text = style(self.text);
+        self.tx_graph = style(self.tx_graph);
+        self.rx_graph = style(self.rx_graph);
+        self.alert = style(self.alert);
+        self.palette = self
+            .palette
+            .iter()
+            .map(|color| capabilities.color(*color))
+            .collect();
+        if !capabilities.unicode {
+            self.tx_symbols = ASCII;
+            self.rx_symbols = ASCII_PATTERN;
+        }
+        self
+    }
+
+    pub fn with_patterns(mut self, patterns: bool) -> Theme {
+        if patterns {
+            self.rx_symbols = PATTERN;
+        }
+        self
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    app::App,
+    carrier::read_carrier,
+    history::unix_time,
+    networks::chart_window,
+    route::{resolve, route_get},
+    speedtest::{Direction, HttpUrl, Load},
+    units::format_rate,
+};
+
+// Seconds each direction is saturated for
+const TEST_SECONDS: f64 = 10.0;
+
+struct Running {
+    direction: Direction,
+    started: f64,
+    load: Load,
+    interface: Option<String>,
+    download: Option<f64>,
+}
+
+// Average rates in bytes per second, next to the link speed of the
+// interface the endpoints are routed through
+pub struct SpeedTestResult {
+    pub time: f64,
+    pub interface: Option<String>,
+    pub download: Option<f64>,
+    pub upload: Option<f64>,
+    // Mb/s, unknown for virtual interfaces
+    pub link_speed: Option<u32>,
+}
+
+impl SpeedTestResult {
+    // e.g. "speed test via eth0: down 94.2 MB/s (75% of 1000 Mb/s), up 11.0 MB/s (9%)"
+    pub fn describe(&self) -> String {
+        let rate = |name: &str, rate: Option<f64>, first: bool| {
+            let rate = rate?;
+            let share = match self.link_speed {
+                Some(speed) if first => {
+                    format!(" ({:.0}% of {speed} Mb/s)", link_share(rate, speed))
+                }
+                Some(speed) => format!(" ({:.0}%)", link_share(rate, speed)),
+                None => String::new(),
+            };
+            Some(format!("{name} {}{share}", format_rate(rate)))
+        };
+        let parts: Vec<String> = [
+            rate("down", self.download, true),
+            rate("up", self.upload, self.download.is_none()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let via = self
+            .interface
+            .as_ref()
+            .map(|name| format!(" via {name}"))
+            .unwrap_or_default();
+        format!("speed test{via}: {}", parts.join(", "))
+    }
+}
+
+// Percent of the link a rate in bytes per second uses
+fn link_share(rate: f64, speed: u32) -> f64 {
+    rate * 8.0 / (speed as f64 * 1e4)
+}
+
+// Saturates the [speedtest] URLs for a while in each direction. The
+// outcome is drawn into the charts of the interface they are routed
+// through, where a rate far below the link speed points past the NIC.
+#[derive(Default)]
+pub struct SpeedTest {
+    running: Option<Running>,
+    pub result: Option<SpeedTestResult>,
+    pub error: Option<String>,
+    // (seconds ago, rate) lines across the TX and RX charts, rebuilt each
+    // tick for the current chart window
+    pub sent_line: Vec<(f64, f64)>,
+    pub rec_line: Vec<(f64, f64)>,
+}
+
+impl SpeedTest {
+    pub fn status(&self) -> Option<String> {
+        if let Some(running) = &self.running {
+            let name = match running.direction {
+                Direction::Download => "download",
+                Direction::Upload => "upload",
+            };
+            return Some(format!(
+                "speed test: {name} {} {:.0}s",
+                format_rate(running.load.rate()),
+                unix_time() - running.started
+            ));
+        }
+        self.error
+            .clone()
+            .or_else(|| self.result.as_ref().map(SpeedTestResult::describe))
+    }
+
+    // The reference line to draw on an interface's TX or RX chart, empty
+    // unless the last test went through it
+    pub fn line(&self, interface: &str, direction: Direction) -> &[(f64, f64)] {
+        match (&self.result, direction) {
+            (Some(result), _) if result.interface.as_deref() != Some(interface) => &[],
+            (None, _) => &[],
+            (Some(_), Direction::Upload) => &self.sent_line,
+            (Some(_), Direction::Download) => &self.rec_line,
+        }
+    }
+
+    // Appended to the graph titles of the interface the test went through
+    pub fn title(&self, interface: &str, direction: Direction) -> String {
+        let Some(result) = self
+            .result
+            .as_ref()
+            .filter(|result| result.interface.as_deref() == Some(interface))
+        else {
+            return String::new();
+        };
+        let rate = match direction {
+            Direction::Download => result.download,
+            Direction::Upload => result.upload,
+        };
+        rate.map(|rate| format!(" (speed test {})", format_rate(rate)))
+            .unwrap_or_default()
+    }
+}
+
+fn url(app: &App, direction: Direction) -> Option<HttpUrl> {
+    let url = match direction {
+        Direction::Download => &app.config.speedtest.download,
+        Direction::Upload => &app.config.speedtest.upload,
+    };
+    url.as_deref().and_then(|url| HttpUrl::parse(url).ok())
+}
+
+// Starts a test, or cancels the one running
+pub fn toggle_speed_test(app: &mut App) {
+    if let Some(running) = app.speed_test.running.take() {
+        running.load.stop();
+        return;
+    }
+    app.speed_test.error = None;
+    if app.bufferbloat.status().is_some() {
+        app.speed_test.error = Some("a bufferbloat test is running".to_string());
+        return;
+    }
+    let Some((direction, url)) = [Direction::Download, Direction::Upload]
+        .into_iter()
+        .find_map(|direction| Some((direction, url(app, direction)?)))
+    else {
+        app.speed_test.error =
+            Some("set a [speedtest] download or upload URL to run the speed test".to_string());
+        return;
+    };
+    let interface = resolve(url.host.trim_matches(['[', ']']))
+        .and_then(route_get)
+        .ok()
+        .map(|route| route.interface);
+    app.speed_test.running = Some(Running {
+        direction,
+        started: unix_time(),
+        load: Load::start(&url, direction, app.config.speedtest.streams),
+        interface,
+        download: None,
+    });
+}
+
+pub fn update_speed_test(app: &mut App) {
+    if let Some(running) = &app.speed_test.running {
+        if let Some(error) = running.load.error() {
+            if let Some(running) = app.speed_test.running.take() {
+                running.load.stop();
+            }
+            app.speed_test.error = Some(format!("speed test failed: {error}"));
+        } else if unix_time() - running.started >= TEST_SECONDS {
+            finish_direction(app);
+        }
+    }
+    update_lines(app);
+}
+
+fn finish_direction(app: &mut App) {
+    let Some(mut running) = app.speed_test.running.take() else {
+        return;
+    };
+    let direction = running.direction;
+    let rate = running.load.stop();
+    if direction == Direction::Download {
+        if let Some(url) = url(app, Direction::Upload) {
+            running.download = Some(rate);
+            app.speed_test.running = Some(Running {
+                direction: Direction::Upload,
+                started: unix_time(),
+                load: Load::start(&url, Direction::Upload, app.config.speedtest.streams),
+                ..running
+            });
+            return;
+        }
+    }
+    let (download, upload) = match direction {
+        Direction::Download => (Some(rate), None),
+        Direction::Upload => (running.download, Some(rate)),
+    };
+    let link_speed = running
+        .interface
+        .as_deref()
+        .and_then(read_carrier)
+        .and_then(|carrier| carrier.speed);
+    app.speed_test.result = Some(SpeedTestResult {
+        time: unix_time(),
+        interface: running.interface,
+        download,
+        upload,
+        link_speed,
+    });
+}
+
+// Horizontal lines at the measured rates, only charts have a rate axis to
+// draw them against
+fn update_lines(app: &mut App) {
+    let (mut sent_line, mut rec_line) = (Vec::new(), Vec::new());
+    if let Some(result) = &app.speed_test.result {
+        let window = chart_window(app);
+        let log_scale = result
+            .interface
+            .as_ref()
+            .is_some_and(|name| app.log_scale.contains(name));
+        let line = |rate: Option<f64>| {
+            rate.map(|rate| {
+                let y = if log_scale { rate.ln_1p() } else { rate };
+                vec![(-window, y), (0.0, y)]
+            })
+            .unwrap_or_default()
+        };
+        if app.chart && !app.cumulative {
+            sent_line = line(result.upload);
+            rec_line = line(result.download);
+        }
+    }
+    app.speed_test.sent_line = sent_line;
+    app.speed_test.rec_line = rec_line;
+}
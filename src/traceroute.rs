@@ -0,0 +1,393 @@
+use std::{
+    net::IpAddr,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use ratatui::{
+    prelude::Constraint,
+    text::Span,
+    widgets::{Block, Borders, Row, Table},
+};
+
+use crate::{
+    app::{switch_screen, App, Screen},
+    route::resolve,
+    theme::Theme,
+};
+
+const MAX_HOPS: u8 = 30;
+
+pub struct Hop {
+    pub ttl: u8,
+    // the router or target that answered, None when no probe came back
+    pub address: Option<IpAddr>,
+    // round trip of each probe in milliseconds
+    pub rtts: Vec<Option<f64>>,
+    // the target answered, or a router reported it unreachable
+    pub reached: bool,
+}
+
+enum TraceEvent {
+    Resolved(IpAddr),
+    Hop(Hop),
+    Failed(String),
+}
+
+// A traceroute shown on the Latency screen. Hops are probed one after the
+// other on their own thread and arrive with the ticks.
+pub struct Traceroute {
+    pub query: String,
+    pub address: Option<IpAddr>,
+    pub hops: Vec<Hop>,
+    pub error: Option<String>,
+    running: Option<Receiver<TraceEvent>>,
+}
+
+impl Traceroute {
+    pub fn status(&self) -> Option<String> {
+        self.running
+            .as_ref()
+            .map(|_| format!("traceroute to {}: {} hops", self.query, self.hops.len()))
+    }
+}
+
+// The latency probe's target is the obvious thing to trace from the
+// Latency screen
+pub fn current_target(app: &App) -> String {
+    match (&app.latency.target, app.screen) {
+        (Some(target), Screen::Latency) => target.address.to_string(),
+        _ => String::new(),
+    }
+}
+
+// Replaces any traceroute in progress, its thread stops at the next hop
+pub fn start_traceroute(app: &mut App, query: String) {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return;
+    }
+    let (sender, receiver) = mpsc::channel();
+    let target = query.clone();
+    thread::spawn(move || {
+        let address = match resolve(&target) {
+            Ok(address) => address,
+            Err(e) => {
+                let _ = sender.send(TraceEvent::Failed(format!("{target}: {e}")));
+                return;
+            }
+        };
+        if sender.send(TraceEvent::Resolved(address)).is_err() {
+            return;
+        }
+        for ttl in 1..=MAX_HOPS {
+            let hop = match probe::hop(address, ttl) {
+                Ok(hop) => hop,
+                Err(e) => {
+                    let _ = sender.send(TraceEvent::Failed(e.to_string()));
+                    return;
+                }
+            };
+            let reached = hop.reached;
+            if sender.send(TraceEvent::Hop(hop)).is_err() || reached {
+                return;
+            }
+        }
+    });
+    app.traceroute = Some(Traceroute {
+        query,
+        address: None,
+        hops: Vec::new(),
+        error: None,
+        running: Some(receiver),
+    });
+    switch_screen(app, Screen::Latency);
+}
+
+pub fn update_traceroute(app: &mut App) {
+    let Some(trace) = &mut app.traceroute else {
+        return;
+    };
+    let Some(receiver) = &trace.running else {
+        return;
+    };
+    loop {
+        match receiver.try_recv() {
+            Ok(TraceEvent::Resolved(address)) => trace.address = Some(address),
+            Ok(TraceEvent::Hop(hop)) => trace.hops.push(hop),
+            Ok(TraceEvent::Failed(e)) => trace.error = Some(e),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                trace.running = None;
+                return;
+            }
+        }
+    }
+}
+
+const TRACEROUTE_WIDTHS: [Constraint; 5] = [
+    Constraint::Length(4),
+    Constraint::Min(16),
+    Constraint::Length(10),
+    Constraint::Length(10),
+    Constraint::Length(10),
+];
+
+pub fn create_traceroute_table<'a>(trace: &Traceroute, theme: &Theme) -> Table<'a> {
+    let rows: Vec<Row> = trace
+        .hops
+        .iter()
+        .map(|hop| {
+            let mut cells = vec![
+                hop.ttl.to_string(),
+                hop.address
+                    .map_or("*".to_string(), |address| address.to_string()),
+            ];
+            cells.extend(hop.rtts.iter().map(|rtt| match rtt {
+                Some(ms) => format!("{ms:.1} ms"),
+                None => "*".to_string(),
+            }));
+            Row::new(cells)
+        })
+        .collect();
+    let destination = match trace.address {
+        Some(address) if address.to_string() != trace.query => {
+            format!("{} ({address})", trace.query)
+        }
+        _ => trace.query.to_string(),
+    };
+    let state = match (&trace.error, &trace.running) {
+        (Some(e), _) => e.to_string(),
+        (None, Some(_)) => "running".to_string(),
+        (None, None) if trace.hops.last().is_some_and(|hop| hop.reached) => {
+            match trace.hops.last().and_then(|hop| hop.address) == trace.address {
+                true => "done".to_string(),
+                false => "target unreachable".to_string(),
+            }
+        }
+        (None, None) => format!("target not reached in {MAX_HOPS} hops"),
+    };
+    let block = Block::default()
+        .title(Span::styled(
+            format!("Traceroute to {destination}, {state} (esc to close)"),
+            theme.title,
+        ))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Table::new(rows)
+        .header(Row::new(vec!["Hop", "Address", "1", "2", "3"]).style(theme.title))
+        .style(theme.text)
+        .block(block)
+        .widths(&TRACEROUTE_WIDTHS)
+}
+
+#[cfg(not(target_os = "linux"))]
+mod probe {
+    use std::{io, net::IpAddr};
+
+    use super::Hop;
+
+    pub fn hop(_address: IpAddr, _ttl: u8) -> io::Result<Hop> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "traceroute is only supported on Linux",
+        ))
+    }
+}
+
+// UDP probes with a limited hop count. With IP_RECVERR the kernel queues
+// the ICMP time exceeded and port unreachable answers on the sending
+// socket's error queue, like tracepath(8), so no raw socket is needed.
+#[cfg(target_os = "linux")]
+mod probe {
+    use std::{
+        io, mem,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket},
+        os::fd::AsRawFd,
+        time::{Duration, Instant},
+    };
+
+    use super::Hop;
+
+    // probes sent to each hop
+    const PROBES: usize = 3;
+    const HOP_TIMEOUT: Duration = Duration::from_secs(1);
+    // destination ports above this, like traceroute(8), which nothing listens on
+    const BASE_PORT: u16 = 33434;
+
+    const TIME_EXCEEDED_V4: u8 = 11;
+    const UNREACHABLE_V4: u8 = 3;
+    const TIME_EXCEEDED_V6: u8 = 3;
+    const UNREACHABLE_V6: u8 = 1;
+
+    struct Reply {
+        from: IpAddr,
+        rtt: f64,
+        reached: bool,
+    }
+
+    pub fn hop(address: IpAddr, ttl: u8) -> io::Result<Hop> {
+        let mut probes = Vec::new();
+        for i in 0..PROBES {
+            let port = BASE_PORT + (ttl as u16 - 1) * PROBES as u16 + i as u16;
+            probes.push((send(address, ttl, port)?, Instant::now()));
+        }
+        let mut replies: Vec<Option<Reply>> = (0..PROBES).map(|_| None).collect();
+        let deadline = Instant::now() + HOP_TIMEOUT;
+        while replies.iter().any(Option::is_none) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            // errors are reported as POLLERR without asking for events
+            let mut fds: Vec<libc::pollfd> = probes
+                .iter()
+                .map(|(socket, _)| libc::pollfd {
+                    fd: socket.as_raw_fd(),
+                    events: 0,
+                    revents: 0,
+                })
+                .collect();
+            let ready = unsafe {
+                libc::poll(
+                    fds.as_mut_ptr(),
+                    fds.len() as libc::nfds_t,
+                    left.as_millis() as libc::c_int,
+                )
+            };
+            if ready < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            for (i, fd) in fds.iter().enumerate() {
+                if fd.revents & libc::POLLERR != 0 && replies[i].is_none() {
+                    let (socket, sent) = &probes[i];
+                    replies[i] = read_error(socket)?.map(|(from, reached)| Reply {
+                        from,
+                        rtt: sent.elapsed().as_secs_f64() * 1000.0,
+                        reached,
+                    });
+                }
+            }
+        }
+        Ok(Hop {
+            ttl,
+            address: replies.iter().flatten().map(|reply| reply.from).next(),
+            reached: replies.iter().flatten().any(|reply| reply.reached),
+            rtts: replies
+                .iter()
+                .map(|reply| reply.as_ref().map(|r| r.rtt))
+                .collect(),
+        })
+    }
+
+    fn send(address: IpAddr, ttl: u8, port: u16) -> io::Result<UdpSocket> {
+        let (bind, level, recverr, hops) = match address {
+            IpAddr::V4(_) => (
+                "0.0.0.0:0",
+                libc::IPPROTO_IP,
+                libc::IP_RECVERR,
+                libc::IP_TTL,
+            ),
+            IpAddr::V6(_) => (
+                "[::]:0",
+                libc::IPPROTO_IPV6,
+                libc::IPV6_RECVERR,
+                libc::IPV6_UNICAST_HOPS,
+            ),
+        };
+        let socket = UdpSocket::bind(bind)?;
+        setsockopt(&socket, level, recverr, 1)?;
+        setsockopt(&socket, level, hops, ttl as libc::c_int)?;
+        socket.set_nonblocking(true)?;
+        socket.connect((address, port))?;
+        socket.send(b"net-stat")?;
+        Ok(socket)
+    }
+
+    fn setsockopt(
+        socket: &UdpSocket,
+        level: libc::c_int,
+        name: libc::c_int,
+        value: libc::c_int,
+    ) -> io::Result<()> {
+        let set = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                level,
+                name,
+                &value as *const libc::c_int as *const libc::c_void,
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if set < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Who answered the probe and whether the trace ends there, None for
+    // errors that didn't come from an ICMP message
+    fn read_error(socket: &UdpSocket) -> io::Result<Option<(IpAddr, bool)>> {
+        let mut data = [0u8; 64];
+        let mut control = [0u8; 512];
+        let mut iov = libc::iovec {
+            iov_base: data.as_mut_ptr() as *mut libc::c_void,
+            iov_len: data.len(),
+        };
+        let mut message: libc::msghdr = unsafe { mem::zeroed() };
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        message.msg_controllen = control.len() as _;
+        let received =
+            unsafe { libc::recvmsg(socket.as_raw_fd(), &mut message, libc::MSG_ERRQUEUE) };
+        if received < 0 {
+            let error = io::Error::last_os_error();
+            return match error.kind() {
+                io::ErrorKind::WouldBlock => Ok(None),
+                _ => Err(error),
+            };
+        }
+        let mut header = unsafe { libc::CMSG_FIRSTHDR(&message) };
+        while !header.is_null() {
+            let (level, kind) = unsafe { ((*header).cmsg_level, (*header).cmsg_type) };
+            let recverr = (level == libc::IPPROTO_IP && kind == libc::IP_RECVERR)
+                || (level == libc::IPPROTO_IPV6 && kind == libc::IPV6_RECVERR);
+            if recverr {
+                let error = unsafe { libc::CMSG_DATA(header) } as *const libc::sock_extended_err;
+                let (origin, icmp_type) = unsafe { ((*error).ee_origin, (*error).ee_type) };
+                let offender = unsafe { libc::SO_EE_OFFENDER(error) };
+                let Some(from) = (unsafe { offender_address(offender) }) else {
+                    return Ok(None);
+                };
+                return Ok(match (origin, icmp_type) {
+                    (libc::SO_EE_ORIGIN_ICMP, TIME_EXCEEDED_V4)
+                    | (libc::SO_EE_ORIGIN_ICMP6, TIME_EXCEEDED_V6) => Some((from, false)),
+                    // port unreachable from the target, any other
+                    // unreachable ends the trace as well
+                    (libc::SO_EE_ORIGIN_ICMP, UNREACHABLE_V4)
+                    | (libc::SO_EE_ORIGIN_ICMP6, UNREACHABLE_V6) => Some((from, true)),
+                    _ => None,
+                });
+            }
+            header = unsafe { libc::CMSG_NXTHDR(&message, header) };
+        }
+        Ok(None)
+    }
+
+    unsafe fn offender_address(address: *const libc::sockaddr) -> Option<IpAddr> {
+        match (*address).sa_family as libc::c_int {
+            libc::AF_INET => {
+                let address = &*(address as *const libc::sockaddr_in);
+                Some(IpAddr::V4(Ipv4Addr::from(u32::from_be(
+                    address.sin_addr.s_addr,
+                ))))
+            }
+            libc::AF_INET6 => {
+                let address = &*(address as *const libc::sockaddr_in6);
+                Some(IpAddr::V6(Ipv6Addr::from(address.sin6_addr.s6_addr)))
+            }
+            _ => None,
+        }
+    }
+}
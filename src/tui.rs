@@ -0,0 +1,318 @@
+use std::{mem, time::Duration};
+
+use crossterm::{
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event,
+        Event::{Key, Mouse, Resize},
+        EventStream, MouseButton, MouseEventKind,
+    },
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use futures::StreamExt;
+use ratatui::prelude::{CrosstermBackend, Terminal};
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+
+use crate::{
+    alerts::JsonLinesSink,
+    api::Api,
+    app::{ui, update, App},
+    capabilities::Capabilities,
+    cli::TuiOptions,
+    collector::{self, Collector, Pipeline},
+    collectors::remote::RemoteBackend,
+    config::Config,
+    damage::mark_drawn,
+    error::{Error, Result, ResultExt},
+    export::{ExportWorker, Exporter},
+    hooks::HookSink,
+    http::{answer, next_request, HttpApi},
+    input::key_action,
+    journal::{JournalSink, LogTarget},
+    picker::open_picker,
+    replay::{self, Recorder},
+    stream, Action,
+};
+
+pub fn startup(capabilities: &Capabilities) -> Result<()> {
+    enable_raw_mode().or_ui(|| "failed to enable raw mode")?;
+    execute!(std::io::stderr(), EnterAlternateScreen)
+        .or_ui(|| "failed to enter the alternate screen")?;
+    if capabilities.mouse {
+        execute!(std::io::stderr(), EnableMouseCapture)
+            .or_ui(|| "failed to enable mouse capture")?;
+    }
+    Ok(())
+}
+
+pub fn shutdown(capabilities: &Capabilities) -> Result<()> {
+    if capabilities.mouse {
+        execute!(std::io::stderr(), DisableMouseCapture)
+            .or_ui(|| "failed to disable mouse capture")?;
+    }
+    execute!(std::io::stderr(), LeaveAlternateScreen)
+        .or_ui(|| "failed to leave the alternate screen")?;
+    disable_raw_mode().or_ui(|| "failed to disable raw mode")?;
+    Ok(())
+}
+
+// Interval between collector samples
+pub const TICK_RATE: Duration = Duration::from_millis(250);
+
+pub fn event_action(app: &mut App, event: Event) -> Action {
+    match event {
+        Key(key) if app.macros.contains_key(&key.code) => Action::RunMacro(key.code),
+        Key(key) => key_action(app, key),
+        Mouse(mouse) => match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => Action::Click(mouse.column, mouse.row),
+            MouseEventKind::ScrollDown => Action::SelectNext,
+            MouseEventKind::ScrollUp => Action::SelectPrev,
+            _ => Action::None,
+        },
+        Resize(..) => Action::Resize,
+        _ => Action::None,
+    }
+}
+
+// Terminal events, collector samples and signals are separate streams merged
+// here, network tasks can add their own without blocking the UI
+pub async fn run(capabilities: Capabilities, options: TuiOptions) -> Result<App> {
+    // ratatui terminal
+    let mut t = Terminal::new(CrosstermBackend::new(std::io::stderr()))
+        .or_ui(|| "failed to set up the terminal")?;
+
+    let mut app = open_app(capabilities, &options)?;
+    if options.pick {
+        open_picker(&mut app);
+    }
+
+    // secondary instances attach through the local API, a second primary
+    // instance simply runs without one. Attached instances expect this
+    // machine's counters, so a remote session doesn't serve them.
+    let api = match options.is_remote() {
+        true => None,
+        false => Api::start().ok(),
+    };
+    let mut http = match &app.config.http_api {
+        Some(bind) => Some(HttpApi::start(bind).await?),
+        None => None,
+    };
+    let mut hangup = Signals::hangup()?;
+    let queue = app.config.pipeline_queue;
+    let pipeline = Pipeline::default();
+    let mut samples = pipeline.subscribe("ui", queue);
+    if let Some(api) = &api {
+        api.serve(pipeline.subscribe("api", queue));
+    }
+    start_sampling(&mut app, &options, &pipeline).await?;
+    let mut events = EventStream::new();
+
+    loop {
+        let action = tokio::select! {
+            _ = hangup.recv() => Action::Detach,
+            // answered from the current state, nothing to redraw
+            request = next_request(&mut http) => {
+                answer(&app, request);
+                continue;
+            }
+            snapshot = samples.recv() => match snapshot {
+                Some(snapshot) => Action::Tick(snapshot),
+                None => return Err(stopped(&pipeline)),
+            },
+            // the event stream fails or ends once the terminal is gone
+            event = events.next() => match event {
+                Some(Ok(event)) => event_action(&mut app, event),
+                _ => Action::Detach,
+            },
+        };
+        // nothing changed, wait for the next event without redrawing
+        if matches!(action, Action::None) {
+            continue;
+        }
+
+        // application update
+        let sample = matches!(action, Action::Tick(_));
+        update(&mut app, action);
+
+        // application exit, there is nothing left to draw to once detached
+        if app.should_quit {
+            break;
+        }
+
+        // samples that change nothing on screen wait for the next redraw
+        if sample && !app.damage.needs_redraw() {
+            continue;
+        }
+
+        // application render
+        t.draw(|f| {
+            ui(f, &mut app);
+        })
+        .or_ui(|| "failed to draw")?;
+        mark_drawn(&mut app);
+    }
+
+    finish_sampling(&mut app, &pipeline);
+    Ok(app)
+}
+
+// The config with the command line applied and the alert sinks it names,
+// shared by the TUI and the plain-text accessible mode
+pub fn open_app(capabilities: Capabilities, options: &TuiOptions) -> Result<App> {
+    let mut config = Config::load()?;
+    if options.is_remote() {
+        // the stored history belongs to this machine's interfaces
+        config.persist_history = false;
+    }
+    if let Some(backend) = options.backend {
+        config.backend = backend;
+    }
+    if options.replay.is_some() {
+        // recorded traffic doesn't count towards this month's quotas
+        for interface in config.interfaces.values_mut() {
+            interface.quota = None;
+        }
+        // nor is the canary probed, it would be set against recorded rates
+        config.canary = Default::default();
+    }
+    let mut app = App::new(config, capabilities)?;
+    app.remote = options.remote.clone().or(options.connect.clone());
+    if let Some(path) = &options.alerts_json {
+        app.alert_sinks.push(Box::new(JsonLinesSink::open(path)?));
+    }
+    if options.replay.is_some() {
+        // alerts and exports of a recording would read as happening now,
+        // only --alerts-json asks for them explicitly
+        app.exporter = Exporter::default();
+        return Ok(app);
+    }
+    if let Some(target) = &app.config.alert_log {
+        let sink = JournalSink::open(LogTarget::parse(target)?)?;
+        app.alert_sinks.push(Box::new(sink));
+    }
+    if !app.config.hooks.is_empty() {
+        app.alert_sinks
+            .push(Box::new(HookSink::spawn(&app.config.hooks)));
+    }
+    Ok(app)
+}
+
+// Starts the exporter and the samples from wherever the options point.
+// Everything reading the pipeline subscribes before this so no sample is
+// missed.
+pub async fn start_sampling(
+    app: &mut App,
+    options: &TuiOptions,
+    pipeline: &Pipeline,
+) -> Result<()> {
+    if !app.exporter.is_empty() {
+        let exporter = mem::take(&mut app.exporter);
+        let subscription = pipeline.subscribe("export", app.config.pipeline_queue);
+        app.export_worker = Some(ExportWorker::spawn(
+            exporter,
+            app.config.clone(),
+            subscription,
+        ));
+    }
+    if let Some(path) = &options.record {
+        let subscription = pipeline.subscribe("record", app.config.pipeline_queue);
+        app.recorder = Some(Recorder::spawn(path, subscription)?);
+    }
+    match (&options.replay, &options.remote, &options.connect) {
+        (Some(path), ..) => app.replay = Some(replay::spawn(pipeline, replay::open(path)?)),
+        (_, Some(target), _) => {
+            let backend = RemoteBackend::open(target)?;
+            collector::spawn(
+                pipeline,
+                TICK_RATE,
+                Collector::with_backend(Box::new(backend)),
+            );
+        }
+        (.., Some(address)) => stream::connect(pipeline, address).await?,
+        _ => collector::spawn(pipeline, TICK_RATE, Collector::new(app.config.backend)?),
+    }
+    app.pipeline = Some(pipeline.clone());
+    Ok(())
+}
+
+// Lets the exporter and recorder write out what they were sent before
+// exiting
+pub fn finish_sampling(app: &mut App, pipeline: &Pipeline) {
+    pipeline.close();
+    if let Some(worker) = app.export_worker.take() {
+        worker.finish();
+    }
+    if let Some(recorder) = app.recorder.take() {
+        recorder.finish();
+    }
+}
+
+// What ended the samples, once the pipeline has no more
+pub fn stopped(pipeline: &Pipeline) -> Error {
+    Error::backend(
+        pipeline
+            .stop_reason()
+            .unwrap_or_else(|| "the collector thread stopped".to_string()),
+    )
+}
+
+// Signals a run stops on. Only unix has them, elsewhere ctrl-c stands in for
+// all of them and a closed console ends the event stream instead of a hangup.
+pub struct Signals {
+    #[cfg(unix)]
+    signals: Vec<Signal>,
+}
+
+impl Signals {
+    // SIGHUP, the terminal went away
+    #[cfg(unix)]
+    pub fn hangup() -> Result<Signals> {
+        Signals::watch(&[(SignalKind::hangup(), "terminal hangups")])
+    }
+
+    // SIGINT and SIGTERM
+    #[cfg(unix)]
+    pub fn termination() -> Result<Signals> {
+        Signals::watch(&[
+            (SignalKind::interrupt(), "ctrl-c"),
+            (SignalKind::terminate(), "termination"),
+        ])
+    }
+
+    #[cfg(unix)]
+    fn watch(kinds: &[(SignalKind, &str)]) -> Result<Signals> {
+        let signals = kinds
+            .iter()
+            .map(|(kind, name)| signal(*kind).or_ui(|| format!("failed to watch for {name}")))
+            .collect::<Result<_>>()?;
+        Ok(Signals { signals })
+    }
+
+    #[cfg(not(unix))]
+    pub fn hangup() -> Result<Signals> {
+        Ok(Signals {})
+    }
+
+    #[cfg(not(unix))]
+    pub fn termination() -> Result<Signals> {
+        Ok(Signals {})
+    }
+
+    #[cfg(unix)]
+    pub async fn recv(&mut self) {
+        let signals = self
+            .signals
+            .iter_mut()
+            .map(|signal| Box::pin(signal.recv()));
+        futures::future::select_all(signals).await;
+    }
+
+    // never resolves when ctrl-c can't be watched for
+    #[cfg(not(unix))]
+    pub async fn recv(&mut self) {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
@@ -0,0 +1,152 @@
+#[cfg(unix)]
+use libc::mktime;
+
+const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+// Human readable byte count, e.g. 1536 -> "1.5 KB"
+pub fn format_bytes(bytes: f64) -> String {
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value:.0} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+pub fn format_rate(bytes_per_second: f64) -> String {
+    format!("{}/s", format_bytes(bytes_per_second))
+}
+
+// Compact duration for axis labels, e.g. 90 -> "90s", 3600 -> "60m", 18000 -> "5h"
+pub fn format_duration(seconds: f64) -> String {
+    if seconds < 120.0 {
+        format!("{seconds:.0}s")
+    } else if seconds < 4.0 * 3600.0 {
+        format!("{:.0}m", seconds / 60.0)
+    } else {
+        format!("{:.0}h", seconds / 3600.0)
+    }
+}
+
+// Parse sizes like "10 GB", "512M" or "1.5TiB", using the same 1024 based
+// units as `format_bytes`
+pub fn parse_bytes(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let unit = unit.trim().to_ascii_uppercase();
+    let unit = unit.trim_end_matches('B').trim_end_matches('I');
+    let exponent = match unit {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => return None,
+    };
+    Some((number * 1024f64.powi(exponent)) as u64)
+}
+
+// Bytes per second from a network speed in bits with decimal prefixes, e.g.
+// "1 Gb/s", "100 Mbit/s" or "10 Mbps", or from bytes as format_rate writes
+// them, e.g. "12.5 MB/s"
+pub fn parse_rate(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let text = text
+        .strip_suffix("/s")
+        .or_else(|| text.strip_suffix("ps"))
+        .unwrap_or(text)
+        .trim_end();
+    let Some(bits) = text.strip_suffix("bit").or_else(|| text.strip_suffix('b')) else {
+        return parse_bytes(text).map(|bytes| bytes as f64);
+    };
+    let split = bits
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(bits.len());
+    let (number, prefix) = bits.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let exponent = match prefix.trim().to_ascii_uppercase().as_str() {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => return None,
+    };
+    Some(number * 1000f64.powi(exponent) / 8.0)
+}
+
+fn local_time(unix: f64) -> libc::tm {
+    let time = unix as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        #[cfg(unix)]
+        libc::localtime_r(&time, &mut tm);
+        #[cfg(windows)]
+        libc::localtime_s(&mut tm, &time);
+    }
+    tm
+}
+
+// missing from libc's bindings for the Windows CRT
+#[cfg(windows)]
+extern "C" {
+    #[link_name = "_mktime64"]
+    fn mktime(tm: *mut libc::tm) -> libc::time_t;
+}
+
+// Local midnight on `day` of the month `months` after the one `unix` falls
+// in, mktime normalizes months outside the year
+fn month_day(unix: f64, months: i32, day: u32) -> f64 {
+    let mut tm = local_time(unix);
+    tm.tm_mon += months;
+    tm.tm_mday = day as i32;
+    tm.tm_hour = 0;
+    tm.tm_min = 0;
+    tm.tm_sec = 0;
+    tm.tm_isdst = -1;
+    unsafe { mktime(&mut tm) as f64 }
+}
+
+// Start and end of the monthly period resetting on `day` that `unix` falls in
+pub fn monthly_period(unix: f64, day: u32) -> (f64, f64) {
+    let months = if local_time(unix).tm_mday >= day as i32 {
+        0
+    } else {
+        -1
+    };
+    (
+        month_day(unix, months, day),
+        month_day(unix, months + 1, day),
+    )
+}
+
+// Local date of a unix timestamp, e.g. "2024-05-01"
+pub fn format_date(unix: f64) -> String {
+    let tm = local_time(unix);
+    format!(
+        "{}-{:02}-{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday
+    )
+}
+
+// Local wall clock time of a unix timestamp, e.g. "14:03:27"
+pub fn format_clock(unix: f64) -> String {
+    let tm = local_time(unix);
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+// Local date and time of a unix timestamp, e.g. "2024-05-01 14:03:27"
+pub fn format_timestamp(unix: f64) -> String {
+    format!("{} {}", format_date(unix), format_clock(unix))
+}
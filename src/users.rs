@@ -0,0 +1,184 @@
+use std::{collections::HashMap, fs, net::SocketAddr};
+
+use ratatui::{
+    prelude::Constraint,
+    text::Span,
+    widgets::{Block, Borders, Row, Table},
+};
+
+use crate::{
+    app::App,
+    error::Result,
+    sockdiag::SocketBytes,
+    theme::Theme,
+    units::{format_bytes, format_rate},
+};
+
+pub struct UserTraffic {
+    pub uid: u32,
+    pub name: String,
+    pub sockets: usize,
+    pub sent_rate: f64,
+    pub rec_rate: f64,
+    pub sent_total: u64,
+    pub rec_total: u64,
+}
+
+pub type SocketKey = (u64, SocketAddr, SocketAddr);
+
+// Attributes socket traffic to the owning user by diffing per-socket byte
+// counters between ticks
+#[derive(Default)]
+pub struct UserTracker {
+    previous: Option<HashMap<SocketKey, (u64, u64)>>,
+    totals: HashMap<u32, (u64, u64)>,
+    names: HashMap<u32, String>,
+    pub users: Vec<UserTraffic>,
+    pub error: Option<String>,
+}
+
+impl UserTracker {
+    pub fn update(&mut self, sockets: Result<Vec<SocketBytes>>, elapsed: f64) {
+        let sockets = match sockets {
+            Ok(sockets) => sockets,
+            Err(e) => {
+                self.error = Some(format!("{e:#}"));
+                return;
+            }
+        };
+        self.error = None;
+        if self.names.is_empty() {
+            self.names = read_user_names();
+        }
+
+        let current: HashMap<SocketKey, (u64, u64)> = sockets
+            .iter()
+            .map(|socket| (socket_key(socket), (socket.sent, socket.rec)))
+            .collect();
+        let mut users: HashMap<u32, UserTraffic> = HashMap::new();
+        for socket in &sockets {
+            // sockets opened since the last tick count from zero, everything
+            // seen on the very first tick only establishes the starting point
+            let (sent, rec) = match &self.previous {
+                Some(previous) => {
+                    let (sent, rec) = previous.get(&socket_key(socket)).copied().unwrap_or((0, 0));
+                    (
+                        socket.sent.saturating_sub(sent),
+                        socket.rec.saturating_sub(rec),
+                    )
+                }
+                None => (0, 0),
+            };
+            let total = self.totals.entry(socket.uid).or_default();
+            total.0 += sent;
+            total.1 += rec;
+
+            let user = users.entry(socket.uid).or_insert_with(|| UserTraffic {
+                uid: socket.uid,
+                name: self
+                    .names
+                    .get(&socket.uid)
+                    .cloned()
+                    .unwrap_or_else(|| socket.uid.to_string()),
+                sockets: 0,
+                sent_rate: 0.0,
+                rec_rate: 0.0,
+                sent_total: 0,
+                rec_total: 0,
+            });
+            user.sockets += 1;
+            if elapsed > 0.0 {
+                user.sent_rate += sent as f64 / elapsed;
+                user.rec_rate += rec as f64 / elapsed;
+            }
+        }
+        self.previous = Some(current);
+
+        let mut users: Vec<UserTraffic> = users
+            .into_values()
+            .map(|mut user| {
+                let (sent, rec) = self.totals.get(&user.uid).copied().unwrap_or_default();
+                user.sent_total = sent;
+                user.rec_total = rec;
+                user
+            })
+            .collect();
+        users.sort_by(|a, b| {
+            (b.sent_rate + b.rec_rate)
+                .total_cmp(&(a.sent_rate + a.rec_rate))
+                .then((b.sent_total + b.rec_total).cmp(&(a.sent_total + a.rec_total)))
+        });
+        self.users = users;
+    }
+}
+
+pub fn socket_key(socket: &SocketBytes) -> SocketKey {
+    (socket.inode, socket.local, socket.remote)
+}
+
+fn read_user_names() -> HashMap<u32, String> {
+    fs::read_to_string("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            Some((fields.get(2)?.parse().ok()?, fields.first()?.to_string()))
+        })
+        .collect()
+}
+
+pub fn update_users(app: &mut App, sockets: Result<Vec<SocketBytes>>, elapsed: f64) {
+    app.user_traffic.update(sockets, elapsed);
+}
+
+const USER_WIDTHS: [Constraint; 7] = [
+    Constraint::Percentage(20),
+    Constraint::Length(8),
+    Constraint::Length(8),
+    Constraint::Length(12),
+    Constraint::Length(12),
+    Constraint::Length(12),
+    Constraint::Length(12),
+];
+
+pub fn create_users_table<'a>(tracker: &UserTracker, theme: &Theme) -> Table<'a> {
+    let rows: Vec<Row> = tracker
+        .users
+        .iter()
+        .map(|user| {
+            Row::new(vec![
+                user.name.to_string(),
+                user.uid.to_string(),
+                user.sockets.to_string(),
+                format_rate(user.sent_rate),
+                format_rate(user.rec_rate),
+                format_bytes(user.sent_total as f64),
+                format_bytes(user.rec_total as f64),
+            ])
+        })
+        .collect();
+    let title = match &tracker.error {
+        Some(error) => Span::styled(format!("Traffic by user ({error})"), theme.alert),
+        None => Span::styled("Traffic by user (TCP)", theme.title),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Table::new(rows)
+        .header(
+            Row::new(vec![
+                "User",
+                "UID",
+                "Sockets",
+                "Sent",
+                "Recieved",
+                "Total sent",
+                "Total rec",
+            ])
+            .style(theme.title),
+        )
+        .style(theme.text)
+        .block(block)
+        .widths(&USER_WIDTHS)
+}
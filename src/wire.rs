@@ -0,0 +1,201 @@
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use sysinfo::MacAddr;
+
+use crate::{
+    collector::Snapshot,
+    collectors::parse_mac,
+    error::{Error, Result, ResultExt},
+    history::unix_time,
+    iftype::InterfaceType,
+    networks::{InterfaceData, PacketCounters, PacketErrors, RawCounters},
+};
+
+// Version of the JSON sample format, bumped when fields change meaning.
+// Fields may be added without a bump, readers ignore what they don't know.
+pub const VERSION: u32 = 1;
+
+// One collector sample as sent by `net-stat serve`, a single JSON object per
+// line or WebSocket message. Counters are raw, each reader applies its own
+// calibration.
+#[derive(Serialize, Deserialize)]
+pub struct WireSnapshot {
+    pub version: u32,
+    // unix time the sample was taken
+    pub time: f64,
+    pub interfaces: Vec<WireInterface>,
+}
+
+// bytes, packets, multicast, broadcast, errors and drops since the previous
+// sample, and the totals since boot
+#[derive(Serialize, Deserialize)]
+pub struct WireInterface {
+    pub name: String,
+    pub mac: String,
+    pub sent: u64,
+    pub rec: u64,
+    pub sent_total: u64,
+    pub rec_total: u64,
+    #[serde(default)]
+    pub sent_packets: u64,
+    #[serde(default)]
+    pub rec_packets: u64,
+    #[serde(default)]
+    pub sent_packets_total: u64,
+    #[serde(default)]
+    pub rec_packets_total: u64,
+    #[serde(default)]
+    pub multicast: u64,
+    #[serde(default)]
+    pub multicast_total: u64,
+    // left out where the driver counts no broadcasts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broadcast: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broadcast_total: Option<u64>,
+    #[serde(default)]
+    pub rx_errors: u64,
+    #[serde(default)]
+    pub tx_errors: u64,
+    #[serde(default)]
+    pub rx_errors_total: u64,
+    #[serde(default)]
+    pub tx_errors_total: u64,
+    #[serde(default)]
+    pub rx_dropped: u64,
+    #[serde(default)]
+    pub tx_dropped: u64,
+    #[serde(default)]
+    pub rx_dropped_total: u64,
+    #[serde(default)]
+    pub tx_dropped_total: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carrier_changes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // guessed from the name when an older peer leaves it out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<InterfaceType>,
+}
+
+impl From<&InterfaceData> for WireInterface {
+    fn from(interface: &InterfaceData) -> Self {
+        let raw = interface.raw;
+        let errors = interface.errors;
+        let packets = interface.packets;
+        WireInterface {
+            name: interface.name.to_string(),
+            mac: interface.mac.to_string(),
+            sent: raw.sent,
+            rec: raw.rec,
+            sent_total: raw.sent_total,
+            rec_total: raw.rec_total,
+            sent_packets: packets.sent,
+            rec_packets: packets.rec,
+            sent_packets_total: packets.sent_total,
+            rec_packets_total: packets.rec_total,
+            multicast: packets.multicast,
+            multicast_total: packets.multicast_total,
+            broadcast: packets.broadcast,
+            broadcast_total: packets.broadcast_total,
+            rx_errors: errors.rx_errors,
+            tx_errors: errors.tx_errors,
+            rx_errors_total: errors.rx_errors_total,
+            tx_errors_total: errors.tx_errors_total,
+            rx_dropped: errors.rx_dropped,
+            tx_dropped: errors.tx_dropped,
+            rx_dropped_total: errors.rx_dropped_total,
+            tx_dropped_total: errors.tx_dropped_total,
+            carrier_changes: interface.carrier_changes,
+            description: interface.description.clone(),
+            kind: Some(interface.kind),
+        }
+    }
+}
+
+impl From<WireInterface> for InterfaceData {
+    fn from(interface: WireInterface) -> Self {
+        let bytes = parse_mac(&interface.mac).unwrap_or_default();
+        let raw = RawCounters {
+            sent_total: interface.sent_total,
+            rec_total: interface.rec_total,
+            sent: interface.sent,
+            rec: interface.rec,
+        };
+        let kind = interface
+            .kind
+            .unwrap_or_else(|| InterfaceType::guess(&interface.name));
+        InterfaceData {
+            name: interface.name,
+            sent_total: raw.sent_total,
+            rec_total: raw.rec_total,
+            sent: raw.sent,
+            rec: raw.rec,
+            mac: MacAddr(bytes),
+            raw,
+            errors: PacketErrors {
+                rx_errors: interface.rx_errors,
+                tx_errors: interface.tx_errors,
+                rx_errors_total: interface.rx_errors_total,
+                tx_errors_total: interface.tx_errors_total,
+                rx_dropped: interface.rx_dropped,
+                tx_dropped: interface.tx_dropped,
+                rx_dropped_total: interface.rx_dropped_total,
+                tx_dropped_total: interface.tx_dropped_total,
+            },
+            packets: PacketCounters {
+                sent: interface.sent_packets,
+                rec: interface.rec_packets,
+                sent_total: interface.sent_packets_total,
+                rec_total: interface.rec_packets_total,
+                multicast: interface.multicast,
+                multicast_total: interface.multicast_total,
+                broadcast: interface.broadcast,
+                broadcast_total: interface.broadcast_total,
+            },
+            carrier_changes: interface.carrier_changes,
+            description: interface.description,
+            kind,
+        }
+    }
+}
+
+// Without the trailing newline
+pub fn encode(snapshot: &Snapshot) -> Result<String> {
+    encode_at(snapshot, unix_time())
+}
+
+// As taken at unix time `time`
+pub fn encode_at(snapshot: &Snapshot, time: f64) -> Result<String> {
+    let wire = WireSnapshot {
+        version: VERSION,
+        time,
+        interfaces: snapshot.interfaces.iter().map(Into::into).collect(),
+    };
+    serde_json::to_string(&wire).or_export(|| "failed to encode sample")
+}
+
+// The snapshot counts as taken when it arrives
+pub fn decode(line: &str) -> Result<Snapshot> {
+    let wire = parse(line).map_err(|e| e.context("streamed sample"))?;
+    Ok(Snapshot {
+        taken: Instant::now(),
+        recorded: None,
+        interfaces: wire.interfaces.into_iter().map(Into::into).collect(),
+        sockets: Err(Error::backend(
+            "socket statistics are not streamed with the samples",
+        )),
+    })
+}
+
+pub fn parse(line: &str) -> Result<WireSnapshot> {
+    let wire: WireSnapshot = serde_json::from_str(line).or_backend(|| "malformed JSON")?;
+    if wire.version != VERSION {
+        return Err(Error::backend(format!(
+            "format version {}, expected {VERSION}",
+            wire.version
+        )));
+    }
+    Ok(wire)
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+use ratatui::{
+    prelude::Constraint,
+    style::Modifier,
+    text::Span,
+    widgets::{Block, Borders, Row, Table},
+};
+use serde::Deserialize;
+
+use crate::{
+    app::App,
+    collectors::{format_mac, parse_mac},
+    error::{Error, Result, ResultExt},
+    theme::Theme,
+};
+
+// A machine that can be woken, from the config file or the ARP table
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct HostConfig {
+    pub mac: String,
+    // where the magic packet goes, defaults to 255.255.255.255
+    pub broadcast: Option<String>,
+}
+
+pub struct Host {
+    pub name: Option<String>,
+    pub mac: [u8; 6],
+    pub address: Option<IpAddr>,
+    pub interface: Option<String>,
+    pub broadcast: Ipv4Addr,
+}
+
+#[derive(Default)]
+pub struct Hosts {
+    pub hosts: Vec<Host>,
+    pub selected: usize,
+    // outcome of the last wake attempt
+    pub status: Option<String>,
+}
+
+impl Hosts {
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.hosts.len().saturating_sub(1));
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+}
+
+pub fn validate_host(name: &str, host: &HostConfig) -> Result<()> {
+    parse_mac(&host.mac)
+        .ok_or_else(|| Error::config(format!("invalid MAC \"{}\" for host {name}", host.mac)))?;
+    if let Some(broadcast) = &host.broadcast {
+        broadcast.parse::<Ipv4Addr>().map_err(|_| {
+            Error::config(format!(
+                "invalid broadcast address \"{broadcast}\" for host {name}"
+            ))
+        })?;
+    }
+    Ok(())
+}
+
+// Complete entries of /proc/net/arp as (address, mac, interface)
+fn read_arp() -> Vec<(IpAddr, [u8; 6], String)> {
+    fs::read_to_string("/proc/net/arp")
+        .unwrap_or_default()
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // flags 0x0 marks an incomplete entry
+            if fields.get(2) == Some(&"0x0") {
+                return None;
+            }
+            let mac = parse_mac(fields.get(3)?).filter(|mac| *mac != [0; 6])?;
+            Some((
+                fields.first()?.parse().ok()?,
+                mac,
+                fields.get(5)?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+// Configured hosts first, by name, then neighbours only known from ARP
+pub fn update_hosts(app: &mut App) {
+    let arp = read_arp();
+    let mut configured: Vec<(&String, &HostConfig)> = app.config.hosts.iter().collect();
+    configured.sort_by_key(|(name, _)| name.as_str());
+    let mut hosts: Vec<Host> = configured
+        .into_iter()
+        .filter_map(|(name, config)| {
+            let mac = parse_mac(&config.mac)?;
+            let neighbour = arp.iter().find(|(_, arp_mac, _)| *arp_mac == mac);
+            Some(Host {
+                name: Some(name.to_string()),
+                mac,
+                address: neighbour.map(|(address, _, _)| *address),
+                interface: neighbour.map(|(_, _, interface)| interface.to_string()),
+                broadcast: config
+                    .broadcast
+                    .as_deref()
+                    .and_then(|broadcast| broadcast.parse().ok())
+                    .unwrap_or(Ipv4Addr::BROADCAST),
+            })
+        })
+        .collect();
+    let mut seen: HashMap<[u8; 6], ()> = hosts.iter().map(|host| (host.mac, ())).collect();
+    for (address, mac, interface) in arp {
+        if seen.insert(mac, ()).is_none() {
+            hosts.push(Host {
+                name: None,
+                mac,
+                address: Some(address),
+                interface: Some(interface),
+                broadcast: Ipv4Addr::BROADCAST,
+            });
+        }
+    }
+    app.hosts.hosts = hosts;
+    app.hosts.selected = app
+        .hosts
+        .selected
+        .min(app.hosts.hosts.len().saturating_sub(1));
+}
+
+// 6 bytes of 0xff followed by the MAC repeated 16 times
+fn magic_packet(mac: &[u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xff; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(mac);
+    }
+    packet
+}
+
+fn send_magic_packet(host: &Host) -> Result<()> {
+    let socket =
+        UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).or_backend(|| "failed to open socket")?;
+    socket
+        .set_broadcast(true)
+        .or_backend(|| "failed to enable broadcast")?;
+    socket
+        .send_to(
+            &magic_packet(&host.mac),
+            SocketAddr::new(IpAddr::V4(host.broadcast), 9),
+        )
+        .or_backend(|| format!("failed to send to {}", host.broadcast))?;
+    Ok(())
+}
+
+pub fn wake_selected(app: &mut App) {
+    let Some(host) = app.hosts.hosts.get(app.hosts.selected) else {
+        return;
+    };
+    let mac = format_mac(&host.mac);
+    let name = host.name.as_deref().unwrap_or(&mac);
+    app.hosts.status = Some(match send_magic_packet(host) {
+        Ok(()) => format!("magic packet sent to {name}"),
+        Err(e) => format!("waking {name} failed: {e:#}"),
+    });
+}
+
+const HOST_WIDTHS: [Constraint; 4] = [
+    Constraint::Percentage(25),
+    Constraint::Length(19),
+    Constraint::Length(30),
+    Constraint::Length(16),
+];
+
+pub fn create_hosts_table<'a>(hosts: &Hosts, theme: &Theme) -> Table<'a> {
+    let rows: Vec<Row> = hosts
+        .hosts
+        .iter()
+        .enumerate()
+        .map(|(i, host)| {
+            let row = Row::new(vec![
+                host.name.clone().unwrap_or_default(),
+                format_mac(&host.mac),
+                host.address
+                    .map(|address| address.to_string())
+                    .unwrap_or_default(),
+                host.interface.clone().unwrap_or_default(),
+            ]);
+            if i == hosts.selected {
+                row.style(theme.title.add_modifier(Modifier::REVERSED))
+            } else {
+                row
+            }
+        })
+        .collect();
+    let title = match &hosts.status {
+        Some(status) => format!("Hosts ({status})"),
+        None => "Hosts (w to wake)".to_string(),
+    };
+    let block = Block::default()
+        .title(Span::styled(title, theme.title))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Table::new(rows)
+        .header(Row::new(vec!["Name", "MAC", "Address", "Interface"]).style(theme.title))
+        .style(theme.text)
+        .block(block)
+        .widths(&HOST_WIDTHS)
+}
@@ -0,0 +1,162 @@
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    app::App,
+    carrier::read_carrier,
+    history::Resolution,
+    networks::InterfaceData,
+    quota::{quota_status, QuotaStatus},
+    stats::InterfaceStats,
+    theme::Theme,
+    units::{format_bytes, format_date, format_rate},
+};
+
+const GAUGE_WIDTH: usize = 20;
+
+// A progress bar of the period's usage and where it's heading
+fn quota_lines<'a>(status: &QuotaStatus, unicode: bool, theme: &Theme) -> Vec<Line<'a>> {
+    let fraction = (status.used as f64 / status.quota.max(1) as f64).min(1.0);
+    let filled = (fraction * GAUGE_WIDTH as f64).round() as usize;
+    let (full, empty) = if unicode { ("█", "░") } else { ("#", "-") };
+    let style = if status.overage.is_some() {
+        theme.alert
+    } else {
+        theme.text
+    };
+    let gauge = Line::from(vec![
+        Span::raw("Quota: "),
+        Span::styled(full.repeat(filled), style),
+        Span::styled(empty.repeat(GAUGE_WIDTH - filled), theme.border),
+        Span::raw(format!(
+            " {:.0}%, {} of {} since {}",
+            status.used as f64 * 100.0 / status.quota.max(1) as f64,
+            format_bytes(status.used as f64),
+            format_bytes(status.quota as f64),
+            format_date(status.start)
+        )),
+    ]);
+    let projection = match status.overage {
+        Some(_) if status.used >= status.quota => "Over the cap".to_string(),
+        Some(at) => format!(
+            "Projected: {} by {}, over the cap around {}",
+            format_bytes(status.projected as f64),
+            format_date(status.end),
+            format_date(at)
+        ),
+        None => format!(
+            "Projected: {} by {}",
+            format_bytes(status.projected as f64),
+            format_date(status.end)
+        ),
+    };
+    vec![gauge, Line::styled(projection, style)]
+}
+
+// A broadcast storm shows here long before it shows in the bytes
+fn casts_line(stats: &InterfaceStats, interface: &InterfaceData) -> String {
+    let packets = interface.packets;
+    match (&stats.broadcast, packets.broadcast_total) {
+        (Some(broadcast), Some(broadcast_total)) => format!(
+            "Multicast/broadcast recieved/s: {:.0} / {:.0} (peak {:.0} / {:.0}, total {} / {})",
+            stats.multicast.current(),
+            broadcast.current(),
+            stats.multicast.peak,
+            broadcast.peak,
+            packets.multicast_total,
+            broadcast_total
+        ),
+        _ => format!(
+            "Multicast recieved/s: {:.0} (peak {:.0}, total {}), no broadcast counter",
+            stats.multicast.current(),
+            stats.multicast.peak,
+            packets.multicast_total
+        ),
+    }
+}
+
+pub fn toggle_zoom(app: &mut App) {
+    app.zoomed = !app.zoomed;
+}
+
+// Everything known about one interface that doesn't fit the regular block
+pub fn create_extended_stats<'a>(app: &App, name: &str) -> Paragraph<'a> {
+    let theme = &app.theme;
+    let mut lines = Vec::new();
+    if let Some(stats) = app.stats.get(name) {
+        lines.push(Line::from(format!(
+            "This session: sent {}, recieved {}",
+            format_bytes(stats.sent_bytes as f64),
+            format_bytes(stats.rec_bytes as f64)
+        )));
+    }
+    let interface = app.net_interfaces.iter().find(|i| i.name == name);
+    if let (Some(stats), Some(interface)) = (app.stats.get(name), interface) {
+        lines.push(Line::from(casts_line(stats, interface)));
+    }
+    if let Some(description) = interface.and_then(|i| i.description.as_ref()) {
+        lines.push(Line::from(format!("Adapter: {description}")));
+    }
+    if let Some(carrier) = read_carrier(name) {
+        let style = if carrier.up { theme.text } else { theme.alert };
+        lines.push(Line::styled(carrier.describe(), style));
+    } else if let Some(changes) = interface.and_then(|i| i.carrier_changes) {
+        // no sysfs for remote interfaces, some backends report it anyway
+        lines.push(Line::from(format!("Carrier changes: {changes}")));
+    }
+    if let Some(cable_test) = app.cable_tests.describe(name) {
+        lines.push(Line::from(cable_test));
+    }
+    if let Some(history) = app.history.get(name) {
+        let averages: Vec<String> = Resolution::AGGREGATED
+            .iter()
+            .filter_map(|resolution| {
+                let bucket = history.buckets(*resolution)?.back()?;
+                Some(format!(
+                    "{} {} / {}",
+                    resolution.name(),
+                    format_rate(bucket.sent_avg()),
+                    format_rate(bucket.rec_avg())
+                ))
+            })
+            .collect();
+        if !averages.is_empty() {
+            lines.push(Line::from(format!(
+                "Current averages: {}",
+                averages.join(", ")
+            )));
+        }
+    }
+    if let Some(status) = quota_status(app, name) {
+        lines.extend(quota_lines(&status, app.capabilities.unicode, theme));
+    } else if let (Some(quota), Some(interface)) = (app.config.quota(name), interface) {
+        let used = interface.sent_total + interface.rec_total;
+        lines.push(Line::from(format!(
+            "Quota: {} of {} used ({:.0}%)",
+            format_bytes(used as f64),
+            format_bytes(quota as f64),
+            used as f64 * 100.0 / quota.max(1) as f64
+        )));
+    }
+    let firing = app.alerts.firing(name);
+    if !firing.is_empty() {
+        lines.push(Line::styled(
+            format!("Alerts firing: {}", firing.join(", ")),
+            theme.alert,
+        ));
+    }
+    // most recent first, whatever fits the details block
+    for note in app.notes.for_interface(name).rev() {
+        lines.push(Line::from(format!("Note: {}", note.describe())));
+    }
+    let block = Block::default()
+        .title(Span::styled(
+            format!("{name} details (z to return)"),
+            theme.title,
+        ))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    Paragraph::new(lines).style(theme.text).block(block)
+}
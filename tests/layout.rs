@@ -0,0 +1,638 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use net_stat::{
+    alerts::{AlertEvent, AlertState},
+    app::{switch_screen, ui, update, App, Screen},
+    capabilities::Capabilities,
+    collector::{Pipeline, Snapshot},
+    config::{Config, InterfaceConfig},
+    iftype::InterfaceType,
+    input::{key_action, PromptKind},
+    latency::LatencySample,
+    layout::LayoutMode,
+    networks::{InterfaceData, PacketCounters, PacketErrors, RawCounters},
+    picker::open_picker,
+    replay,
+    route::{lookup_route, update_route},
+    sockdiag::SocketBytes,
+    units::format_timestamp,
+    Action,
+};
+use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+use sysinfo::MacAddr;
+
+const SIZES: [(u16, u16); 9] = [
+    (40, 10),
+    (40, 24),
+    (60, 15),
+    (80, 10),
+    (80, 24),
+    (100, 30),
+    (120, 40),
+    (200, 60),
+    (300, 100),
+];
+const INTERFACE_COUNTS: [usize; 6] = [0, 1, 2, 5, 20, 100];
+const LAYOUTS: [LayoutMode; 3] = [LayoutMode::List, LayoutMode::Grid, LayoutMode::Compact];
+
+fn interface(i: usize, tick: u64) -> InterfaceData {
+    let sent = 1000 * (i as u64 + 1) * tick;
+    let rec = 3000 * (i as u64 + 1) * tick;
+    let raw = RawCounters {
+        sent_total: sent * 10,
+        rec_total: rec * 10,
+        sent,
+        rec,
+    };
+    InterfaceData {
+        name: format!("eth{i}"),
+        sent_total: raw.sent_total,
+        rec_total: raw.rec_total,
+        sent,
+        rec,
+        mac: MacAddr([2, 0, 0, 0, 0, i as u8]),
+        raw,
+        errors: PacketErrors::default(),
+        packets: PacketCounters {
+            sent: tick,
+            rec: 2 * tick,
+            sent_total: 10 * tick,
+            rec_total: 20 * tick,
+            ..PacketCounters::default()
+        },
+        carrier_changes: None,
+        description: None,
+        kind: InterfaceType::Unknown,
+    }
+}
+
+fn app(interfaces: usize) -> App {
+    app_with(Config::default(), interfaces)
+}
+
+fn app_with(config: Config, interfaces: usize) -> App {
+    let capabilities = Capabilities::from_env(|name| match name {
+        "TERM" => Some("xterm-256color".to_string()),
+        "LANG" => Some("C.UTF-8".to_string()),
+        _ => None,
+    });
+    let mut app = App::new(config, capabilities).unwrap();
+    let start = Instant::now();
+    // a few samples so the graphs have something to draw
+    for tick in 1..=3 {
+        let snapshot = Snapshot {
+            taken: start + Duration::from_secs(tick),
+            recorded: None,
+            interfaces: (0..interfaces).map(|i| interface(i, tick)).collect(),
+            sockets: Ok(Vec::new()),
+        };
+        update(&mut app, Action::Tick(snapshot));
+    }
+    app
+}
+
+fn render(app: &mut App, width: u16, height: u16) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| ui(f, app)).unwrap();
+    terminal.backend().buffer().clone()
+}
+
+fn row(buffer: &Buffer, y: u16) -> String {
+    (0..buffer.area.width)
+        .map(|x| buffer.get(x, y).symbol.as_str())
+        .collect()
+}
+
+fn contents(buffer: &Buffer) -> String {
+    (0..buffer.area.height)
+        .map(|y| row(buffer, y))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// What every view has to keep readable: the tab bar on the first row and
+// the selected interface named somewhere on screen
+fn assert_readable(buffer: &Buffer, interfaces: usize, context: &str) {
+    assert!(
+        row(buffer, 0).contains("1 Overview"),
+        "{context}: tab bar missing\n{}",
+        contents(buffer)
+    );
+    if interfaces > 0 {
+        assert!(
+            contents(buffer).contains("eth0"),
+            "{context}: selected interface not shown\n{}",
+            contents(buffer)
+        );
+    }
+}
+
+#[test]
+fn overview_layouts_render_at_all_sizes() {
+    for interfaces in INTERFACE_COUNTS {
+        for layout in LAYOUTS {
+            let mut app = app(interfaces);
+            app.layout = layout;
+            for (width, height) in SIZES {
+                let buffer = render(&mut app, width, height);
+                let context = format!("{} {width}x{height} {interfaces} interfaces", layout.name());
+                assert_readable(&buffer, interfaces, &context);
+            }
+        }
+    }
+}
+
+#[test]
+fn detail_view_renders_at_all_sizes() {
+    for interfaces in INTERFACE_COUNTS {
+        let mut app = app(interfaces);
+        update(&mut app, Action::ToggleZoom);
+        for (width, height) in SIZES {
+            let buffer = render(&mut app, width, height);
+            let context = format!("zoomed {width}x{height} {interfaces} interfaces");
+            assert_readable(&buffer, interfaces, &context);
+        }
+    }
+}
+
+#[test]
+fn optional_panels_render_at_all_sizes() {
+    for interfaces in INTERFACE_COUNTS {
+        let mut app = app(interfaces);
+        update(&mut app, Action::TogglePacketGraphs);
+        update(&mut app, Action::ToggleAggregate);
+        update(&mut app, Action::ToggleDebug);
+        for layout in LAYOUTS {
+            app.layout = layout;
+            for (width, height) in SIZES {
+                render(&mut app, width, height);
+            }
+        }
+    }
+}
+
+#[test]
+fn every_screen_renders_at_all_sizes() {
+    let mut app = app(5);
+    for screen in 0..Screen::ALL.len() {
+        update(&mut app, Action::SelectScreen(screen));
+        for (width, height) in SIZES {
+            let buffer = render(&mut app, width, height);
+            assert!(
+                row(&buffer, 0).contains("1 Overview"),
+                "screen {screen} {width}x{height}: tab bar missing\n{}",
+                contents(&buffer)
+            );
+        }
+    }
+}
+
+#[test]
+fn selection_stays_visible_when_scrolled() {
+    for layout in LAYOUTS {
+        let mut app = app(100);
+        app.layout = layout;
+        update(&mut app, Action::SelectIndex(usize::MAX));
+        for (width, height) in SIZES {
+            let buffer = render(&mut app, width, height);
+            assert!(
+                contents(&buffer).contains("eth99"),
+                "{} {width}x{height}: last interface not scrolled into view\n{}",
+                layout.name(),
+                contents(&buffer)
+            );
+        }
+    }
+}
+
+#[test]
+fn removed_interfaces_stay_reviewable() {
+    for interfaces in [1, 3, 20] {
+        let mut app = app(interfaces);
+        update(&mut app, Action::SelectIndex(usize::MAX));
+        let selected = app.net_interfaces[app.selected].name.to_string();
+        // eth0 goes away, shifting the rest up
+        let taken = app.last_tick;
+        for tick in 4..=6 {
+            let snapshot = Snapshot {
+                taken: taken + Duration::from_secs(tick),
+                recorded: None,
+                interfaces: (1..interfaces).map(|i| interface(i, tick)).collect(),
+                sockets: Ok(Vec::new()),
+            };
+            update(&mut app, Action::Tick(snapshot));
+        }
+        assert_eq!(app.hotplug.departed.len(), 1);
+        if interfaces > 1 {
+            assert_eq!(app.net_interfaces[app.selected].name, selected);
+        }
+        for (width, height) in SIZES {
+            let buffer = render(&mut app, width, height);
+            assert!(
+                row(&buffer, 0).contains("1 Overview"),
+                "{interfaces} interfaces {width}x{height}: tab bar missing\n{}",
+                contents(&buffer)
+            );
+        }
+        let buffer = render(&mut app, 120, 40);
+        assert!(
+            contents(&buffer).contains("Removed interfaces"),
+            "{interfaces} interfaces: removed panel missing\n{}",
+            contents(&buffer)
+        );
+        update(&mut app, Action::Dismiss);
+        assert!(!app.hotplug.shown());
+    }
+}
+
+#[test]
+fn overflow_is_indicated_at_all_sizes() {
+    for layout in LAYOUTS {
+        let mut app = app(100);
+        app.layout = layout;
+        for (width, height) in SIZES {
+            update(&mut app, Action::SelectIndex(0));
+            let buffer = render(&mut app, width, height);
+            assert!(
+                contents(&buffer).contains("more interfaces"),
+                "{} {width}x{height}: no overflow indicator\n{}",
+                layout.name(),
+                contents(&buffer)
+            );
+            update(&mut app, Action::SelectIndex(usize::MAX));
+            let buffer = render(&mut app, width, height);
+            assert!(
+                contents(&buffer).contains("above"),
+                "{} {width}x{height}: no indicator for interfaces scrolled past\n{}",
+                layout.name(),
+                contents(&buffer)
+            );
+        }
+    }
+}
+
+#[test]
+fn picker_limits_the_monitored_interfaces() {
+    let mut app = app(20);
+    open_picker(&mut app);
+    let snapshot = Snapshot {
+        taken: app.last_tick + Duration::from_secs(1),
+        recorded: None,
+        interfaces: (0..20).map(|i| interface(i, 4)).collect(),
+        sockets: Ok(Vec::new()),
+    };
+    update(&mut app, Action::Tick(snapshot));
+    // uncheck the first two
+    for code in [KeyCode::Char(' '), KeyCode::Down, KeyCode::Char(' ')] {
+        update(&mut app, Action::PickerInput(code));
+    }
+    for (width, height) in SIZES {
+        let buffer = render(&mut app, width, height);
+        assert!(
+            contents(&buffer).contains("Interfaces to monitor"),
+            "{width}x{height}: picker not shown\n{}",
+            contents(&buffer)
+        );
+    }
+    // the cursor stays in view at the end of a long list
+    update(&mut app, Action::SelectIndex(0));
+    for _ in 0..20 {
+        update(&mut app, Action::PickerInput(KeyCode::Down));
+    }
+    let buffer = render(&mut app, 80, 10);
+    assert!(contents(&buffer).contains("eth9"), "{}", contents(&buffer));
+    update(&mut app, Action::PickerInput(KeyCode::Enter));
+    assert!(app.picker.is_none());
+    assert_eq!(app.net_interfaces.len(), 18);
+    assert_eq!(app.config.monitor.len(), 18);
+}
+
+#[test]
+fn search_filters_interfaces_as_typed() {
+    let mut app = app(12);
+    update(&mut app, Action::OpenPrompt(PromptKind::Search));
+    for c in "eth1".chars() {
+        update(&mut app, Action::PromptInput(KeyCode::Char(c)));
+    }
+    // eth1, eth10 and eth11
+    assert_eq!(app.net_interfaces.len(), 3);
+    update(&mut app, Action::PromptInput(KeyCode::Backspace));
+    assert_eq!(app.net_interfaces.len(), 12);
+    update(&mut app, Action::PromptInput(KeyCode::Char('7')));
+    update(&mut app, Action::PromptInput(KeyCode::Enter));
+    assert!(app.prompt.is_none());
+    // the locked filter holds across samples
+    let snapshot = Snapshot {
+        taken: app.last_tick + Duration::from_secs(1),
+        recorded: None,
+        interfaces: (0..12).map(|i| interface(i, 4)).collect(),
+        sockets: Ok(Vec::new()),
+    };
+    update(&mut app, Action::Tick(snapshot));
+    assert_eq!(app.net_interfaces.len(), 1);
+    assert_eq!(app.net_interfaces[0].name, "eth7");
+    for (width, height) in SIZES {
+        let buffer = render(&mut app, width, height);
+        assert!(
+            !contents(&buffer).contains("eth3"),
+            "{width}x{height}: filtered interface shown\n{}",
+            contents(&buffer)
+        );
+    }
+    update(&mut app, Action::Dismiss);
+    assert_eq!(app.net_interfaces.len(), 12);
+}
+
+#[test]
+fn compare_view_shows_the_marked_pair() {
+    let mut app = app(5);
+    update(&mut app, Action::SelectInterface("eth0".to_string()));
+    update(&mut app, Action::ToggleCompareMark);
+    update(&mut app, Action::SelectInterface("eth1".to_string()));
+    update(&mut app, Action::ToggleCompareMark);
+    for (width, height) in SIZES {
+        render(&mut app, width, height);
+    }
+    let text = contents(&render(&mut app, 120, 40));
+    assert!(text.contains("eth0 vs eth1"), "{text}");
+    // eth1 moves twice eth0's traffic
+    assert!(text.contains("eth1 2.9 KB/s ahead, split 33/67"), "{text}");
+    assert!(!text.contains("eth2"), "{text}");
+    update(&mut app, Action::Dismiss);
+    assert!(app.compare.marked.is_empty());
+}
+
+#[test]
+fn graph_titles_show_values_and_locked_scales() {
+    let mut config = Config::default();
+    config.interfaces.insert(
+        "eth0".to_string(),
+        InterfaceConfig {
+            y_max: Some("1 Gb/s".to_string()),
+            ..InterfaceConfig::default()
+        },
+    );
+    let mut app = app_with(config, 2);
+    let text = contents(&render(&mut app, 120, 50));
+    assert!(
+        text.contains("eth0 TX 2.9 KB/s (max 2.9 KB/s) [scale 119.2 MB/s]"),
+        "{text}"
+    );
+    assert!(
+        !text.contains("eth1 TX 5.9 KB/s (max 5.9 KB/s) [scale"),
+        "{text}"
+    );
+    // locked at eth1's peaks so far, 6000 and 18000 bytes a second
+    update(&mut app, Action::SelectInterface("eth1".to_string()));
+    update(&mut app, Action::ToggleScaleLock);
+    let text = contents(&render(&mut app, 120, 50));
+    assert!(
+        text.contains("eth1 TX 5.9 KB/s (max 5.9 KB/s) [scale 5.9 KB/s]"),
+        "{text}"
+    );
+    assert!(
+        text.contains("eth1 RX 17.6 KB/s (max 17.6 KB/s) [scale 17.6 KB/s]"),
+        "{text}"
+    );
+    update(&mut app, Action::ToggleScaleLock);
+    let text = contents(&render(&mut app, 120, 50));
+    assert!(
+        !text.contains("eth1 TX 5.9 KB/s (max 5.9 KB/s) [scale"),
+        "{text}"
+    );
+}
+
+#[test]
+fn overlaid_graphs_share_one_row() {
+    let mut app = app(3);
+    let text = contents(&render(&mut app, 120, 40));
+    assert!(!text.contains("eth1 TX"), "{text}");
+    update(&mut app, Action::CycleOverlay);
+    let text = contents(&render(&mut app, 120, 40));
+    assert!(
+        text.contains("eth0 RX 8.8 KB/s (max 8.8 KB/s) TX 2.9 KB/s (max 2.9 KB/s)"),
+        "{text}"
+    );
+    assert!(text.contains("eth1 RX"), "{text}");
+    assert!(!text.contains("eth0 TX"), "{text}");
+    assert!(text.contains("RX and TX overlaid"), "{text}");
+    update(&mut app, Action::CycleOverlay);
+    assert!(contents(&render(&mut app, 120, 40)).contains("TX mirrored below RX"));
+    update(&mut app, Action::TogglePacketGraphs);
+    for zoomed in [false, true] {
+        if zoomed {
+            update(&mut app, Action::ToggleZoom);
+        }
+        for (width, height) in SIZES {
+            let buffer = render(&mut app, width, height);
+            let context = format!("mirrored zoomed {zoomed} {width}x{height}");
+            assert_readable(&buffer, 3, &context);
+        }
+    }
+}
+
+#[test]
+fn stacked_panel_shows_each_share() {
+    let mut app = app(3);
+    update(&mut app, Action::ToggleStacked);
+    let text = contents(&render(&mut app, 120, 40));
+    assert!(
+        text.contains("All interfaces stacked eth0 17% eth1 33% eth2 50%"),
+        "{text}"
+    );
+    assert!(!text.contains("All TX"), "{text}");
+    for (width, height) in SIZES {
+        render(&mut app, width, height);
+    }
+    update(&mut app, Action::ToggleStacked);
+    assert!(contents(&render(&mut app, 120, 40)).contains("All TX"));
+}
+
+#[test]
+fn detail_view_shows_the_rate_percentiles() {
+    let mut app = app(1);
+    update(&mut app, Action::ToggleZoom);
+    let text = contents(&render(&mut app, 100, 50));
+    // 1000, 2000 and 3000 bytes sent a second, each the middle of its bucket
+    assert!(
+        text.contains("eth0 rates this session, 861 B/s to 9.5 KB/s"),
+        "{text}"
+    );
+    assert!(
+        text.contains("RX p50 6.2 KB/s, p95 8.7 KB/s, p99 8.7 KB/s"),
+        "{text}"
+    );
+    assert!(
+        text.contains("TX p50 1.8 KB/s, p95 3.1 KB/s, p99 3.1 KB/s"),
+        "{text}"
+    );
+    let text = contents(&render(&mut app, 100, 30));
+    assert!(!text.contains("rates this session"), "{text}");
+}
+
+#[test]
+fn latency_targets_show_loss_and_jitter() {
+    let mut app = app(1);
+    // one in five probes lost, the rest alternating 10 and 30 ms
+    for i in 0..20 {
+        app.latency.samples.push_back(LatencySample {
+            time: i as f64,
+            rtt: (i % 5 != 4).then_some(if i % 2 == 0 { 10.0 } else { 30.0 }),
+            throughput: 0.0,
+        });
+    }
+    let screen = Screen::ALL.iter().position(|s| *s == Screen::Latency);
+    update(&mut app, Action::SelectScreen(screen.unwrap()));
+    let text = contents(&render(&mut app, 160, 40));
+    assert!(
+        text.contains("Targets (loss over 5%, jitter over 30 ms alert)"),
+        "{text}"
+    );
+    assert!(text.contains("gateway"), "{text}");
+    assert!(text.contains("20.0%"), "{text}");
+    assert!(text.contains("16.0 ms"), "{text}");
+}
+
+#[test]
+fn socket_strip_flags_a_growing_state() {
+    let mut app = app(1);
+    let socket = |state: u8| SocketBytes {
+        inode: 0,
+        uid: 0,
+        local: "127.0.0.1:80".parse().unwrap(),
+        remote: "127.0.0.1:40000".parse().unwrap(),
+        state,
+        sent: 0,
+        rec: 0,
+    };
+    // TIME_WAIT piles up by one every sample while the rest hold steady
+    for count in 1..=11 {
+        let mut sockets = vec![socket(1), socket(1), socket(8)];
+        sockets.extend((0..count).map(|_| socket(6)));
+        app.socket_states.update(&Ok(sockets));
+    }
+    update(&mut app, Action::ToggleSocketStrip);
+    let buffer = render(&mut app, 120, 40);
+    let strip = row(&buffer, 1);
+    assert!(strip.starts_with("ESTABLISHED 2 "), "{strip}");
+    assert!(strip.contains("| TIME_WAIT 11 "), "{strip}");
+    assert!(strip.contains("| CLOSE_WAIT 1 "), "{strip}");
+    assert!(strip.contains("| SYN_RECV 0 "), "{strip}");
+    // the sparklines before a name take a cell per character, not per byte
+    let at = |name: &str| {
+        let bytes = strip.find(name).unwrap();
+        buffer.get(strip[..bytes].chars().count() as u16, 1).fg
+    };
+    assert_eq!(Some(at("TIME_WAIT")), app.theme.alert.fg);
+    assert_eq!(Some(at("CLOSE_WAIT")), app.theme.text.fg);
+}
+
+#[test]
+fn interfaces_hidden_when_they_appear_draw_once_shown() {
+    let mut app = app(2);
+    update(&mut app, Action::TogglePhysicalOnly);
+    let start = app.last_tick;
+    for tick in 1..=3 {
+        let mut interfaces: Vec<InterfaceData> = (0..2)
+            .map(|i| InterfaceData {
+                kind: InterfaceType::Ethernet,
+                ..interface(i, 3 + tick)
+            })
+            .collect();
+        // e.g. a container's veth showing up under physical-only
+        interfaces.push(InterfaceData {
+            name: "veth0".to_string(),
+            ..interface(2, 3 + tick)
+        });
+        let snapshot = Snapshot {
+            taken: start + Duration::from_secs(tick),
+            recorded: None,
+            interfaces,
+            sockets: Ok(Vec::new()),
+        };
+        update(&mut app, Action::Tick(snapshot));
+    }
+    assert!(app.net_interfaces.iter().all(|i| i.name != "veth0"));
+    update(&mut app, Action::TogglePhysicalOnly);
+    app.selected = app
+        .net_interfaces
+        .iter()
+        .position(|i| i.name == "veth0")
+        .unwrap();
+    let buffer = render(&mut app, 100, 30);
+    assert!(contents(&buffer).contains("veth0"), "{}", contents(&buffer));
+    // sampled while hidden too
+    assert_eq!(app.net_interface_graphs["veth0"].sent.len(), 3);
+}
+
+#[test]
+fn modems_stay_shown_under_physical_only() {
+    for name in ["ppp0", "wwan0", "wwp0s20f0u6", "rmnet_data0", "pdp_ip0"] {
+        assert_eq!(InterfaceType::guess(name), InterfaceType::Modem, "{name}");
+        assert!(InterfaceType::guess(name).physical(), "{name}");
+    }
+    assert!(InterfaceType::parse_filter("physical")
+        .unwrap()
+        .contains(&InterfaceType::Modem));
+}
+
+#[test]
+fn huge_counts_are_capped() {
+    let mut app = app(5);
+    let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+    for _ in 0..12 {
+        assert!(matches!(key_action(&mut app, key('9')), Action::None));
+    }
+    let action = key_action(&mut app, key('j'));
+    let Action::Sequence(actions) = &action else {
+        panic!("a counted motion is a sequence");
+    };
+    assert_eq!(actions.len(), 999);
+    update(&mut app, action);
+    assert_eq!(app.selected, 4);
+}
+
+#[test]
+fn replayed_alerts_are_marked() {
+    let mut app = app(1);
+    switch_screen(&mut app, Screen::Alerts);
+    assert!(!contents(&render(&mut app, 120, 30)).contains("replayed"));
+    app.replay = Some(replay::spawn(&Pipeline::default(), Vec::new()));
+    // fired when it was recorded, a while before today
+    let recorded = 1_700_000_000.0;
+    app.alerts.record(&AlertEvent {
+        rule: "busy".to_string(),
+        interface: "eth0".to_string(),
+        state: AlertState::Fired,
+        value: 2048.0,
+        threshold: 1024.0,
+        time: recorded,
+        since: recorded - 30.0,
+    });
+    let buffer = render(&mut app, 120, 30);
+    let text = contents(&buffer);
+    assert!(text.contains("), replayed"), "{text}");
+    assert!(text.contains(&format_timestamp(recorded)), "{text}");
+}
+
+#[test]
+fn route_lookups_answer_on_a_later_tick() {
+    let mut app = app(1);
+    lookup_route(&mut app, "localhost".to_string());
+    let text = contents(&render(&mut app, 100, 30));
+    assert!(text.contains("Looking up localhost"), "{text}");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while app
+        .route
+        .as_ref()
+        .is_some_and(|lookup| lookup.route.is_none())
+    {
+        assert!(Instant::now() < deadline, "the lookup never answered");
+        std::thread::sleep(Duration::from_millis(10));
+        update_route(&mut app);
+    }
+    let text = contents(&render(&mut app, 100, 30));
+    assert!(!text.contains("Looking up"), "{text}");
+    assert!(text.contains("localhost"), "{text}");
+}
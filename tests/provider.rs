@@ -0,0 +1,801 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use net_stat::{
+    accessible::Announcer,
+    alerts::{AlertEngine, AlertRuleConfig},
+    app::{ui, update, App},
+    capabilities::Capabilities,
+    collector::{Collector, Pipeline},
+    collectors::{
+        format_mac,
+        mock::{MockBackend, MockCounters},
+        parse_mac,
+        procfs::parse_net_dev,
+    },
+    config::{Config, InterfaceConfig},
+    connections::{
+        parse_socket_table, sort_connections, Connection, ConnectionRates, ConnectionSort, Protocol,
+    },
+    containers::{container_id, parse_docker_names},
+    derived::parse_derived,
+    discovery::{announcement, parse_announcement, Peer},
+    error::{Context, ResultExt},
+    forecast::update_forecasts,
+    geoip::GeoIp,
+    history::{self, InterfaceHistory, Resolution},
+    mqtt::MqttConfig,
+    netlink,
+    quota::Quotas,
+    replay::{self, Recorder},
+    sockdiag::SocketBytes,
+    stream::{accept_key, client_frame, frame},
+    tcp::{parse_counters, TcpHealth},
+    Action,
+};
+use ratatui::{backend::TestBackend, Terminal};
+
+// Totals of mock0 and mock1 at each step, one second apart
+fn script() -> Vec<Vec<MockCounters>> {
+    [
+        (0, 0, 0, 0),
+        (2048, 4096, 1024, 0),
+        (4096, 12288, 2048, 1024),
+        // mock1's counters reset, e.g. its driver was reloaded
+        (6144, 20480, 0, 0),
+    ]
+    .into_iter()
+    .map(|(sent0, rec0, sent1, rec1)| {
+        vec![
+            MockCounters::new("mock0", sent0, rec0),
+            MockCounters::new("mock1", sent1, rec1),
+        ]
+    })
+    .collect()
+}
+
+// A dashboard fed by the mock through a collector, one step per second
+fn run(steps: usize) -> App {
+    run_with(Config::default(), steps)
+}
+
+fn run_with(config: Config, steps: usize) -> App {
+    run_each(config, steps, |_| {})
+}
+
+// Also hands the dashboard to `each` after every step
+fn run_each(config: Config, steps: usize, each: impl FnMut(&App)) -> App {
+    run_script(config, script(), steps, each)
+}
+
+fn run_script(
+    config: Config,
+    script: Vec<Vec<MockCounters>>,
+    steps: usize,
+    mut each: impl FnMut(&App),
+) -> App {
+    let capabilities = Capabilities::from_env(|name| match name {
+        "TERM" => Some("xterm-256color".to_string()),
+        "LANG" => Some("C.UTF-8".to_string()),
+        _ => None,
+    });
+    let mut app = App::new(config, capabilities).unwrap();
+    let mut collector = Collector::with_backend(Box::new(MockBackend::new(script)));
+    let start = Instant::now();
+    for step in 1..=steps {
+        let mut snapshot = collector.sample().unwrap();
+        snapshot.taken = start + Duration::from_secs(step as u64);
+        update(&mut app, Action::Tick(snapshot));
+        each(&app);
+    }
+    app
+}
+
+#[test]
+fn rates_come_from_counter_deltas() {
+    let app = run(3);
+    let stats = &app.stats["mock0"];
+    assert_eq!(stats.sent.current(), 2048.0);
+    assert_eq!(stats.rec.current(), 8192.0);
+    assert_eq!(stats.rec.peak, 8192.0);
+    assert_eq!(stats.rec_bytes, 12288);
+    assert_eq!(stats.rec_packets.current(), 8.0);
+    let graph = &app.net_interface_graphs["mock1"];
+    assert_eq!(graph.sent, vec![0, 1024, 1024]);
+    assert_eq!(graph.rec, vec![0, 0, 1024]);
+}
+
+#[test]
+fn counter_resets_count_as_nothing_moved() {
+    let app = run(4);
+    let mock1 = app
+        .net_interfaces
+        .iter()
+        .find(|interface| interface.name == "mock1")
+        .unwrap();
+    assert_eq!((mock1.sent, mock1.rec), (0, 0));
+    assert_eq!(app.stats["mock1"].sent_bytes, 2048);
+    // the script ran out, its last step repeats with nothing moved
+    let app = run(6);
+    assert_eq!(app.stats["mock0"].rec.current(), 0.0);
+    assert_eq!(app.stats["mock0"].rec_bytes, 20480);
+}
+
+#[test]
+fn history_keeps_every_byte_across_a_save() {
+    let app = run(4);
+    let total = |history: &HashMap<String, InterfaceHistory>| {
+        let buckets = history["mock0"].buckets(Resolution::Minute).unwrap();
+        let sent: u64 = buckets.iter().map(|bucket| bucket.sent).sum();
+        let rec: u64 = buckets.iter().map(|bucket| bucket.rec).sum();
+        (sent, rec)
+    };
+    assert_eq!(total(&app.history), (6144, 20480));
+    let path = std::env::temp_dir().join(format!("net-stat-history-{}", std::process::id()));
+    history::save(&path, &app.history).unwrap();
+    let loaded = history::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(total(&loaded), (6144, 20480));
+}
+
+#[test]
+fn widgets_show_the_scripted_rates() {
+    let mut app = run(3);
+    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let contents: String = (0..buffer.area.height)
+        .flat_map(|y| (0..buffer.area.width).map(move |x| (x, y)))
+        .map(|(x, y)| buffer.get(x, y).symbol.as_str())
+        .collect();
+    assert!(contents.contains("mock0"), "{contents}");
+    assert!(contents.contains("8.0 KB/s"), "{contents}");
+    assert!(contents.contains("2.0 KB/s"), "{contents}");
+}
+
+#[test]
+fn proc_net_dev_is_parsed() {
+    let text = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:  123456     789    0    0    0     0          0         0   123456     789    0    0    0     0       0          0
+  eth0: 9876543   12345    1    2    0     0          0        10  5432100    6789    3    4    0     0       0          0
+garbage
+";
+    let links = parse_net_dev(text);
+    assert_eq!(links.len(), 2);
+    let (name, totals) = &links[1];
+    assert_eq!(name, "eth0");
+    assert_eq!((totals.rx_bytes, totals.rx_packets), (9876543, 12345));
+    assert_eq!((totals.rx_errors, totals.rx_dropped), (1, 2));
+    assert_eq!((totals.tx_bytes, totals.tx_packets), (5432100, 6789));
+    assert_eq!((totals.tx_errors, totals.tx_dropped), (3, 4));
+    assert_eq!(totals.rx_multicast, 10);
+}
+
+#[test]
+fn tcp_health_comes_from_snmp_and_netstat() {
+    let snmp = |out_segs: u64, retrans_segs: u64, out_rsts: u64| {
+        format!(
+            "\
+Ip: Forwarding DefaultTTL
+Ip: 1 64
+Tcp: RtoAlgorithm MaxConn ActiveOpens AttemptFails EstabResets InSegs OutSegs RetransSegs InErrs OutRsts
+Tcp: 1 -1 10 2 3 500 {out_segs} {retrans_segs} 0 {out_rsts}
+"
+        )
+    };
+    let netstat = |listen_drops: u64| {
+        format!(
+            "\
+TcpExt: SyncookiesSent ListenOverflows ListenDrops
+TcpExt: 0 {listen_drops} {listen_drops}
+"
+        )
+    };
+    let counters = parse_counters(&snmp(1000, 10, 4), &netstat(1)).unwrap();
+    assert_eq!((counters.out_segs, counters.retrans_segs), (1000, 10));
+    assert_eq!((counters.out_rsts, counters.estab_resets), (4, 3));
+    assert_eq!((counters.attempt_fails, counters.listen_drops), (2, 1));
+    assert!(parse_counters("Ip: Forwarding\nIp: 1\n", "").is_none());
+
+    let mut tcp = TcpHealth::default();
+    tcp.push(counters, 1.0);
+    tcp.push(
+        parse_counters(&snmp(2000, 60, 8), &netstat(1)).unwrap(),
+        2.0,
+    );
+    assert_eq!(tcp.retransmits, vec![25]);
+    assert_eq!(tcp.resets, vec![2]);
+    assert_eq!(tcp.listen_drops, vec![0]);
+    assert_eq!(tcp.retransmit_share(), 5.0);
+    assert_eq!(
+        tcp.warning().as_deref(),
+        Some("TCP retransmitting 5.0% of segments")
+    );
+}
+
+#[test]
+fn connections_sort_by_their_socket_rates() {
+    let table = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 0100007F:9C40 01 00000000:00000000 00:00000000 00000000  1000        0 101 1
+   1: 0100007F:1F90 0100007F:9C41 01 00000000:00000000 00:00000000 00000000  1000        0 102 1
+";
+    let mut connections = parse_socket_table(table, Protocol::Tcp);
+    assert_eq!(connections.len(), 2);
+    let socket = |connection: &Connection, sent: u64, rec: u64| SocketBytes {
+        inode: connection.inode,
+        uid: connection.uid,
+        local: connection.local,
+        remote: connection.remote,
+        state: 1,
+        sent,
+        rec,
+    };
+    let mut rates = ConnectionRates::default();
+    rates.update(&Ok(vec![socket(&connections[0], 0, 0)]), 1.0);
+    assert!(rates.rates.is_empty());
+    // the second socket opened since and counts from zero
+    let sample = vec![
+        socket(&connections[0], 2000, 1000),
+        socket(&connections[1], 8000, 16000),
+    ];
+    rates.update(&Ok(sample), 2.0);
+    for connection in &mut connections {
+        let key = (connection.inode, connection.local, connection.remote);
+        connection.rate = rates.rates.get(&key).copied();
+    }
+    assert_eq!(connections[0].rate, Some((1000.0, 500.0)));
+    sort_connections(&mut connections, ConnectionSort::Rate);
+    assert_eq!(connections[0].inode, 102);
+    assert_eq!(connections[0].rate, Some((4000.0, 8000.0)));
+}
+
+#[test]
+fn multicast_and_broadcast_are_counted() {
+    // mock1's driver keeps no broadcast counter
+    let script = (0..3)
+        .map(|step| {
+            vec![
+                MockCounters {
+                    multicast: 50 * step,
+                    broadcast: Some(400 * step),
+                    ..MockCounters::new("mock0", 0, 0)
+                },
+                MockCounters {
+                    multicast: 3 * step,
+                    ..MockCounters::new("mock1", 0, 0)
+                },
+            ]
+        })
+        .collect();
+    let mut app = run_script(Config::default(), script, 3, |_| {});
+    let stats = &app.stats["mock0"];
+    assert_eq!(stats.multicast.current(), 50.0);
+    assert_eq!(stats.broadcast.as_ref().unwrap().current(), 400.0);
+    assert!(app.stats["mock1"].broadcast.is_none());
+
+    update(&mut app, Action::ToggleZoom);
+    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let contents: String = (0..buffer.area.height)
+        .flat_map(|y| (0..buffer.area.width).map(move |x| (x, y)))
+        .map(|(x, y)| buffer.get(x, y).symbol.as_str())
+        .collect();
+    assert!(
+        contents
+            .contains("Multicast/broadcast recieved/s: 50 / 400 (peak 50 / 400, total 100 / 800)"),
+        "{contents}"
+    );
+}
+
+#[test]
+fn derived_series_follow_their_expressions() {
+    let mut config = Config::default();
+    for (name, expression) in [
+        ("both_rx", "mock0.rx + mock1.rx"),
+        ("tx_share", "100 * mock0.tx / max(mock0.total, 1)"),
+        ("missing", "'no-such'.rx * 2 - (1)"),
+    ] {
+        config
+            .derived
+            .insert(name.to_string(), expression.to_string());
+    }
+    let mut app = run_with(config, 3);
+    let value = |name: &str| {
+        app.derived
+            .iter()
+            .find(|series| series.name == name)
+            .unwrap()
+            .value
+    };
+    assert_eq!(value("both_rx"), 9216.0);
+    assert_eq!(value("tx_share"), 20.0);
+    assert_eq!(value("missing"), -1.0);
+    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let top: String = (0..buffer.area.width)
+        .map(|x| buffer.get(x, 2).symbol.as_str())
+        .collect();
+    assert!(top.contains("both_rx 9.0 KB/s"), "{top}");
+
+    let mut config = Config::default();
+    config
+        .derived
+        .insert("broken".to_string(), "mock0.rx +".to_string());
+    let capabilities = Capabilities::from_env(|_| None);
+    let error = App::new(config, capabilities).err().unwrap();
+    assert!(
+        error.to_string().contains("derived series broken"),
+        "{error}"
+    );
+}
+
+#[test]
+fn accessible_mode_reads_out_the_rates() {
+    let config = Config {
+        announce_interval: 1.0,
+        ..Config::default()
+    };
+    let mut announcer = Announcer::default();
+    let mut lines = Vec::new();
+    run_each(config, 4, |app| lines.push(announcer.announce(app)));
+    assert_eq!(
+        lines,
+        vec![
+            vec![],
+            vec![
+                "mock0: down 4.0 KB/s, up 2.0 KB/s".to_string(),
+                "mock1: down 0 B/s, up 1.0 KB/s".to_string(),
+            ],
+            vec![
+                "mock0: down 8.0 KB/s, up 2.0 KB/s, rising".to_string(),
+                "mock1: down 1.0 KB/s, up 1.0 KB/s, rising".to_string(),
+            ],
+            vec![
+                "mock0: down 8.0 KB/s, up 2.0 KB/s, steady".to_string(),
+                "mock1: idle".to_string(),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn containers_are_found_by_cgroup_and_named_by_docker() {
+    let id = "4f1c2b9e8d7a6f5e4d3c2b1a0f9e8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b3a2f1e";
+    // cgroup v2 under systemd, v1 without it, and a plain process
+    let systemd = format!("0::/system.slice/docker-{id}.scope\n");
+    let cgroupfs = format!("12:pids:/docker/{id}\n11:memory:/docker/{id}\n");
+    assert_eq!(container_id(&systemd).as_deref(), Some(id));
+    assert_eq!(container_id(&cgroupfs).as_deref(), Some(id));
+    assert_eq!(
+        container_id("0::/user.slice/user-1000.slice/session-2.scope\n"),
+        None
+    );
+
+    let json = format!(r#"[{{"Id":"{id}","Names":["/web-1"],"State":"running"}}]"#);
+    let names = parse_docker_names(&json);
+    assert_eq!(names.get(id).map(String::as_str), Some("web-1"));
+    assert!(parse_docker_names("not json").is_empty());
+}
+
+#[test]
+fn errors_keep_their_cause() {
+    let error = Err::<(), _>(std::io::Error::other("no such device"))
+        .or_backend(|| "failed to read eth0")
+        .context(|| "sampling")
+        .unwrap_err();
+    assert_eq!(error.to_string(), "sampling: failed to read eth0");
+    assert_eq!(
+        format!("{error:#}"),
+        "sampling: failed to read eth0: no such device"
+    );
+    let source = std::error::Error::source(&error).unwrap();
+    assert_eq!(source.to_string(), "no such device");
+    assert_eq!(error.clone().class(), "backend");
+}
+
+#[test]
+fn announcements_name_the_agent_and_its_port() {
+    let from = "192.168.1.20".parse().unwrap();
+    let peer = parse_announcement(announcement(9091, "nas box").as_bytes(), from);
+    assert_eq!(
+        peer,
+        Some(Peer {
+            address: "192.168.1.20:9091".parse().unwrap(),
+            name: "nas box".to_string(),
+        })
+    );
+    assert!(parse_announcement(b"net-stat 9091", from).is_some());
+    assert!(parse_announcement(b"something else 9091", from).is_none());
+    assert!(parse_announcement(b"net-stat port", from).is_none());
+}
+
+#[test]
+fn recordings_replay_every_sample() {
+    let path = std::env::temp_dir().join(format!("net-stat-{}.nst", std::process::id()));
+    let pipeline = Pipeline::default();
+    let recorder = Recorder::spawn(&path, pipeline.subscribe("record", 8)).unwrap();
+    let mut collector = Collector::with_backend(Box::new(MockBackend::new(script())));
+    for _ in 0..3 {
+        pipeline.publish(&collector.sample().unwrap());
+    }
+    pipeline.close();
+    recorder.finish();
+
+    let frames = replay::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(frames.len(), 3);
+    let pipeline = Pipeline::default();
+    let mut samples = pipeline.subscribe("ui", 8);
+    let _control = replay::spawn(&pipeline, frames);
+    let rec: Vec<u64> = (0..3)
+        .map(|_| samples.blocking_recv().unwrap().interfaces[0].rec)
+        .collect();
+    pipeline.close();
+    assert_eq!(rec, vec![0, 4096, 8192]);
+}
+
+#[test]
+fn quotas_that_stopped_saving_leave_the_file_alone() {
+    let path = std::env::temp_dir().join(format!("net-stat-usage-{}.json", std::process::id()));
+    let mut quotas = Quotas::open(Some(path.clone()));
+    quotas.stop_saving();
+    quotas.save();
+    assert!(!path.exists());
+    let mut quotas = Quotas::open(Some(path.clone()));
+    quotas.save();
+    assert!(path.exists());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn replays_leave_this_machines_counters_alone() {
+    let local = run(2);
+    let mut app = run(0);
+    app.replay = Some(replay::spawn(&Pipeline::default(), Vec::new()));
+    let mut collector = Collector::with_backend(Box::new(MockBackend::new(script())));
+    for _ in 0..2 {
+        update(&mut app, Action::Tick(collector.sample().unwrap()));
+    }
+    assert!(app.is_remote());
+    assert_eq!(
+        local.tcp.available,
+        std::path::Path::new("/proc/net/snmp").exists()
+    );
+    assert!(!app.tcp.available);
+}
+
+#[test]
+fn replayed_rules_run_on_the_recordings_clock() {
+    let mut config = Config::default();
+    config.alerts.push(AlertRuleConfig {
+        name: Some("busy".to_string()),
+        metric: "total".to_string(),
+        above: "1 KB".to_string(),
+        duration: 30.0,
+        ..Default::default()
+    });
+    let mut app = run_with(config, 0);
+    app.replay = Some(replay::spawn(&Pipeline::default(), Vec::new()));
+    // mock0 busy throughout, recorded ten seconds apart
+    let script = (0..6u64)
+        .map(|step| vec![MockCounters::new("mock0", step << 20, 0)])
+        .collect();
+    let mut collector = Collector::with_backend(Box::new(MockBackend::new(script)));
+    let start = Instant::now();
+    let recorded = 1_700_000_000.0;
+    for step in 0..6 {
+        let mut snapshot = collector.sample().unwrap();
+        snapshot.taken = start + Duration::from_secs(step * 10);
+        snapshot.recorded = Some(recorded + step as f64 * 10.0);
+        update(&mut app, Action::Tick(snapshot));
+    }
+    let fired = app.alerts.events.back().unwrap();
+    assert_eq!(fired.rule, "busy");
+    assert_eq!(fired.since, recorded + 10.0);
+    assert_eq!(fired.time, recorded + 40.0);
+}
+
+#[test]
+fn rules_count_every_time_they_fired() {
+    let config = AlertRuleConfig {
+        name: Some("busy".to_string()),
+        metric: "total".to_string(),
+        above: "1 KB".to_string(),
+        duration: 0.0,
+        ..Default::default()
+    };
+    let mut engine = AlertEngine::from_config(&[config]).unwrap();
+    // fired and resolved again, more often than the history keeps events
+    for i in 0..600 {
+        let time = i as f64 * 2.0;
+        engine.evaluate(time, "eth0", 1e6, 0.0);
+        engine.evaluate(time + 1.0, "eth0", 0.0, 0.0);
+    }
+    assert!(engine.events.len() < 1200);
+    assert_eq!(engine.fired_count("busy"), 600);
+    assert_eq!(engine.fired_count("idle"), 0);
+}
+
+#[test]
+fn websocket_handshakes_and_frames_follow_rfc_6455() {
+    // the example handshake of section 1.3
+    assert_eq!(
+        accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+    // the examples of section 5.7, a masked "Hello" and an unmasked ping
+    let masked = [
+        0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+    ];
+    assert_eq!(
+        client_frame(&masked),
+        Some((0x1, b"Hello".to_vec(), masked.len()))
+    );
+    assert_eq!(client_frame(&masked[..8]), None);
+    let ping = [0x89, 0x05, b'H', b'e', b'l', b'l', b'o'];
+    assert_eq!(client_frame(&ping), Some((0x9, b"Hello".to_vec(), 7)));
+    assert_eq!(
+        frame(0xa, b"Hello"),
+        [0x8a, 0x05, b'H', b'e', b'l', b'l', b'o']
+    );
+    // 256 bytes take the 16 bit length
+    assert_eq!(frame(0x2, &[0; 256])[..4], [0x82, 0x7e, 0x01, 0x00]);
+}
+
+#[test]
+fn geoip_rejects_looping_and_oversized_databases() {
+    let marker = b"\xab\xcd\xefMaxMind.com";
+    let start = marker.len() as u8;
+    let mut node_count = vec![0xe2, 0x4a];
+    node_count.extend(b"node_count");
+    // a uint64 of 2^63 nodes, then a record size of 24 bits
+    node_count.extend([0x08, 0x02, 0x80, 0, 0, 0, 0, 0, 0, 0, 0x4b]);
+    node_count.extend(b"record_size");
+    node_count.extend([0xa1, 24]);
+    let files = [
+        // metadata whose only value points back at the metadata
+        vec![0xe1, 0x41, b'a', 0x20, start],
+        // a pointer to itself
+        vec![0x20, start],
+        node_count,
+    ];
+    for (i, metadata) in files.iter().enumerate() {
+        let path =
+            std::env::temp_dir().join(format!("net-stat-geoip-{}-{i}.mmdb", std::process::id()));
+        std::fs::write(&path, [&marker[..], metadata].concat()).unwrap();
+        let mut geoip = GeoIp::open(&[path.display().to_string()]).unwrap();
+        assert_eq!(geoip.locate("192.0.2.1".parse().unwrap()), None);
+        let error = geoip.error.unwrap();
+        assert!(error.contains("is not a MaxMind DB file"), "{error}");
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+// The derived series worked out from the second sample, mock0 sending
+// 2048 and receiving 4096 bytes a second, mock1 sending 1024 and idle
+fn derived_values(expressions: &[&str]) -> Vec<f64> {
+    let mut config = Config::default();
+    for (i, expression) in expressions.iter().enumerate() {
+        config
+            .derived
+            .insert(format!("series{i:02}"), expression.to_string());
+    }
+    let app = run_with(config, 2);
+    app.derived.iter().map(|series| series.value).collect()
+}
+
+#[test]
+fn derived_series_follow_arithmetic_precedence() {
+    assert_eq!(
+        derived_values(&[
+            "mock0.tx + mock0.rx * 2",
+            "(mock0.tx + mock0.rx) * 2",
+            // left to right within the same precedence
+            "mock0.rx - mock0.tx - 1024",
+            "mock0.rx / mock0.tx / 2",
+            "max(mock0.tx, mock1.tx) + min(mock0.tx, mock1.tx, 1)",
+            "'mock0'.total",
+        ]),
+        [10240.0, 12288.0, 1024.0, 1.0, 2049.0, 6144.0]
+    );
+}
+
+#[test]
+fn derived_series_negate_and_divide_by_idle_interfaces() {
+    assert_eq!(
+        derived_values(&[
+            "-mock0.tx * 2 + 5000",
+            "- -mock1.tx",
+            "2 * -mock1.tx",
+            "abs(-mock1.tx)",
+            // an idle or missing interface as the divisor gives 0
+            "mock0.tx / mock1.rx",
+            "mock0.tx / missing.total",
+        ]),
+        [904.0, 1024.0, -2048.0, 1024.0, 0.0, 0.0]
+    );
+}
+
+#[test]
+fn derived_series_explain_what_is_wrong() {
+    for (source, message) in [
+        ("'br-lan.rx", "unterminated quote"),
+        ("mock0.tx +", "unexpected end"),
+        ("max(mock0.tx", "expected ')' at the end"),
+        ("(mock0.tx mock0.rx)", "expected ')', found mock0"),
+        (
+            "mock0.speed",
+            "unknown metric mock0.speed, expected tx, rx or total",
+        ),
+        ("mock0.tx $", "unexpected '$'"),
+        ("abs(1, 2)", "abs takes one argument"),
+        ("sum(1)", "unknown function sum, expected min, max or abs"),
+    ] {
+        let config = BTreeMap::from([("broken".to_string(), source.to_string())]);
+        let error = parse_derived(&config).err().unwrap().to_string();
+        assert_eq!(
+            error,
+            format!("derived series broken \"{source}\": {message}")
+        );
+    }
+}
+
+#[test]
+fn macs_parse_as_sysfs_and_the_config_write_them() {
+    let mac = [0x02, 0x42, 0xac, 0x11, 0x00, 0x0f];
+    assert_eq!(parse_mac("02:42:ac:11:00:0f\n"), Some(mac));
+    assert_eq!(parse_mac("02-42-AC-11-00-0F"), Some(mac));
+    assert_eq!(format_mac(&mac), "02:42:ac:11:00:0f");
+    // an IPv6 tunnel's address, a short part and nothing at all
+    assert_eq!(
+        parse_mac("00:00:00:00:00:00:00:00:00:00:00:00:00:00:00:00"),
+        None
+    );
+    assert_eq!(parse_mac("2:42:ac:11:00:0f"), None);
+    assert_eq!(parse_mac(""), None);
+}
+
+#[test]
+fn mqtt_topics_keep_interface_names_to_one_level() {
+    let config = MqttConfig::default();
+    assert_eq!(config.topic("eth0", "tx_rate"), "net-stat/eth0/tx_rate");
+    assert_eq!(
+        config.topic("wg/home+#", "rx_total"),
+        "net-stat/wg_home__/rx_total"
+    );
+}
+
+#[test]
+fn quota_forecasts_count_the_period_not_the_boot() {
+    let mut config = Config::default();
+    config.interfaces.insert(
+        "mock0".to_string(),
+        InterfaceConfig {
+            quota: Some("1 GB".to_string()),
+            ..Default::default()
+        },
+    );
+    // 50 GB moved since boot, then a steady 1 MB a second
+    let script = (0..40u64)
+        .map(|step| vec![MockCounters::new("mock0", (50 << 30) + step * (1 << 20), 0)])
+        .collect();
+    let mut app = run_script(config, script, 40, |_| {});
+    // the graphs keep wall clock times, spread the samples a second apart
+    let graph = app.net_interface_graphs.get_mut("mock0").unwrap();
+    graph.times = (0..graph.sent.len()).map(|i| i as f64).collect();
+    update_forecasts(&mut app);
+    let quota = app.config.quota("mock0").unwrap();
+    let used = app.quotas.used("mock0").unwrap();
+    assert!(used < quota);
+    let forecast = &app.forecasts["mock0"];
+    assert_eq!(forecast.quota, Some((quota - used) as f64 / forecast.rate));
+    // without the period's usage, e.g. in a remote session, there's no ETA
+    app.quotas = Quotas::open(None);
+    update_forecasts(&mut app);
+    assert_eq!(app.forecasts["mock0"].quota, None);
+}
+
+#[test]
+fn hidden_interfaces_keep_counting_towards_their_quota() {
+    let mut config = Config::default();
+    config.interfaces.insert(
+        "mock0".to_string(),
+        InterfaceConfig {
+            quota: Some("1 GB".to_string()),
+            ..Default::default()
+        },
+    );
+    let capabilities = Capabilities::from_env(|_| None);
+    let mut app = App::new(config, capabilities).unwrap();
+    let mut collector = Collector::with_backend(Box::new(MockBackend::new(script())));
+    let start = Instant::now();
+    for step in 1..=4 {
+        let mut snapshot = collector.sample().unwrap();
+        snapshot.taken = start + Duration::from_secs(step);
+        update(&mut app, Action::Tick(snapshot));
+        if step == 2 {
+            // a search showing only mock1 while mock0 keeps moving bytes
+            update(
+                &mut app,
+                Action::SearchInterfaces(Some("mock1".to_string())),
+            );
+        }
+    }
+    assert!(app.net_interfaces.iter().all(|i| i.name != "mock0"));
+    assert_eq!(app.quotas.used("mock0"), Some(6144 + 20480));
+    assert_eq!(app.stats["mock0"].rec_bytes, 20480);
+}
+
+#[test]
+fn netlink_messages_split_into_their_attributes() {
+    let mut payload = netlink::attribute(3, b"eth0\0");
+    let nested = netlink::attribute(1, &7u32.to_ne_bytes());
+    payload.extend(netlink::attribute(9 | netlink::NLA_F_NESTED, &nested));
+    let mut datagram = netlink::request(16, 0, 42, &payload);
+    datagram.extend(netlink::request(3, 0, 42, &[]));
+    // a truncated message at the end is left out
+    datagram.extend([64, 0, 0, 0]);
+
+    let messages = netlink::messages(&datagram);
+    assert_eq!(messages.len(), 2);
+    assert_eq!((messages[0].kind, messages[0].sequence), (16, 42));
+    assert_eq!(messages[1].kind, 3);
+    let attributes = netlink::attributes(messages[0].payload);
+    // the name is padded to 4 bytes, the next attribute still lines up
+    assert_eq!(attributes[0], (3, &b"eth0\0"[..]));
+    assert_eq!(attributes[1].0, 9);
+    let nested = netlink::attributes(attributes[1].1);
+    assert_eq!(netlink::read_u32(nested[0].1, 0), 7);
+
+    assert!(netlink::outcome(&0i32.to_ne_bytes()).is_ok());
+    let refused = netlink::outcome(&(-13i32).to_ne_bytes()).unwrap_err();
+    assert_eq!(refused.raw_os_error(), Some(13));
+}
+
+// Loads the programs past the verifier, so only as root with the feature
+#[cfg(all(target_os = "linux", feature = "ebpf"))]
+#[test]
+fn ebpf_backend_counts_loopback_bytes() {
+    use std::io::{Read, Write};
+
+    use net_stat::collectors::{ebpf::EbpfBackend, Backend};
+
+    if unsafe { libc::geteuid() } != 0 {
+        return;
+    }
+    let mut backend = EbpfBackend::open().unwrap();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let reader = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+    });
+    let mut client = std::net::TcpStream::connect(address).unwrap();
+    let local = client.local_addr().unwrap();
+    for _ in 0..10 {
+        client.write_all(&[1; 100_000]).unwrap();
+    }
+    std::thread::sleep(Duration::from_millis(100));
+    let sockets = backend.sockets().unwrap();
+    let sent = sockets
+        .iter()
+        .find(|socket| socket.local == local && socket.remote == address)
+        .unwrap();
+    assert_eq!(sent.sent, 1_000_000);
+    assert_ne!(sent.inode, 0);
+    let received = sockets
+        .iter()
+        .find(|socket| socket.local == address && socket.remote == local)
+        .unwrap();
+    assert_eq!(received.rec, 1_000_000);
+    drop(client);
+    reader.join().unwrap();
+}